@@ -0,0 +1,535 @@
+// Author: CyberCraft Alchemist
+// Async scanning backend that multiplexes connections on a small thread pool
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use rayon::prelude::*;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpSocket, TcpStream};
+use tokio::runtime::Builder;
+use tokio::sync::Semaphore;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+use log::{debug, error};
+
+use crate::constants::{ASYNC_MAX_CONNECTIONS, ASYNC_MAX_HOSTS, ASYNC_WORKER_THREADS, BANNER_READ_GAP_MS, TCP_PING_PORTS};
+use crate::models::{PortState, ScanConfig, ScanResult, ServiceProbeResult};
+use crate::progress::{NoProgress, ScanProgress};
+use crate::proxy;
+use crate::resolver;
+use crate::scanner;
+use crate::schedule;
+use crate::utils;
+
+// Result of the network phase for a single host
+struct HostProbe {
+    ip: IpAddr,
+    is_online: bool,
+    open_ports: Vec<(u16, Option<ServiceProbeResult>)>, // (port, banner probe exchange)
+    closed_port_count: usize,
+    filtered_port_count: usize,
+}
+
+// Settings each host's probe task copies from the configuration
+#[derive(Clone, Copy)]
+struct ProbeOptions {
+    timeout_ms: u64,
+    scan_offline_hosts: bool,
+    verbose: bool,
+}
+
+/// Scan all targets using the async backend
+///
+/// Connection attempts and banner grabs run on a tokio runtime; service
+/// identification and vulnerability detection then run on the blocking path.
+pub fn scan(config: &ScanConfig) -> io::Result<Vec<ScanResult>> {
+    scan_with_progress(config, &NoProgress)
+}
+
+/// Scan all targets using the async backend, reporting progress as hosts complete
+pub fn scan_with_progress(config: &ScanConfig, progress: &dyn ScanProgress) -> io::Result<Vec<ScanResult>> {
+    run_scan(config, progress, &Arc::new(AtomicBool::new(false)))
+}
+
+/// Scan all targets using the async backend until finished or until `cancel` is set
+///
+/// In-flight connection attempts are not interrupted and still wait for their timeout.
+pub fn run_scan(
+    config: &ScanConfig,
+    progress: &dyn ScanProgress,
+    cancel: &Arc<AtomicBool>
+) -> io::Result<Vec<ScanResult>> {
+    // Resolve targets to IP addresses, plus any found through SRV and MX records
+    let config = &scanner::prepare_targets(config);
+    scanner::check_scope(config).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    // Shuffling needs every target up front; otherwise stream them so large
+    // ranges are never fully materialized
+    let targets: Box<dyn Iterator<Item = IpAddr>> = if config.randomize_scan {
+        let mut targets = scanner::resolve_targets(config);
+        utils::randomize_hosts(&mut targets, &mut utils::scan_rng(config.random_seed, 0));
+        Box::new(targets.into_iter())
+    } else {
+        Box::new(scanner::target_stream(config))
+    };
+
+    let start_time = Instant::now();
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(ASYNC_WORKER_THREADS)
+        .enable_all()
+        .build()?;
+
+    let probes = runtime.block_on(probe_hosts(targets, config, progress, cancel, start_time));
+
+    // Analyze hosts with open ports outside the runtime, since the DNS and
+    // HTTP clients used by analysis are blocking
+    let mut results: Vec<ScanResult> = probes.into_par_iter()
+        .filter_map(|probe| {
+            let ip = probe.ip;
+            
+            // Hosts without open ports are reported as completed but only kept
+            // when a UDP check such as SNMP turned something up
+            if probe.open_ports.is_empty() {
+                let result = scanner::build_host_result(
+                    &ip,
+                    ip.to_string(),
+                    probe.is_online,
+                    Vec::new(),
+                    probe.closed_port_count,
+                    probe.filtered_port_count,
+                    config
+                );
+                progress.on_host_completed(&result);
+                return if result.is_reportable() { Some(result) } else { None };
+            }
+            
+            let (hostname, hostnames) = resolver::resolve_hostnames_comprehensive(&ip);
+
+            let port_results = probe.open_ports.into_par_iter()
+                .map(|(port, probe)| scanner::analyze_open_port(&ip, port, probe, config))
+                .collect();
+
+            let mut result = scanner::build_host_result(
+                &ip,
+                hostname,
+                probe.is_online,
+                port_results,
+                probe.closed_port_count,
+                probe.filtered_port_count,
+                config
+            );
+            result.hostnames = hostnames;
+            progress.on_host_completed(&result);
+            
+            Some(result)
+        })
+        .collect();
+
+    results.sort_by_key(|r| r.host.parse::<IpAddr>().ok());
+
+    Ok(results)
+}
+
+/// Probe every target concurrently, bounded by a shared connection semaphore
+///
+/// At most `ASYNC_MAX_HOSTS` hosts are in flight; the next target is only
+/// taken from `targets` once one of them has finished.
+async fn probe_hosts(
+    targets: impl Iterator<Item = IpAddr>,
+    config: &ScanConfig,
+    progress: &dyn ScanProgress,
+    cancel: &Arc<AtomicBool>,
+    start_time: Instant
+) -> Vec<HostProbe> {
+    let semaphore = Arc::new(Semaphore::new(ASYNC_MAX_CONNECTIONS));
+    let mut ports = scanner::ports_for_config(config);
+
+    // Randomize ports if requested; every host shares this order
+    if config.randomize_scan {
+        utils::randomize_ports(&mut ports, &mut utils::scan_rng(config.random_seed, 1));
+    }
+
+    let ports = Arc::new(ports);
+    let mut hosts = JoinSet::new();
+    let options = ProbeOptions {
+        timeout_ms: config.timeout_ms,
+        scan_offline_hosts: config.scan_offline_hosts,
+        verbose: config.verbose,
+    };
+    
+    // Port tasks report open ports here as soon as they connect, since the
+    // progress reporter can't be moved into spawned tasks
+    let (open_tx, mut open_rx) = mpsc::unbounded_channel();
+    let mut probes = Vec::new();
+
+    'targets: for ip in targets {
+        report_open_ports(&mut open_rx, progress);
+        
+        // Wait for a host to finish before starting another past the limit,
+        // still passing on open ports while waiting
+        while hosts.len() >= ASYNC_MAX_HOSTS {
+            report_open_ports(&mut open_rx, progress);
+            if let Ok(Some(result)) = timeout(Duration::from_millis(100), hosts.join_next()).await {
+                probes.extend(host_probe(result));
+            }
+        }
+        
+        // Hold new hosts until the scan window is open; in-flight hosts finish
+        if let Some(window) = &config.scan_window {
+            loop {
+                report_open_ports(&mut open_rx, progress);
+                if cancel.load(Ordering::Relaxed) {
+                    break 'targets;
+                }
+                
+                let remaining = schedule::time_until_open(window, Utc::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                tokio::time::sleep(remaining.min(Duration::from_secs(1))).await;
+            }
+        }
+        
+        // Stop launching new hosts once the scan has been cancelled or its time budget is spent
+        if scanner::enforce_time_budget(config, start_time, cancel) || cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        
+        progress.on_host_started(&ip);
+        hosts.spawn(probe_host(
+            ip,
+            Arc::clone(&ports),
+            Arc::clone(&semaphore),
+            Arc::clone(cancel),
+            open_tx.clone(),
+            options,
+        ));
+    }
+    
+    // The channel closes once every host task, and with it every port task, has finished
+    drop(open_tx);
+    while let Some((ip, port)) = open_rx.recv().await {
+        progress.on_port_open(&ip, port);
+    }
+
+    while let Some(result) = hosts.join_next().await {
+        probes.extend(host_probe(result));
+    }
+
+    probes
+}
+
+/// The probe of a finished host task, logging tasks that panicked
+fn host_probe(result: Result<HostProbe, tokio::task::JoinError>) -> Option<HostProbe> {
+    result.map_err(|e| error!("Probing a host failed unexpectedly, skipping it: {}", e)).ok()
+}
+
+/// Pass open ports reported so far to the progress reporter without waiting for more
+fn report_open_ports(open_rx: &mut UnboundedReceiver<(IpAddr, u16)>, progress: &dyn ScanProgress) {
+    while let Ok((ip, port)) = open_rx.try_recv() {
+        progress.on_port_open(&ip, port);
+    }
+}
+
+/// Check whether a host is online and collect banners from its open ports
+async fn probe_host(
+    ip: IpAddr,
+    ports: Arc<Vec<u16>>,
+    semaphore: Arc<Semaphore>,
+    cancel: Arc<AtomicBool>,
+    open_tx: UnboundedSender<(IpAddr, u16)>,
+    options: ProbeOptions
+) -> HostProbe {
+    let ProbeOptions { timeout_ms, scan_offline_hosts, verbose } = options;
+    let is_online = is_host_online(ip, &semaphore, timeout_ms).await;
+
+    // If host is not online and we're not doing a complete scan, return early
+    if !is_online && !scan_offline_hosts {
+        return HostProbe {
+            ip,
+            is_online,
+            open_ports: Vec::new(),
+            closed_port_count: 0,
+            filtered_port_count: 0,
+        };
+    }
+
+    let mut checks = JoinSet::new();
+
+    // Take a connection permit before spawning each port's task, so only ports
+    // that are about to be probed have a task
+    for &port in ports.iter() {
+        let permit = match Arc::clone(&semaphore).acquire_owned().await {
+            Ok(permit) => permit,
+            Err(_) => break,
+        };
+        
+        // Leave the remaining ports unprobed once the scan is cancelled
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        
+        let open_tx = open_tx.clone();
+        checks.spawn(async move {
+            let _permit = permit;
+
+            let state = probe_port(&ip, port, timeout_ms).await;
+            if state != PortState::Open {
+                return Some((port, state, None));
+            }
+            
+            // Report the port now rather than when the whole host finishes
+            let _ = open_tx.send((ip, port));
+
+            let probe = probe_service(&ip, port, timeout_ms).await;
+
+            Some((port, state, probe))
+        });
+    }
+
+    let mut probe = HostProbe {
+        ip,
+        is_online,
+        open_ports: Vec::new(),
+        closed_port_count: 0,
+        filtered_port_count: 0,
+    };
+
+    while let Some(result) = checks.join_next().await {
+        match result {
+            Ok(Some((port, PortState::Open, service_probe))) => {
+                debug!("{} open", SocketAddr::new(ip, port));
+                probe.open_ports.push((port, service_probe));
+            },
+            Ok(Some((port, PortState::Closed, _))) => {
+                if verbose {
+                    debug!("{} closed", SocketAddr::new(ip, port));
+                }
+                probe.closed_port_count += 1;
+            },
+            Ok(Some((port, PortState::Filtered, _))) => {
+                if verbose {
+                    debug!("{} filtered", SocketAddr::new(ip, port));
+                }
+                probe.filtered_port_count += 1;
+            },
+            _ => {}
+        }
+    }
+
+    probe
+}
+
+/// Check if a host is alive using ICMP ping, falling back to TCP probing
+async fn is_host_online(ip: IpAddr, semaphore: &Arc<Semaphore>, timeout_ms: u64) -> bool {
+    // An ARP sweep of the host's subnet is authoritative
+    if let Some(answered) = utils::arp_liveness(&ip) {
+        return answered;
+    }
+    
+    // The ping helper spawns a process, so keep it off the async workers
+    if let Ok(true) = tokio::task::spawn_blocking(move || utils::ping_host(&ip)).await {
+        return true;
+    }
+
+    for port in TCP_PING_PORTS.iter() {
+        let _permit = match semaphore.acquire().await {
+            Ok(permit) => permit,
+            Err(_) => return false,
+        };
+
+        if is_port_open(&ip, *port, timeout_ms).await {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Check if a port is open by attempting a TCP connection
+pub async fn is_port_open(ip: &IpAddr, port: u16, timeout_ms: u64) -> bool {
+    matches!(probe_port(ip, port, timeout_ms).await, PortState::Open)
+}
+
+/// Probe a port and classify it as open, closed (refused) or filtered (no response)
+///
+/// Connects that time out are retried up to `utils::probe_retries()` times;
+/// refused connections are not.
+pub async fn probe_port(ip: &IpAddr, port: u16, timeout_ms: u64) -> PortState {
+    let addr = SocketAddr::new(*ip, port);
+    let retries = utils::probe_retries();
+    let mut attempt = 0;
+
+    loop {
+        wait_for_connection_slot().await;
+
+        match timeout(Duration::from_millis(timeout_ms), connect(addr)).await {
+            Ok(Ok(_)) => return PortState::Open,
+            Ok(Err(e)) if matches!(e.kind(), io::ErrorKind::ConnectionRefused | io::ErrorKind::ConnectionReset) => {
+                return PortState::Closed;
+            },
+            Ok(Err(e)) if e.kind() != io::ErrorKind::TimedOut => return PortState::Filtered,
+            _ if attempt < retries => {
+                attempt += 1;
+                debug!("{} timed out, retrying ({}/{})", addr, attempt, retries);
+                tokio::time::sleep(utils::probe_retry_delay(attempt)).await;
+            },
+            _ => return PortState::Filtered,
+        }
+    }
+}
+
+/// Open a TCP connection, from the configured source address when there is one
+async fn connect(addr: SocketAddr) -> io::Result<TcpStream> {
+    let source = match proxy::source_address_for(&addr.ip()) {
+        Some(source) => source,
+        None => return TcpStream::connect(addr).await,
+    };
+    
+    let socket = if addr.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+    socket.bind(SocketAddr::new(source, 0))?;
+    socket.connect(addr).await
+}
+
+/// Wait for the shared connection rate limit without blocking a runtime worker
+async fn wait_for_connection_slot() {
+    let delay = utils::reserve_connection_slot();
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Get the service banner from an open port
+pub async fn get_service_banner(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<String> {
+    probe_service(ip, port, timeout_ms).await.map(|result| result.banner)
+}
+
+/// Send an open port the probe chosen by `utils::service_probe` and keep the exchange
+pub async fn probe_service(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<ServiceProbeResult> {
+    let addr = SocketAddr::new(*ip, port);
+    let duration = Duration::from_millis(timeout_ms);
+    wait_for_connection_slot().await;
+
+    let mut stream = match timeout(duration, connect(addr)).await {
+        Ok(Ok(stream)) => stream,
+        _ => return None,
+    };
+
+    let probe = utils::service_probe(port);
+    if !matches!(timeout(duration, stream.write_all(probe)).await, Ok(Ok(_))) {
+        return None;
+    }
+
+    // Read the response until the service goes quiet, waiting less for segments after the first
+    let max_bytes = utils::max_banner_bytes();
+    let gap = Duration::from_millis(BANNER_READ_GAP_MS.min(timeout_ms));
+    let mut response = Vec::new();
+    let mut buffer = [0; 4096];
+    while response.len() < max_bytes {
+        let wait = if response.is_empty() { duration } else { gap };
+        let size = match timeout(wait, stream.read(&mut buffer)).await {
+            Ok(Ok(size)) if size > 0 => size,
+            _ => break,
+        };
+        response.extend_from_slice(&buffer[..size.min(max_bytes - response.len())]);
+    }
+    if response.is_empty() {
+        return None;
+    }
+
+    Some(ServiceProbeResult {
+        banner: utils::format_banner(&response),
+        probe: probe.to_vec(),
+        response,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+    use std::thread;
+    
+    /// Records the ports reported open
+    #[derive(Default)]
+    struct OpenPorts(Mutex<Vec<u16>>);
+    
+    impl ScanProgress for OpenPorts {
+        fn on_port_open(&self, _ip: &IpAddr, port: u16) {
+            self.0.lock().unwrap().push(port);
+        }
+    }
+    
+    #[test]
+    fn open_ports_are_found_and_reported_with_permits_taken_per_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let config = ScanConfig {
+            target: "127.0.0.1".to_string(),
+            ports: vec![closed, open],
+            timeout_ms: 300,
+            banner_only: true,
+            offline_mode: true,
+            scan_offline_hosts: true,
+            ..ScanConfig::default()
+        };
+        let progress = OpenPorts::default();
+        
+        let results = scan_with_progress(&config, &progress).unwrap();
+        
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].open_ports.iter().map(|p| p.port).collect::<Vec<u16>>(), vec![open]);
+        assert_eq!(results[0].closed_port_count, 1);
+        assert_eq!(*progress.0.lock().unwrap(), vec![open]);
+        drop(listener);
+    }
+    
+    /// Listener whose accept queue is already full, so the next connect to it goes
+    /// unanswered until the queue is drained `drain_after` later
+    fn spawn_backlogged_listener(drain_after: Duration) -> u16 {
+        let socket = socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+        socket.bind(&SocketAddr::from(([127, 0, 0, 1], 0)).into()).unwrap();
+        socket.listen(0).unwrap();
+        let listener: TcpListener = socket.into();
+        let port = listener.local_addr().unwrap().port();
+        let queued = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+        thread::spawn(move || {
+            thread::sleep(drain_after);
+            drop(queued);
+            for stream in listener.incoming() {
+                drop(stream);
+            }
+        });
+        port
+    }
+    
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        Builder::new_current_thread().enable_all().build().unwrap().block_on(future)
+    }
+    
+    #[test]
+    fn a_timed_out_connect_is_retried_until_the_port_accepts() {
+        let timeout_ms = 150;
+        let port = spawn_backlogged_listener(Duration::from_millis(timeout_ms + 20));
+        assert_eq!(utils::probe_retries(), 1);
+        
+        assert!(matches!(block_on(probe_port(&IpAddr::from([127, 0, 0, 1]), port, timeout_ms)), PortState::Open));
+    }
+    
+    #[test]
+    fn a_refused_connect_is_not_retried() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        assert_eq!(utils::probe_retries(), 1);
+        
+        let started = Instant::now();
+        assert!(matches!(block_on(probe_port(&IpAddr::from([127, 0, 0, 1]), port, 1000)), PortState::Closed));
+        assert!(started.elapsed() < Duration::from_millis(crate::constants::PROBE_RETRY_BASE_DELAY_MS), "refused connect took {:?}", started.elapsed());
+    }
+}
@@ -8,11 +8,268 @@ use regex::Regex;
 pub const VERSION: &str = "1.0.0";
 pub const TOOL_NAME: &str = "Rust Network Vulnerability Scanner";
 
+// Version of the JSON report layout; bump it whenever report fields are added, removed or renamed
+pub const REPORT_SCHEMA_VERSION: &str = "9";
+
 // Define timeout durations
 pub const PING_TIMEOUT_MS: u64 = 1000;
 pub const PORT_SCAN_TIMEOUT_MS: u64 = 2000;
 pub const BANNER_GRAB_TIMEOUT_MS: u64 = 3000;
 
+// Characters of a banner kept in results and reports; longer banners are cut with an ellipsis
+pub const BANNER_MAX_LEN: usize = 512;
+
+// Bytes read from a service's response by default, and the most that can be configured
+pub const DEFAULT_MAX_BANNER_BYTES: usize = 8192;
+pub const MAX_BANNER_BYTES_LIMIT: usize = 1024 * 1024;
+
+// How long to wait for further segments of a response once the first has arrived
+pub const BANNER_READ_GAP_MS: u64 = 300;
+
+// Accepted ranges for scan thread counts and connection timeouts
+pub const MIN_THREADS: usize = 1;
+pub const MAX_THREADS: usize = 1000;
+pub const MIN_TIMEOUT_MS: u64 = 100;
+pub const MAX_TIMEOUT_MS: u64 = 60000;
+
+// Adaptive timeouts: RTT samples taken per host, the multiple of the slowest sample used as the
+// port timeout, the default lower bound, and the smallest lower bound accepted
+pub const RTT_SAMPLES: usize = 3;
+pub const RTT_TIMEOUT_MULTIPLIER: u32 = 4;
+pub const ADAPTIVE_MIN_TIMEOUT_MS: u64 = 50;
+pub const ADAPTIVE_TIMEOUT_FLOOR_MS: u64 = 10;
+
+// Fast filter: timeout for the sentinel sweep of TCP_PING_PORTS, also used as the port
+// timeout for hosts whose sentinels were all filtered
+pub const FAST_FILTER_TIMEOUT_MS: u64 = 100;
+
+// Retries of timed-out port probes: maximum count, base backoff and random jitter added to each delay
+pub const MAX_PROBE_RETRIES: u8 = 10;
+pub const PROBE_RETRY_BASE_DELAY_MS: u64 = 50;
+pub const PROBE_RETRY_JITTER_MS: u64 = 50;
+
+// NVD API rate limits: requests allowed per rolling window, with and without an API key
+pub const NVD_RATE_WINDOW_SECS: u64 = 30;
+pub const NVD_REQUESTS_PER_WINDOW: u32 = 5;
+pub const NVD_REQUESTS_PER_WINDOW_WITH_KEY: u32 = 50;
+pub const NVD_MAX_ATTEMPTS: u32 = 3;
+pub const NVD_RETRY_BASE_DELAY_MS: u64 = 2000;
+
+// CVEs referenced by one banner that are looked up at the same time
+pub const CVE_LOOKUP_CONCURRENCY: usize = 4;
+
+// Exploit-DB search endpoint (returns JSON for XHR requests) and exploit page base URL
+pub const EXPLOIT_DB_SEARCH_URL: &str = "https://www.exploit-db.com/search";
+pub const EXPLOIT_DB_EXPLOIT_URL: &str = "https://www.exploit-db.com/exploits";
+
+// NVD CVE API 2.0 endpoint and the page size used for CPE queries (NVD allows up to 2000)
+pub const NVD_CVE_API_URL: &str = "https://services.nvd.nist.gov/rest/json/cves/2.0";
+pub const NVD_CPE_RESULTS_PER_PAGE: usize = 2000;
+
+// Banner products from `extract_product_versions` and their CPE 2.3 (vendor, product)
+pub const CPE_PRODUCTS: &[(&str, &str, &str)] = &[
+    ("apache_http_server", "apache", "http_server"),
+    ("nginx", "f5", "nginx"),
+    ("openssh", "openbsd", "openssh"),
+    ("iis", "microsoft", "internet_information_services"),
+    ("mysql", "oracle", "mysql"),
+    ("mariadb", "mariadb", "mariadb"),
+    ("exim", "exim", "exim"),
+    ("tomcat", "apache", "tomcat"),
+    ("php", "php", "php"),
+];
+
+// OSV.dev API base URL
+pub const OSV_API_URL: &str = "https://api.osv.dev/v1";
+
+// GitHub API base URL for security advisory (GHSA) lookups
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+// Banner products and the OSV package (name, ecosystem) to query for them; an empty
+// ecosystem queries the package name across all ecosystems
+pub const OSV_PACKAGES: &[(&str, &str, &str)] = &[
+    ("apache_http_server", "apache-httpd", ""),
+    ("nginx", "nginx", ""),
+    ("openssh", "openssh", ""),
+];
+
+// Shodan InternetDB (free, no API key) host lookup URL
+pub const SHODAN_INTERNETDB_URL: &str = "https://internetdb.shodan.io";
+
+// FIRST EPSS API; several CVEs can be queried per request as a comma-separated list
+pub const EPSS_API_URL: &str = "https://api.first.org/data/v1/epss";
+pub const EPSS_BATCH_SIZE: usize = 100;
+
+// Share of a vulnerability's risk weight taken from its EPSS probability rather than its severity
+pub const EPSS_RISK_WEIGHT: f32 = 0.5;
+
+// Number of highest-risk hosts averaged into the network-wide risk score
+pub const NETWORK_RISK_WORST_HOSTS: usize = 3;
+
+// Days before entries in the on-disk CVE cache are considered stale
+pub const DEFAULT_CVE_CACHE_TTL_DAYS: u64 = 7;
+
+// Ports probed by TCP ping when ICMP is unavailable
+pub const TCP_PING_PORTS: [u16; 7] = [80, 443, 22, 445, 3389, 8080, 23];
+
+// SRV services looked up for domain targets with --enum-srv
+pub const SRV_SERVICES: &[&str] = &[
+    "_sip._tcp", "_sip._udp", "_sips._tcp",
+    "_ldap._tcp", "_ldaps._tcp", "_gc._tcp",
+    "_kerberos._tcp", "_kerberos._udp", "_kpasswd._tcp",
+    "_xmpp-client._tcp", "_xmpp-server._tcp",
+    "_imap._tcp", "_imaps._tcp", "_pop3._tcp", "_pop3s._tcp", "_submission._tcp",
+    "_autodiscover._tcp", "_caldav._tcp", "_caldavs._tcp", "_carddav._tcp", "_carddavs._tcp",
+    "_h323cs._tcp", "_vlmcs._tcp", "_minecraft._tcp",
+];
+
+// Port scanned on mail exchangers found through MX records
+pub const MX_SMTP_PORT: u16 = 25;
+
+// Out-of-scope targets named in the error that aborts a scan
+pub const SCOPE_VIOLATIONS_LISTED: usize = 10;
+
+// Timeout for posting findings to a notification webhook
+pub const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+// Default length of a passive capture on an interface
+pub const PASSIVE_CAPTURE_SECS: u64 = 60;
+
+// Read timeout on the capture socket, so the capture can check its deadline and Ctrl-C
+pub const PASSIVE_READ_TIMEOUT_MS: u64 = 200;
+
+// Bytes of captured server data kept as a banner
+pub const PASSIVE_BANNER_MAX_LEN: usize = 1024;
+
+// How long to keep listening for ARP replies after the last request is sent
+pub const ARP_REPLY_WAIT_MS: u64 = 1000;
+
+// Read timeout on the raw socket, so reply collection can check its deadline
+pub const ARP_READ_TIMEOUT_MS: u64 = 100;
+
+// Domains whose PTR names are auto-generated by cloud and hosting providers,
+// passed over for a specific name when an address has several
+pub const GENERIC_PTR_SUFFIXES: &[&str] = &[
+    ".compute.amazonaws.com",
+    ".compute-1.amazonaws.com",
+    ".compute.internal",
+    ".bc.googleusercontent.com",
+    ".cloudapp.azure.com",
+    ".cloudapp.net",
+    ".linodeusercontent.com",
+    ".vultrusercontent.com",
+    ".your-server.de",
+];
+
+// IEEE OUI prefixes (first three MAC octets) and the vendors they are assigned to,
+// trimmed to industrial control, network and virtualization vendors
+pub const OUI_VENDORS: &[(&str, &str)] = &[
+    // Industrial automation and OT
+    ("00000A", "Omron"),
+    ("000054", "Schneider Electric"),
+    ("000064", "Yokogawa"),
+    ("0000BC", "Rockwell Automation"),
+    ("000105", "Beckhoff"),
+    ("000E8C", "Siemens"),
+    ("001B1B", "Siemens"),
+    ("001C06", "Siemens"),
+    ("001D9C", "Rockwell Automation"),
+    ("002692", "Mitsubishi Electric"),
+    ("0030A7", "Schweitzer Engineering Laboratories"),
+    ("0030DE", "WAGO"),
+    ("004084", "Honeywell"),
+    ("006065", "B&R Industrial Automation"),
+    ("008063", "Hirschmann"),
+    ("0080F4", "Schneider Electric"),
+    ("0090E8", "Moxa"),
+    ("00A045", "Phoenix Contact"),
+    ("080006", "Siemens"),
+    ("5C8816", "Rockwell Automation"),
+    ("F45433", "Rockwell Automation"),
+    // Network and security appliances
+    ("00000C", "Cisco"),
+    ("0004F2", "Polycom"),
+    ("000585", "Juniper Networks"),
+    ("00090F", "Fortinet"),
+    ("00095B", "Netgear"),
+    ("000B86", "Aruba Networks"),
+    ("00146C", "Netgear"),
+    ("00180A", "Cisco Meraki"),
+    ("001B17", "Palo Alto Networks"),
+    ("002722", "Ubiquiti"),
+    ("0418D6", "Ubiquiti"),
+    ("24A43C", "Ubiquiti"),
+    ("50C7BF", "TP-Link"),
+    ("F4F26D", "TP-Link"),
+    // Cameras, printers and storage
+    ("0000AA", "Xerox"),
+    ("001132", "Synology"),
+    ("00408C", "Axis Communications"),
+    ("008077", "Brother"),
+    ("4419B6", "Hikvision"),
+    // Computers, servers and NICs
+    ("000393", "Apple"),
+    ("001422", "Dell"),
+    ("0017F2", "Apple"),
+    ("001A11", "Google"),
+    ("001B21", "Intel"),
+    ("002590", "Super Micro Computer"),
+    ("00E04C", "Realtek"),
+    ("3C5AB4", "Google"),
+    ("B827EB", "Raspberry Pi"),
+    ("DCA632", "Raspberry Pi"),
+    ("E45F01", "Raspberry Pi"),
+    // Virtual machines
+    ("000569", "VMware"),
+    ("000C29", "VMware"),
+    ("00155D", "Microsoft Hyper-V"),
+    ("005056", "VMware"),
+    ("080027", "VirtualBox"),
+    ("525400", "QEMU/KVM"),
+];
+
+// TCP ports ranked by how often they are found open on the internet (nmap top 100)
+pub const TOP_PORTS: [u16; 100] = [
+    80, 23, 443, 21, 22, 25, 3389, 110, 445, 139,
+    143, 53, 135, 3306, 8080, 1723, 111, 995, 993, 5900,
+    1025, 587, 8888, 199, 1720, 465, 548, 113, 81, 6001,
+    10000, 514, 5060, 179, 1026, 2000, 8443, 8000, 32768, 554,
+    26, 1433, 49152, 2001, 515, 8008, 49154, 1027, 5666, 646,
+    5000, 5631, 631, 49153, 8081, 2049, 88, 79, 5800, 106,
+    2121, 1110, 49155, 6000, 513, 990, 5357, 427, 49156, 543,
+    544, 5101, 144, 7, 389, 8009, 3128, 444, 9999, 5009,
+    7070, 5190, 3000, 5432, 1900, 3986, 13, 1029, 9, 5051,
+    6646, 49157, 1028, 873, 1755, 2717, 4899, 9100, 119, 37,
+];
+
+// Attempts at drawing a public address before generate_random_ipv4 remaps the first octet
+pub const RANDOM_IPV4_MAX_ATTEMPTS: usize = 32;
+
+// Ports whose services speak TLS from the first byte
+pub const TLS_PORTS: [u16; 12] = [443, 465, 636, 853, 989, 990, 993, 995, 3269, 5986, 8443, 8883];
+
+// Shortest IPv6 CIDR prefix that will be expanded (a /112 is 65,536 addresses)
+pub const MIN_IPV6_PREFIX_LEN: u8 = 112;
+
+// Maximum number of addresses generated from an IP range
+pub const MAX_RANGE_SIZE: u128 = 65535;
+
+// Async backend tuning: worker threads, maximum simultaneous connections and hosts in flight
+pub const ASYNC_WORKER_THREADS: usize = 4;
+pub const ASYNC_MAX_CONNECTIONS: usize = 1000;
+pub const ASYNC_MAX_HOSTS: usize = 256;
+
+// Paths checked for web admin login forms during default credential checks
+pub const WEB_LOGIN_PATHS: [&str; 7] = [
+    "/",
+    "/login",
+    "/admin",
+    "/admin/login",
+    "/login.html",
+    "/admin/index.php",
+    "/user/login",
+];
+
 // MITRE ATT&CK Framework Mappings
 lazy_static::lazy_static! {
     pub static ref MITRE_ATTACK_MAPPINGS: HashMap<String, Vec<String>> = {
@@ -29,11 +286,192 @@ lazy_static::lazy_static! {
         m.insert("CWE-502".to_string(), vec!["T1195".to_string()]); // Deserialization
         m.insert("CWE-269".to_string(), vec!["T1068".to_string()]); // Improper Privilege Management
         m.insert("CWE-287".to_string(), vec!["T1110".to_string()]); // Authentication Issues
+        m.insert("CWE-20".to_string(), vec!["T1190".to_string()]); // Improper Input Validation
+        m.insert("CWE-74".to_string(), vec!["T1190".to_string()]); // Injection
+        m.insert("CWE-77".to_string(), vec!["T1059".to_string()]); // Command Injection
+        m.insert("CWE-917".to_string(), vec!["T1190".to_string(), "T1059".to_string()]); // Expression Language Injection
+        m.insert("CWE-611".to_string(), vec!["T1190".to_string()]); // XML External Entities
+        m.insert("CWE-918".to_string(), vec!["T1190".to_string()]); // Server-Side Request Forgery
+        m.insert("CWE-119".to_string(), vec!["T1190".to_string()]); // Memory Buffer Errors
+        m.insert("CWE-120".to_string(), vec!["T1190".to_string()]); // Classic Buffer Overflow
+        m.insert("CWE-125".to_string(), vec!["T1190".to_string()]); // Out-of-bounds Read
+        m.insert("CWE-787".to_string(), vec!["T1190".to_string()]); // Out-of-bounds Write
+        m.insert("CWE-416".to_string(), vec!["T1203".to_string()]); // Use After Free
+        m.insert("CWE-434".to_string(), vec!["T1505.003".to_string()]); // Unrestricted File Upload (web shells)
+        m.insert("CWE-284".to_string(), vec!["T1078".to_string()]); // Improper Access Control
+        m.insert("CWE-862".to_string(), vec!["T1078".to_string()]); // Missing Authorization
+        m.insert("CWE-863".to_string(), vec!["T1078".to_string()]); // Incorrect Authorization
+        m.insert("CWE-798".to_string(), vec!["T1078.001".to_string()]); // Hard-coded Credentials
+        m.insert("CWE-521".to_string(), vec!["T1110".to_string()]); // Weak Password Requirements
+        m.insert("CWE-307".to_string(), vec!["T1110".to_string()]); // No Limit on Authentication Attempts
+        m.insert("CWE-522".to_string(), vec!["T1552".to_string()]); // Insufficiently Protected Credentials
+        m.insert("CWE-312".to_string(), vec!["T1552".to_string()]); // Cleartext Storage of Sensitive Information
+        m.insert("CWE-319".to_string(), vec!["T1040".to_string()]); // Cleartext Transmission
+        m.insert("CWE-295".to_string(), vec!["T1557".to_string()]); // Improper Certificate Validation
+        m.insert("CWE-327".to_string(), vec!["T1557".to_string()]); // Broken or Risky Cryptography
+        m.insert("CWE-59".to_string(), vec!["T1068".to_string()]); // Link Following
+        m.insert("CWE-732".to_string(), vec!["T1068".to_string()]); // Incorrect Permission Assignment
+        m.insert("CWE-426".to_string(), vec!["T1574".to_string()]); // Untrusted Search Path
+        m.insert("CWE-427".to_string(), vec!["T1574".to_string()]); // Uncontrolled Search Path Element
+        m.insert("CWE-400".to_string(), vec!["T1499".to_string()]); // Uncontrolled Resource Consumption
+        m.insert("CWE-770".to_string(), vec!["T1499".to_string()]); // Allocation Without Limits
+        m.insert("CWE-601".to_string(), vec!["T1566".to_string()]); // Open Redirect
         
         m
     };
 }
 
+// Tactics each ATT&CK technique in `MITRE_ATTACK_MAPPINGS` serves; sub-techniques use their parent's entry
+pub const MITRE_TECHNIQUE_TACTICS: &[(&str, &[&str])] = &[
+    ("T1040", &["Credential Access", "Discovery"]),
+    ("T1059", &["Execution"]),
+    ("T1068", &["Privilege Escalation"]),
+    ("T1078", &["Initial Access", "Persistence", "Privilege Escalation", "Defense Evasion"]),
+    ("T1083", &["Discovery"]),
+    ("T1110", &["Credential Access"]),
+    ("T1190", &["Initial Access"]),
+    ("T1195", &["Initial Access"]),
+    ("T1203", &["Execution"]),
+    ("T1499", &["Impact"]),
+    ("T1505", &["Persistence"]),
+    ("T1552", &["Credential Access"]),
+    ("T1557", &["Credential Access", "Collection"]),
+    ("T1566", &["Initial Access"]),
+    ("T1574", &["Persistence", "Privilege Escalation", "Defense Evasion"]),
+];
+
+// Confidence in a service identification, by how it was made
+pub const SERVICE_CONFIDENCE_PROBE: f32 = 1.0;             // A protocol handshake succeeded
+pub const SERVICE_CONFIDENCE_BANNER_CONFIRMED: f32 = 0.95; // The banner matches the port's usual service
+pub const SERVICE_CONFIDENCE_BANNER: f32 = 0.85;           // The banner names a service other than the port's
+pub const SERVICE_CONFIDENCE_PORT: f32 = 0.5;              // Port number only, no banner
+pub const SERVICE_CONFIDENCE_PORT_UNRECOGNISED: f32 = 0.3; // Port number only, banner not recognised
+
+// Most of an HTTP response body read when fingerprinting web services
+pub const HTTP_MAX_BODY_BYTES: u64 = 256 * 1024;
+
+// HTTP methods flagged when a web server advertises them in its Allow header;
+// PUT and DELETE can modify content, the rest aid proxying and request tracing
+pub const DANGEROUS_HTTP_METHODS: &[&str] = &["PUT", "DELETE", "CONNECT", "TRACE", "TRACK"];
+
+// Largest SMB message accepted from a server during negotiation
+pub const SMB_MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+// Identification string sent when reading an SSH server's key exchange offer
+pub const SSH_CLIENT_VERSION: &str = "SSH-2.0-Rust-Scanner_1.0";
+
+// Lines an SSH server may send before its identification string, and the longest line read
+pub const SSH_MAX_PREAMBLE_LINES: usize = 20;
+pub const SSH_MAX_LINE_BYTES: u64 = 255;
+
+// Largest SSH packet accepted while reading the key exchange offer
+pub const SSH_MAX_PACKET_BYTES: usize = 35000;
+
+// Weak SSH algorithms, matched as substrings of the names a server offers: SHA-1 and
+// 1024-bit key exchange, DSA host keys, RC4, CBC-mode and 64-bit block ciphers, and MD5,
+// SHA-1 and 64-bit MACs
+pub const WEAK_SSH_KEX: &[&str] = &["group1-sha1", "group14-sha1", "group-exchange-sha1", "gss-gex-sha1", "rsa1024-sha1"];
+pub const WEAK_SSH_HOST_KEYS: &[&str] = &["ssh-dss"];
+pub const WEAK_SSH_CIPHERS: &[&str] = &["arcfour", "-cbc", "des-", "blowfish", "cast128", "none"];
+pub const WEAK_SSH_MACS: &[&str] = &["hmac-md5", "hmac-sha1", "umac-64", "none"];
+
+// Default ports of databases checked for access without credentials
+pub const REDIS_PORT: u16 = 6379;
+pub const MONGODB_PORT: u16 = 27017;
+pub const ELASTICSEARCH_PORT: u16 = 9200;
+
+// Largest MongoDB reply accepted when listing databases
+pub const MONGODB_MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+
+// Databases or indices named in an unauthenticated-access finding before the rest are counted
+pub const EXPOSED_DATABASE_LISTED_NAMES: usize = 10;
+
+// HTTP/2 client connection preface, sent before the first SETTINGS frame
+pub const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+// Largest HTTP/2 frame payload accepted, the protocol's default SETTINGS_MAX_FRAME_SIZE
+pub const HTTP2_MAX_FRAME_BYTES: usize = 16 * 1024;
+
+// Most gRPC response data read while listing services
+pub const GRPC_MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+// Ports conventionally used by gRPC services, probed for HTTP/2 even when they sent a banner
+pub const GRPC_PORTS: [u16; 3] = [50051, 50052, 50053];
+
+// gRPC server reflection methods, tried in order until one answers
+pub const GRPC_REFLECTION_PATHS: [&str; 2] = [
+    "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+    "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+
+// SMB2/3 dialects offered when negotiating, oldest first
+pub const SMB2_DIALECTS: [(u16, &str); 5] = [
+    (0x0202, "SMB 2.0.2"),
+    (0x0210, "SMB 2.1"),
+    (0x0300, "SMB 3.0"),
+    (0x0302, "SMB 3.0.2"),
+    (0x0311, "SMB 3.1.1"),
+];
+
+// SNMP agent port and the OID queried to identify the device (sysDescr.0)
+pub const SNMP_PORT: u16 = 161;
+pub const SNMP_SYSDESCR_OID: &str = "1.3.6.1.2.1.1.1.0";
+
+// IKE (ISAKMP) port, probed over UDP for IPsec VPN endpoints
+pub const IKE_PORT: u16 = 500;
+
+// IKEv1 phase 1 transforms offered one at a time, all with pre-shared key auth,
+// as (encryption, key length, hash, DH group) attribute values, strongest first
+pub const IKE_TRANSFORMS: [(u16, u16, u16, u16); 7] = [
+    (7, 256, 4, 14), // AES-256/SHA2-256/group 14
+    (7, 128, 2, 14), // AES-128/SHA1/group 14
+    (7, 128, 2, 2),  // AES-128/SHA1/group 2
+    (5, 0, 2, 2),    // 3DES/SHA1/group 2
+    (5, 0, 1, 2),    // 3DES/MD5/group 2
+    (1, 0, 2, 1),    // DES/SHA1/group 1
+    (1, 0, 1, 1),    // DES/MD5/group 1
+];
+
+// DH group of the transforms offered in an aggressive-mode probe, whose key exchange is sent up front
+pub const IKE_AGGRESSIVE_DH_GROUP: u16 = 2;
+
+// Well-known IKE vendor ID prefixes (hex) and the product or feature they reveal
+pub const IKE_VENDOR_IDS: [(&str, &str); 8] = [
+    ("4a131c81070358455c5728f20e95452f", "RFC 3947 NAT-T"),
+    ("90cb80913ebb696e086381b5ec427b1f", "NAT-T draft 02"),
+    ("afcad71368a1f1c96b8696fc77570100", "Dead Peer Detection"),
+    ("12f5f28c457168a9702d9fe274cc0100", "Cisco Unity"),
+    ("4048b7d56ebce88525e7de7f00d6c2d3", "Cisco IKE Fragmentation"),
+    ("09002689dfd6b712", "XAUTH"),
+    ("1e2b516905991c7d7c96fcbfb587e461", "Microsoft Windows"),
+    ("f4ed19e0c114eb516faaac0ee37daf2807b4381f", "Check Point"),
+];
+
+// Web technology fingerprints
+lazy_static::lazy_static! {
+    // Format: (technology, where to look ("header" or "body"), pattern with an optional version capture).
+    // Header patterns are matched against each "name: value" line with lowercase names.
+    pub static ref HTTP_TECHNOLOGY_SIGNATURES: Vec<(&'static str, &'static str, Regex)> = vec![
+        ("Apache", "header", Regex::new(r"(?i)^server: Apache(?:/(\d+\.\d+\.\d+))?").unwrap()),
+        ("nginx", "header", Regex::new(r"(?i)^server: nginx(?:/(\d+\.\d+\.\d+))?").unwrap()),
+        ("Microsoft-IIS", "header", Regex::new(r"(?i)^server: Microsoft-IIS(?:/(\d+\.\d+))?").unwrap()),
+        ("PHP", "header", Regex::new(r"(?i)^x-powered-by: PHP(?:/(\d+\.\d+\.\d+))?").unwrap()),
+        ("ASP.NET", "header", Regex::new(r"(?i)^x-aspnet-version: (\d+\.\d+(?:\.\d+)*)").unwrap()),
+        ("ASP.NET", "header", Regex::new(r"(?i)^x-powered-by: ASP\.NET").unwrap()),
+        ("Express", "header", Regex::new(r"(?i)^x-powered-by: Express").unwrap()),
+        ("Jenkins", "header", Regex::new(r"(?i)^x-jenkins: (\d+(?:\.\d+)*)").unwrap()),
+        ("Drupal", "header", Regex::new(r"(?i)^x-generator: Drupal(?: (\d+))?").unwrap()),
+        ("WordPress", "body", Regex::new(r#"(?i)<meta name="generator" content="WordPress ?(\d+(?:\.\d+)*)?"#).unwrap()),
+        ("WordPress", "body", Regex::new(r"(?i)/wp-(?:content|includes)/").unwrap()),
+        ("Joomla", "body", Regex::new(r#"(?i)<meta name="generator" content="Joomla!? ?(\d+(?:\.\d+)*)?"#).unwrap()),
+        ("Apache Tomcat", "body", Regex::new(r"(?i)Apache Tomcat(?:/(\d+\.\d+\.\d+))?").unwrap()),
+        ("Jenkins", "body", Regex::new(r"(?i)<title>[^<]*Jenkins").unwrap()),
+        ("Grafana", "body", Regex::new(r"(?i)<title>Grafana</title>").unwrap()),
+        ("GitLab", "body", Regex::new(r"(?i)<meta content=.GitLab. property=.og:site_name.").unwrap()),
+        ("phpMyAdmin", "body", Regex::new(r"(?i)<title>phpMyAdmin").unwrap()),
+    ];
+}
+
 // Define service probing templates
 lazy_static::lazy_static! {
     pub static ref SERVICE_PROBES: HashMap<u16, Vec<u8>> = {
@@ -381,6 +819,15 @@ lazy_static::lazy_static! {
             "Change default SNMP community strings and restrict access to authorized hosts".to_string()
         ));
         
+        // IKE/IPsec misconfigurations, found by probing 500/udp rather than from a banner
+        m.push((
+            "ike",
+            Regex::new(r"(?i)aggressive mode|\b3?des\b|\bmd5\b").unwrap(),
+            "MISCONFIG-IKE-WEAK-TRANSFORM".to_string(),
+            "IPsec VPN accepts aggressive mode or weak IKE transforms".to_string(),
+            "Disable IKEv1 aggressive mode and DES/3DES, MD5 and DH groups 1, 2 and 5; prefer IKEv2 with AES and DH group 14 or stronger".to_string()
+        ));
+        
         m
     };
 
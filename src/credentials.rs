@@ -0,0 +1,402 @@
+// Author: CyberCraft Alchemist
+// Default credential checks for discovered services
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use rand::{thread_rng, Rng};
+use rand::distributions::Alphanumeric;
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::redirect::Policy;
+
+use crate::constants::{DEFAULT_CREDENTIALS, SNMP_SYSDESCR_OID, WEB_LOGIN_PATHS};
+use crate::cveapi;
+use crate::models::Vulnerability;
+use crate::proxy;
+use crate::utils;
+
+// Each credential pair is submitted at most this many times to avoid account lockout
+const MAX_ATTEMPTS_PER_CREDENTIAL: usize = 1;
+
+// Phrases commonly shown by login pages after a failed attempt
+const LOGIN_FAILURE_MARKERS: [&str; 8] = [
+    "invalid", "incorrect", "failed", "denied", "wrong", "try again", "unauthorized", "error",
+];
+
+/// Structure describing an HTML login form discovered on a web service
+#[derive(Debug, Clone)]
+pub struct LoginForm {
+    pub url: String,
+    pub method: String,
+    pub username_field: String,
+    pub password_field: String,
+    pub hidden_fields: Vec<(String, String)>,
+}
+
+/// Check a web service for an admin login form that accepts default credentials
+pub fn check_web_login_credentials(ip: &IpAddr, port: u16, service: &str, timeout_ms: u64) -> Option<Vulnerability> {
+    let base_url = web_base_url(ip, port, service);
+
+    // Redirects are not followed so that post-login redirects can be inspected
+    let client = proxy::client_builder()
+        .timeout(Duration::from_millis(timeout_ms.max(1000)))
+        .redirect(Policy::none())
+        .danger_accept_invalid_certs(true)
+        .build()
+        .ok()?;
+
+    let form = find_login_form(&client, &base_url)?;
+
+    // Submit a random credential first; if that also looks successful the
+    // heuristics cannot tell success from failure on this panel
+    if submit_login(&client, &form, &random_string(12), &random_string(16)) != Some(false) {
+        return None;
+    }
+
+    for (username, password) in web_credentials_for_port(port) {
+        for _ in 0..MAX_ATTEMPTS_PER_CREDENTIAL {
+            match submit_login(&client, &form, username, password) {
+                Some(true) => return Some(create_default_creds_vulnerability(&form.url, username, password)),
+                Some(false) => break,
+                None => continue,
+            }
+        }
+    }
+
+    None
+}
+
+/// Fetch the common admin paths and return the first login form found
+pub fn find_login_form(client: &Client, base_url: &str) -> Option<LoginForm> {
+    for path in WEB_LOGIN_PATHS.iter() {
+        let page_url = format!("{}{}", base_url, path);
+
+        let response = match client.get(&page_url).send() {
+            Ok(resp) if resp.status().is_success() => resp,
+            _ => continue,
+        };
+
+        if let Ok(body) = response.text() {
+            if let Some(form) = parse_login_form(&body, base_url, path) {
+                return Some(form);
+            }
+        }
+    }
+
+    None
+}
+
+/// Parse an HTML page and extract the first form containing a password field
+pub fn parse_login_form(html: &str, base_url: &str, page_path: &str) -> Option<LoginForm> {
+    let form_regex = Regex::new(r#"(?is)<form\b([^>]*)>(.*?)</form>"#).ok()?;
+    let input_regex = Regex::new(r#"(?is)<input\b[^>]*>"#).ok()?;
+
+    for form_caps in form_regex.captures_iter(html) {
+        let attributes = form_caps.get(1).map_or("", |m| m.as_str());
+        let contents = form_caps.get(2).map_or("", |m| m.as_str());
+
+        let mut username_field = None;
+        let mut password_field = None;
+        let mut hidden_fields = Vec::new();
+
+        for input in input_regex.find_iter(contents) {
+            let input = input.as_str();
+            let name = match html_attribute(input, "name") {
+                Some(name) => name,
+                None => continue,
+            };
+            let input_type = html_attribute(input, "type")
+                .unwrap_or_else(|| "text".to_string())
+                .to_lowercase();
+
+            match input_type.as_str() {
+                "password" if password_field.is_none() => password_field = Some(name),
+                "hidden" => hidden_fields.push((name, html_attribute(input, "value").unwrap_or_default())),
+                "text" | "email" if username_field.is_none() => username_field = Some(name),
+                _ => {}
+            }
+        }
+
+        if let (Some(username_field), Some(password_field)) = (username_field, password_field) {
+            let action = html_attribute(attributes, "action").unwrap_or_default();
+            let method = html_attribute(attributes, "method")
+                .unwrap_or_else(|| "POST".to_string())
+                .to_uppercase();
+
+            return Some(LoginForm {
+                url: resolve_form_action(base_url, page_path, &action),
+                method,
+                username_field,
+                password_field,
+                hidden_fields,
+            });
+        }
+    }
+
+    None
+}
+
+/// Submit a credential pair to a login form
+///
+/// Returns `Some(true)` if the login appears to succeed, `Some(false)` if it
+/// appears to fail and `None` if no usable response was received.
+fn submit_login(client: &Client, form: &LoginForm, username: &str, password: &str) -> Option<bool> {
+    let mut fields: Vec<(String, String)> = form.hidden_fields.clone();
+    fields.push((form.username_field.clone(), username.to_string()));
+    fields.push((form.password_field.clone(), password.to_string()));
+
+    let request = if form.method == "GET" {
+        client.get(&form.url).query(&fields)
+    } else {
+        client.post(&form.url).form(&fields)
+    };
+
+    let response = request.send().ok()?;
+    let status = response.status();
+    let location = response.headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|l| l.to_str().ok())
+        .map(|l| l.to_string());
+    let body = response.text().unwrap_or_default();
+
+    Some(is_login_successful(status.as_u16(), location.as_deref(), &body))
+}
+
+/// Decide whether a login response indicates success using status, redirect and content heuristics
+pub fn is_login_successful(status: u16, location: Option<&str>, body: &str) -> bool {
+    match status {
+        // Redirects away from the login page usually indicate a new session
+        300..=399 => match location {
+            Some(location) => {
+                let location = location.to_lowercase();
+                !["login", "signin", "logon", "error", "fail", "denied"]
+                    .iter()
+                    .any(|marker| location.contains(marker))
+            },
+            None => false,
+        },
+        // A page without a password field or failure message is treated as logged in
+        200..=299 => {
+            let body = body.to_lowercase();
+            !body.contains("type=\"password\"")
+                && !body.contains("type='password'")
+                && !body.contains("type=password")
+                && !LOGIN_FAILURE_MARKERS.iter().any(|marker| body.contains(marker))
+        },
+        _ => false,
+    }
+}
+
+/// Get the default HTTP credential pairs for a port, falling back to all HTTP pairs
+pub(crate) fn web_credentials_for_port(port: u16) -> Vec<(&'static str, &'static str)> {
+    let for_port: Vec<(&'static str, &'static str)> = DEFAULT_CREDENTIALS.iter()
+        .filter(|(service, default_port, _, _)| *service == "http" && *default_port == port)
+        .map(|(_, _, username, password)| (*username, *password))
+        .collect();
+
+    if !for_port.is_empty() {
+        return for_port;
+    }
+
+    let mut all_http: Vec<(&'static str, &'static str)> = DEFAULT_CREDENTIALS.iter()
+        .filter(|(service, _, _, _)| *service == "http")
+        .map(|(_, _, username, password)| (*username, *password))
+        .collect();
+    all_http.sort();
+    all_http.dedup();
+    all_http
+}
+
+/// Build the finding reported when a web panel accepts default credentials
+fn create_default_creds_vulnerability(url: &str, username: &str, password: &str) -> Vulnerability {
+    cveapi::create_full_vulnerability(
+        "DEFAULT-CREDS-WEB".to_string(),
+        format!("Web admin login at {} accepts default credentials {}:{}", url, username, password),
+        Some("CRITICAL".to_string()),
+        Some(9.8),
+        Some(vec!["https://cwe.mitre.org/data/definitions/1392.html".to_string()]),
+        Some(false),
+        Some(true),
+        Some("Change the default credentials on the web administration interface".to_string()),
+        Some("Authentication".to_string()),
+        Some("CWE-1392".to_string()), // Use of Default Credentials
+        Some("Web".to_string()),
+        Some(vec!["Initial Access".to_string(), "Persistence".to_string()]),
+        Some(vec!["T1078.001".to_string()]), // Valid Accounts: Default Accounts
+    )
+}
+
+/// Get the default credential pairs listed for a service
+pub fn credentials_for_service(service: &str) -> Vec<(&'static str, &'static str)> {
+    DEFAULT_CREDENTIALS.iter()
+        .filter(|(name, _, _, _)| *name == service)
+        .map(|(_, _, username, password)| (*username, *password))
+        .collect()
+}
+
+/// Try default credentials against an FTP server using USER/PASS
+pub fn check_ftp_credentials(ip: &IpAddr, port: u16, credentials: &[(&str, &str)], timeout_ms: u64) -> Option<Vulnerability> {
+    for (username, password) in credentials {
+        if try_ftp_login(ip, port, username, password, timeout_ms) == Some(true) {
+            return Some(create_service_creds_vulnerability("ftp", &format!("ftp://{}", SocketAddr::new(*ip, port)), username));
+        }
+    }
+    
+    None
+}
+
+/// Attempt a single FTP login, returning `None` if the server could not be reached
+fn try_ftp_login(ip: &IpAddr, port: u16, username: &str, password: &str, timeout_ms: u64) -> Option<bool> {
+    let timeout = Duration::from_millis(timeout_ms);
+    
+    utils::wait_for_connection_slot();
+    let stream = proxy::connect(&SocketAddr::new(*ip, port), timeout).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    stream.set_write_timeout(Some(timeout)).ok()?;
+    let mut writer = stream.try_clone().ok()?;
+    let mut reader = BufReader::new(stream);
+    
+    if read_ftp_reply(&mut reader)? != 220 {
+        return None;
+    }
+    
+    writer.write_all(format!("USER {}\r\n", username).as_bytes()).ok()?;
+    let reply = match read_ftp_reply(&mut reader)? {
+        230 => return Some(true), // Logged in without a password
+        331 => {
+            writer.write_all(format!("PASS {}\r\n", password).as_bytes()).ok()?;
+            read_ftp_reply(&mut reader)?
+        },
+        _ => return Some(false),
+    };
+    
+    let _ = writer.write_all(b"QUIT\r\n");
+    Some(reply == 230)
+}
+
+/// Read a possibly multi-line FTP reply and return its status code
+fn read_ftp_reply<R: BufRead>(reader: &mut R) -> Option<u16> {
+    let mut line = String::new();
+    
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        
+        // The last line of a reply is "<code> <text>", continuation lines use "<code>-"
+        let code = line.get(..3)?.parse::<u16>().ok();
+        if code.is_some() && line.as_bytes().get(3) != Some(&b'-') {
+            return code;
+        }
+    }
+}
+
+/// Try default community strings against an SNMP agent with a v2c GetRequest for sysDescr
+pub fn check_snmp_communities(ip: &IpAddr, port: u16, communities: &[&str], timeout_ms: u64) -> Option<Vulnerability> {
+    let (community, _) = find_snmp_community(ip, port, communities, timeout_ms)?;
+    Some(create_service_creds_vulnerability("snmp", &format!("snmp://{}", SocketAddr::new(*ip, port)), &community))
+}
+
+/// Return the first community string an SNMP agent answers to, with the sysDescr it returned
+pub fn find_snmp_community(ip: &IpAddr, port: u16, communities: &[&str], timeout_ms: u64) -> Option<(String, String)> {
+    communities.iter().find_map(|community| {
+        utils::wait_for_connection_slot();
+        utils::snmp_get_on_port(ip, port, community, SNMP_SYSDESCR_OID, timeout_ms)
+            .map(|sys_descr| (community.to_string(), sys_descr))
+    })
+}
+
+/// Try default credentials against an SSH server using password authentication
+#[cfg(feature = "ssh")]
+pub fn check_ssh_credentials(ip: &IpAddr, port: u16, credentials: &[(&str, &str)], timeout_ms: u64) -> Option<Vulnerability> {
+    for (username, password) in credentials {
+        utils::wait_for_connection_slot();
+        let stream = proxy::connect(&SocketAddr::new(*ip, port), Duration::from_millis(timeout_ms)).ok()?;
+        
+        let mut session = ssh2::Session::new().ok()?;
+        session.set_timeout(timeout_ms as u32);
+        session.set_tcp_stream(stream);
+        session.handshake().ok()?;
+        
+        if session.userauth_password(username, password).is_ok() && session.authenticated() {
+            return Some(create_service_creds_vulnerability("ssh", &format!("ssh://{}", SocketAddr::new(*ip, port)), username));
+        }
+    }
+    
+    None
+}
+
+/// Build the finding reported when a network service accepts default credentials
+fn create_service_creds_vulnerability(service: &str, target: &str, username: &str) -> Vulnerability {
+    let attack_vector = if service == "http" { "Web" } else { "Network" };
+    let account = if service == "snmp" { "community string" } else { "user" };
+    
+    cveapi::create_full_vulnerability(
+        format!("DEFAULT-CREDS-{}", service.to_uppercase()),
+        format!("{} service at {} accepts default credentials for {} '{}'", service.to_uppercase(), target, account, username),
+        Some("CRITICAL".to_string()),
+        Some(9.8),
+        Some(vec!["https://cwe.mitre.org/data/definitions/1392.html".to_string()]),
+        Some(false),
+        Some(true),
+        Some(format!("Change or disable the default {} '{}' on the {} service", account, username, service.to_uppercase())),
+        Some("Authentication".to_string()),
+        Some("CWE-1392".to_string()), // Use of Default Credentials
+        Some(attack_vector.to_string()),
+        Some(vec!["Initial Access".to_string(), "Persistence".to_string()]),
+        Some(vec!["T1078.001".to_string()]), // Valid Accounts: Default Accounts
+    )
+}
+
+/// Build the base URL for a web service
+pub(crate) fn web_base_url(ip: &IpAddr, port: u16, service: &str) -> String {
+    let scheme = if port == 443 || port == 8443 || service.to_lowercase().contains("https") {
+        "https"
+    } else {
+        "http"
+    };
+    match ip {
+        IpAddr::V4(_) => format!("{}://{}:{}", scheme, ip, port),
+        IpAddr::V6(_) => format!("{}://[{}]:{}", scheme, ip, port),
+    }
+}
+
+/// Extract an attribute value from an HTML tag
+fn html_attribute(tag: &str, attribute: &str) -> Option<String> {
+    let pattern = format!(r#"(?i)\b{}\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#, attribute);
+    let regex = Regex::new(&pattern).ok()?;
+    let caps = regex.captures(tag)?;
+
+    caps.get(1)
+        .or_else(|| caps.get(2))
+        .or_else(|| caps.get(3))
+        .map(|m| m.as_str().to_string())
+}
+
+/// Resolve a form action relative to the page it was found on
+fn resolve_form_action(base_url: &str, page_path: &str, action: &str) -> String {
+    if action.is_empty() {
+        format!("{}{}", base_url, page_path)
+    } else if action.starts_with("http://") || action.starts_with("https://") {
+        action.to_string()
+    } else if action.starts_with('/') {
+        format!("{}{}", base_url, action)
+    } else {
+        let directory = match page_path.rfind('/') {
+            Some(index) => &page_path[..=index],
+            None => "/",
+        };
+        format!("{}{}{}", base_url, directory, action)
+    }
+}
+
+/// Generate a random alphanumeric string for baseline login attempts
+fn random_string(length: usize) -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
@@ -1,10 +1,34 @@
 // Author: CyberCraft Alchemist
 // Attack path generation and analysis module for RustNetScan
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use crate::models::{Vulnerability, AttackPath, AttackStep};
 
+// Likelihood scoring weights. A vulnerability scores
+//   0.3 * CVSS / 10
+// + 0.4 if it is actively exploited
+// + 0.2 if a public exploit is available
+// + the CVSS attack-vector adjustment below,
+// clamped to 0.0-1.0. A path takes the score of its most likely vulnerability.
+const LIKELIHOOD_CVSS_WEIGHT: f32 = 0.3;
+const LIKELIHOOD_ACTIVELY_EXPLOITED_WEIGHT: f32 = 0.4;
+const LIKELIHOOD_EXPLOIT_AVAILABLE_WEIGHT: f32 = 0.2;
+const LIKELIHOOD_VECTOR_ADJUSTMENTS: [(&str, f32); 4] = [
+    ("AV:N", 0.1),   // Network
+    ("AV:A", 0.05),  // Adjacent network
+    ("AV:L", -0.05), // Local
+    ("AV:P", -0.1),  // Physical
+];
+
+// Scores at or above these thresholds are labelled High and Medium; the rest Low
+const LIKELIHOOD_HIGH_THRESHOLD: f32 = 0.6;
+const LIKELIHOOD_MEDIUM_THRESHOLD: f32 = 0.3;
+
 /// Generate attack paths based on discovered vulnerabilities
+///
+/// Output is deterministic: vulnerabilities within a path are ordered by CVSS
+/// score (highest first) then ID, and paths by likelihood score then entry point.
 pub fn generate_attack_paths(vulnerabilities: &[Vulnerability]) -> Vec<AttackPath> {
     let mut attack_paths = Vec::new();
     
@@ -15,6 +39,9 @@ pub fn generate_attack_paths(vulnerabilities: &[Vulnerability]) -> Vec<AttackPat
             categorized_vulns.entry(category.clone()).or_insert_with(Vec::new).push(vuln);
         }
     }
+    for vulns in categorized_vulns.values_mut() {
+        sort_by_priority(vulns);
+    }
     
     // Create paths for web vulnerabilities
     if let Some(web_vulns) = categorized_vulns.get("Web Application") {
@@ -77,11 +104,13 @@ pub fn generate_attack_paths(vulnerabilities: &[Vulnerability]) -> Vec<AttackPat
             }
             
             if !steps.is_empty() {
+                let score = likelihood_score(web_vulns);
                 attack_paths.push(AttackPath {
                     entry_point: "Web Application".to_string(),
                     steps,
                     impact: "Critical - Potential for data breach and system compromise".to_string(),
-                    likelihood: "High".to_string(),
+                    likelihood: likelihood_label(score).to_string(),
+                    likelihood_score: score,
                     mitigations: vec!["Apply security patches".to_string(), "Implement WAF".to_string(), "Use input validation".to_string()],
                 });
             }
@@ -136,11 +165,13 @@ pub fn generate_attack_paths(vulnerabilities: &[Vulnerability]) -> Vec<AttackPat
                 });
             }
             
+            let score = likelihood_score(ics_vulns);
             attack_paths.push(AttackPath {
                 entry_point: "Industrial Control System".to_string(),
                 steps,
                 impact: "Critical - Potential for physical damage or operational disruption".to_string(),
-                likelihood: "Medium".to_string(),
+                likelihood: likelihood_label(score).to_string(),
+                likelihood_score: score,
                 mitigations: vec!["Network segmentation".to_string(), "Access control".to_string(), "ICS-specific monitoring".to_string()],
             });
         }
@@ -181,19 +212,68 @@ pub fn generate_attack_paths(vulnerabilities: &[Vulnerability]) -> Vec<AttackPat
                 mitre_technique: Some("T1068".to_string()),
             });
             
+            let score = likelihood_score(remote_vulns);
             attack_paths.push(AttackPath {
                 entry_point: "Remote Service".to_string(),
                 steps,
                 impact: "High - Potential for system compromise and data theft".to_string(),
-                likelihood: "High".to_string(),
+                likelihood: likelihood_label(score).to_string(),
+                likelihood_score: score,
                 mitigations: vec!["Patch systems".to_string(), "Use strong authentication".to_string(), "Network segmentation".to_string()],
             });
         }
     }
     
+    attack_paths.sort_by(|a, b| {
+        b.likelihood_score.partial_cmp(&a.likelihood_score).unwrap_or(Ordering::Equal)
+            .then_with(|| a.entry_point.cmp(&b.entry_point))
+    });
     attack_paths
 }
 
+/// Order vulnerabilities by CVSS score (highest first), then ID
+fn sort_by_priority(vulnerabilities: &mut [&Vulnerability]) {
+    vulnerabilities.sort_by(|a, b| {
+        b.cvss_score.unwrap_or(0.0).partial_cmp(&a.cvss_score.unwrap_or(0.0)).unwrap_or(Ordering::Equal)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// Likelihood (0.0-1.0) that a single vulnerability is exploited, using the weights above
+pub fn vulnerability_likelihood(vuln: &Vulnerability) -> f32 {
+    let mut score = vuln.cvss_score.unwrap_or(0.0).clamp(0.0, 10.0) / 10.0 * LIKELIHOOD_CVSS_WEIGHT;
+    if vuln.actively_exploited == Some(true) {
+        score += LIKELIHOOD_ACTIVELY_EXPLOITED_WEIGHT;
+    }
+    if vuln.exploit_available == Some(true) {
+        score += LIKELIHOOD_EXPLOIT_AVAILABLE_WEIGHT;
+    }
+    if let Some(vector) = &vuln.cvss_vector {
+        if let Some((_, adjustment)) = LIKELIHOOD_VECTOR_ADJUSTMENTS.iter().find(|(metric, _)| vector.contains(metric)) {
+            score += adjustment;
+        }
+    }
+    score.clamp(0.0, 1.0)
+}
+
+/// Likelihood score of a path: that of its most likely vulnerability
+pub fn likelihood_score(vulnerabilities: &[&Vulnerability]) -> f32 {
+    vulnerabilities.iter()
+        .map(|v| vulnerability_likelihood(v))
+        .fold(0.0, f32::max)
+}
+
+/// Label for a likelihood score
+pub fn likelihood_label(score: f32) -> &'static str {
+    if score >= LIKELIHOOD_HIGH_THRESHOLD {
+        "High"
+    } else if score >= LIKELIHOOD_MEDIUM_THRESHOLD {
+        "Medium"
+    } else {
+        "Low"
+    }
+}
+
 /// Extract service type from vulnerability data
 pub fn extract_service_from_vulnerability(vuln: &Vulnerability) -> Option<String> {
     if let Some(attack_vector) = &vuln.attack_vector {
@@ -362,15 +442,16 @@ pub fn generate_data_exfiltration_path(vulnerabilities: &[Vulnerability]) -> Opt
     });
     
     if has_data_access {
-        let vuln_ids: Vec<String> = vulnerabilities.iter()
+        let mut matching: Vec<&Vulnerability> = vulnerabilities.iter()
             .filter(|v| {
                 v.description.contains("SQL") || 
                 v.description.contains("XSS") || 
                 v.description.contains("RCE") ||
                 v.description.contains("File Inclusion")
             })
-            .map(|v| v.id.clone())
             .collect();
+        sort_by_priority(&mut matching);
+        let vuln_ids: Vec<String> = matching.iter().map(|v| v.id.clone()).collect();
         
         let mut steps = Vec::new();
         
@@ -402,11 +483,13 @@ pub fn generate_data_exfiltration_path(vulnerabilities: &[Vulnerability]) -> Opt
             .flat_map(|v| generate_mitigations(v))
             .collect::<Vec<String>>();
         
+        let score = likelihood_score(&matching);
         Some(AttackPath {
             entry_point: "Web Application or Service Vulnerability".to_string(),
             steps,
             impact: "Critical - Data Exfiltration".to_string(),
-            likelihood: "Medium".to_string(),
+            likelihood: likelihood_label(score).to_string(),
+            likelihood_score: score,
             mitigations,
         })
     } else {
@@ -424,14 +507,15 @@ pub fn generate_lateral_movement_path(vulnerabilities: &[Vulnerability]) -> Opti
     });
     
     if has_lateral_potential {
-        let vuln_ids: Vec<String> = vulnerabilities.iter()
+        let mut matching: Vec<&Vulnerability> = vulnerabilities.iter()
             .filter(|v| {
                 v.description.contains("RCE") || 
                 v.description.contains("Privilege") || 
                 v.attack_vector.as_ref().map_or(false, |av| av == "Remote Access")
             })
-            .map(|v| v.id.clone())
             .collect();
+        sort_by_priority(&mut matching);
+        let vuln_ids: Vec<String> = matching.iter().map(|v| v.id.clone()).collect();
         
         let mut steps = Vec::new();
         
@@ -463,82 +547,13 @@ pub fn generate_lateral_movement_path(vulnerabilities: &[Vulnerability]) -> Opti
             .flat_map(|v| generate_mitigations(v))
             .collect::<Vec<String>>();
         
+        let score = likelihood_score(&matching);
         Some(AttackPath {
             entry_point: "Remote Service Vulnerability".to_string(),
             steps,
             impact: "Critical - Lateral Movement".to_string(),
-            likelihood: "High".to_string(),
-            mitigations,
-        })
-    } else {
-        None
-    }
-}
-
-/// Generate specific ICS attack path
-pub fn generate_ics_attack_path(vulnerabilities: &[Vulnerability]) -> Option<AttackPath> {
-    // Check if we have ICS-related vulnerabilities
-    let has_ics_vulns = vulnerabilities.iter().any(|v| {
-        v.category.as_ref().map_or(false, |c| c.contains("Industrial")) ||
-        v.attack_vector.as_ref().map_or(false, |av| av.contains("Industrial")) ||
-        v.description.contains("PLC") ||
-        v.description.contains("SCADA") ||
-        v.description.contains("ICS")
-    });
-    
-    if has_ics_vulns {
-        let vuln_ids: Vec<String> = vulnerabilities.iter()
-            .filter(|v| {
-                v.category.as_ref().map_or(false, |c| c.contains("Industrial")) ||
-                v.attack_vector.as_ref().map_or(false, |av| av.contains("Industrial")) ||
-                v.description.contains("PLC") ||
-                v.description.contains("SCADA") ||
-                v.description.contains("ICS")
-            })
-            .map(|v| v.id.clone())
-            .collect();
-        
-        let mut steps = Vec::new();
-        
-        steps.push(AttackStep {
-            description: "Initial Access: Exploitation of industrial protocol vulnerability".to_string(),
-            vulnerabilities: vuln_ids.clone(),
-            mitre_technique: Some("T0866".to_string()),
-        });
-        
-        steps.push(AttackStep {
-            description: "Discovery: Enumeration of industrial control devices".to_string(),
-            vulnerabilities: vuln_ids.clone(),
-            mitre_technique: Some("T0846".to_string()),
-        });
-        
-        steps.push(AttackStep {
-            description: "Lateral Movement: Pivot to engineering workstations".to_string(),
-            vulnerabilities: vuln_ids.clone(),
-            mitre_technique: Some("T0859".to_string()),
-        });
-        
-        steps.push(AttackStep {
-            description: "Collection: SCADA data collection".to_string(),
-            vulnerabilities: vuln_ids.clone(),
-            mitre_technique: Some("T0802".to_string()),
-        });
-        
-        steps.push(AttackStep {
-            description: "Impact: Manipulation of industrial process".to_string(),
-            vulnerabilities: vuln_ids.clone(),
-            mitre_technique: Some("T0831".to_string()),
-        });
-        
-        let mitigations = vulnerabilities.iter()
-            .flat_map(|v| generate_mitigations(v))
-            .collect::<Vec<String>>();
-        
-        Some(AttackPath {
-            entry_point: "Industrial Control System Vulnerability".to_string(),
-            steps,
-            impact: "Critical - Physical Process Manipulation".to_string(),
-            likelihood: "Medium".to_string(),
+            likelihood: likelihood_label(score).to_string(),
+            likelihood_score: score,
             mitigations,
         })
     } else {
@@ -1,38 +1,157 @@
 // CVE cache implementation
 
 use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use log::info;
+use crate::constants::DEFAULT_CVE_CACHE_TTL_DAYS;
 use crate::models::Vulnerability;
 
-// Cache to store previously retrieved CVE data
-static mut CVE_CACHE: Option<HashMap<String, Vulnerability>> = None;
+// A cached vulnerability together with the time it was retrieved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    vulnerability: Vulnerability,
+    cached_at: u64, // Seconds since the Unix epoch
+}
+
+// Cache to store previously retrieved CVE data, shared across scan threads
+lazy_static::lazy_static! {
+    static ref CVE_CACHE: RwLock<HashMap<String, CacheEntry>> = RwLock::new(HashMap::new());
+}
 
-/// Initialize the CVE cache
-#[allow(static_mut_refs)]
+/// Initialize the CVE cache, loading previous lookups from the default cache file if present
 pub fn init_cve_cache() {
-    unsafe {
-        if CVE_CACHE.is_none() {
-            CVE_CACHE = Some(HashMap::new());
+    lazy_static::initialize(&CVE_CACHE);
+
+    if let Some(path) = default_cache_path() {
+        if path.exists() {
+            let _ = load_from_disk(&path, Duration::from_secs(DEFAULT_CVE_CACHE_TTL_DAYS * 24 * 60 * 60));
         }
     }
 }
 
 /// Get a vulnerability from the cache
-#[allow(static_mut_refs)]
 pub fn get_from_cache(cve_id: &str) -> Option<Vulnerability> {
-    unsafe {
-        if let Some(cache) = &CVE_CACHE {
-            return cache.get(cve_id).cloned();
-        }
+    match CVE_CACHE.read() {
+        Ok(cache) => cache.get(cve_id).map(|entry| entry.vulnerability.clone()),
+        Err(poisoned) => poisoned.into_inner().get(cve_id).map(|entry| entry.vulnerability.clone()),
     }
-    None
 }
 
 /// Add a vulnerability to the cache
-#[allow(static_mut_refs)]
 pub fn add_to_cache(cve_id: String, vulnerability: Vulnerability) {
-    unsafe {
-        if let Some(cache) = &mut CVE_CACHE {
-            cache.insert(cve_id, vulnerability);
+    let entry = CacheEntry {
+        vulnerability,
+        cached_at: now_secs(),
+    };
+
+    match CVE_CACHE.write() {
+        Ok(mut cache) => {
+            cache.insert(cve_id, entry);
+        },
+        Err(poisoned) => {
+            poisoned.into_inner().insert(cve_id, entry);
+        }
+    }
+}
+
+/// Default location of the on-disk cache (e.g. ~/.cache/rustnetscan/cve_cache.json)
+pub fn default_cache_path() -> Option<PathBuf> {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+    Some(cache_dir.join("rustnetscan").join("cve_cache.json"))
+}
+
+/// Load cached vulnerabilities from a JSON file, skipping entries older than `ttl`
+///
+/// Returns the number of entries loaded into the cache.
+pub fn load_from_disk(path: &Path, ttl: Duration) -> io::Result<usize> {
+    let contents = fs::read_to_string(path)?;
+    let entries: HashMap<String, CacheEntry> = serde_json::from_str(&contents)?;
+
+    let oldest_allowed = now_secs().saturating_sub(ttl.as_secs());
+    let mut loaded = 0;
+
+    let mut cache = CVE_CACHE.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for (cve_id, entry) in entries {
+        if entry.cached_at >= oldest_allowed {
+            cache.insert(cve_id, entry);
+            loaded += 1;
+        }
+    }
+
+    info!("Loaded {} CVE cache entries from {}", loaded, path.display());
+    Ok(loaded)
+}
+
+/// Write the in-memory cache to a JSON file, creating parent directories as needed
+pub fn save_to_disk(path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let json = {
+        let cache = CVE_CACHE.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        serde_json::to_string(&*cache)?
+    };
+
+    fs::write(path, json)
+}
+
+/// Current time in seconds since the Unix epoch
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    
+    fn vulnerability(id: &str) -> Vulnerability {
+        serde_json::from_value(serde_json::json!({ "id": id, "description": format!("Cached {}", id) })).unwrap()
+    }
+    
+    #[test]
+    fn concurrent_reads_and_writes_keep_the_cache_consistent() {
+        const THREADS: usize = 50;
+        const ENTRIES: usize = 40;
+        let id = |thread: usize, entry: usize| format!("CVE-CACHE-TEST-{}-{}", thread, entry);
+        
+        let workers: Vec<_> = (0..THREADS).map(|t| {
+            thread::spawn(move || {
+                for e in 0..ENTRIES {
+                    add_to_cache(id(t, e), vulnerability(&id(t, e)));
+                    
+                    // Read back this thread's entry and one another thread may be writing
+                    assert_eq!(get_from_cache(&id(t, e)).unwrap().id, id(t, e));
+                    if let Some(other) = get_from_cache(&id((t + 1) % THREADS, e)) {
+                        assert_eq!(other.id, id((t + 1) % THREADS, e));
+                    }
+                }
+            })
+        }).collect();
+        
+        for worker in workers {
+            assert!(worker.join().is_ok(), "a cache worker panicked");
         }
+        
+        let cache = CVE_CACHE.read().unwrap();
+        assert!(!CVE_CACHE.is_poisoned());
+        let stored = cache.keys().filter(|key| key.starts_with("CVE-CACHE-TEST-")).count();
+        assert_eq!(stored, THREADS * ENTRIES);
+        assert!(cache.iter()
+            .filter(|(key, _)| key.starts_with("CVE-CACHE-TEST-"))
+            .all(|(key, entry)| entry.vulnerability.id == **key));
     }
 }
@@ -0,0 +1,286 @@
+// CPE-based version matching against the NVD CVE database
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use log::debug;
+use serde::Deserialize;
+use serde_json::Value;
+use crate::constants::{CPE_PRODUCTS, NVD_CVE_API_URL, NVD_CPE_RESULTS_PER_PAGE};
+use crate::models::Vulnerability;
+use crate::cveapi::cache::add_to_cache;
+use crate::cveapi::error::{check_response, parse_json, CveApiError};
+use crate::cveapi::detection::extract_product_versions;
+use crate::cveapi::lookup::send_nvd_request;
+use crate::cveapi::models::create_vulnerability;
+use crate::proxy;
+
+// Vulnerabilities already retrieved for a CPE 2.3 name, shared across hosts
+lazy_static::lazy_static! {
+    static ref CPE_RESULTS: Mutex<HashMap<String, Vec<Vulnerability>>> = Mutex::new(HashMap::new());
+}
+
+// A product identified in a banner, as CPE 2.3 components
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cpe {
+    pub vendor: String,
+    pub product: String,
+    pub version: String,
+    pub update: String, // e.g. "p1" for OpenSSH 7.4p1, empty when there is none
+}
+
+impl Cpe {
+    /// Map a product and version from `extract_product_versions` to a CPE
+    pub fn from_product(product: &str, version: &str) -> Option<Cpe> {
+        let (_, vendor, cpe_product) = CPE_PRODUCTS.iter().find(|(name, _, _)| *name == product)?;
+    
+        // OpenSSH portable releases carry the patch level as the CPE update, e.g. 7.4p1
+        let (version, update) = match version.find(['p', 'P']) {
+            Some(index) if product == "openssh" => (&version[..index], version[index..].to_lowercase()),
+            _ => (version, String::new()),
+        };
+    
+        Some(Cpe {
+            vendor: vendor.to_string(),
+            product: cpe_product.to_string(),
+            version: version.to_string(),
+            update,
+        })
+    }
+    
+    /// Format as a CPE 2.3 formatted string, e.g. `cpe:2.3:a:openbsd:openssh:7.4:p1:*:*:*:*:*:*`
+    pub fn to_cpe23(&self) -> String {
+        let update = if self.update.is_empty() { "*" } else { &self.update };
+        format!("cpe:2.3:a:{}:{}:{}:{}:*:*:*:*:*:*", self.vendor, self.product, self.version, update)
+    }
+}
+
+/// Identify the products in a banner that have a known CPE
+pub fn banner_cpes(banner: &str) -> Vec<Cpe> {
+    extract_product_versions(banner).into_iter()
+        .filter_map(|(product, version)| Cpe::from_product(product, &version))
+        .collect()
+}
+
+/// Retrieve the NVD CVEs whose configurations include this product version
+///
+/// NVD is queried by exact `cpeName` first; when the name is not in the CPE
+/// dictionary the product is searched with `virtualMatchString`. Either way,
+/// each CVE's version ranges are checked locally before it is returned.
+pub fn lookup_cpe_vulnerabilities(cpe: &Cpe) -> Result<Vec<Vulnerability>, CveApiError> {
+    let cpe_name = cpe.to_cpe23();
+    if let Some(cached) = CPE_RESULTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(&cpe_name) {
+        return Ok(cached.clone());
+    }
+    
+    let client = proxy::client_builder()
+        .timeout(Duration::from_secs(30))
+        .build()?;
+    
+    debug!("Looking up CVEs for {}", cpe_name);
+    let url = format!("{}?cpeName={}&resultsPerPage={}", NVD_CVE_API_URL, cpe_name, NVD_CPE_RESULTS_PER_PAGE);
+    let mut response = send_nvd_request(&client, &url)?;
+    
+    if !response.status().is_success() {
+        debug!("{} is not in the NVD CPE dictionary, searching by product", cpe_name);
+        let url = format!("{}?virtualMatchString=cpe:2.3:a:{}:{}&resultsPerPage={}",
+                          NVD_CVE_API_URL, cpe.vendor, cpe.product, NVD_CPE_RESULTS_PER_PAGE);
+        response = check_response("NVD", send_nvd_request(&client, &url)?)?;
+    }
+    
+    let vulnerabilities = parse_nvd_cpe_response(&parse_json(response)?, cpe);
+    for vuln in &vulnerabilities {
+        add_to_cache(vuln.id.clone(), vuln.clone());
+    }
+    
+    CPE_RESULTS.lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(cpe_name, vulnerabilities.clone());
+    
+    Ok(vulnerabilities)
+}
+
+/// Build vulnerabilities from an NVD CVE API 2.0 response, keeping only CVEs that apply to `cpe`
+fn parse_nvd_cpe_response(nvd_json: &Value, cpe: &Cpe) -> Vec<Vulnerability> {
+    nvd_json.get("vulnerabilities")
+        .and_then(|v| v.as_array())
+        .map(|entries| entries.iter()
+            .filter_map(|entry| entry.get("cve"))
+            .filter(|cve| cve_applies_to(cve, cpe))
+            .filter_map(|cve| parse_nvd_cve(cve, cpe))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Whether any vulnerable CPE match in the CVE's configurations covers `cpe`
+fn cve_applies_to(cve: &Value, cpe: &Cpe) -> bool {
+    cve.get("configurations")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|configuration| configuration.get("nodes").and_then(|n| n.as_array()))
+        .flatten()
+        .filter_map(|node| node.get("cpeMatch").and_then(|m| m.as_array()))
+        .flatten()
+        .filter_map(|cpe_match| CpeMatch::deserialize(cpe_match).ok())
+        .any(|cpe_match| cpe_match.applies_to(cpe))
+}
+
+// One NVD CPE match criterion, as found in both the 2.0 API and the 1.1 JSON feeds
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CpeMatch {
+    #[serde(default)]
+    pub vulnerable: bool,
+    #[serde(alias = "cpe23Uri")]
+    pub criteria: String, // e.g. "cpe:2.3:a:openbsd:openssh:*:*:*:*:*:*:*:*"
+    pub version_start_including: Option<String>,
+    pub version_start_excluding: Option<String>,
+    pub version_end_including: Option<String>,
+    pub version_end_excluding: Option<String>,
+}
+
+impl CpeMatch {
+    /// The "vendor:product" this criterion applies to
+    pub fn product_key(&self) -> Option<String> {
+        let parts: Vec<&str> = self.criteria.split(':').collect();
+        if parts.len() < 5 {
+            return None;
+        }
+        Some(format!("{}:{}", parts[3], parts[4]))
+    }
+    
+    /// Check whether this criterion covers `cpe`: vendor and product must match,
+    /// then the exact version or the versionStart/versionEnd range must include the
+    /// detected version
+    pub fn applies_to(&self, cpe: &Cpe) -> bool {
+        if !self.vulnerable {
+            return false;
+        }
+        
+        let parts: Vec<&str> = self.criteria.split(':').collect();
+        if parts.len() < 7 || parts[3] != cpe.vendor || parts[4] != cpe.product {
+            return false;
+        }
+        
+        let is_wildcard = |part: &str| part == "*" || part == "-";
+        
+        // An exact version in the criteria must match, along with its update if both are known
+        if !is_wildcard(parts[5]) {
+            let update_matches = is_wildcard(parts[6]) || cpe.update.is_empty() || parts[6].eq_ignore_ascii_case(&cpe.update);
+            return compare_versions(parts[5], &cpe.version) == Ordering::Equal && update_matches;
+        }
+        
+        // Which comparisons of the detected version against each bound keep it in range
+        let bounds: [(&Option<String>, &[Ordering]); 4] = [
+            (&self.version_start_including, &[Ordering::Equal, Ordering::Greater]),
+            (&self.version_start_excluding, &[Ordering::Greater]),
+            (&self.version_end_including, &[Ordering::Less, Ordering::Equal]),
+            (&self.version_end_excluding, &[Ordering::Less]),
+        ];
+        
+        bounds.iter().all(|(bound, accepted)| match bound {
+            Some(bound) => accepted.contains(&compare_versions(&cpe.version, bound)),
+            None => true,
+        })
+    }
+}
+
+/// Compare dotted version strings component by component
+///
+/// Numeric components compare as numbers and others as text, so 1.10 > 1.9;
+/// missing trailing components count as zero, so 2.4 == 2.4.0.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (version_components(a), version_components(b));
+    
+    for i in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(i), b.get(i)) {
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                _ => x.cmp(y),
+            },
+            (Some(x), None) => if x.parse::<u64>() == Ok(0) { Ordering::Equal } else { Ordering::Greater },
+            (None, Some(y)) => if y.parse::<u64>() == Ok(0) { Ordering::Equal } else { Ordering::Less },
+            (None, None) => Ordering::Equal,
+        };
+    
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    
+    Ordering::Equal
+}
+
+/// Split a version into runs of digits and runs of letters, e.g. "2.4.49-rc1" -> [2, 4, 49, rc, 1]
+fn version_components(version: &str) -> Vec<String> {
+    let mut components: Vec<String> = Vec::new();
+    let mut current = String::new();
+    
+    for c in version.chars() {
+        let continues_run = matches!(current.chars().last(),
+            Some(last) if c.is_ascii_alphanumeric() && last.is_ascii_digit() == c.is_ascii_digit());
+    
+        if !continues_run && !current.is_empty() {
+            components.push(std::mem::take(&mut current));
+        }
+        if c.is_ascii_alphanumeric() {
+            current.push(c.to_ascii_lowercase());
+        }
+    }
+    if !current.is_empty() {
+        components.push(current);
+    }
+    
+    components
+}
+
+/// Build a vulnerability from an NVD CVE API 2.0 `cve` object
+fn parse_nvd_cve(cve: &Value, cpe: &Cpe) -> Option<Vulnerability> {
+    let id = cve.get("id")?.as_str()?.to_string();
+    
+    let description = cve.get("descriptions")
+        .and_then(|d| d.as_array())
+        .and_then(|d| d.iter().find(|entry| entry.get("lang").and_then(|l| l.as_str()) == Some("en")))
+        .and_then(|entry| entry.get("value"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("No description available")
+        .to_string();
+    
+    let references: Option<Vec<String>> = cve.get("references")
+        .and_then(|r| r.as_array())
+        .map(|refs| refs.iter()
+            .filter_map(|r| r.get("url").and_then(|u| u.as_str()).map(|u| u.to_string()))
+            .collect());
+    
+    // Prefer the newest CVSS version NVD has scored
+    let metric = ["cvssMetricV31", "cvssMetricV30", "cvssMetricV2"].iter()
+        .filter_map(|key| cve.get("metrics")?.get(*key)?.as_array()?.first())
+        .next();
+    let cvss_data = metric.and_then(|m| m.get("cvssData"));
+    let cvss_score = cvss_data.and_then(|d| d.get("baseScore")).and_then(|s| s.as_f64()).map(|s| s as f32);
+    let cvss_vector = cvss_data.and_then(|d| d.get("vectorString")).and_then(|v| v.as_str()).map(|v| v.to_string());
+    // CVSS v2 keeps the severity next to cvssData rather than inside it
+    let severity = cvss_data.and_then(|d| d.get("baseSeverity"))
+        .or_else(|| metric.and_then(|m| m.get("baseSeverity")))
+        .and_then(|s| s.as_str())
+        .map(|s| s.to_string());
+    
+    let cwe_id = cve.get("weaknesses")
+        .and_then(|w| w.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|weakness| weakness.get("description").and_then(|d| d.as_array()))
+        .flatten()
+        .filter_map(|d| d.get("value").and_then(|v| v.as_str()))
+        .find(|value| value.starts_with("CWE-"))
+        .map(|value| value.to_string());
+    
+    let mut vuln = create_vulnerability(id, description, severity, cvss_score, references);
+    vuln.cvss_vector = cvss_vector;
+    vuln.cwe_id = cwe_id;
+    vuln.mitigation = Some(format!("Update {} {} to a release that fixes this vulnerability", cpe.product, cpe.version));
+    
+    Some(vuln)
+}
@@ -1,10 +1,14 @@
 // Vulnerability detection functionality
 
 use regex::Regex;
-use crate::models::Vulnerability;
-use crate::constants::VULNERABILITY_PATTERNS;
+use crate::models::{Severity, Vulnerability, HttpInfo, Misconfiguration, SmbInfo, SshAlgorithms, TlsCertInfo, TlsVersion};
+use crate::constants::{DANGEROUS_HTTP_METHODS, EXPOSED_DATABASE_LISTED_NAMES, SECURITY_MISCONFIGURATIONS, VULNERABILITY_PATTERNS,
+                       WEAK_SSH_CIPHERS, WEAK_SSH_HOST_KEYS, WEAK_SSH_KEX, WEAK_SSH_MACS};
 use crate::cveapi::models::{create_full_vulnerability, categorize_vulnerability, determine_attack_vector};
-use crate::cveapi::lookup::lookup_vulnerability;
+use crate::cveapi::lookup::lookup_vulnerabilities;
+use crate::cveapi::cpe::{banner_cpes, lookup_cpe_vulnerabilities};
+use crate::cveapi::offline_db::{find_offline_vulnerabilities, offline_database};
+use log::warn;
 
 /// Public function to perform a comprehensive vulnerability scan
 pub fn check_service_vulnerabilities(
@@ -18,23 +22,36 @@ pub fn check_service_vulnerabilities(
     let offline_results = match_offline_vulnerabilities(service, banner);
     results.extend(offline_results);
     
-    // Then check for known service vulnerabilities
-    check_known_service_vulnerabilities(service, banner, &mut results);
+    // Then check for version-specific vulnerabilities in the products the banner names
+    check_known_service_vulnerabilities(service, banner, do_api_lookup, &mut results);
+    
+    // Resolve CVEs referenced in the banner, from the imported feeds when offline
+    if !do_api_lookup {
+        if let (Some(database), Ok(cve_regex)) = (offline_database(), Regex::new(r"CVE-\d{4}-\d{4,}")) {
+            for cve_match in cve_regex.find_iter(banner) {
+                if let Some(vuln) = database.get(cve_match.as_str()) {
+                    if !results.iter().any(|v| v.id == vuln.id) {
+                        results.push(vuln.clone());
+                    }
+                }
+            }
+        }
+    }
     
     // If online lookup is enabled, check for any CVEs referenced in the banner
     if do_api_lookup {
         // Look for CVE patterns in banner
         if let Ok(cve_regex) = Regex::new(r"CVE-\d{4}-\d{4,}") {
+            let mut cve_ids: Vec<&str> = Vec::new();
             for cve_match in cve_regex.find_iter(banner) {
                 let cve_id = cve_match.as_str();
                 
                 // Check if we already have this CVE in results
-                if !results.iter().any(|v| v.id == cve_id) {
-                    if let Ok(Some(vuln)) = lookup_vulnerability(cve_id) {
-                        results.push(vuln);
-                    }
+                if !results.iter().any(|v| v.id == cve_id) && !cve_ids.contains(&cve_id) {
+                    cve_ids.push(cve_id);
                 }
             }
+            results.extend(lookup_vulnerabilities(&cve_ids).into_iter().flatten());
         }
     }
     
@@ -47,7 +64,7 @@ pub fn check_service_vulnerabilities(
         
         // If attack vector is not set, determine it
         if vuln.attack_vector.is_none() {
-            vuln.attack_vector = Some(determine_attack_vector(service, banner));
+            vuln.attack_vector = Some(determine_attack_vector(service, banner, vuln.cvss_vector.as_deref()));
         }
     }
     
@@ -65,7 +82,7 @@ pub fn match_offline_vulnerabilities(service: &str, banner: &str) -> Vec<Vulnera
                 let vuln = create_full_vulnerability(
                     pattern.2.clone(), // vulnerability_id
                     pattern.3.clone(), // vulnerability_description
-                    Some("MEDIUM".to_string()), // Default severity
+                    Some(Severity::Medium.to_string()), // Default severity
                     Some(5.0), // Default CVSS score
                     Some(vec!["https://nvd.nist.gov".to_string()]), // Default reference
                     Some(false), // Default actively exploited status
@@ -73,7 +90,7 @@ pub fn match_offline_vulnerabilities(service: &str, banner: &str) -> Vec<Vulnera
                     Some("Update the affected software".to_string()), // Default mitigation
                     Some(categorize_vulnerability(&pattern.2)), // Category based on pattern id
                     None, // No CWE-ID for offline patterns
-                    Some(determine_attack_vector(service, banner)),
+                    Some(determine_attack_vector(service, banner, None)),
                     None, // No MITRE tactics for offline patterns
                     None, // No MITRE techniques for offline patterns
                 );
@@ -87,47 +104,605 @@ pub fn match_offline_vulnerabilities(service: &str, banner: &str) -> Vec<Vulnera
 }
 
 /// Check for vulnerabilities in known services based on banner information
-pub fn check_known_service_vulnerabilities(_service: &str, banner: &str, results: &mut Vec<Vulnerability>) {
+///
+/// Online, each product version found in the banner is mapped to a CPE and
+/// matched against NVD's version ranges. Offline, or when NVD cannot be
+/// reached, the imported NVD feeds and the offline pattern table are used instead.
+pub fn check_known_service_vulnerabilities(service: &str, banner: &str, do_api_lookup: bool, results: &mut Vec<Vulnerability>) {
+    let mut found = Vec::new();
+    let mut lookup_failed = !do_api_lookup;
+    
+    if do_api_lookup {
+        for cpe in banner_cpes(banner) {
+            match lookup_cpe_vulnerabilities(&cpe) {
+                Ok(vulnerabilities) => found.extend(vulnerabilities),
+                Err(e) => {
+                    warn!("CVE lookup for {} failed: {}", cpe.to_cpe23(), e);
+                    lookup_failed = true;
+                },
+            }
+        }
+    }
+    
+    if lookup_failed {
+        found.extend(find_offline_vulnerabilities(banner));
+        found.extend(match_offline_vulnerabilities(service, banner));
+    }
+    
+    for vuln in found {
+        if !results.iter().any(|v| v.id == vuln.id) {
+            results.push(vuln);
+        }
+    }
+}
+
+/// Extract (product, version) pairs from a service banner
+pub fn extract_product_versions(banner: &str) -> Vec<(&'static str, String)> {
     // This is a simplified example; real implementation would be more comprehensive
     let product_regexes = [
         (r"Apache/(\d+\.\d+\.\d+)", "apache_http_server"),
         (r"nginx/(\d+\.\d+\.\d+)", "nginx"),
         (r"OpenSSH[_-](\d+\.\d+[pP]?\d*)", "openssh"),
         (r"Microsoft-IIS/(\d+\.\d+)", "iis"),
+        (r"Apache Tomcat/(\d+\.\d+\.\d+)", "tomcat"),
+        (r"WordPress/(\d+(?:\.\d+)*)", "wordpress"),
+        (r"Jenkins/(\d+(?:\.\d+)*)", "jenkins"),
+        (r"PHP/(\d+\.\d+\.\d+)", "php"),
+        (r"Exim (\d+\.\d+(?:\.\d+)?)", "exim"),
         // Add more patterns for different services
     ];
     
-    for (pattern, product_name) in product_regexes.iter() {
-        if let Ok(regex) = regex::Regex::new(pattern) {
-            if let Some(caps) = regex.captures(banner) {
-                if caps.len() > 1 {
-                    let version = caps.get(1).unwrap().as_str();
-                    
-                    // In a real implementation, you would query a database of known vulnerabilities
-                    // for this product and version. Here we just add a placeholder.
-                    if product_name == &"apache_http_server" && version.starts_with("2.4.") {
-                        let vuln = Vulnerability {
-                            id: "PRODUCT-VULN-APACHE".to_string(),
-                            description: format!("Potential vulnerabilities in Apache {} detected", version),
-                            severity: Some("MEDIUM".to_string()),
-                            cvss_score: Some(5.0),
-                            references: Some(vec![
-                                "https://httpd.apache.org/security/vulnerabilities_24.html".to_string()
-                            ]),
-                            actively_exploited: Some(false),
-                            exploit_available: Some(true),
-                            mitigation: Some("Update to the latest Apache version".to_string()),
-                            category: Some("Web Server".to_string()),
-                            cwe_id: None,
-                            attack_vector: Some("Network".to_string()),
-                            mitre_tactics: None,
-                            mitre_techniques: None,
-                        };
-                        results.push(vuln);
-                    }
-                    // Add similar checks for other products
+    let mut products: Vec<(&'static str, String)> = product_regexes.iter()
+        .filter_map(|(pattern, product_name)| {
+            let regex = Regex::new(pattern).ok()?;
+            let version = regex.captures(banner)?.get(1)?.as_str().to_string();
+            Some((*product_name, version))
+        })
+        .collect();
+    
+    products.extend(mysql_handshake_version(banner));
+    
+    products
+}
+
+/// Read the server version from a MySQL handshake, which banners hold hex-encoded
+///
+/// MariaDB prefixes its version with "5.5.5-" for compatibility, e.g.
+/// "5.5.5-10.3.27-MariaDB-log".
+fn mysql_handshake_version(banner: &str) -> Option<(&'static str, String)> {
+    let pairs = banner.as_bytes().chunks_exact(2);
+    if banner.len() < 12 || !pairs.remainder().is_empty() || !banner.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    
+    let bytes = pairs
+        .map(|pair| std::str::from_utf8(pair).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+        .collect::<Option<Vec<u8>>>()?;
+    
+    // 3-byte length, sequence id, then protocol version 10 and a NUL-terminated version string
+    if bytes[4] != 0x0a {
+        return None;
+    }
+    let end = bytes[5..].iter().position(|b| *b == 0)? + 5;
+    let server_version = std::str::from_utf8(&bytes[5..end]).ok()?;
+    
+    let version_regex = Regex::new(r"(\d+\.\d+\.\d+)").ok()?;
+    match server_version.strip_prefix("5.5.5-") {
+        Some(mariadb) if mariadb.contains("MariaDB") => {
+            Some(("mariadb", version_regex.captures(mariadb)?.get(1)?.as_str().to_string()))
+        },
+        _ => Some(("mysql", version_regex.captures(server_version)?.get(1)?.as_str().to_string())),
+    }
+}
+
+/// Audit the HTTP security headers returned by a web service on `port`
+///
+/// Severity follows impact: a missing HSTS header on an HTTPS site and a
+/// wildcard CORS policy that also allows credentials are medium, other gaps
+/// are low or informational.
+pub fn assess_security_headers(headers: &HttpInfo, port: u16) -> Vec<Misconfiguration> {
+    let mut results = Vec::new();
+    let header = |name: &str| headers.headers.get(name).map(|v| v.to_lowercase());
+    
+    // HSTS only has an effect when the site is served over TLS
+    if headers.is_https {
+        match header("strict-transport-security") {
+            None => results.push(create_header_misconfiguration(
+                "HSTS",
+                port,
+                "Strict-Transport-Security header is missing, allowing protocol downgrade attacks",
+                Severity::Medium,
+                "Send Strict-Transport-Security with a max-age of at least 15552000 seconds",
+            )),
+            Some(value) => {
+                let max_age = value.split(';')
+                    .filter_map(|d| d.trim().strip_prefix("max-age="))
+                    .filter_map(|v| v.trim_matches('"').parse::<u64>().ok())
+                    .next()
+                    .unwrap_or(0);
+                
+                if max_age < 15552000 {
+                    results.push(create_header_misconfiguration(
+                        "HSTS",
+                        port,
+                        &format!("Strict-Transport-Security header has a weak max-age of {} seconds", max_age),
+                        Severity::Low,
+                        "Increase the Strict-Transport-Security max-age to at least 15552000 seconds",
+                    ));
                 }
             }
         }
     }
+    
+    let csp = header("content-security-policy");
+    match &csp {
+        None => results.push(create_header_misconfiguration(
+            "CSP",
+            port,
+            "Content-Security-Policy header is missing, increasing exposure to cross-site scripting",
+            Severity::Low,
+            "Define a restrictive Content-Security-Policy for the application",
+        )),
+        Some(value) => {
+            let weaknesses: Vec<&str> = ["'unsafe-inline'", "'unsafe-eval'"].iter()
+                .filter(|w| value.contains(*w))
+                .cloned()
+                .chain(value.split([' ', ';']).filter(|s| *s == "*"))
+                .collect();
+            
+            if !weaknesses.is_empty() {
+                results.push(create_header_misconfiguration(
+                    "CSP",
+                    port,
+                    &format!("Content-Security-Policy header is weak: allows {}", weaknesses.join(", ")),
+                    Severity::Low,
+                    "Remove unsafe-inline, unsafe-eval and wildcard sources from the Content-Security-Policy",
+                ));
+            }
+        }
+    }
+    
+    // CSP frame-ancestors supersedes X-Frame-Options
+    let has_frame_ancestors = matches!(&csp, Some(value) if value.contains("frame-ancestors"));
+    match header("x-frame-options") {
+        None if !has_frame_ancestors => results.push(create_header_misconfiguration(
+            "X-FRAME-OPTIONS",
+            port,
+            "X-Frame-Options header is missing, allowing clickjacking",
+            Severity::Low,
+            "Send X-Frame-Options: DENY or SAMEORIGIN, or a CSP frame-ancestors directive",
+        )),
+        Some(value) if value != "deny" && value != "sameorigin" => results.push(create_header_misconfiguration(
+            "X-FRAME-OPTIONS",
+            port,
+            &format!("X-Frame-Options header has a weak value: {}", value),
+            Severity::Low,
+            "Set X-Frame-Options to DENY or SAMEORIGIN",
+        )),
+        _ => {}
+    }
+    
+    match header("x-content-type-options") {
+        Some(value) if value == "nosniff" => {}
+        Some(value) => results.push(create_header_misconfiguration(
+            "X-CONTENT-TYPE-OPTIONS",
+            port,
+            &format!("X-Content-Type-Options header has an invalid value: {}", value),
+            Severity::Info,
+            "Set X-Content-Type-Options to nosniff",
+        )),
+        None => results.push(create_header_misconfiguration(
+            "X-CONTENT-TYPE-OPTIONS",
+            port,
+            "X-Content-Type-Options header is missing, allowing MIME type sniffing",
+            Severity::Info,
+            "Send X-Content-Type-Options: nosniff",
+        )),
+    }
+    
+    // Any origin may read responses; with credentials allowed too, authenticated data is exposed
+    if let Some(origin) = header("access-control-allow-origin") {
+        if origin.trim() == "*" || origin.trim() == "null" {
+            let with_credentials = header("access-control-allow-credentials").map(|v| v.trim() == "true").unwrap_or(false);
+            let (description, severity) = if with_credentials {
+                (format!("Access-Control-Allow-Origin is '{}' and credentials are allowed, exposing authenticated responses to any site", origin.trim()), Severity::Medium)
+            } else {
+                (format!("Access-Control-Allow-Origin is '{}', letting any site read responses", origin.trim()), Severity::Low)
+            };
+            results.push(create_header_misconfiguration(
+                "CORS",
+                port,
+                &description,
+                severity,
+                "Restrict Access-Control-Allow-Origin to a list of trusted origins",
+            ));
+        }
+    }
+    
+    results
+}
+
+/// Check a TLS certificate for expiry, self-signing and weak signature algorithms
+pub fn assess_tls_certificate(cert: &TlsCertInfo) -> Vec<Vulnerability> {
+    let mut results = Vec::new();
+    let name = cert.subject_cn.as_deref().unwrap_or(&cert.subject);
+    
+    if cert.is_expired {
+        results.push(create_tls_finding(
+            "EXPIRED",
+            &format!("TLS certificate for {} is outside its validity period ({} to {})", name, cert.not_before, cert.not_after),
+            Severity::Medium,
+            "CWE-298",
+            "Renew the certificate and deploy it to the service",
+        ));
+    }
+    
+    if cert.is_self_signed {
+        results.push(create_tls_finding(
+            "SELF-SIGNED",
+            &format!("TLS certificate for {} is self-signed, so clients cannot verify the server's identity", name),
+            Severity::Low,
+            "CWE-295",
+            "Replace the certificate with one issued by a trusted certificate authority",
+        ));
+    }
+    
+    let algorithm = cert.signature_algorithm.to_lowercase();
+    if algorithm.contains("md5") || algorithm.contains("md2") || algorithm.contains("sha1") {
+        results.push(create_tls_finding(
+            "WEAK-SIGNATURE",
+            &format!("TLS certificate for {} is signed with weak algorithm {}", name, cert.signature_algorithm),
+            Severity::Medium,
+            "CWE-327",
+            "Reissue the certificate with a SHA-256 or stronger signature algorithm",
+        ));
+    }
+    
+    results
+}
+
+/// Flag deprecated SSL/TLS protocol versions accepted by a service
+pub fn assess_tls_versions(versions: &[TlsVersion]) -> Option<Vulnerability> {
+    let weak: Vec<String> = versions.iter()
+        .filter(|version| version.is_weak())
+        .map(|version| version.to_string())
+        .collect();
+    if weak.is_empty() {
+        return None;
+    }
+    
+    let id = "MISCONFIG-SSL-OLD-PROTOCOL";
+    let (description, mitigation) = SECURITY_MISCONFIGURATIONS.iter()
+        .find(|(_, _, misconfig_id, _, _)| misconfig_id == id)
+        .map(|(_, _, _, description, mitigation)| (description.clone(), mitigation.clone()))
+        .unwrap_or_else(|| ("Server supporting outdated SSL/TLS protocols".to_string(), String::new()));
+    
+    // SSLv3 is broken outright (POODLE), TLS 1.0/1.1 are deprecated
+    let severity = if versions.contains(&TlsVersion::Ssl3) { Severity::High } else { Severity::Medium };
+    
+    Some(create_full_vulnerability(
+        id.to_string(),
+        format!("{}: {}", description, weak.join(", ")),
+        Some(severity.to_string()),
+        None, // Protocol findings are not scored
+        Some(vec!["https://datatracker.ietf.org/doc/html/rfc8996".to_string()]),
+        Some(false),
+        Some(false),
+        Some(mitigation),
+        Some("Cryptographic Issue".to_string()),
+        Some("CWE-327".to_string()),
+        Some("Network".to_string()),
+        None,
+        None,
+    ))
+}
+
+/// Flag SMB servers that accept SMB1 or don't require message signing
+pub fn assess_smb(info: &SmbInfo) -> Vec<Vulnerability> {
+    let mut results = Vec::new();
+    
+    if info.smb1_enabled {
+        results.push(create_full_vulnerability(
+            "MISCONFIG-SMBV1-ENABLED".to_string(),
+            "SMBv1 (NT LM 0.12) is enabled, exposing the protocol targeted by EternalBlue (MS17-010) and WannaCry".to_string(),
+            Some(Severity::High.to_string()),
+            None, // Protocol findings are not scored
+            Some(vec![
+                "https://learn.microsoft.com/en-us/security-updates/securitybulletins/2017/ms17-010".to_string(),
+                "https://learn.microsoft.com/en-us/windows-server/storage/file-server/troubleshoot/detect-enable-and-disable-smbv1-v2-v3".to_string(),
+            ]),
+            Some(false),
+            Some(false),
+            Some("Disable SMBv1 on the server and clients; SMB2 and later are supported by every current Windows release".to_string()),
+            Some("Security Misconfiguration".to_string()),
+            Some("CWE-477".to_string()),
+            Some("Network".to_string()),
+            None,
+            None,
+        ));
+    }
+    
+    if !info.signing_required {
+        let description = if info.signing_enabled {
+            "SMB server supports but does not require message signing, allowing NTLM relay and tampering with SMB traffic"
+        } else {
+            "SMB server does not support message signing, allowing NTLM relay and tampering with SMB traffic"
+        };
+        
+        results.push(create_full_vulnerability(
+            "MISCONFIG-SMB-SIGNING-DISABLED".to_string(),
+            description.to_string(),
+            Some(Severity::Medium.to_string()),
+            None,
+            Some(vec!["https://learn.microsoft.com/en-us/troubleshoot/windows-server/networking/overview-server-message-block-signing".to_string()]),
+            Some(false),
+            Some(false),
+            Some("Require SMB signing on the server (\"Microsoft network server: Digitally sign communications (always)\" or \"server signing = mandatory\" in Samba)".to_string()),
+            Some("Security Misconfiguration".to_string()),
+            Some("CWE-294".to_string()),
+            Some("Network".to_string()),
+            None,
+            None,
+        ));
+    }
+    
+    results
+}
+
+/// Flag the weak key exchange, host key, cipher and MAC algorithms an SSH server offers
+pub fn assess_ssh_algorithms(algorithms: &SshAlgorithms) -> Option<Vulnerability> {
+    let weak = |offered: &[String], patterns: &[&str]| -> Vec<String> {
+        offered.iter()
+            .filter(|name| patterns.iter().any(|pattern| name.contains(pattern)))
+            .cloned()
+            .collect()
+    };
+    
+    let found: Vec<String> = [
+        ("key exchange", weak(&algorithms.kex, WEAK_SSH_KEX)),
+        ("host key", weak(&algorithms.host_key, WEAK_SSH_HOST_KEYS)),
+        ("cipher", weak(&algorithms.ciphers, WEAK_SSH_CIPHERS)),
+        ("MAC", weak(&algorithms.macs, WEAK_SSH_MACS)),
+    ].iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(kind, names)| format!("{}: {}", kind, names.join(", ")))
+        .collect();
+    if found.is_empty() {
+        return None;
+    }
+    
+    Some(create_full_vulnerability(
+        "MISCONFIG-SSH-WEAK-CIPHER".to_string(),
+        format!("SSH server offers weak algorithms ({})", found.join("; ")),
+        Some(Severity::Medium.to_string()),
+        None, // Protocol findings are not scored
+        Some(vec!["https://www.openssh.com/legacy.html".to_string()]),
+        Some(false),
+        Some(false),
+        Some("Remove legacy algorithms from the server's KexAlgorithms, HostKeyAlgorithms, Ciphers and MACs settings, keeping curve25519 or ECDH key exchange, AES-GCM, AES-CTR or ChaCha20-Poly1305 ciphers and SHA-2 MACs".to_string()),
+        Some("Cryptographic Issue".to_string()),
+        Some("CWE-327".to_string()),
+        Some("Network".to_string()),
+        None,
+        None,
+    ))
+}
+
+/// Build the finding for a database that answered queries without credentials
+///
+/// `names` are the databases or indices it listed, of which the first few are
+/// named in the description.
+pub fn assess_unauthenticated_database(database: &str, names: &[String]) -> Vulnerability {
+    let mut description = format!("{} accepts commands without authentication, exposing its data to anyone who can reach it", database);
+    if !names.is_empty() {
+        let listed: Vec<&str> = names.iter().take(EXPOSED_DATABASE_LISTED_NAMES).map(|n| n.as_str()).collect();
+        description.push_str(&format!("; it listed {}", listed.join(", ")));
+        if names.len() > listed.len() {
+            description.push_str(&format!(" and {} more", names.len() - listed.len()));
+        }
+    }
+    
+    create_full_vulnerability(
+        format!("EXPOSED-{}-NOAUTH", database.to_uppercase()),
+        description,
+        Some(Severity::Critical.to_string()),
+        None, // Protocol findings are not scored
+        None,
+        Some(false),
+        Some(false),
+        Some(format!("Enable authentication on {} and bind it to trusted interfaces or firewall it from untrusted networks", database)),
+        Some("Security Misconfiguration".to_string()),
+        Some("CWE-306".to_string()),
+        Some("Network".to_string()),
+        None,
+        None,
+    )
+}
+
+/// Flag the risky methods a web server advertises, with a separate finding for TRACE
+///
+/// PUT and DELETE let clients change content on the server and are rated
+/// High; the other methods in `DANGEROUS_HTTP_METHODS` are rated Medium.
+pub fn assess_http_methods(methods: &[String]) -> Vec<Vulnerability> {
+    let mut results = Vec::new();
+    
+    let dangerous: Vec<&str> = methods.iter()
+        .map(|m| m.as_str())
+        .filter(|m| DANGEROUS_HTTP_METHODS.contains(m))
+        .collect();
+    if dangerous.is_empty() {
+        return results;
+    }
+    
+    let severity = if dangerous.iter().any(|m| *m == "PUT" || *m == "DELETE") {
+        Severity::High
+    } else {
+        Severity::Medium
+    };
+    
+    results.push(create_full_vulnerability(
+        "MISCONFIG-HTTP-DANGEROUS-METHODS".to_string(),
+        format!("Web server allows potentially dangerous HTTP methods: {}", dangerous.join(", ")),
+        Some(severity.to_string()),
+        None, // Protocol findings are not scored
+        Some(vec!["https://owasp.org/www-project-web-security-testing-guide/latest/4-Web_Application_Security_Testing/02-Configuration_and_Deployment_Management_Testing/06-Test_HTTP_Methods".to_string()]),
+        Some(false),
+        Some(false),
+        Some("Disable HTTP methods the application doesn't need, allowing only GET, HEAD and POST where possible".to_string()),
+        Some("Security Misconfiguration".to_string()),
+        Some("CWE-749".to_string()),
+        Some("Network".to_string()),
+        None,
+        None,
+    ));
+    
+    if dangerous.iter().any(|m| *m == "TRACE" || *m == "TRACK") {
+        results.push(create_full_vulnerability(
+            "MISCONFIG-HTTP-TRACE-ENABLED".to_string(),
+            "Web server allows TRACE, which echoes requests back and enables Cross-Site Tracing (XST) to read cookies and credentials marked HttpOnly".to_string(),
+            Some(Severity::Medium.to_string()),
+            None,
+            Some(vec!["https://owasp.org/www-community/attacks/Cross_Site_Tracing".to_string()]),
+            Some(false),
+            Some(false),
+            Some("Disable TRACE and TRACK (\"TraceEnable off\" in Apache, rejecting the methods in other servers)".to_string()),
+            Some("Security Misconfiguration".to_string()),
+            Some("CWE-693".to_string()),
+            Some("Network".to_string()),
+            None,
+            None,
+        ));
+    }
+    
+    results
+}
+
+/// Build a finding for a problem with a TLS certificate
+fn create_tls_finding(issue: &str, description: &str, severity: Severity, cwe_id: &str, mitigation: &str) -> Vulnerability {
+    create_full_vulnerability(
+        format!("MISCONFIG-TLS-CERT-{}", issue),
+        description.to_string(),
+        Some(severity.to_string()),
+        None, // Certificate findings are not scored
+        Some(vec!["https://owasp.org/www-project-web-security-testing-guide/latest/4-Web_Application_Security_Testing/09-Testing_for_Weak_Cryptography/01-Testing_for_Weak_Transport_Layer_Security".to_string()]),
+        Some(false),
+        Some(false),
+        Some(mitigation.to_string()),
+        Some("Cryptographic Issue".to_string()),
+        Some(cwe_id.to_string()),
+        Some("Network".to_string()),
+        None,
+        None,
+    )
+}
+
+/// Build a finding for an absent or weak HTTP security header
+fn create_header_misconfiguration(header: &str, port: u16, description: &str, severity: Severity, recommendation: &str) -> Misconfiguration {
+    Misconfiguration {
+        id: format!("MISCONFIG-HTTP-HEADER-{}", header),
+        port,
+        category: "http".to_string(),
+        description: description.to_string(),
+        severity: severity.to_string(),
+        recommendation: recommendation.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn response(is_https: bool, headers: &[(&str, &str)]) -> HttpInfo {
+        HttpInfo {
+            status_code: Some(200),
+            headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+            is_https,
+            ..HttpInfo::default()
+        }
+    }
+    
+    /// (id, severity) of each finding, sorted for comparison
+    fn findings(headers: &HttpInfo) -> Vec<(String, String)> {
+        let mut found: Vec<(String, String)> = assess_security_headers(headers, 443).into_iter()
+            .map(|finding| (finding.id, finding.severity))
+            .collect();
+        found.sort();
+        found
+    }
+    
+    fn expected(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut expected: Vec<(String, String)> = entries.iter()
+            .map(|(header, severity)| (format!("MISCONFIG-HTTP-HEADER-{}", header), severity.to_string()))
+            .collect();
+        expected.sort();
+        expected
+    }
+    
+    #[test]
+    fn hardened_headers_produce_no_findings() {
+        let headers = response(true, &[
+            ("strict-transport-security", "max-age=31536000; includeSubDomains"),
+            ("content-security-policy", "default-src 'self'; frame-ancestors 'none'"),
+            ("x-content-type-options", "nosniff"),
+            ("access-control-allow-origin", "https://app.example.com"),
+        ]);
+        
+        assert!(findings(&headers).is_empty());
+    }
+    
+    #[test]
+    fn missing_headers_are_reported_by_impact() {
+        assert_eq!(findings(&response(true, &[])), expected(&[
+            ("HSTS", "MEDIUM"),
+            ("CSP", "LOW"),
+            ("X-FRAME-OPTIONS", "LOW"),
+            ("X-CONTENT-TYPE-OPTIONS", "INFO"),
+        ]));
+        
+        // HSTS means nothing over plain HTTP
+        assert_eq!(findings(&response(false, &[])), expected(&[
+            ("CSP", "LOW"),
+            ("X-FRAME-OPTIONS", "LOW"),
+            ("X-CONTENT-TYPE-OPTIONS", "INFO"),
+        ]));
+    }
+    
+    #[test]
+    fn weak_header_values_are_reported() {
+        let headers = response(true, &[
+            ("strict-transport-security", "max-age=3600"),
+            ("content-security-policy", "script-src 'self' 'unsafe-inline' *"),
+            ("x-frame-options", "ALLOW-FROM https://example.com"),
+            ("x-content-type-options", "sniff"),
+        ]);
+        
+        assert_eq!(findings(&headers), expected(&[
+            ("HSTS", "LOW"),
+            ("CSP", "LOW"),
+            ("X-FRAME-OPTIONS", "LOW"),
+            ("X-CONTENT-TYPE-OPTIONS", "INFO"),
+        ]));
+        
+        let descriptions: Vec<String> = assess_security_headers(&headers, 443).into_iter().map(|f| f.description).collect();
+        assert!(descriptions.contains(&"Strict-Transport-Security header has a weak max-age of 3600 seconds".to_string()));
+        assert!(descriptions.contains(&"Content-Security-Policy header is weak: allows 'unsafe-inline', *".to_string()));
+    }
+    
+    #[test]
+    fn wildcard_cors_is_worse_with_credentials() {
+        let hardened = [
+            ("content-security-policy", "default-src 'self'"),
+            ("x-frame-options", "DENY"),
+            ("x-content-type-options", "nosniff"),
+        ];
+        
+        let open = response(false, &[hardened.as_slice(), &[("access-control-allow-origin", "*")]].concat());
+        assert_eq!(findings(&open), expected(&[("CORS", "LOW")]));
+        
+        let with_credentials = response(false, &[hardened.as_slice(), &[
+            ("access-control-allow-origin", "*"),
+            ("access-control-allow-credentials", "true"),
+        ]].concat());
+        assert_eq!(findings(&with_credentials), expected(&[("CORS", "MEDIUM")]));
+    }
 }
@@ -1,54 +1,69 @@
 // Vulnerability enrichment functionality
 
-use std::error::Error;
+use std::collections::HashMap;
 use std::time::Duration;
-use reqwest::blocking::Client;
 use serde_json::Value;
-use crate::constants::MITRE_ATTACK_MAPPINGS;
+use crate::constants::{MITRE_ATTACK_MAPPINGS, MITRE_TECHNIQUE_TACTICS, EPSS_API_URL, EPSS_BATCH_SIZE, EXPLOIT_DB_SEARCH_URL, EXPLOIT_DB_EXPLOIT_URL};
+use crate::cveapi::error::{check_response, parse_json, CveApiError};
+use crate::cveapi::lookup::send_nvd_request;
+use crate::proxy;
+
+// MITRE ATT&CK tactics and techniques mapped from a CVE or CWE
+pub type MitreMapping = (Option<Vec<String>>, Option<Vec<String>>);
 
 /// Add exploit database integration
-pub fn check_exploit_db(cve_id: &str) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+///
+/// Returns one `https://www.exploit-db.com/exploits/<id>` link per published
+/// exploit, or `None` when exploit-db has none for the CVE.
+pub fn check_exploit_db(cve_id: &str) -> Result<Option<Vec<String>>, CveApiError> {
     // Initialize the HTTP client
-    let client = Client::builder()
+    let client = proxy::client_builder()
         .timeout(Duration::from_secs(5))
         .build()?;
     
-    // Query ExploitDB API
-    let url = format!("https://www.exploit-db.com/search?cve={}", cve_id);
+    // The search page returns JSON instead of HTML for XHR requests
+    let url = format!("{}?cve={}", EXPLOIT_DB_SEARCH_URL, cve_id.trim_start_matches("CVE-"));
     
-    let response = match client.get(&url).send() {
-        Ok(resp) if resp.status().is_success() => resp,
-        Ok(_) => return Ok(None), // No successful response
-        Err(_) => return Ok(None), // Error in request, treat as no exploits found
-    };
+    let response = client.get(&url)
+        .header("X-Requested-With", "XMLHttpRequest")
+        .header("Accept", "application/json")
+        .send()?;
     
-    let response_text = match response.text() {
-        Ok(text) => text,
-        Err(_) => return Ok(None),
-    };
+    let search_json: Value = parse_json(check_response("exploit-db", response)?)?;
+    let mut exploits = parse_exploit_db_response(&search_json);
     
-    // Check if there are exploits (simplified check)
-    if response_text.contains("No results") || !response_text.contains(cve_id) {
-        return Ok(None);
+    // Try to get additional exploits from other sources
+    if let Ok(Some(other_exploits)) = check_metasploit_exploits(cve_id) {
+        exploits.extend(other_exploits);
     }
     
-    // Extract exploit links (this is a simplified approach)
-    let exploits = vec![
-        format!("https://www.exploit-db.com/search?cve={}", cve_id),
-        // In a real implementation, we would parse actual exploit URLs from the response
-    ];
-    
-    // Try to get additional exploits from other sources
-    if let Ok(Some(mut other_exploits)) = check_metasploit_exploits(cve_id) {
-        exploits.iter().for_each(|e| other_exploits.push(e.clone()));
-        return Ok(Some(other_exploits));
+    if exploits.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(exploits))
     }
+}
+
+/// Build exploit links from the IDs in an exploit-db search response
+fn parse_exploit_db_response(search_json: &Value) -> Vec<String> {
+    let entries = match search_json.get("data").and_then(|d| d.as_array()) {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
     
-    Ok(Some(exploits))
+    entries.iter()
+        .filter_map(|entry| match entry.get("id") {
+            // IDs are usually strings but accept numbers too
+            Some(Value::String(id)) => id.parse::<u64>().ok(),
+            Some(id) => id.as_u64(),
+            None => None,
+        })
+        .map(|id| format!("{}/{}", EXPLOIT_DB_EXPLOIT_URL, id))
+        .collect()
 }
 
 /// Check for Metasploit exploits
-fn check_metasploit_exploits(_cve_id: &str) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+fn check_metasploit_exploits(_cve_id: &str) -> Result<Option<Vec<String>>, CveApiError> {
     // This is a simplified implementation - in a real-world scenario, 
     // we would query Metasploit's database or a public API
     
@@ -57,9 +72,9 @@ fn check_metasploit_exploits(_cve_id: &str) -> Result<Option<Vec<String>>, Box<d
 }
 
 /// Function to check if a vulnerability is actively exploited in the wild
-pub fn check_active_exploitation(cve_id: &str) -> Result<bool, Box<dyn Error>> {
+pub fn check_active_exploitation(cve_id: &str) -> Result<bool, CveApiError> {
     // Initialize the HTTP client
-    let client = Client::builder()
+    let client = proxy::client_builder()
         .timeout(Duration::from_secs(5))
         .build()?;
     
@@ -90,66 +105,49 @@ pub fn check_active_exploitation(cve_id: &str) -> Result<bool, Box<dyn Error>> {
     Ok(false)
 }
 
-/// Map a CVE to MITRE ATT&CK tactics and techniques
-pub fn map_to_mitre_attack(cve_id: &str) -> Result<(Option<Vec<String>>, Option<Vec<String>>), Box<dyn Error>> {
-    // Check if we have a direct mapping in our constants
-    for (cve_pattern, tactics_techniques) in MITRE_ATTACK_MAPPINGS.iter() {
-        if cve_pattern.is_empty() || cve_id.contains(cve_pattern) {
-            // Get the tactics and techniques
-            if let Some(tactics_techniques_vec) = tactics_techniques.get(0) {
-                return Ok((
-                    Some(vec![tactics_techniques_vec.clone()]),
-                    Some(tactics_techniques.clone())
-                ));
-            }
-        }
+/// Map a CVE to MITRE ATT&CK tactics and techniques through its CWE
+pub fn map_to_mitre_attack(cve_id: &str) -> Result<MitreMapping, CveApiError> {
+    match lookup_cwe_for_cve(cve_id)? {
+        Some(cwe_id) => Ok(map_cwe_to_mitre_attack(&cwe_id)),
+        None => Ok((None, None)),
     }
-    
-    // If no direct mapping, try to determine based on CVE description
-    // In a real implementation, we would perform NLP or other analysis to map
-    // the vulnerability to MITRE ATT&CK tactics and techniques
-    
-    // For now, attempt to get this information from an API or database
-    let client = Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()?;
-    
-    // Example API call - in reality you would use a proper API for this
-    let url = format!("https://example.com/api/mitre-mapping/{}", cve_id);
-    
-    let response = match client.get(&url).send() {
-        Ok(resp) if resp.status().is_success() => resp,
-        _ => return Ok((None, None)), // No mapping found
-    };
-    
-    let mapping_json: Value = match response.json() {
-        Ok(json) => json,
-        Err(_) => return Ok((None, None)),
+}
+
+/// Map a CWE (e.g. "CWE-89") to MITRE ATT&CK tactics and techniques using the offline table
+pub fn map_cwe_to_mitre_attack(cwe_id: &str) -> MitreMapping {
+    let techniques = match MITRE_ATTACK_MAPPINGS.get(&cwe_id.trim().to_uppercase()) {
+        Some(techniques) => techniques,
+        None => return (None, None),
     };
     
-    // Extract tactics and techniques from the response
-    let tactics = mapping_json.get("tactics")
-        .and_then(|t| t.as_array())
-        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect());
-    
-    let techniques = mapping_json.get("techniques")
-        .and_then(|t| t.as_array())
-        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect());
+    let mut tactics: Vec<String> = Vec::new();
+    for technique in techniques {
+        let parent = technique.split('.').next().unwrap_or(technique);
+        let technique_tactics = MITRE_TECHNIQUE_TACTICS.iter()
+            .find(|(id, _)| *id == parent)
+            .map(|(_, tactics)| *tactics)
+            .unwrap_or_default();
+        for tactic in technique_tactics {
+            if !tactics.iter().any(|t| t == tactic) {
+                tactics.push(tactic.to_string());
+            }
+        }
+    }
     
-    Ok((tactics, techniques))
+    (Some(tactics).filter(|t| !t.is_empty()), Some(techniques.clone()))
 }
 
 /// Lookup CWE for a given CVE
-pub fn lookup_cwe_for_cve(cve_id: &str) -> Result<Option<String>, Box<dyn Error>> {
+pub fn lookup_cwe_for_cve(cve_id: &str) -> Result<Option<String>, CveApiError> {
     // Initialize the HTTP client
-    let client = Client::builder()
+    let client = proxy::client_builder()
         .timeout(Duration::from_secs(5))
         .build()?;
     
     // Query NVD API for CWE information
     let url = format!("https://services.nvd.nist.gov/rest/json/cves/2.0?cveId={}", cve_id);
     
-    let response = match client.get(&url).send() {
+    let response = match send_nvd_request(&client, &url) {
         Ok(resp) if resp.status().is_success() => resp,
         _ => return Ok(None), // No CWE information available
     };
@@ -159,7 +157,20 @@ pub fn lookup_cwe_for_cve(cve_id: &str) -> Result<Option<String>, Box<dyn Error>
         Err(_) => return Ok(None),
     };
     
-    // Try to extract CWE from the response
+    // API 2.0 lists weaknesses per CVE; skip placeholders such as NVD-CWE-noinfo
+    let weakness = nvd_json.pointer("/vulnerabilities/0/cve/weaknesses")
+        .and_then(|w| w.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|w| w.get("description").and_then(|d| d.as_array()))
+        .flatten()
+        .filter_map(|d| d.get("value").and_then(|v| v.as_str()))
+        .find(|value| value.starts_with("CWE-"));
+    if let Some(cwe) = weakness {
+        return Ok(Some(cwe.to_string()));
+    }
+    
+    // Try to extract CWE from the legacy response layout
     if let Some(vulnerabilities) = nvd_json.get("result").and_then(|r| r.get("CVE_Items")).and_then(|i| i.as_array()) {
         if let Some(vuln) = vulnerabilities.first() {
             if let Some(cwe_nodes) = vuln.get("cve").and_then(|c| c.get("problemtype")).and_then(|p| p.get("problemtype_data")).and_then(|d| d.as_array()) {
@@ -178,3 +189,54 @@ pub fn lookup_cwe_for_cve(cve_id: &str) -> Result<Option<String>, Box<dyn Error>
     
     Ok(None)
 }
+
+/// Lookup the FIRST EPSS exploitation probability (0-1) for a CVE
+pub fn lookup_epss(cve_id: &str) -> Result<Option<f32>, CveApiError> {
+    let scores = lookup_epss_batch(&[cve_id])?;
+    Ok(scores.get(&cve_id.to_uppercase()).copied())
+}
+
+/// Lookup EPSS scores for several CVEs, sending up to EPSS_BATCH_SIZE IDs per request
+pub fn lookup_epss_batch(cve_ids: &[&str]) -> Result<HashMap<String, f32>, CveApiError> {
+    let mut scores = HashMap::new();
+    if cve_ids.is_empty() {
+        return Ok(scores);
+    }
+    
+    // Initialize the HTTP client
+    let client = proxy::client_builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    
+    for chunk in cve_ids.chunks(EPSS_BATCH_SIZE) {
+        let url = format!("{}?cve={}", EPSS_API_URL, chunk.join(","));
+        
+        let response = client.get(&url).send()?;
+        let epss_json: Value = parse_json(check_response("EPSS", response)?)?;
+        scores.extend(parse_epss_response(&epss_json));
+    }
+    
+    Ok(scores)
+}
+
+/// Extract CVE -> EPSS score pairs from an EPSS API response
+fn parse_epss_response(epss_json: &Value) -> HashMap<String, f32> {
+    let mut scores = HashMap::new();
+    
+    if let Some(entries) = epss_json.get("data").and_then(|d| d.as_array()) {
+        for entry in entries {
+            let cve = entry.get("cve").and_then(|c| c.as_str());
+            // The API returns scores as strings, e.g. "0.00043"
+            let epss = entry.get("epss").and_then(|e| match e {
+                Value::String(s) => s.parse::<f32>().ok(),
+                other => other.as_f64().map(|f| f as f32),
+            });
+            
+            if let (Some(cve), Some(epss)) = (cve, epss) {
+                scores.insert(cve.to_uppercase(), epss.clamp(0.0, 1.0));
+            }
+        }
+    }
+    
+    scores
+}
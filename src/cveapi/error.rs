@@ -0,0 +1,50 @@
+// Errors returned by the CVE and enrichment API clients
+
+use reqwest::blocking::Response;
+use reqwest::StatusCode;
+use thiserror::Error;
+
+// Why a vulnerability database or enrichment lookup failed
+#[derive(Debug, Error)]
+pub enum CveApiError {
+    #[error("HTTP request failed: {0}")]
+    Http(#[source] reqwest::Error),          // Connection, TLS or protocol failure
+    #[error("{0} rate limited the request")]
+    RateLimited(&'static str),              // Service throttled us and retries were exhausted
+    #[error("{0} has no record of the requested item")]
+    NotFound(&'static str),                 // Service answered 404
+    #[error("{service} returned HTTP {status}")]
+    Status { service: &'static str, status: u16 }, // Any other unsuccessful status
+    #[error("Failed to parse response: {0}")]
+    Parse(#[from] serde_json::Error),       // Body was not the JSON we expected
+    #[error("Request timed out")]
+    Timeout,
+}
+
+impl From<reqwest::Error> for CveApiError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            CveApiError::Timeout
+        } else {
+            CveApiError::Http(error)
+        }
+    }
+}
+
+/// Pass a successful response through, or classify an unsuccessful one
+pub(crate) fn check_response(service: &'static str, response: Response) -> Result<Response, CveApiError> {
+    match response.status() {
+        status if status.is_success() => Ok(response),
+        StatusCode::NOT_FOUND => Err(CveApiError::NotFound(service)),
+        StatusCode::TOO_MANY_REQUESTS => Err(CveApiError::RateLimited(service)),
+        status => Err(CveApiError::Status { service, status: status.as_u16() }),
+    }
+}
+
+/// Read a response body and parse it as JSON
+///
+/// Parsing the text separately keeps JSON errors distinct from transport errors.
+pub(crate) fn parse_json<T: serde::de::DeserializeOwned>(response: Response) -> Result<T, CveApiError> {
+    let body = response.text()?;
+    Ok(serde_json::from_str(&body)?)
+}
@@ -1,100 +1,316 @@
 // Vulnerability lookup functionality
 
-use std::error::Error;
-use std::time::Duration;
-use reqwest::blocking::Client;
+use std::sync::{Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use reqwest::blocking::{Client, Response};
+use reqwest::StatusCode;
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{json, Value};
+use log::{debug, warn};
 
-use crate::models::Vulnerability;
+use crate::constants::{CVE_LOOKUP_CONCURRENCY, NVD_RATE_WINDOW_SECS, NVD_REQUESTS_PER_WINDOW, NVD_REQUESTS_PER_WINDOW_WITH_KEY,
+                       NVD_MAX_ATTEMPTS, NVD_RETRY_BASE_DELAY_MS, OSV_API_URL, GITHUB_API_URL};
+use crate::models::{CveSource, Severity, Vulnerability};
+use crate::proxy;
 use crate::cveapi::cache::{get_from_cache, add_to_cache};
-use crate::cveapi::enrichment::{check_exploit_db, check_active_exploitation, map_to_mitre_attack, lookup_cwe_for_cve};
+use crate::cveapi::error::{check_response, parse_json, CveApiError};
+use crate::cveapi::enrichment::{check_exploit_db, check_active_exploitation, map_cwe_to_mitre_attack, lookup_cwe_for_cve, lookup_epss};
+
+// Token bucket limiting NVD requests across all scan threads
+struct TokenBucket {
+    tokens: f64,
+    capacity: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32) -> Self {
+        Self {
+            tokens: f64::from(capacity),
+            capacity: f64::from(capacity),
+            last_refill: Instant::now(),
+        }
+    }
+    
+    /// Take a token, or return how long to wait until one is available
+    fn try_acquire(&mut self, capacity: u32) -> Option<Duration> {
+        let capacity = f64::from(capacity);
+        let refill_rate = capacity / NVD_RATE_WINDOW_SECS as f64;
+        
+        // Adjust when the active limit changes (e.g. an API key was configured)
+        if (self.capacity - capacity).abs() > f64::EPSILON {
+            self.capacity = capacity;
+            self.tokens = self.tokens.min(capacity);
+        }
+        
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * refill_rate).min(self.capacity);
+        self.last_refill = now;
+        
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / refill_rate))
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref NVD_API_KEY: RwLock<Option<String>> = RwLock::new(None);
+    static ref NVD_RATE_LIMITER: Mutex<TokenBucket> = Mutex::new(TokenBucket::new(NVD_REQUESTS_PER_WINDOW));
+    static ref CVE_SOURCE_ORDER: RwLock<Vec<CveSource>> = RwLock::new(CveSource::DEFAULT_ORDER.to_vec());
+}
+
+/// Set the order in which online databases are tried when looking up a CVE
+pub fn set_cve_source_order(order: Vec<CveSource>) {
+    let mut sources = CVE_SOURCE_ORDER.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *sources = order;
+}
+
+/// Current CVE lookup order, including any sources moved back after rate limiting
+pub fn cve_source_order() -> Vec<CveSource> {
+    CVE_SOURCE_ORDER.read()
+        .map(|sources| sources.clone())
+        .unwrap_or_else(|poisoned| poisoned.into_inner().clone())
+}
+
+/// Move a rate-limited source to the back of the lookup order for the rest of the run
+fn demote_cve_source(source: CveSource) {
+    let mut sources = CVE_SOURCE_ORDER.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(index) = sources.iter().position(|s| *s == source) {
+        let source = sources.remove(index);
+        sources.push(source);
+    }
+}
+
+/// Configure the NVD API key used for lookups (overrides the NVD_API_KEY environment variable)
+pub fn set_nvd_api_key(api_key: Option<String>) {
+    let mut key = NVD_API_KEY.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *key = api_key.filter(|k| !k.is_empty());
+}
+
+/// Get the active NVD API key, if any
+fn nvd_api_key() -> Option<String> {
+    let configured = NVD_API_KEY.read()
+        .map(|key| key.clone())
+        .unwrap_or_else(|poisoned| poisoned.into_inner().clone());
+    
+    configured.or_else(|| std::env::var("NVD_API_KEY").ok().filter(|k| !k.is_empty()))
+}
+
+/// Block until the NVD rate limit allows another request
+fn wait_for_nvd_slot(has_api_key: bool) {
+    let capacity = if has_api_key { NVD_REQUESTS_PER_WINDOW_WITH_KEY } else { NVD_REQUESTS_PER_WINDOW };
+    
+    loop {
+        let wait = NVD_RATE_LIMITER.lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .try_acquire(capacity);
+        
+        match wait {
+            None => return,
+            Some(delay) => {
+                debug!("NVD rate limit reached, waiting {:?}", delay);
+                thread::sleep(delay);
+            },
+        }
+    }
+}
+
+/// Send a rate-limited request to the NVD API, retrying with backoff when throttled
+///
+/// Fails with `CveApiError::RateLimited` once NVD has throttled every attempt;
+/// other statuses are returned for the caller to interpret.
+pub(crate) fn send_nvd_request(client: &Client, url: &str) -> Result<Response, CveApiError> {
+    let api_key = nvd_api_key();
+    let mut attempt = 0;
+    
+    loop {
+        wait_for_nvd_slot(api_key.is_some());
+        
+        let mut request = client.get(url);
+        if let Some(key) = &api_key {
+            request = request.header("apiKey", key);
+        }
+        
+        let response = request.send()?;
+        let status = response.status();
+        
+        // NVD signals throttling with 429 or 403
+        let throttled = status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::FORBIDDEN;
+        attempt += 1;
+        if throttled && attempt >= NVD_MAX_ATTEMPTS {
+            return Err(CveApiError::RateLimited("NVD"));
+        }
+        if throttled {
+            let retry_after = response.headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_secs);
+            let backoff = Duration::from_millis(NVD_RETRY_BASE_DELAY_MS * 2u64.pow(attempt - 1));
+            let delay = retry_after.unwrap_or(backoff);
+            
+            warn!("NVD throttled the request ({}), retrying in {:?}", status, delay);
+            thread::sleep(delay);
+            continue;
+        }
+        
+        return Ok(response);
+    }
+}
 
 /// Lookup vulnerability information from multiple sources
-pub fn lookup_vulnerability(cve_id: &str) -> Result<Option<Vulnerability>, Box<dyn Error>> {
+///
+/// Sources are tried in the order set by `set_cve_source_order` (NVD, MITRE,
+/// then CIRCL by default). A source that rate limits a lookup is moved to the
+/// back of the order for the rest of the run.
+pub fn lookup_vulnerability(cve_id: &str) -> Result<Option<Vulnerability>, CveApiError> {
     // First check if we have this CVE in our cache
     if let Some(cached_vuln) = get_from_cache(cve_id) {
+        debug!("{} found in the CVE cache", cve_id);
         return Ok(Some(cached_vuln));
     }
+    debug!("Looking up {} online", cve_id);
 
     // Define a client with reasonable timeout
-    let client = Client::builder()
+    let client = proxy::client_builder()
         .timeout(Duration::from_secs(10))
         .build()?;
 
-    // Try NVD API first
-    match lookup_vulnerability_nvd(&client, cve_id) {
-        Ok(Some(mut vuln)) => {
-            // Check for exploit information and active exploitation
-            let exploit_info = check_exploit_db(cve_id).unwrap_or(None);
-            let is_active_threat = check_active_exploitation(cve_id).unwrap_or(false);
-            
-            // Add MITRE ATT&CK mapping
-            if let Ok(mapping) = map_to_mitre_attack(cve_id) {
-                vuln.mitre_tactics = mapping.0;
-                vuln.mitre_techniques = mapping.1;
-            }
-            
-            // Check for CWE information
-            if let Ok(Some(cwe_id)) = lookup_cwe_for_cve(cve_id) {
-                vuln.cwe_id = Some(cwe_id);
-            }
-            
-            // Update vulnerability with exploit info
-            vuln.actively_exploited = Some(is_active_threat);
-            vuln.exploit_available = Some(exploit_info.is_some());
-            
-            // If actively exploited, update description and severity
-            if is_active_threat {
-                vuln.description = format!("[ACTIVELY EXPLOITED] {}", vuln.description);
-                // Upgrade severity if actively exploited
-                if let Some(ref current_severity) = vuln.severity {
-                    if current_severity != "CRITICAL" {
-                        vuln.severity = Some("CRITICAL".to_string());
-                    }
-                }
-            }
+    // Try each source in the configured order until one has a record
+    let mut last_error = None;
+    for source in cve_source_order() {
+        let result = match source {
+            CveSource::Nvd => lookup_vulnerability_nvd(&client, cve_id)
+                .map(|vuln| vuln.map(|vuln| enrich_nvd_vulnerability(cve_id, vuln))),
+            CveSource::Mitre => lookup_vulnerability_mitre(&client, cve_id),
+            CveSource::Circl => lookup_vulnerability_circl(&client, cve_id),
+            CveSource::Osv => lookup_vulnerability_osv(&client, cve_id),
+        };
+        
+        match result {
+            Ok(Some(vuln)) => {
+                // Cache the result before returning
+                add_to_cache(cve_id.to_string(), vuln.clone());
+                return Ok(Some(vuln));
+            },
+            Ok(None) => {
+                debug!("{} not found in {}", cve_id, source);
+                last_error = None;
+            },
+            Err(CveApiError::RateLimited(service)) => {
+                warn!("{} rate limited the lookup of {}, trying it last from now on", service, cve_id);
+                demote_cve_source(source);
+                last_error = Some(CveApiError::RateLimited(service));
+            },
+            Err(e) => {
+                warn!("{} lookup of {} failed: {}", source, cve_id, e);
+                last_error = Some(e);
+            },
+        }
+    }
+    
+    // Report a failure only when the last source consulted couldn't answer
+    match last_error {
+        Some(e) => Err(e),
+        None => {
+            debug!("{} not found in any CVE database", cve_id);
+            Ok(None)
+        },
+    }
+}
+
+/// Look up several CVEs, up to `CVE_LOOKUP_CONCURRENCY` at a time
+///
+/// Results are in the order of `cve_ids`, with `None` for CVEs that weren't
+/// found or whose lookup failed.
+pub fn lookup_vulnerabilities(cve_ids: &[&str]) -> Vec<Option<Vulnerability>> {
+    let mut found = Vec::with_capacity(cve_ids.len());
+    
+    for batch in cve_ids.chunks(CVE_LOOKUP_CONCURRENCY) {
+        thread::scope(|scope| {
+            let lookups: Vec<_> = batch.iter()
+                .map(|cve_id| scope.spawn(move || lookup_vulnerability(cve_id)))
+                .collect();
             
-            // Add exploit links to references if available
-            if let Some(exploit_links) = exploit_info {
-                if let Some(ref mut refs) = vuln.references {
-                    for link in exploit_links {
-                        refs.push(link);
-                    }
-                } else {
-                    vuln.references = Some(exploit_links);
+            for (cve_id, lookup) in batch.iter().zip(lookups) {
+                match lookup.join() {
+                    Ok(Ok(vuln)) => found.push(vuln),
+                    Ok(Err(e)) => {
+                        debug!("Lookup of {} failed: {}", cve_id, e);
+                        found.push(None);
+                    },
+                    Err(_) => found.push(None),
                 }
             }
-            
-            // Cache the enhanced result before returning
-            add_to_cache(cve_id.to_string(), vuln.clone());
-            Ok(Some(vuln))
-        },
-        Ok(None) => {
-            // Try MITRE CVE first, then fall back to CIRCL CVE API
-            match lookup_vulnerability_mitre(&client, cve_id) {
-                Ok(Some(vuln)) => {
-                    // Cache the result before returning
-                    add_to_cache(cve_id.to_string(), vuln.clone());
-                    Ok(Some(vuln))
-                },
-                Ok(None) => {
-                    // Fall back to CIRCL CVE API
-                    match lookup_vulnerability_circl(&client, cve_id) {
-                        Ok(Some(vuln)) => {
-                            // Cache the result before returning
-                            add_to_cache(cve_id.to_string(), vuln.clone());
-                            Ok(Some(vuln))
-                        },
-                        Ok(None) => Ok(None),
-                        Err(e) => Err(e),
-                    }
-                },
-                Err(e) => Err(e),
+        });
+    }
+    
+    found
+}
+
+/// Add exploit, active exploitation, ATT&CK, CWE and EPSS details to an NVD record
+fn enrich_nvd_vulnerability(cve_id: &str, mut vuln: Vulnerability) -> Vulnerability {
+    // Each detail comes from its own service, so query them side by side
+    let known_cwe = vuln.cwe_id.clone();
+    let (exploit_info, is_active_threat, cwe_id, epss) = thread::scope(|scope| {
+        let exploit_info = scope.spawn(|| check_exploit_db(cve_id).unwrap_or(None));
+        let is_active_threat = scope.spawn(|| check_active_exploitation(cve_id).unwrap_or(false));
+        let cwe_id = scope.spawn(|| known_cwe.or_else(|| lookup_cwe_for_cve(cve_id).ok().flatten()));
+        let epss = lookup_epss(cve_id).ok().flatten();
+        
+        (
+            exploit_info.join().unwrap_or(None),
+            is_active_threat.join().unwrap_or(false),
+            cwe_id.join().ok().flatten(),
+            epss,
+        )
+    });
+    
+    // Add the CWE and the MITRE ATT&CK mapping it implies
+    if let Some(cwe_id) = cwe_id {
+        let (tactics, techniques) = map_cwe_to_mitre_attack(&cwe_id);
+        if techniques.is_some() {
+            vuln.mitre_tactics = tactics;
+            vuln.mitre_techniques = techniques;
+        }
+        vuln.cwe_id = Some(cwe_id);
+    }
+    
+    // Add the EPSS exploitation probability
+    if epss.is_some() {
+        vuln.epss_score = epss;
+    }
+    
+    // Update vulnerability with exploit info
+    vuln.actively_exploited = Some(is_active_threat);
+    vuln.exploit_available = Some(exploit_info.is_some());
+    
+    // If actively exploited, update description and severity
+    if is_active_threat {
+        vuln.description = format!("[ACTIVELY EXPLOITED] {}", vuln.description);
+        // Upgrade severity if actively exploited
+        if vuln.severity.is_some() && vuln.severity_level() != Severity::Critical {
+            vuln.severity = Some(Severity::Critical.to_string());
+        }
+    }
+    
+    // Add exploit links to references if available
+    if let Some(exploit_links) = exploit_info {
+        if let Some(ref mut refs) = vuln.references {
+            for link in exploit_links {
+                refs.push(link);
             }
-        },
-        Err(e) => Err(e),
+        } else {
+            vuln.references = Some(exploit_links);
+        }
     }
+    
+    vuln
 }
 
 /// Data structures for NVD API response
@@ -148,6 +364,8 @@ struct NvdBaseMetricV3 {
 struct NvdCvssV3 {
     base_score: f32,
     base_severity: String,
+    #[serde(rename = "vectorString")]
+    vector_string: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -159,26 +377,22 @@ struct NvdBaseMetricV2 {
 #[derive(Deserialize)]
 struct NvdCvssV2 {
     base_score: f32,
+    #[serde(rename = "vectorString")]
+    vector_string: Option<String>,
 }
 
 /// Lookup vulnerability information from the MITRE CVE database
-pub fn lookup_vulnerability_mitre(client: &Client, cve_id: &str) -> Result<Option<Vulnerability>, Box<dyn Error>> {
+pub fn lookup_vulnerability_mitre(client: &Client, cve_id: &str) -> Result<Option<Vulnerability>, CveApiError> {
     // MITRE CVE API URL
     let url = format!("https://cveawg.mitre.org/api/cve/{}", cve_id);
     
-    let response = match client.get(&url).send() {
+    let response = match check_response("MITRE CVE", client.get(&url).send()?) {
         Ok(resp) => resp,
-        Err(e) => return Err(Box::new(e)),
+        Err(CveApiError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e),
     };
     
-    if !response.status().is_success() {
-        return Ok(None); // Not found or other non-success status
-    }
-    
-    let response_json: Value = match response.json() {
-        Ok(json) => json,
-        Err(e) => return Err(Box::new(e)),
-    };
+    let response_json: Value = parse_json(response)?;
     
     // Extract relevant information from MITRE response
     if let Some(obj) = response_json.as_object() {
@@ -217,23 +431,17 @@ pub fn lookup_vulnerability_mitre(client: &Client, cve_id: &str) -> Result<Optio
 }
 
 /// Lookup vulnerability through NVD API
-pub fn lookup_vulnerability_nvd(client: &Client, cve_id: &str) -> Result<Option<Vulnerability>, Box<dyn Error>> {
+pub fn lookup_vulnerability_nvd(client: &Client, cve_id: &str) -> Result<Option<Vulnerability>, CveApiError> {
     // NVD API URL
     let url = format!("https://services.nvd.nist.gov/rest/json/cves/2.0?cveId={}", cve_id);
     
-    let response = match client.get(&url).send() {
+    let response = match check_response("NVD", send_nvd_request(client, &url)?) {
         Ok(resp) => resp,
-        Err(e) => return Err(Box::new(e)),
+        Err(CveApiError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e),
     };
     
-    if !response.status().is_success() {
-        return Ok(None); // Not found or other non-success status
-    }
-    
-    let nvd_response: NvdResponse = match response.json() {
-        Ok(json) => json,
-        Err(e) => return Err(Box::new(e)),
-    };
+    let nvd_response: NvdResponse = parse_json(response)?;
     
     if nvd_response.result.cve_items.is_empty() {
         return Ok(None);
@@ -252,27 +460,30 @@ pub fn lookup_vulnerability_nvd(client: &Client, cve_id: &str) -> Result<Option<
         refs.iter().map(|r| r.url.clone()).collect()
     });
     
-    // Extract severity and CVSS score
-    let (severity, cvss_score) = if let Some(impact) = &cve_item.impact {
+    // Extract severity, CVSS score and vector
+    let (severity, cvss_score, cvss_vector) = if let Some(impact) = &cve_item.impact {
         if let Some(metric_v3) = &impact.base_metric_v3 {
-            (Some(metric_v3.cvss_v3.base_severity.clone()), Some(metric_v3.cvss_v3.base_score))
+            (Some(metric_v3.cvss_v3.base_severity.clone()), Some(metric_v3.cvss_v3.base_score),
+             metric_v3.cvss_v3.vector_string.clone())
         } else if let Some(metric_v2) = &impact.base_metric_v2 {
-            (Some(metric_v2.severity.clone()), Some(metric_v2.cvss_v2.base_score))
+            (Some(metric_v2.severity.clone()), Some(metric_v2.cvss_v2.base_score),
+             metric_v2.cvss_v2.vector_string.clone())
         } else {
-            (None, None)
+            (None, None, None)
         }
     } else {
-        (None, None)
+        (None, None, None)
     };
     
     // Create the vulnerability
-    let vuln = crate::cveapi::models::create_vulnerability(
+    let mut vuln = crate::cveapi::models::create_vulnerability(
         cve_id.to_string(),
         description,
         severity,
         cvss_score,
         references,
     );
+    vuln.cvss_vector = cvss_vector;
     
     Ok(Some(vuln))
 }
@@ -289,23 +500,17 @@ struct CirclCveResponse {
 }
 
 /// Lookup vulnerability through CIRCL CVE API
-pub fn lookup_vulnerability_circl(client: &Client, cve_id: &str) -> Result<Option<Vulnerability>, Box<dyn Error>> {
+pub fn lookup_vulnerability_circl(client: &Client, cve_id: &str) -> Result<Option<Vulnerability>, CveApiError> {
     // CIRCL CVE API URL
     let url = format!("https://cve.circl.lu/api/cve/{}", cve_id);
     
-    let response = match client.get(&url).send() {
+    let response = match check_response("CIRCL", client.get(&url).send()?) {
         Ok(resp) => resp,
-        Err(e) => return Err(Box::new(e)),
+        Err(CveApiError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e),
     };
     
-    if !response.status().is_success() {
-        return Ok(None); // Not found or other non-success status
-    }
-    
-    let circl_response: CirclCveResponse = match response.json() {
-        Ok(json) => json,
-        Err(e) => return Err(Box::new(e)),
-    };
+    let circl_response: CirclCveResponse = parse_json(response)?;
     
     // Get description from summary
     let description = circl_response.summary
@@ -315,12 +520,7 @@ pub fn lookup_vulnerability_circl(client: &Client, cve_id: &str) -> Result<Optio
     let cvss_score = circl_response.cvss3.or(circl_response.cvss);
     
     // Determine severity based on CVSS
-    let severity = cvss_score.map(|score| {
-        if score >= 9.0 { "CRITICAL" }
-        else if score >= 7.0 { "HIGH" }
-        else if score >= 4.0 { "MEDIUM" }
-        else { "LOW" }
-    }).map(String::from);
+    let severity = cvss_score.map(|score| Severity::from_cvss(score).to_string());
     
     // Create vulnerability
     let vuln = crate::cveapi::models::create_vulnerability(
@@ -333,3 +533,319 @@ pub fn lookup_vulnerability_circl(client: &Client, cve_id: &str) -> Result<Optio
     
     Ok(Some(vuln))
 }
+
+/// Lookup a vulnerability through the OSV.dev API
+///
+/// OSV indexes advisories by their own IDs (GHSA, OSV, ...) and by CVE alias,
+/// so CVE IDs can be looked up directly.
+pub fn lookup_vulnerability_osv(client: &Client, cve_id: &str) -> Result<Option<Vulnerability>, CveApiError> {
+    let url = format!("{}/vulns/{}", OSV_API_URL, cve_id);
+    
+    let response = match check_response("OSV", client.get(&url).send()?) {
+        Ok(resp) => resp,
+        Err(CveApiError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    
+    let advisory: Value = parse_json(response)?;
+    Ok(parse_osv_advisory(&advisory))
+}
+
+/// Query OSV.dev for advisories affecting a specific package version
+///
+/// An empty `ecosystem` searches the package name across all ecosystems.
+pub fn query_osv_package(client: &Client, package: &str, ecosystem: &str, version: &str) -> Result<Vec<Vulnerability>, CveApiError> {
+    let mut query = json!({
+        "version": version,
+        "package": { "name": package },
+    });
+    if !ecosystem.is_empty() {
+        query["package"]["ecosystem"] = json!(ecosystem);
+    }
+    
+    let response = client.post(format!("{}/query", OSV_API_URL))
+        .json(&query)
+        .send()?;
+    
+    let response_json: Value = parse_json(check_response("OSV", response)?)?;
+    Ok(parse_osv_query_response(&response_json))
+}
+
+/// Convert the advisories in an OSV `/v1/query` response into vulnerabilities
+fn parse_osv_query_response(response_json: &Value) -> Vec<Vulnerability> {
+    response_json.get("vulns")
+        .and_then(|v| v.as_array())
+        .map(|advisories| advisories.iter().filter_map(parse_osv_advisory).collect())
+        .unwrap_or_default()
+}
+
+/// Convert a single OSV advisory into a vulnerability
+///
+/// Advisories with a CVE alias are reported under the CVE ID so they merge with
+/// findings from the other sources.
+fn parse_osv_advisory(advisory: &Value) -> Option<Vulnerability> {
+    let osv_id = advisory.get("id")?.as_str()?;
+    
+    let id = advisory.get("aliases")
+        .and_then(|a| a.as_array())
+        .and_then(|aliases| aliases.iter().filter_map(|a| a.as_str()).find(|a| a.starts_with("CVE-")))
+        .unwrap_or(osv_id)
+        .to_string();
+    
+    let description = advisory.get("summary")
+        .or_else(|| advisory.get("details"))
+        .and_then(|d| d.as_str())
+        .unwrap_or("No description available")
+        .trim()
+        .to_string();
+    
+    // Ecosystem databases (e.g. GitHub) rate advisories as LOW/MODERATE/HIGH/CRITICAL
+    let database_specific = advisory.get("database_specific");
+    let severity = database_specific
+        .and_then(|d| d.get("severity"))
+        .and_then(|s| s.as_str())
+        .and_then(|s| s.parse::<Severity>().ok())
+        .map(|s| s.to_string());
+    
+    let cwe_id = database_specific
+        .and_then(|d| d.get("cwe_ids"))
+        .and_then(|c| c.as_array())
+        .and_then(|c| c.first())
+        .and_then(|c| c.as_str())
+        .map(|c| c.to_string());
+    
+    let mut references = vec![format!("https://osv.dev/vulnerability/{}", osv_id)];
+    if let Some(refs) = advisory.get("references").and_then(|r| r.as_array()) {
+        references.extend(refs.iter().filter_map(|r| r.get("url").and_then(|u| u.as_str())).map(|u| u.to_string()));
+    }
+    
+    let mut vuln = crate::cveapi::models::create_full_vulnerability(
+        id,
+        description,
+        severity,
+        None,
+        Some(references),
+        None,
+        None,
+        None,
+        None,
+        cwe_id,
+        None,
+        None,
+        None,
+    );
+    
+    // Keep the CVSS vector when the advisory carries one
+    vuln.cvss_vector = advisory.get("severity")
+        .and_then(|s| s.as_array())
+        .and_then(|entries| entries.iter().find(|e| {
+            matches!(e.get("type").and_then(|t| t.as_str()), Some(t) if t.starts_with("CVSS_"))
+        }))
+        .and_then(|e| e.get("score"))
+        .and_then(|s| s.as_str())
+        .map(|s| s.to_string());
+    
+    Some(vuln)
+}
+
+/// Lookup a GitHub Security Advisory by GHSA ID, or the advisory cross-referencing a CVE ID
+///
+/// With a `GITHUB_TOKEN` the GraphQL API is used; the GraphQL API cannot be
+/// queried anonymously, so without a token the REST global advisories
+/// endpoint is used instead.
+pub fn lookup_vulnerability_ghsa(client: &Client, ghsa_or_cve_id: &str) -> Result<Option<Vulnerability>, CveApiError> {
+    let id = ghsa_or_cve_id.trim();
+    let is_ghsa = id.to_uppercase().starts_with("GHSA-");
+    if !is_ghsa && !id.to_uppercase().starts_with("CVE-") {
+        return Ok(None);
+    }
+    
+    let token = std::env::var("GITHUB_TOKEN").ok().filter(|t| !t.is_empty());
+    let advisory = match token {
+        Some(token) => {
+            // GHSA IDs use lowercase after the prefix, CVE IDs uppercase
+            let (query, variables) = if is_ghsa {
+                (format!("query($id: String!) {{ securityAdvisory(ghsaId: $id) {{ {} }} }}", GHSA_GRAPHQL_FIELDS),
+                 json!({ "id": format!("GHSA-{}", &id[5..].to_lowercase()) }))
+            } else {
+                (format!("query($id: String!) {{ securityAdvisories(identifier: {{type: CVE, value: $id}}, first: 1) {{ nodes {{ {} }} }} }}", GHSA_GRAPHQL_FIELDS),
+                 json!({ "id": id.to_uppercase() }))
+            };
+            
+            let response = client.post(format!("{}/graphql", GITHUB_API_URL))
+                .bearer_auth(token)
+                .header(reqwest::header::USER_AGENT, "rustnet_scan")
+                .json(&json!({ "query": query, "variables": variables }))
+                .send()?;
+            
+            let body: Value = parse_json(check_response("GitHub", response)?)?;
+            let data = body.get("data");
+            let node = if is_ghsa {
+                data.and_then(|d| d.get("securityAdvisory"))
+            } else {
+                data.and_then(|d| d.pointer("/securityAdvisories/nodes/0"))
+            };
+            node.and_then(parse_ghsa_graphql_advisory)
+        },
+        None => {
+            let url = if is_ghsa {
+                format!("{}/advisories/GHSA-{}", GITHUB_API_URL, &id[5..].to_lowercase())
+            } else {
+                format!("{}/advisories?cve_id={}", GITHUB_API_URL, id.to_uppercase())
+            };
+            
+            let response = client.get(&url)
+                .header(reqwest::header::USER_AGENT, "rustnet_scan")
+                .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+                .send()?;
+            let response = match check_response("GitHub", response) {
+                Ok(resp) => resp,
+                Err(CveApiError::NotFound(_)) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            
+            let body: Value = parse_json(response)?;
+            let advisory = if is_ghsa { Some(&body) } else { body.get(0) };
+            advisory.and_then(parse_ghsa_rest_advisory)
+        },
+    };
+    
+    Ok(advisory.map(|advisory| ghsa_to_vulnerability(&advisory)))
+}
+
+// Advisory fields requested from the GitHub GraphQL API
+const GHSA_GRAPHQL_FIELDS: &str = "ghsaId summary severity permalink \
+    identifiers { type value } references { url } cvss { score vectorString } cwes(first: 1) { nodes { cweId } } \
+    vulnerabilities(first: 20) { nodes { package { ecosystem name } vulnerableVersionRange firstPatchedVersion { identifier } } }";
+
+// GitHub Security Advisory fields common to the GraphQL and REST APIs
+struct GhsaAdvisory {
+    ghsa_id: String,
+    cve_id: Option<String>,
+    summary: String,
+    severity: Option<Severity>,
+    cvss_score: Option<f32>,
+    cvss_vector: Option<String>,
+    cwe_id: Option<String>,
+    references: Vec<String>,
+    affected: Vec<String>, // "<ecosystem> <package> <range>" for each affected package
+}
+
+/// Extract an advisory from a GraphQL `SecurityAdvisory` object
+fn parse_ghsa_graphql_advisory(node: &Value) -> Option<GhsaAdvisory> {
+    let ghsa_id = node.get("ghsaId")?.as_str()?.to_string();
+    
+    let cve_id = node.get("identifiers")
+        .and_then(|i| i.as_array())
+        .and_then(|identifiers| identifiers.iter().find(|i| i.get("type").and_then(|t| t.as_str()) == Some("CVE")))
+        .and_then(|i| i.get("value"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string());
+    
+    let mut references = vec![format!("https://github.com/advisories/{}", ghsa_id)];
+    if let Some(refs) = node.get("references").and_then(|r| r.as_array()) {
+        references.extend(refs.iter().filter_map(|r| r.get("url").and_then(|u| u.as_str())).map(|u| u.to_string()));
+    }
+    
+    let affected = node.pointer("/vulnerabilities/nodes")
+        .and_then(|n| n.as_array())
+        .map(|nodes| nodes.iter().filter_map(|n| {
+            Some(format!("{} {} {}",
+                         n.pointer("/package/ecosystem")?.as_str()?.to_lowercase(),
+                         n.pointer("/package/name")?.as_str()?,
+                         n.get("vulnerableVersionRange")?.as_str()?))
+        }).collect())
+        .unwrap_or_default();
+    
+    Some(GhsaAdvisory {
+        ghsa_id,
+        cve_id,
+        summary: node.get("summary").and_then(|s| s.as_str()).unwrap_or("No description available").trim().to_string(),
+        severity: node.get("severity").and_then(|s| s.as_str()).and_then(ghsa_severity),
+        cvss_score: node.pointer("/cvss/score").and_then(|s| s.as_f64()).map(|s| s as f32).filter(|s| *s > 0.0),
+        cvss_vector: node.pointer("/cvss/vectorString").and_then(|v| v.as_str()).map(|v| v.to_string()),
+        cwe_id: node.pointer("/cwes/nodes/0/cweId").and_then(|c| c.as_str()).map(|c| c.to_string()),
+        references,
+        affected,
+    })
+}
+
+/// Extract an advisory from a REST `/advisories` object
+fn parse_ghsa_rest_advisory(advisory: &Value) -> Option<GhsaAdvisory> {
+    let ghsa_id = advisory.get("ghsa_id")?.as_str()?.to_string();
+    
+    let mut references = vec![format!("https://github.com/advisories/{}", ghsa_id)];
+    if let Some(refs) = advisory.get("references").and_then(|r| r.as_array()) {
+        references.extend(refs.iter().filter_map(|r| r.as_str()).map(|u| u.to_string()));
+    }
+    
+    let affected = advisory.get("vulnerabilities")
+        .and_then(|v| v.as_array())
+        .map(|entries| entries.iter().filter_map(|v| {
+            Some(format!("{} {} {}",
+                         v.pointer("/package/ecosystem")?.as_str()?,
+                         v.pointer("/package/name")?.as_str()?,
+                         v.get("vulnerable_version_range")?.as_str()?))
+        }).collect())
+        .unwrap_or_default();
+    
+    Some(GhsaAdvisory {
+        ghsa_id,
+        cve_id: advisory.get("cve_id").and_then(|c| c.as_str()).map(|c| c.to_string()),
+        summary: advisory.get("summary").and_then(|s| s.as_str()).unwrap_or("No description available").trim().to_string(),
+        severity: advisory.get("severity").and_then(|s| s.as_str()).and_then(ghsa_severity),
+        cvss_score: advisory.pointer("/cvss/score").and_then(|s| s.as_f64()).map(|s| s as f32).filter(|s| *s > 0.0),
+        cvss_vector: advisory.pointer("/cvss/vector_string").and_then(|v| v.as_str()).map(|v| v.to_string()),
+        cwe_id: advisory.pointer("/cwes/0/cwe_id").and_then(|c| c.as_str()).map(|c| c.to_string()),
+        references,
+        affected,
+    })
+}
+
+/// Map a GHSA severity (`LOW`, `MODERATE`, `HIGH`, `CRITICAL`, or lowercase `medium` from REST) to ours
+///
+/// Advisories GitHub has not rated (`unknown`) have no severity.
+fn ghsa_severity(severity: &str) -> Option<Severity> {
+    match severity.to_uppercase().as_str() {
+        "CRITICAL" => Some(Severity::Critical),
+        "HIGH" => Some(Severity::High),
+        "MODERATE" | "MEDIUM" => Some(Severity::Medium),
+        "LOW" => Some(Severity::Low),
+        _ => None,
+    }
+}
+
+/// Convert a GitHub advisory into a vulnerability
+///
+/// Advisories with a CVE alias are reported under the CVE ID so they merge with
+/// findings from the other sources.
+fn ghsa_to_vulnerability(advisory: &GhsaAdvisory) -> Vulnerability {
+    let mut description = format!("[{}] {}", advisory.ghsa_id, advisory.summary);
+    if !advisory.affected.is_empty() {
+        description.push_str(&format!(" (affected: {})", advisory.affected.join("; ")));
+    }
+    
+    // Fall back to the CVSS score when GitHub has not assigned a severity
+    let severity = advisory.severity
+        .or_else(|| advisory.cvss_score.map(Severity::from_cvss))
+        .map(|s| s.to_string());
+    
+    let mut vuln = crate::cveapi::models::create_full_vulnerability(
+        advisory.cve_id.clone().unwrap_or_else(|| advisory.ghsa_id.clone()),
+        description,
+        severity,
+        advisory.cvss_score,
+        Some(advisory.references.clone()),
+        None,
+        None,
+        None,
+        None,
+        advisory.cwe_id.clone(),
+        None,
+        None,
+        None,
+    );
+    vuln.cvss_vector = advisory.cvss_vector.clone();
+    
+    vuln
+}
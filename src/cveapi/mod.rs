@@ -2,19 +2,30 @@
 // CVE database API and vulnerability detection functionalities - Main module
 
 // Re-export all public components
-pub use self::cache::{init_cve_cache, get_from_cache, add_to_cache};
-pub use self::lookup::{lookup_vulnerability, lookup_vulnerability_nvd, lookup_vulnerability_mitre, lookup_vulnerability_circl};
-pub use self::detection::{check_service_vulnerabilities, match_offline_vulnerabilities, check_known_service_vulnerabilities};
-pub use self::enrichment::{check_exploit_db, check_active_exploitation, map_to_mitre_attack, lookup_cwe_for_cve};
-pub use self::models::{create_vulnerability, create_full_vulnerability, categorize_vulnerability, determine_attack_vector};
+pub use self::error::CveApiError;
+pub use self::cache::{init_cve_cache, get_from_cache, add_to_cache, load_from_disk, save_to_disk, default_cache_path};
+pub use self::lookup::{set_nvd_api_key, set_cve_source_order, cve_source_order, lookup_vulnerability, lookup_vulnerabilities, lookup_vulnerability_nvd, lookup_vulnerability_mitre, lookup_vulnerability_circl,
+                       lookup_vulnerability_osv, lookup_vulnerability_ghsa, query_osv_package};
+pub use self::detection::{check_service_vulnerabilities, match_offline_vulnerabilities, check_known_service_vulnerabilities, extract_product_versions, assess_security_headers,
+                          assess_tls_certificate, assess_tls_versions, assess_smb, assess_unauthenticated_database, assess_http_methods, assess_ssh_algorithms};
+pub use self::enrichment::{check_exploit_db, check_active_exploitation, map_to_mitre_attack, map_cwe_to_mitre_attack, lookup_cwe_for_cve,
+                           lookup_epss, lookup_epss_batch};
+pub use self::models::{create_vulnerability, create_full_vulnerability, categorize_vulnerability, determine_attack_vector,
+                       parse_cvss_vector, merge_vulnerabilities, CvssMetrics};
+pub use self::cpe::{Cpe, CpeMatch, banner_cpes, lookup_cpe_vulnerabilities, compare_versions};
+pub use self::offline_db::{OfflineCveDatabase, set_offline_database, offline_database, find_offline_vulnerabilities};
 pub use self::attack_path::{generate_attack_paths, extract_service_from_vulnerability, calculate_impact, 
                           generate_mitigations, build_attack_progression, get_technique_for_vulnerability, 
-                          generate_data_exfiltration_path, generate_lateral_movement_path, generate_ics_attack_path};
+                          generate_data_exfiltration_path, generate_lateral_movement_path,
+                          vulnerability_likelihood, likelihood_score, likelihood_label};
 
 // Submodules
 mod cache;
+mod error;
 mod lookup;
 mod detection;
 mod enrichment;
 mod models;
 mod attack_path;
+mod cpe;
+mod offline_db;
@@ -1,6 +1,22 @@
 // Vulnerability models and construction helpers
 
-use crate::models::Vulnerability;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Severity, Vulnerability};
+
+// Base metrics decomposed from a CVSS v2 or v3.x vector string
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CvssMetrics {
+    pub version: Option<String>,             // "3.1", "3.0" or "2.0"
+    pub attack_vector: Option<String>,       // Network, Adjacent Network, Local or Physical
+    pub attack_complexity: Option<String>,   // Low or High (v2 also Medium)
+    pub privileges_required: Option<String>, // None, Low or High (v2: authentication None, Single or Multiple)
+    pub user_interaction: Option<String>,    // None or Required (v3 only)
+    pub scope: Option<String>,               // Unchanged or Changed (v3 only)
+    pub confidentiality: Option<String>,     // None, Low or High (v2: None, Partial or Complete)
+    pub integrity: Option<String>,
+    pub availability: Option<String>,
+}
 
 /// Create a new vulnerability object with all fields properly initialized
 pub fn create_vulnerability(
@@ -43,11 +59,15 @@ pub fn create_full_vulnerability(
     mitre_tactics: Option<Vec<String>>,
     mitre_techniques: Option<Vec<String>>
 ) -> Vulnerability {
+    // Store severities in canonical form so every source uses the same casing
+    let severity = severity.map(|s| s.parse::<Severity>().map(|level| level.to_string()).unwrap_or(s));
+    
     Vulnerability {
         id,
         description,
         severity,
         cvss_score,
+        cvss_vector: None,
         references,
         actively_exploited,
         exploit_available,
@@ -57,6 +77,74 @@ pub fn create_full_vulnerability(
         attack_vector,
         mitre_tactics,
         mitre_techniques,
+        epss_score: None,
+    }
+}
+
+/// Combine vulnerabilities that share an ID into a single entry per ID
+///
+/// Fields missing from one entry are filled in from the others, and list
+/// fields (references, MITRE tactics and techniques) are unioned. The result
+/// is sorted by ID.
+pub fn merge_vulnerabilities(vulnerabilities: Vec<Vulnerability>) -> Vec<Vulnerability> {
+    let mut merged: Vec<Vulnerability> = Vec::new();
+    
+    for vuln in vulnerabilities {
+        match merged.iter_mut().find(|existing| existing.id == vuln.id) {
+            Some(existing) => merge_vulnerability(existing, vuln),
+            None => merged.push(vuln),
+        }
+    }
+    
+    merged.sort_by(|a, b| a.id.cmp(&b.id));
+    merged
+}
+
+/// Merge the fields of `other` into `target`, keeping values `target` already has
+fn merge_vulnerability(target: &mut Vulnerability, other: Vulnerability) {
+    // Prefer the more informative description
+    if target.description.len() < other.description.len() {
+        target.description = other.description;
+    }
+    
+    target.severity = target.severity.take().or(other.severity);
+    target.cvss_score = target.cvss_score.or(other.cvss_score);
+    target.epss_score = target.epss_score.or(other.epss_score);
+    target.cvss_vector = target.cvss_vector.take().or(other.cvss_vector);
+    target.mitigation = target.mitigation.take().or(other.mitigation);
+    target.category = target.category.take().or(other.category);
+    target.cwe_id = target.cwe_id.take().or(other.cwe_id);
+    target.attack_vector = target.attack_vector.take().or(other.attack_vector);
+    
+    // Any source reporting exploitation is enough to flag it
+    target.actively_exploited = merge_flags(target.actively_exploited, other.actively_exploited);
+    target.exploit_available = merge_flags(target.exploit_available, other.exploit_available);
+    
+    target.references = union_lists(target.references.take(), other.references);
+    target.mitre_tactics = union_lists(target.mitre_tactics.take(), other.mitre_tactics);
+    target.mitre_techniques = union_lists(target.mitre_techniques.take(), other.mitre_techniques);
+}
+
+/// Combine two optional flags, treating true from either side as true
+fn merge_flags(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a || b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Union two optional lists, keeping the order of first appearance
+fn union_lists(a: Option<Vec<String>>, b: Option<Vec<String>>) -> Option<Vec<String>> {
+    match (a, b) {
+        (Some(mut a), Some(b)) => {
+            for item in b {
+                if !a.contains(&item) {
+                    a.push(item);
+                }
+            }
+            Some(a)
+        },
+        (a, b) => a.or(b),
     }
 }
 
@@ -96,8 +184,18 @@ pub fn categorize_vulnerability(vuln_id: &str) -> String {
     }
 }
 
-/// Determine the attack vector based on service and banner
-pub fn determine_attack_vector(service: &str, _banner: &str) -> String {
+/// Determine the attack vector based on the CVSS vector, service and banner
+///
+/// When a CVSS vector is available its AV component is authoritative. Adjacent,
+/// local and physical vectors are reported as such; network vectors are refined
+/// into the service category (Web, Remote Access, ...) used by attack path analysis.
+pub fn determine_attack_vector(service: &str, _banner: &str, cvss_vector: Option<&str>) -> String {
+    if let Some(attack_vector) = cvss_vector.and_then(|vector| parse_cvss_vector(vector).attack_vector) {
+        if attack_vector != "Network" {
+            return attack_vector;
+        }
+    }
+    
     // This is a simplified implementation that could be expanded
     if service.contains("http") || service.contains("web") {
         "Web".to_string()
@@ -119,3 +217,143 @@ pub fn determine_attack_vector(service: &str, _banner: &str) -> String {
         "Network".to_string()
     }
 }
+
+/// Decompose a CVSS vector string (v2 or v3.x) into its base metrics
+///
+/// Unknown or missing components are left as None.
+pub fn parse_cvss_vector(vector: &str) -> CvssMetrics {
+    let mut metrics = CvssMetrics::default();
+    let is_v2 = !vector.starts_with("CVSS:3");
+    
+    for component in vector.trim().split('/') {
+        let (key, value) = match component.split_once(':') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        
+        let impact = |value: &str| match value {
+            "N" => Some("None"),
+            "L" => Some("Low"),
+            "H" => Some("High"),
+            "P" => Some("Partial"),
+            "C" => Some("Complete"),
+            _ => None,
+        };
+        
+        let parsed = match key {
+            "CVSS" => Some(value),
+            "AV" => match value {
+                "N" => Some("Network"),
+                "A" => Some("Adjacent Network"),
+                "L" => Some("Local"),
+                "P" => Some("Physical"),
+                _ => None,
+            },
+            "AC" => match value {
+                "L" => Some("Low"),
+                "M" => Some("Medium"),
+                "H" => Some("High"),
+                _ => None,
+            },
+            "PR" => impact(value),
+            "Au" => match value {
+                "N" => Some("None"),
+                "S" => Some("Single"),
+                "M" => Some("Multiple"),
+                _ => None,
+            },
+            "UI" => match value {
+                "N" => Some("None"),
+                "R" => Some("Required"),
+                _ => None,
+            },
+            "S" => match value {
+                "U" => Some("Unchanged"),
+                "C" => Some("Changed"),
+                _ => None,
+            },
+            "C" | "I" | "A" => impact(value),
+            _ => None,
+        }.map(String::from);
+        
+        match key {
+            "CVSS" => metrics.version = parsed,
+            "AV" => metrics.attack_vector = parsed,
+            "AC" => metrics.attack_complexity = parsed,
+            "PR" | "Au" => metrics.privileges_required = parsed,
+            "UI" => metrics.user_interaction = parsed,
+            "S" => metrics.scope = parsed,
+            "C" => metrics.confidentiality = parsed,
+            "I" => metrics.integrity = parsed,
+            "A" => metrics.availability = parsed,
+            _ => {}
+        }
+    }
+    
+    if is_v2 && metrics.attack_vector.is_some() {
+        metrics.version = Some("2.0".to_string());
+    }
+    
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn strings(items: &[&str]) -> Option<Vec<String>> {
+        Some(items.iter().map(|item| item.to_string()).collect())
+    }
+    
+    #[test]
+    fn duplicate_vulnerabilities_merge_fields_from_every_source() {
+        // What a pattern match knows: a mitigation and MITRE mapping, no scoring
+        let from_pattern = create_full_vulnerability(
+            "CVE-2021-41773".to_string(),
+            "Path traversal".to_string(),
+            None,
+            None,
+            strings(&["https://httpd.apache.org/security/vulnerabilities_24.html"]),
+            None,
+            Some(true),
+            Some("Upgrade to Apache 2.4.51".to_string()),
+            None,
+            None,
+            None,
+            strings(&["Initial Access"]),
+            strings(&["T1190"]),
+        );
+        // What NVD knows: scoring, a fuller description and its own references
+        let from_nvd = create_full_vulnerability(
+            "CVE-2021-41773".to_string(),
+            "A path traversal flaw in Apache HTTP Server 2.4.49".to_string(),
+            Some("HIGH".to_string()),
+            Some(7.5),
+            strings(&["https://nvd.nist.gov/vuln/detail/CVE-2021-41773", "https://httpd.apache.org/security/vulnerabilities_24.html"]),
+            Some(true),
+            None,
+            None,
+            None,
+            Some("CWE-22".to_string()),
+            None,
+            None,
+            strings(&["T1190", "T1083"]),
+        );
+        let other = create_vulnerability("CVE-2019-0211".to_string(), "Privilege escalation".to_string(), None, None, None);
+        
+        let merged = merge_vulnerabilities(vec![from_pattern, other, from_nvd]);
+        
+        assert_eq!(merged.iter().map(|vuln| vuln.id.as_str()).collect::<Vec<&str>>(), vec!["CVE-2019-0211", "CVE-2021-41773"]);
+        let vuln = &merged[1];
+        assert_eq!(vuln.description, "A path traversal flaw in Apache HTTP Server 2.4.49");
+        assert_eq!(vuln.severity.as_deref(), Some("HIGH"));
+        assert_eq!(vuln.cvss_score, Some(7.5));
+        assert_eq!(vuln.cwe_id.as_deref(), Some("CWE-22"));
+        assert_eq!(vuln.mitigation.as_deref(), Some("Upgrade to Apache 2.4.51"));
+        assert_eq!(vuln.actively_exploited, Some(true));
+        assert_eq!(vuln.exploit_available, Some(true));
+        assert_eq!(vuln.references, strings(&["https://httpd.apache.org/security/vulnerabilities_24.html", "https://nvd.nist.gov/vuln/detail/CVE-2021-41773"]));
+        assert_eq!(vuln.mitre_tactics, strings(&["Initial Access"]));
+        assert_eq!(vuln.mitre_techniques, strings(&["T1190", "T1083"]));
+    }
+}
@@ -0,0 +1,397 @@
+// Local CVE database imported from the NVD JSON 1.1 data feeds for offline scanning
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use flate2::read::GzDecoder;
+use log::{debug, info};
+use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use crate::models::Vulnerability;
+use crate::cveapi::cpe::{Cpe, CpeMatch};
+use crate::cveapi::models::create_vulnerability;
+
+// Database consulted by offline scans, if one was imported
+lazy_static::lazy_static! {
+    static ref OFFLINE_DATABASE: RwLock<Option<Arc<OfflineCveDatabase>>> = RwLock::new(None);
+}
+
+/// Use `database` for offline vulnerability lookups, or stop using one with None
+pub fn set_offline_database(database: Option<OfflineCveDatabase>) {
+    *OFFLINE_DATABASE.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = database.map(Arc::new);
+}
+
+/// The database used for offline vulnerability lookups, if one was imported
+pub fn offline_database() -> Option<Arc<OfflineCveDatabase>> {
+    OFFLINE_DATABASE.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Find vulnerabilities for the products named in a banner in the imported database
+pub fn find_offline_vulnerabilities(banner: &str) -> Vec<Vulnerability> {
+    match offline_database() {
+        Some(database) => crate::cveapi::cpe::banner_cpes(banner).iter()
+            .flat_map(|cpe| database.find_by_cpe(cpe))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// CVEs indexed by id and by the "vendor:product" of their vulnerable CPE matches
+#[derive(Debug, Default)]
+pub struct OfflineCveDatabase {
+    vulnerabilities: Vec<Vulnerability>,
+    cpe_matches: Vec<Vec<CpeMatch>>,          // Vulnerable CPE matches, parallel to `vulnerabilities`
+    by_id: HashMap<String, usize>,
+    by_product: HashMap<String, Vec<usize>>,
+}
+
+impl OfflineCveDatabase {
+    pub fn new() -> Self {
+        OfflineCveDatabase::default()
+    }
+    
+    /// Import every `nvdcve-1.1-*.json` or `.json.gz` feed in a directory
+    pub fn import_feed_dir(dir: &Path) -> Result<OfflineCveDatabase, Box<dyn Error>> {
+        let mut feeds: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                name.starts_with("nvdcve-") && (name.ends_with(".json") || name.ends_with(".json.gz"))
+            })
+            .collect();
+    
+        if feeds.is_empty() {
+            return Err(format!("No nvdcve-*.json(.gz) feeds found in {}", dir.display()).into());
+        }
+    
+        // Yearly feeds first, then "modified" and "recent" so their updates win
+        feeds.sort_by_key(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+            (name.contains("modified") || name.contains("recent"), name)
+        });
+    
+        let mut database = OfflineCveDatabase::new();
+        for feed in feeds {
+            let count = database.import_feed_file(&feed)
+                .map_err(|e| format!("Failed to import {}: {}", feed.display(), e))?;
+            debug!("Imported {} CVEs from {}", count, feed.display());
+        }
+        info!("Offline CVE database holds {} CVEs", database.len());
+    
+        Ok(database)
+    }
+    
+    /// Import one feed file, decompressing it if the name ends in `.gz`
+    pub fn import_feed_file(&mut self, path: &Path) -> Result<usize, Box<dyn Error>> {
+        let file = BufReader::new(File::open(path)?);
+    
+        if path.extension().and_then(|e| e.to_str()) == Some("gz") {
+            self.import_feed(BufReader::new(GzDecoder::new(file)))
+        } else {
+            self.import_feed(file)
+        }
+    }
+    
+    /// Import an NVD JSON 1.1 feed, returning the number of CVEs read
+    ///
+    /// Items are indexed one at a time as the feed is parsed, so the whole
+    /// document is never held in memory.
+    pub fn import_feed<R: Read>(&mut self, reader: R) -> Result<usize, Box<dyn Error>> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let count = FeedSeed { database: self }.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+    
+        Ok(count)
+    }
+    
+    /// Number of CVEs in the database
+    pub fn len(&self) -> usize {
+        self.vulnerabilities.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.vulnerabilities.is_empty()
+    }
+    
+    /// Look up a CVE by id
+    pub fn get(&self, cve_id: &str) -> Option<&Vulnerability> {
+        self.by_id.get(&cve_id.to_uppercase()).map(|&index| &self.vulnerabilities[index])
+    }
+    
+    /// Find the CVEs whose vulnerable configurations include this product version
+    pub fn find_by_cpe(&self, cpe: &Cpe) -> Vec<Vulnerability> {
+        let key = format!("{}:{}", cpe.vendor, cpe.product);
+    
+        self.by_product.get(&key)
+            .into_iter()
+            .flatten()
+            .filter(|&&index| self.cpe_matches[index].iter().any(|m| m.applies_to(cpe)))
+            .map(|&index| {
+                let mut vuln = self.vulnerabilities[index].clone();
+                vuln.mitigation = Some(format!("Update {} {} to a release that fixes this vulnerability", cpe.product, cpe.version));
+                vuln
+            })
+            .collect()
+    }
+    
+    /// Index one feed item, replacing an earlier entry for the same CVE
+    fn insert(&mut self, item: FeedItem) {
+        let mut cpe_matches = Vec::new();
+        item.configurations.nodes.iter().for_each(|node| node.collect_vulnerable(&mut cpe_matches));
+    
+        // CVEs without affected products can't be matched against banners
+        if cpe_matches.is_empty() {
+            return;
+        }
+    
+        let vuln = item.into_vulnerability();
+        let index = match self.by_id.get(&vuln.id) {
+            Some(&index) => {
+                self.vulnerabilities[index] = vuln;
+                index
+            },
+            None => {
+                self.by_id.insert(vuln.id.clone(), self.vulnerabilities.len());
+                self.vulnerabilities.push(vuln);
+                self.cpe_matches.push(Vec::new());
+                self.vulnerabilities.len() - 1
+            },
+        };
+    
+        for key in cpe_matches.iter().filter_map(|m| m.product_key()) {
+            let entries = self.by_product.entry(key).or_default();
+            if !entries.contains(&index) {
+                entries.push(index);
+            }
+        }
+        self.cpe_matches[index] = cpe_matches;
+    }
+}
+
+impl fmt::Display for OfflineCveDatabase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} CVEs covering {} products", self.len(), self.by_product.len())
+    }
+}
+
+// Walks the top-level feed object, streaming the CVE_Items array into the database
+struct FeedSeed<'a> {
+    database: &'a mut OfflineCveDatabase,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for FeedSeed<'a> {
+    type Value = usize;
+    
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for FeedSeed<'a> {
+    type Value = usize;
+    
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an NVD JSON 1.1 feed object")
+    }
+    
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<usize, A::Error> {
+        let database = self.database;
+        let mut count = 0;
+    
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "CVE_Items" {
+                count += map.next_value_seed(ItemsSeed { database: &mut *database })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+    
+        Ok(count)
+    }
+}
+
+// Indexes each element of CVE_Items as soon as it has been parsed
+struct ItemsSeed<'a> {
+    database: &'a mut OfflineCveDatabase,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ItemsSeed<'a> {
+    type Value = usize;
+    
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ItemsSeed<'a> {
+    type Value = usize;
+    
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of CVE items")
+    }
+    
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<usize, A::Error> {
+        let mut count = 0;
+        while let Some(item) = seq.next_element::<FeedItem>()? {
+            self.database.insert(item);
+            count += 1;
+        }
+    
+        Ok(count)
+    }
+}
+
+/// Data structures for the NVD JSON 1.1 feed items, limited to the fields used
+#[derive(Deserialize)]
+struct FeedItem {
+    cve: FeedCve,
+    #[serde(default)]
+    configurations: FeedConfigurations,
+    #[serde(default)]
+    impact: FeedImpact,
+}
+
+#[derive(Deserialize)]
+struct FeedCve {
+    #[serde(rename = "CVE_data_meta")]
+    meta: FeedMeta,
+    #[serde(default)]
+    problemtype: FeedProblemType,
+    #[serde(default)]
+    references: FeedReferences,
+    #[serde(default)]
+    description: FeedDescription,
+}
+
+#[derive(Deserialize)]
+struct FeedMeta {
+    #[serde(rename = "ID")]
+    id: String,
+}
+
+#[derive(Deserialize, Default)]
+struct FeedProblemType {
+    problemtype_data: Vec<FeedProblemTypeData>,
+}
+
+#[derive(Deserialize)]
+struct FeedProblemTypeData {
+    description: Vec<FeedLangValue>,
+}
+
+#[derive(Deserialize, Default)]
+struct FeedReferences {
+    reference_data: Vec<FeedReference>,
+}
+
+#[derive(Deserialize)]
+struct FeedReference {
+    url: String,
+}
+
+#[derive(Deserialize, Default)]
+struct FeedDescription {
+    description_data: Vec<FeedLangValue>,
+}
+
+#[derive(Deserialize)]
+struct FeedLangValue {
+    #[serde(default)]
+    lang: String,
+    value: String,
+}
+
+#[derive(Deserialize, Default)]
+struct FeedConfigurations {
+    nodes: Vec<FeedNode>,
+}
+
+#[derive(Deserialize)]
+struct FeedNode {
+    #[serde(default)]
+    children: Vec<FeedNode>,
+    #[serde(default)]
+    cpe_match: Vec<CpeMatch>,
+}
+
+impl FeedNode {
+    /// Collect the vulnerable CPE matches of this node and its children
+    fn collect_vulnerable(&self, matches: &mut Vec<CpeMatch>) {
+        matches.extend(self.cpe_match.iter().filter(|m| m.vulnerable).cloned());
+        for child in &self.children {
+            child.collect_vulnerable(matches);
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct FeedImpact {
+    #[serde(rename = "baseMetricV3")]
+    base_metric_v3: Option<FeedBaseMetricV3>,
+    #[serde(rename = "baseMetricV2")]
+    base_metric_v2: Option<FeedBaseMetricV2>,
+}
+
+#[derive(Deserialize)]
+struct FeedBaseMetricV3 {
+    #[serde(rename = "cvssV3")]
+    cvss_v3: FeedCvss,
+}
+
+#[derive(Deserialize)]
+struct FeedBaseMetricV2 {
+    #[serde(rename = "cvssV2")]
+    cvss_v2: FeedCvss,
+    severity: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeedCvss {
+    vector_string: Option<String>,
+    base_score: Option<f32>,
+    base_severity: Option<String>,
+}
+
+impl FeedItem {
+    /// Convert the feed item to a vulnerability, preferring CVSS v3 over v2
+    fn into_vulnerability(self) -> Vulnerability {
+        let description = self.cve.description.description_data.iter()
+            .find(|d| d.lang == "en")
+            .map_or("No description available", |d| &d.value)
+            .to_string();
+    
+        let references: Vec<String> = self.cve.references.reference_data.into_iter()
+            .map(|r| r.url)
+            .collect();
+    
+        let (severity, cvss_score, cvss_vector) = if let Some(metric) = self.impact.base_metric_v3 {
+            (metric.cvss_v3.base_severity, metric.cvss_v3.base_score, metric.cvss_v3.vector_string)
+        } else if let Some(metric) = self.impact.base_metric_v2 {
+            (metric.severity, metric.cvss_v2.base_score, metric.cvss_v2.vector_string)
+        } else {
+            (None, None, None)
+        };
+    
+        let cwe_id = self.cve.problemtype.problemtype_data.iter()
+            .flat_map(|p| p.description.iter())
+            .map(|d| d.value.clone())
+            .find(|value| value.starts_with("CWE-"));
+    
+        let mut vuln = create_vulnerability(
+            self.cve.meta.id.to_uppercase(),
+            description,
+            severity,
+            cvss_score,
+            Some(references).filter(|r| !r.is_empty()),
+        );
+        vuln.cvss_vector = cvss_vector;
+        vuln.cwe_id = cwe_id;
+    
+        vuln
+    }
+}
@@ -12,21 +12,38 @@ pub mod report;
 pub mod resolver;
 pub mod cveapi;
 pub mod plugins;
+pub mod credentials;
+pub mod schedule;
+pub mod progress;
+pub mod proxy;
+pub mod notify;
+pub mod profiles;
+#[cfg(feature = "async")]
+pub mod async_scanner;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "passive")]
+pub mod passive;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 
 // Re-exports for convenience
-pub use models::{ScanConfig, ScanResult, Vulnerability, PortResult, HostInfo};
+pub use models::{ScanConfig, ScanConfigBuilder, ScanPlan, ScanResult, Vulnerability, PortResult, HostInfo, Severity, TlsVersion};
 pub use constants::{VERSION, TOOL_NAME};
-pub use scanner::{scan_port_range, quick_scan, ot_scan, check_vulnerability, discover_hosts};
+pub use scanner::{scan_port_range, quick_scan, ot_scan, check_vulnerability, discover_hosts, plan_scan, check_scope};
+// `check_vulnerabilities` at the root already checks a service banner, so the batch host check gets its own name
+pub use scanner::check_vulnerabilities as check_vulnerability_batch;
 pub use scanner as scanner_module;
 pub use plugins::{VulnerabilityDetectorPlugin, PluginRegistry};
+pub use progress::{ScanProgress, NoProgress, JsonlProgress};
 
 // Function to get version
 pub fn version() -> &'static str {
     constants::VERSION
 }
 
-// Wrapper function for scanning
-pub fn scan(config: ScanConfig) -> Vec<ScanResult> {
+// Wrapper function for scanning; fails when a target lies outside the allowed scope
+pub fn scan(config: ScanConfig) -> std::io::Result<Vec<ScanResult>> {
     scanner::scan(config)
 }
 
@@ -70,12 +87,12 @@ pub fn check_port(host: &str, port: u16, timeout_ms: u64) -> bool {
 pub fn is_host_online(host: &str, timeout_ms: u64) -> bool {
     // Parse host to IpAddr
     if let Ok(ip) = host.parse::<IpAddr>() {
-        utils::ping_host(&ip) || utils::tcp_ping_host(&ip, timeout_ms)
+        utils::host_responds(&ip, timeout_ms)
     } else {
         // Try to resolve hostname
         if let Ok(ips) = resolver::resolve_hostname(host) {
             for ip in ips {
-                if utils::ping_host(&ip) || utils::tcp_ping_host(&ip, timeout_ms) {
+                if utils::host_responds(&ip, timeout_ms) {
                     return true;
                 }
             }
@@ -125,7 +142,7 @@ pub fn get_banner(host: &str, port: u16, timeout_ms: u64) -> Option<String> {
 
 /// Identify service on a port
 pub fn identify_service(port: u16, banner: &str) -> String {
-    utils::identify_service(port, banner)
+    utils::identify_service_name(port, banner)
 }
 
 /// Check vulnerabilities for a service
@@ -136,9 +153,13 @@ pub fn check_vulnerabilities(service: &str, banner: &str, offline_mode: bool) ->
 /// Generate a report from scan results
 pub fn generate_report(results: &[ScanResult], format: &str, filename: &str) -> std::io::Result<()> {
     match format.to_uppercase().as_str() {
-        "TEXT" => report::generate_text_report(results, filename),
-        "HTML" => report::generate_html_report(results, filename),
-        "JSON" => report::generate_json_report(results, filename),
+        "TEXT" => report::generate_text_report(results, filename, None, None),
+        "HTML" => report::generate_html_report(results, filename, None, None),
+        "JSON" => report::generate_json_report(results, filename, None, None),
+        "SARIF" => report::generate_sarif_report(results, filename, None, None),
+        "MARKDOWN" | "MD" => report::generate_markdown_report(results, filename, None, None),
+        "JSONL" => report::generate_jsonl_report(results, filename, None),
+        "NMAP-XML" | "NMAP" => report::generate_nmap_xml_report(results, filename, None, None),
         _ => Err(std::io::Error::new(
             std::io::ErrorKind::InvalidInput,
             format!("Unsupported report format: {}", format),
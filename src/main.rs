@@ -5,94 +5,500 @@ use clap::App;
 use clap::Arg;
 use clap::ArgMatches;
 use colored::*;
-use std::time::Instant;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use chrono::Local;
+use log::LevelFilter;
 
-use rustnet_scan::models::ScanConfig;
+use rustnet_scan::models::{CveSource, HostInfo, ReportFilter, ScanConfig, ScanMetadata, ScanPlan, ScanResult, Severity};
+use rustnet_scan::progress::{JsonlProgress, ScanProgress};
 use rustnet_scan::constants;
 use rustnet_scan::cveapi;
+use rustnet_scan::plugins::PluginRegistry;
+use rustnet_scan::profiles;
+use rustnet_scan::proxy;
 use rustnet_scan::report;
+use rustnet_scan::resolver;
 use rustnet_scan::scanner;
+use rustnet_scan::schedule;
+#[cfg(feature = "tui")]
+use rustnet_scan::tui;
+#[cfg(feature = "metrics")]
+use rustnet_scan::metrics;
 
 #[cfg(not(debug_assertions))]
 const DEFAULT_THREADS: &str = "50";
 #[cfg(debug_assertions)]
 const DEFAULT_THREADS: &str = "10";
 
+// Process exit codes
+const EXIT_CLEAN: i32 = 0;       // Scan finished with no findings at or above --fail-on
+const EXIT_FINDINGS: i32 = 1;    // Findings at or above --fail-on
+const EXIT_USAGE: i32 = 2;       // Invalid arguments or setup failure before scanning
+const EXIT_NO_HOSTS: i32 = 3;    // No target host was reachable
+
+// Hosts listed individually in a text dry-run plan
+const DRY_RUN_LISTED_HOSTS: usize = 256;
+
 fn main() {
-    // Initialize CVE cache
-    cveapi::init_cve_cache();
-    
     // Parse command-line arguments
     let matches = parse_args();
     
+    // Describe the detector plugins and stop; no target is needed for this
+    if matches.is_present("list-plugins") {
+        let config = ScanConfig {
+            enabled_plugins: matches.values_of("enable-plugin").map(plugin_names),
+            disabled_plugins: matches.values_of("disable-plugin").map(plugin_names).unwrap_or_default(),
+            ..ScanConfig::default()
+        };
+        print_plugins(&PluginRegistry::with_config(&config));
+        std::process::exit(EXIT_CLEAN);
+    }
+    
     // Validate and process arguments
-    let config = match build_config(&matches) {
+    let fail_on = match matches.value_of("fail-on").map(|level| level.parse::<Severity>()).transpose() {
+        Ok(fail_on) => fail_on,
+        Err(err) => {
+            eprintln!("{} {}", "Error:".red().bold(), err);
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+    
+    let report_filter = match report_filter(&matches) {
+        Ok(filter) => filter,
+        Err(err) => {
+            eprintln!("{} {}", "Error:".red().bold(), err);
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+    
+    let mut config = match build_config(&matches) {
         Ok(config) => config,
         Err(err) => {
             eprintln!("{} {}", "Error:".red().bold(), err);
-            std::process::exit(1);
+            std::process::exit(EXIT_USAGE);
         }
     };
     
-    // Display banner
-    print_banner();
+    init_logging(&matches, &config);
+    
+    // Explain targets that yield no addresses; with none left there is nothing to scan.
+    // The addresses found are kept in the configuration and reused by the scan.
+    let target_errors = scanner::check_targets(&mut config);
+    for err in &target_errors {
+        eprintln!("{} Skipping target: {}", "Warning:".yellow().bold(), err);
+    }
+    let target_spec_count = usize::from(!config.target.is_empty()) + config.input_targets.len();
+    if !target_errors.is_empty() && target_errors.len() == target_spec_count {
+        eprintln!("{} None of the targets could be resolved to an address", "Error:".red().bold());
+        std::process::exit(EXIT_NO_HOSTS);
+    }
+    
+    // Refuse to go on if any target lies outside the allowed scope
+    if let Err(err) = scanner::check_scope(&config) {
+        eprintln!("{} {}", "Error:".red().bold(), err);
+        std::process::exit(EXIT_USAGE);
+    }
+    
+    // Show what would be scanned and stop before any packets are sent
+    if matches.is_present("dry-run") {
+        print_scan_plan(&scanner::plan_scan(&config), &config.output_format);
+        std::process::exit(EXIT_CLEAN);
+    }
+    
+    // Initialize CVE cache from disk unless disabled
+    let cache_path = match cache_settings(&matches) {
+        Ok(settings) => settings.map(|(path, ttl)| {
+            if path.exists() {
+                if let Err(e) = cveapi::load_from_disk(&path, ttl) {
+                    eprintln!("{} Failed to load CVE cache from {}: {}", "Warning:".yellow().bold(), path.display(), e);
+                }
+            }
+            path
+        }),
+        Err(err) => {
+            eprintln!("{} {}", "Error:".red().bold(), err);
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+    
+    // Import the NVD JSON feeds used for offline vulnerability lookups
+    if let Some(dir) = matches.value_of("cve-feed-dir") {
+        match cveapi::OfflineCveDatabase::import_feed_dir(Path::new(dir)) {
+            Ok(database) => {
+                eprintln!("{} {} from {}", "Offline CVE database:".green().bold(), database, dir);
+                if !config.offline_mode {
+                    eprintln!("{} The offline CVE database is only consulted with --offline", "Warning:".yellow().bold());
+                }
+                cveapi::set_offline_database(Some(database));
+            },
+            Err(e) => {
+                eprintln!("{} Failed to import CVE feeds from {}: {}", "Error:".red().bold(), dir, e);
+                std::process::exit(EXIT_USAGE);
+            }
+        }
+    }
+    
+    // "-" streams JSON lines to stdout, which then carries nothing else
+    let formats: Vec<String> = config.output_format.split(',').map(|f| f.to_string()).collect();
+    let output = matches.value_of("output");
+    let output_dir = matches.value_of("output-dir");
+    let stream_to_stdout = output == Some("-");
+    if stream_to_stdout && formats != ["JSONL"] {
+        eprintln!("{} Writing to stdout (--output -) requires --format JSONL", "Error:".red().bold());
+        std::process::exit(EXIT_USAGE);
+    }
+    if output.is_some() && formats.len() > 1 {
+        eprintln!("{} --output writes a single format; use --output-dir to write several", "Error:".red().bold());
+        std::process::exit(EXIT_USAGE);
+    }
+    if output.is_some() && output_dir.is_some() {
+        eprintln!("{} --output and --output-dir can't be combined", "Error:".red().bold());
+        std::process::exit(EXIT_USAGE);
+    }
+    if let Some(dir) = output_dir {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!("{} Failed to create output directory {}: {}", "Error:".red().bold(), dir, e);
+            std::process::exit(EXIT_USAGE);
+        }
+    }
+    
+    // Sweep for live hosts and write the inventory instead of scanning ports
+    if matches.is_present("discover-only") {
+        std::process::exit(run_discovery(&config, &formats, output, output_dir));
+    }
+    if formats.iter().any(|format| format == "CSV") {
+        eprintln!("{} CSV output is only available with --discover-only", "Error:".red().bold());
+        std::process::exit(EXIT_USAGE);
+    }
+    
+    // The dashboard takes over the terminal for the length of the scan
+    let use_dashboard = matches.is_present("tui");
+    if use_dashboard && !cfg!(feature = "tui") {
+        eprintln!("{} The dashboard (--tui) requires building with the 'tui' feature", "Error:".red().bold());
+        std::process::exit(EXIT_USAGE);
+    }
+    let metrics_addr = matches.value_of("metrics-addr");
+    if metrics_addr.is_some() && !cfg!(feature = "metrics") {
+        eprintln!("{} Metrics export (--metrics-addr) requires building with the 'metrics' feature", "Error:".red().bold());
+        std::process::exit(EXIT_USAGE);
+    }
+    if use_dashboard && stream_to_stdout {
+        eprintln!("{} --tui can't be combined with streaming to stdout (--output -)", "Error:".red().bold());
+        std::process::exit(EXIT_USAGE);
+    }
     
-    // Display scan information
-    println!("{} {}", "Target:".green().bold(), config.target);
-    println!("{} {}", "Ports:".green().bold(), 
-        if config.ports.is_empty() { "Common ports".to_string() } else { config.ports.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(",") });
-    println!("{} {}", "Threads:".green().bold(), config.threads);
-    println!("{} {}", "Timeout:".green().bold(), format!("{}ms", config.timeout_ms));
-    println!("{} {}", "Randomize scan:".green().bold(), config.randomize_scan);
-    println!("{} {}", "Output format:".green().bold(), config.output_format);
-    println!();
+    // Load the previous scan up front so a bad path fails before scanning
+    let previous_results = match matches.value_of("diff") {
+        Some(path) => match report::load_json_report(path) {
+            Ok(results) => Some(results),
+            Err(e) => {
+                eprintln!("{} Failed to load previous report {}: {}", "Error:".red().bold(), path, e);
+                std::process::exit(EXIT_USAGE);
+            }
+        },
+        None => None,
+    };
+    
+    if !stream_to_stdout {
+        // Display banner
+        print_banner();
+        
+        // Display scan information
+        if let Some(path) = matches.value_of("config") {
+            println!("{} {}", "Profile:".green().bold(), path);
+        }
+        if !config.target.is_empty() {
+            println!("{} {}", "Target:".green().bold(), config.target);
+        }
+        if let Some(path) = matches.value_of("input-file") {
+            println!("{} {} ({} entries)", "Input file:".green().bold(), path, config.input_targets.len());
+        }
+        if let Some(interface) = &config.passive_interface {
+            println!("{} {} for {}s", "Passive capture:".green().bold(), interface, config.passive_duration_secs);
+        }
+        if let Some(path) = &config.passive_pcap {
+            println!("{} {}", "Passive capture file:".green().bold(), path);
+        }
+        let port_description = if !config.ports.is_empty() {
+            config.ports.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(",")
+        } else if let Some(count) = config.top_ports {
+            format!("Top {} ports", count)
+        } else {
+            "Common ports".to_string()
+        };
+        println!("{} {}", "Ports:".green().bold(), port_description);
+        println!("{} {}", "Threads:".green().bold(), config.threads);
+        println!("{} {}", "Timeout:".green().bold(), format!("{}ms", config.timeout_ms));
+        if let Some(rate) = config.max_pps {
+            println!("{} {}/s", "Rate limit:".green().bold(), rate);
+        }
+        if let Some(max_time) = config.max_duration_secs {
+            println!("{} {}s", "Time budget:".green().bold(), max_time);
+        }
+        match config.random_seed {
+            Some(seed) if config.randomize_scan => println!("{} true (seed {})", "Randomize scan:".green().bold(), seed),
+            _ => println!("{} {}", "Randomize scan:".green().bold(), config.randomize_scan),
+        }
+        println!("{} {}", "Output format:".green().bold(), config.output_format);
+        if let Some(window) = &config.scan_window {
+            println!("{} {}", "Scan window:".green().bold(), schedule::format_window(window));
+        }
+        println!();
+    }
+    if config.check_default_credentials {
+        eprintln!("{} Default credential checks are enabled. Login attempts will be made against", "WARNING:".red().bold());
+        eprintln!("         discovered FTP, HTTP, SNMP and SSH services. This may trigger intrusion alerts");
+        eprintln!("         or lock out accounts. Only use it on systems you are authorized to test.");
+        eprintln!();
+    }
     
     // Record scan start time
     let start_time = Instant::now();
+    let start_timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
     
-    println!("{}", "Starting network scan...".cyan().bold());
+    if !stream_to_stdout {
+        println!("{}", "Starting network scan...".cyan().bold());
+    }
     
-    // Perform the scan
-    let scan_results = scanner::scan(config.clone());
+    if let Some(window) = &config.scan_window {
+        if !schedule::is_within_window(window, chrono::Utc::now()) {
+            eprintln!("{}", "Outside the scan window, waiting for it to open...".yellow());
+        }
+    }
     
-    // Print summary
-    println!("\n{} {} hosts, {} open ports, {} vulnerabilities", 
-        "Found:".green().bold(),
-        scan_results.len(),
-        scan_results.iter().map(|r| r.open_ports.len()).sum::<usize>(),
-        scan_results.iter().flat_map(|r| &r.open_ports).map(|p| p.vulnerabilities.len()).sum::<usize>()
-    );
-    
-    // Generate report based on chosen format
+    // Stop the scan on Ctrl-C but still report what was found; a second Ctrl-C exits immediately
+    let cancel = Arc::new(AtomicBool::new(false));
+    let handler_cancel = Arc::clone(&cancel);
+    if let Err(e) = ctrlc::set_handler(move || {
+        if handler_cancel.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        eprintln!("\n{} Stopping scan, waiting for in-flight connections to time out...", "Interrupted:".yellow().bold());
+    }) {
+        eprintln!("{} Failed to install Ctrl-C handler: {}", "Warning:".yellow().bold(), e);
+    }
+    
+    // Name the reports up front since JSON lines are written while scanning
     let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
-    let output_filename = format!("scan_report_{}.{}", timestamp, config.output_format.to_lowercase());
+    let report_files: Vec<(String, String)> = formats.iter()
+        .map(|format| {
+            let filename = output.map(|o| o.to_string())
+                .unwrap_or_else(|| report_filename(output_dir, &timestamp, format));
+            (format.clone(), filename)
+        })
+        .collect();
+    let jsonl_filename = report_files.iter()
+        .find(|(format, _)| format == "JSONL")
+        .map(|(_, filename)| filename.clone());
     
-    match config.output_format.as_str() {
-        "TEXT" => {
-            if let Err(e) = report::generate_text_report(&scan_results, &output_filename) {
-                eprintln!("{} Failed to generate text report: {}", "Error:".red().bold(), e);
+    // Perform the scan, showing a live counter of progress or the dashboard
+    let console = ConsoleProgress::new(!stream_to_stdout && !use_dashboard);
+    #[cfg(feature = "tui")]
+    let dashboard = if use_dashboard {
+        match tui::start_dashboard(scanner::count_targets(&config), Arc::clone(&cancel), &console) {
+            Ok(dashboard) => Some(dashboard),
+            Err(e) => {
+                eprintln!("{} Failed to start the dashboard: {}", "Error:".red().bold(), e);
+                std::process::exit(EXIT_USAGE);
             }
-        },
-        "HTML" => {
-            if let Err(e) = report::generate_html_report(&scan_results, &output_filename) {
-                eprintln!("{} Failed to generate HTML report: {}", "Error:".red().bold(), e);
+        }
+    } else {
+        None
+    };
+    #[cfg(feature = "tui")]
+    let progress: &dyn ScanProgress = match &dashboard {
+        Some((dashboard_progress, _)) => dashboard_progress,
+        None => &console,
+    };
+    #[cfg(not(feature = "tui"))]
+    let progress: &dyn ScanProgress = &console;
+    
+    // Count scan events for Prometheus while scanning
+    #[cfg(feature = "metrics")]
+    let metrics_progress;
+    #[cfg(feature = "metrics")]
+    let progress: &dyn ScanProgress = match metrics_addr {
+        Some(addr) => {
+            let scan_metrics = Arc::new(metrics::ScanMetrics::new());
+            match metrics::serve(addr, Arc::clone(&scan_metrics)) {
+                Ok(bound) => eprintln!("{} http://{}/metrics", "Metrics:".green().bold(), bound),
+                Err(e) => {
+                    eprintln!("{} Failed to serve metrics on {}: {}", "Error:".red().bold(), addr, e);
+                    std::process::exit(EXIT_USAGE);
+                }
             }
+            metrics_progress = metrics::MetricsProgress::new(scan_metrics, progress);
+            &metrics_progress
         },
-        "JSON" => {
-            if let Err(e) = report::generate_json_report(&scan_results, &output_filename) {
-                eprintln!("{} Failed to generate JSON report: {}", "Error:".red().bold(), e);
+        None => progress,
+    };
+    
+    let scan_results = if let Some(jsonl_filename) = &jsonl_filename {
+        let writer: Box<dyn Write + Send> = if stream_to_stdout {
+            Box::new(std::io::stdout())
+        } else {
+            match std::fs::File::create(jsonl_filename) {
+                Ok(file) => Box::new(file),
+                Err(e) => {
+                    eprintln!("{} Failed to create {}: {}", "Error:".red().bold(), jsonl_filename, e);
+                    std::process::exit(EXIT_USAGE);
+                }
+            }
+        };
+        let progress = JsonlProgress::new(writer, progress).with_filter(report_filter.clone());
+        scanner::run_scan(config.clone(), &progress, &cancel)
+    } else {
+        scanner::run_scan(config.clone(), progress, &cancel)
+    };
+    
+    // Close the dashboard and give the terminal back before printing the summary
+    #[cfg(feature = "tui")]
+    if let Some((dashboard_progress, handle)) = dashboard {
+        drop(dashboard_progress);
+        match handle.join() {
+            Ok(Ok(())) => {},
+            Ok(Err(e)) => eprintln!("{} Dashboard failed: {}", "Warning:".yellow().bold(), e),
+            Err(_) => eprintln!("{} Dashboard thread panicked", "Warning:".yellow().bold()),
+        }
+    }
+    
+    let scan_results = match scan_results {
+        Ok(results) => results,
+        Err(err) => {
+            eprintln!("{} {}", "Error:".red().bold(), err);
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+    
+    let truncated = if scanner::time_budget_exceeded() {
+        eprintln!("\n{}", "Time budget exceeded, reporting partial results".yellow().bold());
+        Some(format!("time budget of {}s exceeded", config.max_duration_secs.unwrap_or_default()))
+    } else if cancel.load(Ordering::SeqCst) {
+        eprintln!("\n{}", "Scan cancelled, reporting partial results".yellow().bold());
+        Some("cancelled by the user".to_string())
+    } else {
+        None
+    };
+    
+    // Print summary
+    if !stream_to_stdout {
+        println!("\n{} {} hosts, {} open ports, {} vulnerabilities", 
+            "Found:".green().bold(),
+            scan_results.len(),
+            scan_results.iter().map(|r| r.open_ports.len()).sum::<usize>(),
+            scan_results.iter()
+                .map(|r| r.open_ports.iter().map(|p| p.vulnerabilities.len()).sum::<usize>() + r.host_vulnerabilities.len())
+                .sum::<usize>()
+        );
+        
+        // Say why nothing was found: no responses differ from targets that never resolved
+        if scan_results.is_empty() && truncated.is_none() {
+            let completed = console.hosts_completed();
+            let reachable = console.hosts_reachable();
+            if completed > 0 && reachable == 0 {
+                let hint = if config.scan_offline_hosts { "" } else { " (use --scan-offline to scan them anyway)" };
+                println!("{} {} target address(es) resolved but none responded{}", "Note:".yellow().bold(), completed, hint);
+            } else if reachable > 0 {
+                println!("{} {} host(s) responded but none had open ports", "Note:".yellow().bold(), reachable);
             }
-        },
-        _ => {
-            eprintln!("{} Unknown output format: {}", "Error:".red().bold(), config.output_format);
+        }
+    }
+    
+    // Record how this report was produced
+    let metadata = ScanMetadata {
+        tool_name: constants::TOOL_NAME.to_string(),
+        version: constants::VERSION.to_string(),
+        command_line: command_line(),
+        start_time: start_timestamp,
+        end_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        parameters: config.clone(),
+        truncated,
+    };
+    
+    // Generate a report for each chosen format
+    let mut saved_files = Vec::new();
+    for (format, filename) in &report_files {
+        match write_report(format, &scan_results, filename, &metadata, &report_filter) {
+            Ok(()) => saved_files.push(filename),
+            Err(e) => eprintln!("{} Failed to generate {} report: {}", "Error:".red().bold(), format, e),
         }
     }
     
     // Calculate and display scan time
-    let duration = start_time.elapsed();
-    println!("\n{} {:.2} seconds", "Scan completed in".green().bold(), duration.as_secs_f64());
-    println!("{} {}", "Report saved to:".green().bold(), output_filename);
+    if !stream_to_stdout {
+        let duration = start_time.elapsed();
+        println!("\n{} {:.2} seconds", "Scan completed in".green().bold(), duration.as_secs_f64());
+        for filename in saved_files {
+            println!("{} {}", "Report saved to:".green().bold(), filename);
+        }
+    }
+    
+    // Compare against the previous scan
+    if let Some(previous_results) = previous_results {
+        let diff = report::diff_reports(&previous_results, &scan_results);
+        let diff_format = formats.iter()
+            .map(|format| format.as_str())
+            .find(|format| *format == "HTML" || *format == "JSON")
+            .unwrap_or("TEXT");
+        let diff_name = format!("scan_diff_{}.{}", timestamp, diff_format.to_lowercase());
+        let diff_filename = match output_dir {
+            Some(dir) => Path::new(dir).join(diff_name).to_string_lossy().into_owned(),
+            None => diff_name,
+        };
+        
+        eprintln!("{} {} new hosts, {} opened ports, {} closed ports, {} changed services, {} new and {} resolved vulnerabilities",
+            "Changes:".green().bold(),
+            diff.new_hosts.len(),
+            diff.opened_ports.len(),
+            diff.closed_ports.len(),
+            diff.changed_services.len(),
+            diff.new_vulnerabilities.len(),
+            diff.resolved_vulnerabilities.len()
+        );
+        match report::generate_diff_report(&diff, &diff_filename, diff_format) {
+            Ok(()) => eprintln!("{} {}", "Diff saved to:".green().bold(), diff_filename),
+            Err(e) => eprintln!("{} Failed to generate diff report: {}", "Error:".red().bold(), e),
+        }
+    }
+    
+    // Flush CVE lookups so the next run can reuse them
+    if let Some(path) = cache_path {
+        if let Err(e) = cveapi::save_to_disk(&path) {
+            eprintln!("{} Failed to save CVE cache to {}: {}", "Warning:".yellow().bold(), path.display(), e);
+        }
+    }
+    
+    // Exiting skips destructors, so flush anything still buffered first
+    let _ = std::io::stdout().flush();
+    std::process::exit(exit_code(&scan_results, console.hosts_reachable(), fail_on));
+}
+
+/// Pick the exit code: findings at or above `fail_on` take precedence over unreachable hosts
+fn exit_code(results: &[ScanResult], hosts_reachable: usize, fail_on: Option<Severity>) -> i32 {
+    if let Some(threshold) = fail_on {
+        let findings: usize = results.iter()
+            .map(|result| match &result.vulnerabilities_summary {
+                Some(summary) => summary.count_at_or_above(threshold),
+                None => scanner::generate_vulnerability_summary(&result.open_ports, &result.host_vulnerabilities)
+                    .count_at_or_above(threshold),
+            })
+            .sum();
+        
+        if findings > 0 {
+            return EXIT_FINDINGS;
+        }
+    }
+    
+    if hosts_reachable == 0 {
+        EXIT_NO_HOSTS
+    } else {
+        EXIT_CLEAN
+    }
 }
 
 fn parse_args() -> ArgMatches<'static> {
@@ -102,13 +508,33 @@ fn parse_args() -> ArgMatches<'static> {
         .about("A comprehensive network vulnerability scanner written in Rust")
         .arg(Arg::with_name("target")
             .help("Target specification (IP, range, CIDR, or hostname)")
-            .required(true)
+            .required_unless_one(&["input-file", "config", "passive", "passive-file", "list-plugins"])
             .index(1))
+        .arg(Arg::with_name("config")
+            .short("c")
+            .long("config")
+            .help("Load scan settings from a TOML profile; command-line flags override it")
+            .takes_value(true))
+        .arg(Arg::with_name("profile")
+            .long("profile")
+            .help("Start from a preset: quick, full, stealth, ot or compliance; command-line flags override it")
+            .possible_values(profiles::NAMES)
+            .conflicts_with("config")
+            .takes_value(true))
+        .arg(Arg::with_name("input-file")
+            .short("i")
+            .long("input-file")
+            .help("Read targets from a file, one per line ('#' starts a comment)")
+            .takes_value(true))
         .arg(Arg::with_name("ports")
             .short("p")
             .long("ports")
             .help("Ports to scan (e.g., '22,80,443' or '1-1000')")
             .takes_value(true))
+        .arg(Arg::with_name("top-ports")
+            .long("top-ports")
+            .help("Scan the N most common ports (ignored when --ports is given)")
+            .takes_value(true))
         .arg(Arg::with_name("threads")
             .short("t")
             .long("threads")
@@ -121,90 +547,496 @@ fn parse_args() -> ArgMatches<'static> {
             .help("Connection timeout in milliseconds")
             .default_value("1000")
             .takes_value(true))
+        .arg(Arg::with_name("adaptive")
+            .long("adaptive")
+            .help("Measure each host's RTT and use a multiple of it as the port timeout, capped at --timeout"))
+        .arg(Arg::with_name("min-timeout")
+            .long("min-timeout")
+            .help("Lowest port timeout in milliseconds that --adaptive will use")
+            .takes_value(true))
+        .arg(Arg::with_name("fast-filter")
+            .long("fast-filter")
+            .help("Probe a few sentinel ports with a short timeout first and scan hosts where all are filtered with that timeout"))
         .arg(Arg::with_name("randomize")
             .short("r")
             .long("randomize")
             .help("Randomize scan order"))
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .help("Seed for a reproducible randomized scan order (implies --randomize)")
+            .takes_value(true))
         .arg(Arg::with_name("format")
             .short("f")
             .long("format")
-            .help("Output format (TEXT, HTML, JSON)")
+            .help("Output format, or a comma-separated list of them (TEXT, HTML, JSON, JSONL, SARIF, MARKDOWN, NMAP-XML; CSV with --discover-only)")
             .default_value("TEXT")
             .takes_value(true))
         .arg(Arg::with_name("output")
             .short("o")
             .long("output")
-            .help("Output file")
+            .help("Output file; use - with --format JSONL to stream results to stdout")
+            .takes_value(true))
+        .arg(Arg::with_name("output-dir")
+            .long("output-dir")
+            .help("Directory to write scan_report_<timestamp>.<ext> files to, one per format (created if missing)")
+            .takes_value(true))
+        .arg(Arg::with_name("diff")
+            .long("diff")
+            .help("Compare results against a previous JSON report and write a diff report")
             .takes_value(true))
         .arg(Arg::with_name("verbose")
             .short("v")
             .long("verbose")
-            .help("Verbose output"))
+            .multiple(true)
+            .help("Log progress to stderr; repeat for per-port detail (-vv) and tracing (-vvv)"))
+        .arg(Arg::with_name("quiet")
+            .short("q")
+            .long("quiet")
+            .conflicts_with("verbose")
+            .help("Only log errors"))
         .arg(Arg::with_name("offline")
             .long("offline")
             .help("Offline mode - don't query online CVE databases"))
+        .arg(Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("Print the hosts, ports and checks the scan would cover, then exit without scanning (JSON with --format JSON)"))
+        .arg(Arg::with_name("discover-only")
+            .long("discover-only")
+            .help("Only find live hosts and write them as TEXT, JSON or CSV, without scanning ports"))
+        .arg(Arg::with_name("record-raw-probes")
+            .long("record-raw-probes")
+            .help("Keep the exact bytes sent to and received from each open port in JSON reports, hex-encoded, as evidence"))
+        .arg(Arg::with_name("max-banner-bytes")
+            .long("max-banner-bytes")
+            .help("Most bytes to read from each service's response while grabbing banners (default: 8192)")
+            .takes_value(true))
+        .arg(Arg::with_name("banner-only")
+            .long("banner-only")
+            .help("Only map open ports, services and banners; skip vulnerability detection and attack path analysis"))
+        .arg(Arg::with_name("safe")
+            .long("safe")
+            .help("Run only non-intrusive checks, safe for production: no default-credential logins, zone transfers, SNMP community guessing, HTTP OPTIONS, database queries or SMB null sessions"))
+        .arg(Arg::with_name("cache-file")
+            .long("cache-file")
+            .help("CVE cache file (default: ~/.cache/rustnetscan/cve_cache.json)")
+            .takes_value(true))
+        .arg(Arg::with_name("cache-ttl")
+            .long("cache-ttl")
+            .help("Days before cached CVE entries expire")
+            .default_value("7")
+            .takes_value(true))
+        .arg(Arg::with_name("no-cache")
+            .long("no-cache")
+            .help("Don't read or write the on-disk CVE cache")
+            .conflicts_with("cache-file"))
+        .arg(Arg::with_name("cve-feed-dir")
+            .long("cve-feed-dir")
+            .help("Directory of NVD JSON 1.1 feeds (nvdcve-1.1-*.json.gz) to match against in --offline mode")
+            .takes_value(true))
+        .arg(Arg::with_name("exclude")
+            .long("exclude")
+            .help("Comma-separated IPs, CIDRs or ranges to skip (e.g. '10.0.0.5,10.0.1.0/24')")
+            .takes_value(true))
+        .arg(Arg::with_name("scope")
+            .long("scope")
+            .help("Comma-separated IPs, CIDRs or ranges every target must fall inside; the scan aborts before sending anything if one doesn't")
+            .takes_value(true))
+        .arg(Arg::with_name("snmp-communities")
+            .long("snmp-communities")
+            .help("Comma-separated SNMP communities to try on 161/udp besides 'public' and 'private' (with --check-default-creds)")
+            .takes_value(true))
+        .arg(Arg::with_name("enable-plugin")
+            .long("enable-plugin")
+            .help("Only run these detector plugins, by name; comma-separated or repeated (e.g. 'OSV Vulnerability Detector')")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("list-plugins")
+            .long("list-plugins")
+            .help("List the detector plugins with their version, description and whether they would run, then exit"))
+        .arg(Arg::with_name("disable-plugin")
+            .long("disable-plugin")
+            .help("Don't run these detector plugins, by name; comma-separated or repeated (e.g. 'ICS-CERT Vulnerability Detector')")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
+        .arg(Arg::with_name("cve-sources")
+            .long("cve-sources")
+            .help("Comma-separated CVE databases to query, in order (nvd, mitre, circl, osv) [default: nvd,mitre,circl]")
+            .takes_value(true))
+        .arg(Arg::with_name("max-time")
+            .long("max-time")
+            .help("Stop starting new hosts after this long and report partial results (seconds, or with an s/m/h suffix such as 90m)")
+            .takes_value(true))
+        .arg(Arg::with_name("rate")
+            .long("rate")
+            .help("Maximum connection attempts per second across all threads")
+            .takes_value(true))
+        .arg(Arg::with_name("retries")
+            .long("retries")
+            .help("Extra attempts for port probes that time out (connection refused is never retried) [default: 1]")
+            .takes_value(true))
+        .arg(Arg::with_name("proxy")
+            .long("proxy")
+            .help("Route TCP connections and API requests through a proxy (e.g. socks5://127.0.0.1:1080 or http://proxy:8080)")
+            .takes_value(true))
+        .arg(Arg::with_name("interface")
+            .long("interface")
+            .help("Make scan connections from this interface's address (API requests keep the default route)")
+            .takes_value(true))
+        .arg(Arg::with_name("source-ip")
+            .long("source-ip")
+            .help("Make scan connections from this local address (API requests keep the default route)")
+            .takes_value(true))
+        .arg(Arg::with_name("dns")
+            .long("dns")
+            .help("Comma-separated DNS servers for name and reverse lookups (e.g. 10.0.0.53,8.8.8.8)")
+            .takes_value(true))
+        .arg(Arg::with_name("enum-srv")
+            .long("enum-srv")
+            .help("Add the hosts and ports behind SRV and MX records of domain targets (SIP, LDAP, Kerberos, mail...)"))
+        .arg(Arg::with_name("no-tls-inspection")
+            .long("no-tls-inspection")
+            .help("Don't inspect certificates or protocol versions on TLS ports"))
+        .arg(Arg::with_name("check-default-creds")
+            .long("check-default-creds")
+            .help("Attempt logins with default credentials (intrusive: may trigger alerts or lock accounts)"))
+        .arg(Arg::with_name("arp")
+            .long("arp")
+            .help("Find hosts on directly-connected subnets with ARP instead of ping (needs root or CAP_NET_RAW)"))
         .arg(Arg::with_name("scan-offline")
             .long("scan-offline")
             .help("Scan hosts even if they don't respond to ping"))
-        .get_matches()
+        .arg(Arg::with_name("window")
+            .long("window")
+            .help("Only start scanning new hosts inside this daily window (HH:MM-HH:MM)")
+            .takes_value(true))
+        .arg(Arg::with_name("timezone")
+            .long("timezone")
+            .help("Timezone for --window (local, UTC, or an offset like +02:00)")
+            .default_value("local")
+            .takes_value(true))
+        .arg(Arg::with_name("async")
+            .long("async")
+            .help("Use the async scanning backend (requires the 'async' feature)"))
+        .arg(Arg::with_name("passive")
+            .long("passive")
+            .value_name("interface")
+            .help("Detect hosts and services by sniffing an interface instead of probing; targets only limit the scope (requires the 'passive' feature and root or CAP_NET_RAW)")
+            .takes_value(true))
+        .arg(Arg::with_name("passive-file")
+            .long("passive-file")
+            .value_name("pcap")
+            .help("Detect hosts and services from a saved pcap capture instead of probing (requires the 'passive' feature)")
+            .takes_value(true))
+        .arg(Arg::with_name("passive-duration")
+            .long("passive-duration")
+            .help("Seconds to sniff with --passive [default: 60]")
+            .takes_value(true))
+        .arg(Arg::with_name("tui")
+            .long("tui")
+            .help("Show a live dashboard of hosts, open ports and findings while scanning; q stops the scan (requires the 'tui' feature)"))
+        .arg(Arg::with_name("metrics-addr")
+            .long("metrics-addr")
+            .value_name("host:port")
+            .help("Serve Prometheus metrics on this address while scanning (requires the 'metrics' feature)")
+            .takes_value(true))
+        .arg(Arg::with_name("webhook")
+            .long("webhook")
+            .value_name("url")
+            .help("POST each host's serious findings to this URL as soon as the host is scanned (Slack incoming webhooks get a Slack message)")
+            .takes_value(true))
+        .arg(Arg::with_name("webhook-severity")
+            .long("webhook-severity")
+            .help("Least severe finding sent to --webhook (critical, high, medium, low, info) [default: high]")
+            .takes_value(true))
+        .arg(Arg::with_name("min-severity")
+            .long("min-severity")
+            .help("Only report vulnerabilities at or above this severity (critical, high, medium, low, info)")
+            .takes_value(true))
+        .arg(Arg::with_name("category")
+            .long("category")
+            .help("Only report vulnerabilities in these comma-separated categories (e.g. 'web,remote access')")
+            .takes_value(true))
+        .arg(Arg::with_name("only-exploited")
+            .long("only-exploited")
+            .help("Only report vulnerabilities known to be actively exploited"))
+        .arg(Arg::with_name("fail-on")
+            .long("fail-on")
+            .help("Exit with code 1 if any vulnerability at or above this severity is found (critical, high, medium, low, info)")
+            .takes_value(true))
+        .get_matches_safe()
+        .unwrap_or_else(|e| match e.kind {
+            clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => e.exit(),
+            _ => {
+                eprintln!("{}", e.message);
+                std::process::exit(EXIT_USAGE);
+            }
+        })
 }
 
 fn build_config(matches: &ArgMatches) -> Result<ScanConfig, String> {
-    let target = matches.value_of("target").unwrap().to_string();
+    // Start from the scan profile or preset, if any; explicit flags override its values
+    let mut config = match (matches.value_of("config"), matches.value_of("profile")) {
+        (Some(path), _) => ScanConfig::from_toml_file(Path::new(path))
+            .map_err(|e| format!("Failed to load config file {}: {}", path, e))?,
+        (None, Some(name)) => profiles::by_name(name)
+            .ok_or_else(|| format!("Unknown scan profile: {}", name))?,
+        (None, None) => ScanConfig::default(),
+    };
+    
+    if let Some(target) = matches.value_of("target") {
+        config.target = target.to_string();
+    }
+    
+    // Read additional targets from a file
+    if let Some(path) = matches.value_of("input-file") {
+        config.input_targets = resolver::read_target_file(Path::new(path))
+            .map_err(|e| format!("Failed to read input file {}: {}", path, e))?;
+    }
+    
+    // Parse hosts and networks to leave out of the scan
+    if let Some(list) = matches.value_of("exclude") {
+        config.exclude = list.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+    }
+    
+    // Parse the networks the scan is allowed to touch
+    if let Some(list) = matches.value_of("scope") {
+        config.allowed_scope = list.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+    }
+    
+    // Parse extra SNMP communities to try
+    if let Some(list) = matches.value_of("snmp-communities") {
+        config.snmp_communities = list.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+    }
+    
+    // Select which detector plugins run
+    if let Some(values) = matches.values_of("enable-plugin") {
+        config.enabled_plugins = Some(plugin_names(values));
+    }
+    if let Some(values) = matches.values_of("disable-plugin") {
+        config.disabled_plugins = plugin_names(values);
+    }
+    
+    // Parse the CVE lookup order
+    if let Some(list) = matches.value_of("cve-sources") {
+        config.cve_source_order = list.split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<CveSource>())
+            .collect::<Result<Vec<_>, _>>()?;
+    }
     
     // Parse port list or range
-    let ports = if let Some(port_str) = matches.value_of("ports") {
-        parse_port_list(port_str)?
+    if let Some(port_str) = matches.value_of("ports") {
+        config.ports = parse_port_list(port_str)?;
+    }
+    
+    // Parse the number of top ports to scan
+    if let Some(count) = matches.value_of("top-ports") {
+        config.top_ports = Some(count.parse::<usize>().unwrap_or(0));
+        // A port list from a profile would otherwise take precedence
+        if !matches.is_present("ports") {
+            config.ports.clear();
+        }
+    }
+    
+    // Parse number of threads
+    if let Some(threads) = explicit_value(matches, "threads") {
+        config.threads = threads.parse::<usize>()
+            .map_err(|_| "Invalid thread count".to_string())?;
+    }
+    
+    // Parse timeout
+    if let Some(timeout) = explicit_value(matches, "timeout") {
+        config.timeout_ms = timeout.parse::<u64>()
+            .map_err(|_| "Invalid timeout value".to_string())?;
+    }
+    if let Some(min_timeout) = matches.value_of("min-timeout") {
+        config.min_timeout_ms = min_timeout.parse::<u64>()
+            .map_err(|_| "Invalid minimum timeout value".to_string())?;
+    }
+    
+    // Parse the optional connection rate limit
+    if let Some(rate) = matches.value_of("rate") {
+        config.max_pps = rate.parse::<u32>().ok().or(Some(0));
+    }
+    
+    // Notify a webhook of serious findings while scanning
+    if let Some(url) = matches.value_of("webhook") {
+        config.webhook_url = Some(url.to_string());
+    }
+    if let Some(level) = matches.value_of("webhook-severity") {
+        config.webhook_min_severity = level.parse::<Severity>()?;
+    }
+    
+    // Parse the time budget for the whole scan
+    if let Some(max_time) = matches.value_of("max-time") {
+        config.max_duration_secs = Some(parse_duration_secs(max_time)?);
+    }
+    
+    // Parse the banner read limit
+    if let Some(max_bytes) = matches.value_of("max-banner-bytes") {
+        config.max_banner_bytes = max_bytes.parse::<usize>()
+            .map_err(|_| "Invalid maximum banner size".to_string())?;
+    }
+    
+    // Parse the retry count for timed-out probes
+    if let Some(retries) = matches.value_of("retries") {
+        config.retries = retries.parse::<u8>()
+            .map_err(|_| "Invalid retry count".to_string())?;
+    }
+    
+    // Route connections through a proxy
+    if let Some(proxy) = matches.value_of("proxy") {
+        config.proxy = Some(proxy.to_string());
+    }
+    
+    // Bind scan connections to a local interface or address
+    if let Some(interface) = matches.value_of("interface") {
+        config.source_interface = Some(interface.to_string());
+    }
+    if let Some(ip) = matches.value_of("source-ip") {
+        config.source_ip = Some(ip.parse::<IpAddr>().map_err(|_| format!("Invalid source address: {}", ip))?);
+    }
+    
+    // Use specific DNS servers instead of the defaults
+    if let Some(list) = matches.value_of("dns") {
+        config.dns_servers = list.split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<IpAddr>().map_err(|_| format!("Invalid DNS server address: {}", s)))
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+    
+    // Parse output formats, given as one format or a list such as html,json,sarif
+    if let Some(format) = explicit_value(matches, "format") {
+        config.output_format = format.to_string();
+    }
+    let mut formats: Vec<&str> = Vec::new();
+    for format in config.output_format.split(',').map(|f| f.trim()).filter(|f| !f.is_empty()) {
+        let format = normalize_format(format);
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    config.output_format = if formats.is_empty() { "TEXT".to_string() } else { formats.join(",") };
+    
+    // Flags can only switch options on (or TLS inspection off) relative to the profile
+    config.randomize_scan |= matches.is_present("randomize");
+    if let Some(seed) = matches.value_of("seed") {
+        config.random_seed = Some(seed.parse::<u64>().map_err(|_| format!("Invalid seed: {}", seed))?);
+        config.randomize_scan = true;
+    }
+    config.adaptive_timeout |= matches.is_present("adaptive");
+    config.fast_filter |= matches.is_present("fast-filter");
+    config.verbose |= matches.is_present("verbose");
+    config.offline_mode |= matches.is_present("offline");
+    config.banner_only |= matches.is_present("banner-only");
+    config.non_intrusive |= matches.is_present("safe");
+    config.record_raw_probes |= matches.is_present("record-raw-probes");
+    config.enumerate_srv |= matches.is_present("enum-srv");
+    
+    // Sniff traffic instead of probing
+    if let Some(interface) = matches.value_of("passive") {
+        config.passive_interface = Some(interface.to_string());
+    }
+    if let Some(path) = matches.value_of("passive-file") {
+        config.passive_pcap = Some(path.to_string());
+    }
+    if let Some(secs) = matches.value_of("passive-duration") {
+        config.passive_duration_secs = secs.parse::<u64>()
+            .map_err(|_| "Invalid passive capture duration".to_string())?;
+    }
+    config.scan_offline_hosts |= matches.is_present("scan-offline");
+    config.arp_discovery |= matches.is_present("arp");
+    config.check_default_credentials |= matches.is_present("check-default-creds");
+    config.use_async |= matches.is_present("async");
+    if matches.is_present("no-tls-inspection") {
+        config.inspect_tls = false;
+    }
+    
+    // Parse the optional scan window
+    if let Some(window) = matches.value_of("window") {
+        config.scan_window = Some(schedule::parse_window(window, matches.value_of("timezone").unwrap())?);
+    }
+    
+    if let Some(key) = std::env::var("NVD_API_KEY").ok().filter(|key| !key.is_empty()) {
+        config.nvd_api_key = Some(key);
+    }
+    
+    // Enforce target, thread, timeout and rate limits on the merged settings
+    config.validate()?;
+    
+    Ok(config)
+}
+
+/// Get an argument's value only if it was given on the command line, ignoring clap defaults
+fn explicit_value<'a>(matches: &'a ArgMatches, name: &str) -> Option<&'a str> {
+    if matches.occurrences_of(name) > 0 {
+        matches.value_of(name)
     } else {
-        Vec::new() // Empty Vec means all ports
+        None
+    }
+}
+
+/// Parse a duration given in seconds or with an s, m or h suffix ("90", "30m", "2h")
+fn parse_duration_secs(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1),
+        Some((i, 'm')) => (&value[..i], 60),
+        Some((i, 'h')) => (&value[..i], 60 * 60),
+        _ => (value, 1),
     };
     
-    // Parse number of threads
-    let threads = matches.value_of("threads").unwrap()
-        .parse::<usize>()
-        .map_err(|_| "Invalid thread count".to_string())?;
+    number.trim().parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid duration: {}", value))
+}
+
+/// Collect plugin names from repeated, comma-separated flag values
+fn plugin_names(values: clap::Values) -> Vec<String> {
+    values.flat_map(|list| list.split(','))
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Build the report filter from the --min-severity, --category and --only-exploited flags
+fn report_filter(matches: &ArgMatches) -> Result<ReportFilter, String> {
+    let min_severity = matches.value_of("min-severity")
+        .map(|level| level.parse::<Severity>())
+        .transpose()?;
+    let categories = matches.value_of("category")
+        .map(|list| list.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect::<Vec<String>>())
+        .filter(|categories| !categories.is_empty());
     
-    // Validate thread count
-    if threads == 0 || threads > 1000 {
-        return Err("Thread count must be between 1 and 1000".to_string());
+    Ok(ReportFilter {
+        min_severity,
+        categories,
+        only_exploited: matches.is_present("only-exploited"),
+    })
+}
+
+/// Determine the CVE cache file and entry TTL, or None if caching is disabled
+fn cache_settings(matches: &ArgMatches) -> Result<Option<(PathBuf, Duration)>, String> {
+    if matches.is_present("no-cache") {
+        return Ok(None);
     }
     
-    // Parse timeout
-    let timeout_ms = matches.value_of("timeout").unwrap()
+    let ttl_days = matches.value_of("cache-ttl").unwrap()
         .parse::<u64>()
-        .map_err(|_| "Invalid timeout value".to_string())?;
-    
-    // Validate timeout
-    if timeout_ms < 100 || timeout_ms > 60000 {
-        return Err("Timeout must be between 100ms and 60000ms".to_string());
-    }
-    
-    // Parse output format
-    let mut output_format = matches.value_of("format").unwrap().to_uppercase();
-    if !["TEXT", "HTML", "JSON"].contains(&output_format.as_str()) {
-        output_format = "TEXT".to_string();
-    }
-    
-    // Create config
-    let config = ScanConfig {
-        target,
-        ports,
-        threads,
-        timeout_ms,
-        randomize_scan: matches.is_present("randomize"),
-        verbose: matches.is_present("verbose"),
-        offline_mode: matches.is_present("offline"),
-        output_format,
-        scan_offline_hosts: matches.is_present("scan-offline"),
-        enhanced_vuln_detection: true,
-        assess_attack_surface: true,
-        check_misconfigurations: true,
-        check_default_credentials: true,
-        mitre_mapping: true,
-        attack_path_analysis: true,
+        .map_err(|_| "Invalid cache TTL".to_string())?;
+    let ttl = Duration::from_secs(ttl_days * 24 * 60 * 60);
+    
+    let path = match matches.value_of("cache-file") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => cveapi::default_cache_path(),
     };
     
-    Ok(config)
+    Ok(path.map(|path| (path, ttl)))
 }
 
 /// Parse port specifications like "80,443" or "1-1000"
@@ -248,6 +1080,269 @@ fn parse_port_list(port_str: &str) -> Result<Vec<u16>, String> {
     Ok(ports)
 }
 
+/// Prints a live counter of scanned hosts and open ports, and counts reachable hosts
+struct ConsoleProgress {
+    display: bool,
+    hosts_started: AtomicUsize,
+    hosts_completed: AtomicUsize,
+    hosts_reachable: AtomicUsize,
+    open_ports: AtomicUsize,
+}
+
+impl ConsoleProgress {
+    fn new(display: bool) -> Self {
+        ConsoleProgress {
+            display,
+            hosts_started: AtomicUsize::new(0),
+            hosts_completed: AtomicUsize::new(0),
+            hosts_reachable: AtomicUsize::new(0),
+            open_ports: AtomicUsize::new(0),
+        }
+    }
+    
+    /// Number of hosts whose scan completed
+    fn hosts_completed(&self) -> usize {
+        self.hosts_completed.load(Ordering::Relaxed)
+    }
+    
+    /// Number of completed hosts that answered a ping or had an open port
+    fn hosts_reachable(&self) -> usize {
+        self.hosts_reachable.load(Ordering::Relaxed)
+    }
+    
+    /// Redraw the progress line in place
+    fn print(&self) {
+        if !self.display {
+            return;
+        }
+        
+        print!("\r{} {} scanned, {} in progress, {} open ports   ",
+            "Progress:".cyan().bold(),
+            self.hosts_completed.load(Ordering::Relaxed),
+            self.hosts_started.load(Ordering::Relaxed).saturating_sub(self.hosts_completed.load(Ordering::Relaxed)),
+            self.open_ports.load(Ordering::Relaxed)
+        );
+        let _ = std::io::stdout().flush();
+    }
+}
+
+impl ScanProgress for ConsoleProgress {
+    fn on_host_started(&self, _ip: &IpAddr) {
+        self.hosts_started.fetch_add(1, Ordering::Relaxed);
+        self.print();
+    }
+    
+    fn on_host_completed(&self, result: &ScanResult) {
+        self.hosts_completed.fetch_add(1, Ordering::Relaxed);
+        if result.is_online || !result.open_ports.is_empty() {
+            self.hosts_reachable.fetch_add(1, Ordering::Relaxed);
+        }
+        self.print();
+    }
+    
+    fn on_port_open(&self, _ip: &IpAddr, _port: u16) {
+        self.open_ports.fetch_add(1, Ordering::Relaxed);
+        self.print();
+    }
+}
+
+/// Send log records to stderr at the level chosen by -v/-q; RUST_LOG takes precedence
+fn init_logging(matches: &ArgMatches, config: &ScanConfig) {
+    let level = if matches.is_present("tui") {
+        // Log lines would be drawn over the dashboard
+        LevelFilter::Off
+    } else if matches.is_present("quiet") {
+        LevelFilter::Error
+    } else {
+        match matches.occurrences_of("verbose") {
+            0 if config.verbose => LevelFilter::Info,
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    
+    // Dependencies such as reqwest are only heard from when something goes wrong
+    env_logger::Builder::new()
+        .filter_level(level.min(LevelFilter::Warn))
+        .filter_module("rustnet_scan", level)
+        .parse_default_env()
+        .init();
+}
+
+/// Reconstruct the command line that started this scan, quoting arguments with spaces
+///
+/// Proxy passwords and webhook URLs are masked, since the command line is
+/// written to reports.
+fn command_line() -> String {
+    let mut previous = String::new();
+    std::env::args()
+        .map(|arg| {
+            let shown = match previous.as_str() {
+                "--proxy" => proxy::redact_url(&arg),
+                "--webhook" => "***".to_string(),
+                _ => match arg.split_once('=') {
+                    Some(("--proxy", url)) => format!("--proxy={}", proxy::redact_url(url)),
+                    Some(("--webhook", _)) => "--webhook=***".to_string(),
+                    _ => arg.clone(),
+                },
+            };
+            previous = arg;
+            if shown.contains(' ') { format!("\"{}\"", shown) } else { shown }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Canonical name of an output format; unknown formats fall back to TEXT
+fn normalize_format(format: &str) -> &'static str {
+    match format.to_uppercase().as_str() {
+        "HTML" => "HTML",
+        "JSON" => "JSON",
+        "JSONL" => "JSONL",
+        "SARIF" => "SARIF",
+        "MARKDOWN" | "MD" => "MARKDOWN",
+        "NMAP-XML" | "NMAP" => "NMAP-XML",
+        "CSV" => "CSV",
+        _ => "TEXT",
+    }
+}
+
+/// Default report path for a format: scan_report_<timestamp>.<ext>, inside `dir` when given
+fn report_filename(dir: Option<&str>, timestamp: &str, format: &str) -> String {
+    let extension = match format {
+        "MARKDOWN" => "md".to_string(),
+        "NMAP-XML" => "xml".to_string(),
+        format => format.to_lowercase(),
+    };
+    let name = format!("scan_report_{}.{}", timestamp, extension);
+    
+    match dir {
+        Some(dir) => Path::new(dir).join(name).to_string_lossy().into_owned(),
+        None => name,
+    }
+}
+
+/// Find the live hosts among the targets and write them in each of `formats`
+///
+/// Returns the process exit code.
+fn run_discovery(config: &ScanConfig, formats: &[String], output: Option<&str>, output_dir: Option<&str>) -> i32 {
+    if let Some(format) = formats.iter().find(|format| !matches!(format.as_str(), "TEXT" | "JSON" | "CSV")) {
+        eprintln!("{} Host discovery writes TEXT, JSON or CSV, not {}", "Error:".red().bold(), format);
+        return EXIT_USAGE;
+    }
+    let exclude = match resolver::ExcludeList::parse(&config.exclude) {
+        Ok(exclude) => exclude,
+        Err(err) => {
+            eprintln!("{} {}", "Error:".red().bold(), err);
+            return EXIT_USAGE;
+        }
+    };
+    
+    print_banner();
+    let start_time = Instant::now();
+    
+    let mut hosts: Vec<HostInfo> = Vec::new();
+    for target in std::iter::once(&config.target).chain(&config.input_targets).filter(|t| !t.is_empty()) {
+        println!("{} {}", "Discovering hosts in".green().bold(), target);
+        for host in scanner::discover_hosts(target, config) {
+            let excluded = host.ip.parse::<IpAddr>().map(|ip| exclude.contains(&ip)).unwrap_or(false);
+            if !excluded && !hosts.iter().any(|h| h.ip == host.ip) {
+                hosts.push(host);
+            }
+        }
+    }
+    hosts.sort_by_key(|host| host.ip.parse::<IpAddr>().ok());
+    
+    println!("\n{} {} live host(s) in {:.2} seconds", "Discovery completed:".green().bold(), hosts.len(), start_time.elapsed().as_secs_f64());
+    
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
+    for format in formats {
+        let filename = output.map(|o| o.to_string()).unwrap_or_else(|| {
+            let name = format!("host_discovery_{}.{}", timestamp, format.to_lowercase());
+            match output_dir {
+                Some(dir) => Path::new(dir).join(name).to_string_lossy().into_owned(),
+                None => name,
+            }
+        });
+        match report::generate_host_discovery_report(&hosts, &filename, format) {
+            Ok(()) => println!("{} {}", "Report saved to:".green().bold(), filename),
+            Err(e) => eprintln!("{} Failed to generate {} report: {}", "Error:".red().bold(), format, e),
+        }
+    }
+    
+    if hosts.is_empty() { EXIT_NO_HOSTS } else { EXIT_CLEAN }
+}
+
+/// Write the report for one output format
+///
+/// JSONL reports are streamed while scanning, so there is nothing left to write for them.
+fn write_report(format: &str, results: &[ScanResult], filename: &str, metadata: &ScanMetadata, filter: &ReportFilter) -> std::io::Result<()> {
+    match format {
+        "HTML" => report::generate_html_report(results, filename, Some(metadata), Some(filter)),
+        "JSON" => report::generate_json_report(results, filename, Some(metadata), Some(filter)),
+        "SARIF" => report::generate_sarif_report(results, filename, Some(metadata), Some(filter)),
+        "MARKDOWN" => report::generate_markdown_report(results, filename, Some(metadata), Some(filter)),
+        "NMAP-XML" => report::generate_nmap_xml_report(results, filename, Some(metadata), Some(filter)),
+        "JSONL" => Ok(()),
+        _ => report::generate_text_report(results, filename, Some(metadata), Some(filter)),
+    }
+}
+
+/// Print a table of the registered detector plugins
+///
+/// A plugin is listed as enabled when it would run with the given
+/// `--enable-plugin` and `--disable-plugin` selection.
+fn print_plugins(registry: &PluginRegistry) {
+    let enabled: Vec<&str> = registry.get_enabled_plugins().iter().map(|p| p.name()).collect();
+    
+    println!("{:<40} {:<9} {:<8} {:<8} Description", "Name", "Version", "Enabled", "Network");
+    for plugin in registry.get_plugins() {
+        println!("{:<40} {:<9} {:<8} {:<8} {}",
+                 plugin.name(),
+                 plugin.version(),
+                 if enabled.contains(&plugin.name()) { "yes" } else { "no" },
+                 if plugin.requires_network() { "required" } else { "-" },
+                 plugin.description());
+    }
+}
+
+/// Print a scan plan as text, or as JSON when a JSON output format was chosen
+fn print_scan_plan(plan: &ScanPlan, output_format: &str) {
+    if output_format.split(',').any(|format| format == "JSON" || format == "JSONL") {
+        match serde_json::to_string_pretty(plan) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("{} Failed to serialize the scan plan: {}", "Error:".red().bold(), e),
+        }
+        return;
+    }
+    
+    println!("{}", "Dry run - no packets will be sent".cyan().bold());
+    println!("{} {}", "Target specs:".green().bold(), plan.target_specs.join(", "));
+    if !plan.exclude.is_empty() {
+        println!("{} {}", "Excluded:".green().bold(), plan.exclude.join(", "));
+    }
+    println!("{} {}", "Hosts:".green().bold(), plan.target_count);
+    for ip in plan.targets.iter().take(DRY_RUN_LISTED_HOSTS) {
+        println!("  {}", ip);
+    }
+    if plan.target_count > DRY_RUN_LISTED_HOSTS {
+        println!("  ... and {} more (use --format JSON for the full list)", plan.target_count - DRY_RUN_LISTED_HOSTS);
+    }
+    let ports: Vec<String> = plan.ports.iter().map(|p| p.to_string()).collect();
+    println!("{} {} ({})", "Ports:".green().bold(), plan.ports.len(), ports.join(","));
+    println!("{} {}", "Threads:".green().bold(), plan.threads);
+    println!("{} {}ms", "Timeout:".green().bold(), plan.timeout_ms);
+    if let Some(rate) = plan.max_pps {
+        println!("{} {}/s", "Rate limit:".green().bold(), rate);
+    }
+    println!("{}", "Active checks:".green().bold());
+    for check in &plan.active_checks {
+        println!("  - {}", check);
+    }
+}
+
 fn print_banner() {
     let banner = r#"
    _____           _   _   _      _   _____                 
@@ -0,0 +1,159 @@
+// Author: CyberCraft Alchemist
+// Prometheus metrics served over HTTP while a scan is running
+
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use log::debug;
+
+use crate::models::{ScanResult, Severity};
+use crate::progress::ScanProgress;
+
+// How long a scraper gets to send its request line
+const REQUEST_TIMEOUT_MS: u64 = 2000;
+
+// Severities in the order their counters are stored and exported
+const SEVERITIES: [Severity; 5] = [Severity::Info, Severity::Low, Severity::Medium, Severity::High, Severity::Critical];
+
+/// Scan counters, updated from progress events and rendered on every scrape
+pub struct ScanMetrics {
+    start_time: Instant,
+    hosts_scanned: AtomicUsize,
+    hosts_in_flight: AtomicUsize,
+    open_ports: AtomicUsize,
+    vulnerabilities: [AtomicUsize; 5], // Indexed like `SEVERITIES`
+}
+
+impl ScanMetrics {
+    pub fn new() -> Self {
+        ScanMetrics {
+            start_time: Instant::now(),
+            hosts_scanned: AtomicUsize::new(0),
+            hosts_in_flight: AtomicUsize::new(0),
+            open_ports: AtomicUsize::new(0),
+            vulnerabilities: Default::default(),
+        }
+    }
+    
+    /// Render the metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        
+        let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, value);
+            }
+        };
+        
+        metric("rustnetscan_hosts_scanned_total", "counter", "Hosts whose scan has completed.",
+            vec![(String::new(), self.hosts_scanned.load(Ordering::Relaxed).to_string())]);
+        metric("rustnetscan_open_ports_total", "counter", "Open ports found.",
+            vec![(String::new(), self.open_ports.load(Ordering::Relaxed).to_string())]);
+        metric("rustnetscan_vulns_total", "counter", "Vulnerabilities found, by severity.",
+            SEVERITIES.iter().zip(&self.vulnerabilities)
+                .map(|(severity, count)| (
+                    format!("{{severity=\"{}\"}}", severity.to_string().to_lowercase()),
+                    count.load(Ordering::Relaxed).to_string(),
+                ))
+                .collect());
+        metric("rustnetscan_scan_duration_seconds", "gauge", "Seconds since the scan started.",
+            vec![(String::new(), format!("{:.3}", self.start_time.elapsed().as_secs_f64()))]);
+        metric("rustnetscan_probes_in_flight", "gauge", "Hosts currently being probed.",
+            vec![(String::new(), self.hosts_in_flight.load(Ordering::Relaxed).to_string())]);
+        
+        out
+    }
+}
+
+impl Default for ScanMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Updates `metrics` from scan events, then forwards them to `inner`
+pub struct MetricsProgress<P: ScanProgress> {
+    metrics: Arc<ScanMetrics>,
+    inner: P,
+}
+
+impl<P: ScanProgress> MetricsProgress<P> {
+    pub fn new(metrics: Arc<ScanMetrics>, inner: P) -> Self {
+        MetricsProgress { metrics, inner }
+    }
+}
+
+impl<P: ScanProgress> ScanProgress for MetricsProgress<P> {
+    fn on_host_started(&self, ip: &IpAddr) {
+        self.metrics.hosts_in_flight.fetch_add(1, Ordering::Relaxed);
+        self.inner.on_host_started(ip);
+    }
+    
+    fn on_host_completed(&self, result: &ScanResult) {
+        let metrics = &self.metrics;
+        let _ = metrics.hosts_in_flight.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| Some(n.saturating_sub(1)));
+        metrics.hosts_scanned.fetch_add(1, Ordering::Relaxed);
+        
+        for vuln in result.open_ports.iter().flat_map(|p| &p.vulnerabilities).chain(&result.host_vulnerabilities) {
+            if let Some(index) = SEVERITIES.iter().position(|s| *s == vuln.severity_level()) {
+                metrics.vulnerabilities[index].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        
+        self.inner.on_host_completed(result);
+    }
+    
+    fn on_port_open(&self, ip: &IpAddr, port: u16) {
+        self.metrics.open_ports.fetch_add(1, Ordering::Relaxed);
+        self.inner.on_port_open(ip, port);
+    }
+}
+
+/// Serve `metrics` on `addr` from a background thread, returning the bound address
+///
+/// `/` and `/metrics` answer with the metrics and other paths get a 404.
+/// The server runs until the process exits.
+pub fn serve(addr: &str, metrics: Arc<ScanMetrics>) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+    
+    thread::Builder::new()
+        .name("metrics".to_string())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(e) = respond(stream, &metrics) {
+                    debug!("Metrics request failed: {}", e);
+                }
+            }
+        })?;
+    
+    Ok(local_addr)
+}
+
+/// Answer one scrape request
+fn respond(stream: TcpStream, metrics: &ScanMetrics) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_millis(REQUEST_TIMEOUT_MS)))?;
+    let mut reader = BufReader::new(stream);
+    
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split('?').next().unwrap_or(path);
+    
+    let (status, body) = match path {
+        "/" | "/metrics" => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", "Not found\n".to_string()),
+    };
+    
+    let mut stream = reader.into_inner();
+    write!(stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body)?;
+    stream.flush()
+}
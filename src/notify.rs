@@ -0,0 +1,232 @@
+// Author: CyberCraft Alchemist
+// Webhook notifications sent as soon as a host turns up serious findings
+
+use std::net::IpAddr;
+use std::time::Duration;
+use log::{debug, warn};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::constants::{TOOL_NAME, WEBHOOK_TIMEOUT_SECS};
+use crate::models::{ScanResult, Severity};
+use crate::progress::ScanProgress;
+use crate::proxy;
+
+// One finding in a webhook payload
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookFinding {
+    pub host: String,
+    pub port: Option<u16>, // None for host-level findings
+    pub id: String,
+    pub severity: Severity,
+    pub description: String,
+}
+
+/// Findings on a host at or above `min_severity`, most severe first
+pub fn host_findings(result: &ScanResult, min_severity: Severity) -> Vec<WebhookFinding> {
+    let port_vulns = result.open_ports.iter()
+        .flat_map(|p| p.vulnerabilities.iter().map(move |v| (Some(p.port), v)));
+    let host_vulns = result.host_vulnerabilities.iter().map(|v| (None, v));
+    
+    let mut findings: Vec<WebhookFinding> = port_vulns.chain(host_vulns)
+        .filter(|(_, vuln)| vuln.severity_level() >= min_severity)
+        .map(|(port, vuln)| WebhookFinding {
+            host: result.host.clone(),
+            port,
+            id: vuln.id.clone(),
+            severity: vuln.severity_level(),
+            description: vuln.description.clone(),
+        })
+        .collect();
+    
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+/// Whether a URL is a Slack incoming webhook, which expects a `text` message
+pub fn is_slack_webhook(url: &str) -> bool {
+    url.contains("hooks.slack.com/")
+}
+
+/// Build the JSON body posted for a host's findings
+///
+/// Slack webhooks get a readable `text` message; other URLs get the findings
+/// as structured JSON.
+pub fn webhook_payload(url: &str, result: &ScanResult, findings: &[WebhookFinding]) -> Value {
+    if is_slack_webhook(url) {
+        let mut text = format!("*{}*: {} finding(s) on {}", TOOL_NAME, findings.len(), result.host);
+        if !result.hostname.is_empty() && result.hostname != result.host {
+            text.push_str(&format!(" ({})", result.hostname));
+        }
+        for finding in findings {
+            let location = match finding.port {
+                Some(port) => format!("port {}", port),
+                None => "host".to_string(),
+            };
+            text.push_str(&format!("\n• [{}] {} on {}: {}", finding.severity, finding.id, location, finding.description));
+        }
+        json!({ "text": text })
+    } else {
+        json!({
+            "tool": TOOL_NAME,
+            "host": result.host,
+            "hostname": result.hostname,
+            "findings": findings,
+        })
+    }
+}
+
+/// POST a host's findings to a webhook
+pub fn send_webhook(url: &str, result: &ScanResult, findings: &[WebhookFinding]) -> Result<(), String> {
+    let client = proxy::client_builder()
+        .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| e.to_string())?;
+    
+    let response = client.post(url)
+        .json(&webhook_payload(url, result, findings))
+        .send()
+        .map_err(|e| e.to_string())?;
+    
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("webhook returned HTTP {}", response.status().as_u16()))
+    }
+}
+
+/// Posts each completed host's serious findings to a webhook, then forwards events to `inner`
+///
+/// Findings are batched into one POST per host, sent as soon as the host
+/// finishes rather than after the report is written.
+pub struct WebhookProgress<P: ScanProgress> {
+    url: String,
+    min_severity: Severity,
+    inner: P,
+}
+
+impl<P: ScanProgress> WebhookProgress<P> {
+    pub fn new(url: &str, min_severity: Severity, inner: P) -> Self {
+        WebhookProgress {
+            url: url.to_string(),
+            min_severity,
+            inner,
+        }
+    }
+}
+
+impl<P: ScanProgress> ScanProgress for WebhookProgress<P> {
+    fn on_host_started(&self, ip: &IpAddr) {
+        self.inner.on_host_started(ip);
+    }
+    
+    fn on_host_completed(&self, result: &ScanResult) {
+        let findings = host_findings(result, self.min_severity);
+        if !findings.is_empty() {
+            match send_webhook(&self.url, result, &findings) {
+                Ok(()) => debug!("Sent {} findings on {} to the webhook", findings.len(), result.host),
+                Err(e) => warn!("Failed to notify the webhook about {}: {}", result.host, e),
+            }
+        }
+        self.inner.on_host_completed(result);
+    }
+    
+    fn on_port_open(&self, ip: &IpAddr, port: u16) {
+        self.inner.on_port_open(ip, port);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+    
+    /// Host with a critical and a low finding on port 445
+    fn host_with_findings() -> ScanResult {
+        serde_json::from_value(json!({
+            "host": "10.0.0.5",
+            "hostname": "fileserver",
+            "is_online": true,
+            "open_ports": [{
+                "port": 445,
+                "service": "microsoft-ds",
+                "banner": "",
+                "vulnerabilities": [
+                    { "id": "MISCONFIG-SMB-SIGNING", "description": "SMB signing is not required", "severity": "LOW" },
+                    { "id": "CVE-2017-0144", "description": "SMBv1 remote code execution", "severity": "CRITICAL" },
+                ],
+            }],
+            "closed_port_count": 0,
+            "filtered_port_count": 0,
+            "scan_time": "2024-01-01 00:00:00",
+        })).unwrap()
+    }
+    
+    /// Accept one HTTP request, answer 200 and return the request body
+    fn spawn_mock_webhook() -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).unwrap();
+            reader.get_mut().write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+            String::from_utf8(body).unwrap()
+        });
+        
+        (url, handle)
+    }
+    
+    #[test]
+    fn webhook_receives_the_critical_finding() {
+        let result = host_with_findings();
+        let findings = host_findings(&result, Severity::High);
+        
+        let (url, server) = spawn_mock_webhook();
+        send_webhook(&url, &result, &findings).unwrap();
+        let payload: Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+        
+        assert_eq!(payload, json!({
+            "tool": TOOL_NAME,
+            "host": "10.0.0.5",
+            "hostname": "fileserver",
+            "findings": [{
+                "host": "10.0.0.5",
+                "port": 445,
+                "id": "CVE-2017-0144",
+                "severity": "CRITICAL",
+                "description": "SMBv1 remote code execution",
+            }],
+        }));
+    }
+    
+    #[test]
+    fn slack_webhooks_get_a_text_message_most_severe_first() {
+        let result = host_with_findings();
+        let findings = host_findings(&result, Severity::Low);
+        
+        let payload = webhook_payload("https://hooks.slack.com/services/T000/B000/XXXX", &result, &findings);
+        let text = payload["text"].as_str().unwrap();
+        
+        assert!(text.contains("2 finding(s) on 10.0.0.5 (fileserver)"));
+        assert!(text.find("CVE-2017-0144") < text.find("MISCONFIG-SMB-SIGNING"));
+    }
+}
@@ -0,0 +1,366 @@
+// Author: CyberCraft Alchemist
+// Passive service detection from sniffed traffic or a saved pcap file
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use chrono::Local;
+use log::{debug, error, info, warn};
+use pnet::datalink::{self, Channel};
+use pnet::packet::Packet;
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket};
+use pnet::packet::ip::{IpNextHeaderProtocol, IpNextHeaderProtocols};
+use pnet::packet::ipv4::Ipv4Packet;
+use pnet::packet::ipv6::Ipv6Packet;
+use pnet::packet::tcp::{TcpFlags, TcpPacket};
+
+use crate::constants::{BANNER_MAX_LEN, PASSIVE_BANNER_MAX_LEN, PASSIVE_READ_TIMEOUT_MS};
+use crate::cveapi;
+use crate::models::{PortResult, ScanConfig, ScanResult};
+use crate::plugins::PluginRegistry;
+use crate::progress::ScanProgress;
+use crate::resolver::ExcludeList;
+use crate::scanner;
+use crate::utils;
+
+// pcap link-layer header types we can decode
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+
+// Hosts, open ports and banners seen in captured traffic
+#[derive(Debug, Default)]
+pub struct PassiveObservations {
+    pub hosts: BTreeSet<IpAddr>,                              // Every address seen as a source or destination
+    pub open_ports: BTreeMap<IpAddr, BTreeMap<u16, String>>, // Ports with evidence of a listener, and their banners
+}
+
+impl PassiveObservations {
+    /// Record the hosts, open ports and banners in one Ethernet frame
+    pub fn observe_ethernet(&mut self, frame: &[u8]) {
+        if let Some(ethernet) = EthernetPacket::new(frame) {
+            match ethernet.get_ethertype() {
+                EtherTypes::Ipv4 | EtherTypes::Ipv6 => self.observe_ip(ethernet.payload()),
+                _ => {},
+            }
+        }
+    }
+    
+    /// Record the hosts, open ports and banners in one IPv4 or IPv6 packet
+    pub fn observe_ip(&mut self, packet: &[u8]) {
+        match packet.first().map(|b| b >> 4) {
+            Some(4) => {
+                if let Some(ipv4) = Ipv4Packet::new(packet) {
+                    let (src, dst) = (IpAddr::V4(ipv4.get_source()), IpAddr::V4(ipv4.get_destination()));
+                    self.observe_transport(src, dst, ipv4.get_next_level_protocol(), ipv4.payload());
+                }
+            },
+            Some(6) => {
+                if let Some(ipv6) = Ipv6Packet::new(packet) {
+                    let (src, dst) = (IpAddr::V6(ipv6.get_source()), IpAddr::V6(ipv6.get_destination()));
+                    self.observe_transport(src, dst, ipv6.get_next_header(), ipv6.payload());
+                }
+            },
+            _ => {},
+        }
+    }
+    
+    /// Look for listener evidence in a TCP segment
+    ///
+    /// A SYN-ACK means the source port is open. Data from an open port, or data
+    /// that starts like an SSH or HTTP server response, is kept as its banner.
+    fn observe_transport(&mut self, src: IpAddr, dst: IpAddr, protocol: IpNextHeaderProtocol, payload: &[u8]) {
+        self.hosts.insert(src);
+        self.hosts.insert(dst);
+        
+        if protocol != IpNextHeaderProtocols::Tcp {
+            return;
+        }
+        let tcp = match TcpPacket::new(payload) {
+            Some(tcp) => tcp,
+            None => return,
+        };
+        
+        let port = tcp.get_source();
+        let flags = tcp.get_flags();
+        if flags & TcpFlags::SYN != 0 && flags & TcpFlags::ACK != 0 {
+            debug!("SYN-ACK from {}:{}", src, port);
+            self.open_ports.entry(src).or_default().entry(port).or_default();
+        }
+        
+        let data = tcp.payload();
+        if data.is_empty() {
+            return;
+        }
+        let known_open = self.open_ports.get(&src).map(|ports| ports.contains_key(&port)).unwrap_or(false);
+        if let Some(banner) = extract_banner(data, known_open) {
+            let existing = self.open_ports.entry(src).or_default().entry(port).or_default();
+            if existing.is_empty() {
+                debug!("Banner from {}:{}: {}", src, port, utils::sanitize_banner(banner.as_bytes(), BANNER_MAX_LEN));
+                *existing = banner;
+            }
+        }
+    }
+}
+
+/// Pull a banner out of the data a server sent
+///
+/// SSH version strings and HTTP response headers are recognised anywhere;
+/// other printable first lines are only trusted from ports known to be open.
+pub fn extract_banner(data: &[u8], known_open: bool) -> Option<String> {
+    let data = &data[..data.len().min(PASSIVE_BANNER_MAX_LEN)];
+    
+    if data.starts_with(b"SSH-") {
+        let line = data.split(|&b| b == b'\n').next().unwrap_or(data);
+        return Some(String::from_utf8_lossy(line).trim_end().to_string());
+    }
+    
+    if data.starts_with(b"HTTP/") {
+        // Keep the status line and headers, which is what identification and header checks use
+        let text = String::from_utf8_lossy(data);
+        let headers = text.split("\r\n\r\n").next().unwrap_or(&text);
+        return Some(headers.to_string());
+    }
+    
+    if known_open {
+        let line = data.split(|&b| b == b'\n').next().unwrap_or(data);
+        let line = String::from_utf8_lossy(line).trim_end().to_string();
+        if !line.is_empty() && line.chars().all(|c| c.is_ascii_graphic() || c == ' ' || c == '\t') {
+            return Some(line);
+        }
+    }
+    
+    None
+}
+
+/// Sniff traffic on `interface` for `duration`, or until `cancel` is set
+///
+/// Nothing is sent. Opening the interface needs root or CAP_NET_RAW; lacking
+/// it is reported as an error rather than an empty capture.
+pub fn capture_interface(interface: &str, duration: Duration, cancel: &AtomicBool) -> Result<PassiveObservations, String> {
+    let iface = datalink::interfaces().into_iter()
+        .find(|iface| iface.name == interface)
+        .ok_or_else(|| format!("No network interface named {}", interface))?;
+    
+    let channel_config = datalink::Config {
+        read_timeout: Some(Duration::from_millis(PASSIVE_READ_TIMEOUT_MS)),
+        promiscuous: true,
+        ..Default::default()
+    };
+    let mut rx = match datalink::channel(&iface, channel_config) {
+        Ok(Channel::Ethernet(_, rx)) => rx,
+        Ok(_) => return Err(format!("Interface {} does not provide an Ethernet channel", iface.name)),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => return Err(format!(
+            "Passive capture needs raw socket privileges on {} (run as root or grant CAP_NET_RAW)", iface.name)),
+        Err(e) => return Err(format!("Could not open {} for capture: {}", iface.name, e)),
+    };
+    
+    info!("Capturing on {} for {}s", iface.name, duration.as_secs());
+    let mut observations = PassiveObservations::default();
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline && !cancel.load(Ordering::Relaxed) {
+        match rx.next() {
+            Ok(frame) => observations.observe_ethernet(frame),
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => {},
+            Err(e) => return Err(format!("Failed to read from {}: {}", iface.name, e)),
+        }
+    }
+    
+    Ok(observations)
+}
+
+/// Read the hosts, open ports and banners from a classic libpcap capture file
+///
+/// Ethernet, raw IP and Linux cooked captures are supported. pcapng files
+/// need converting first (e.g. `editcap -F pcap`).
+pub fn read_pcap_file(path: &Path) -> io::Result<PassiveObservations> {
+    let data = fs::read(path)?;
+    let invalid = |message: &str| io::Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), message));
+    
+    if data.len() < 24 {
+        return Err(invalid("too short to be a pcap file"));
+    }
+    let magic = [data[0], data[1], data[2], data[3]];
+    let little_endian = match magic {
+        [0xd4, 0xc3, 0xb2, 0xa1] | [0x4d, 0x3c, 0xb2, 0xa1] => true,
+        [0xa1, 0xb2, 0xc3, 0xd4] | [0xa1, 0xb2, 0x3c, 0x4d] => false,
+        [0x0a, 0x0d, 0x0d, 0x0a] => return Err(invalid("pcapng is not supported, convert it with 'editcap -F pcap'")),
+        _ => return Err(invalid("not a pcap file")),
+    };
+    let read_u32 = |offset: usize| {
+        let bytes = [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
+        if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) }
+    };
+    
+    let linktype = read_u32(20);
+    if ![LINKTYPE_ETHERNET, LINKTYPE_RAW, LINKTYPE_LINUX_SLL].contains(&linktype) {
+        return Err(invalid(&format!("unsupported link type {}", linktype)));
+    }
+    
+    let mut observations = PassiveObservations::default();
+    let mut offset = 24;
+    while offset + 16 <= data.len() {
+        let captured = read_u32(offset + 8) as usize;
+        let start = offset + 16;
+        let end = start.saturating_add(captured);
+        if end > data.len() {
+            warn!("{}: last packet is truncated", path.display());
+            break;
+        }
+        
+        let frame = &data[start..end];
+        match linktype {
+            LINKTYPE_ETHERNET => observations.observe_ethernet(frame),
+            LINKTYPE_RAW => observations.observe_ip(frame),
+            // 16-byte cooked header with the protocol type in its last two bytes
+            _ => {
+                if frame.len() > 16 && matches!(u16::from_be_bytes([frame[14], frame[15]]), 0x0800 | 0x86dd) {
+                    observations.observe_ip(&frame[16..]);
+                }
+            },
+        }
+        offset = end;
+    }
+    
+    Ok(observations)
+}
+
+/// Turn captured observations into scan results
+///
+/// Services are identified and checked for vulnerabilities from their banners
+/// alone; nothing is sent to the hosts. Only hosts inside `scope` are kept
+/// when it is given.
+pub fn observations_to_results(observations: &PassiveObservations, config: &ScanConfig, scope: Option<&ExcludeList>, exclude: &ExcludeList) -> Vec<ScanResult> {
+    let plugin_registry = PluginRegistry::with_config(config);
+    let scan_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    
+    observations.hosts.iter()
+        .filter(|ip| scope.map(|scope| scope.contains(ip)).unwrap_or(true) && !exclude.contains(ip))
+        .map(|ip| {
+            let open_ports: Vec<PortResult> = observations.open_ports.get(ip)
+                .map(|ports| ports.iter()
+                    .map(|(port, banner)| passive_port_result(*port, banner, config, &plugin_registry))
+                    .collect())
+                .unwrap_or_default();
+            let vulnerabilities_summary = if open_ports.is_empty() || config.banner_only {
+                None
+            } else {
+                Some(scanner::generate_vulnerability_summary(&open_ports, &[]))
+            };
+            
+            let misconfigurations = if config.check_misconfigurations && !config.banner_only {
+                open_ports.iter()
+                    .filter_map(|port| port.http_info.as_ref().map(|http_info| cveapi::assess_security_headers(http_info, port.port)))
+                    .flatten()
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            
+            ScanResult {
+                host: ip.to_string(),
+                hostname: ip.to_string(),
+                hostnames: Vec::new(),
+                is_online: true,
+                scan_time: scan_time.clone(),
+                open_ports,
+                closed_port_count: 0,
+                filtered_port_count: 0,
+                os_info: None,
+                vulnerabilities_summary,
+                attack_paths: None,
+                misconfigurations,
+                host_vulnerabilities: Vec::new(),
+                mac: None,
+                vendor: None,
+                effective_timeout_ms: None,
+                ike_info: None,
+            }
+        })
+        .collect()
+}
+
+/// Identify the service behind a captured banner and check it for vulnerabilities
+fn passive_port_result(port: u16, banner: &str, config: &ScanConfig, plugin_registry: &PluginRegistry) -> PortResult {
+    let identity = utils::identify_service(port, banner);
+    let service = identity.name.clone();
+    let http_info = utils::parse_http_info(banner, false);
+    
+    let vulnerabilities = if config.banner_only || banner.is_empty() {
+        Vec::new()
+    } else if config.enhanced_vuln_detection {
+        plugin_registry.detect_vulnerabilities(&service, banner, config)
+    } else {
+        cveapi::check_service_vulnerabilities(&service, banner, !config.offline_mode)
+    };
+    
+    PortResult {
+        port,
+        service,
+        banner: utils::sanitize_banner(banner.as_bytes(), BANNER_MAX_LEN),
+        vulnerabilities,
+        tls_certificate: None,
+        tls_versions: Vec::new(),
+        http_info,
+        smb_info: None,
+        http2_info: None,
+        ssh_info: None,
+        service_confidence: identity.confidence,
+        service_source: Some(identity.source),
+        raw_probe: None,
+        raw_response: None,
+    }
+}
+
+/// Run a passive scan from the configured interface or capture file
+///
+/// The configured targets, when given as addresses, CIDR blocks or ranges,
+/// limit which hosts are reported; hostnames are ignored since resolving them
+/// would send traffic. Errors are logged and give no results.
+pub fn run(config: &ScanConfig, progress: &dyn ScanProgress, cancel: &AtomicBool) -> Vec<ScanResult> {
+    let observations = match (&config.passive_interface, &config.passive_pcap) {
+        (Some(interface), _) => capture_interface(interface, Duration::from_secs(config.passive_duration_secs), cancel),
+        (None, Some(path)) => read_pcap_file(Path::new(path)).map_err(|e| format!("Failed to read capture: {}", e)),
+        (None, None) => return Vec::new(),
+    };
+    let observations = match observations {
+        Ok(observations) => observations,
+        Err(e) => {
+            error!("{}", e);
+            return Vec::new();
+        }
+    };
+    info!("Saw {} hosts, {} with open ports", observations.hosts.len(), observations.open_ports.len());
+    
+    let specs: Vec<String> = std::iter::once(&config.target)
+        .chain(&config.input_targets)
+        .filter(|spec| !spec.is_empty())
+        .filter(|spec| match ExcludeList::parse(std::slice::from_ref(spec)) {
+            Ok(_) => true,
+            Err(_) => {
+                warn!("Ignoring target {} in passive mode (only addresses, CIDR blocks and ranges limit the scope)", spec);
+                false
+            },
+        })
+        .cloned()
+        .collect();
+    let scope = if specs.is_empty() { None } else { ExcludeList::parse(&specs).ok() };
+    let exclude = ExcludeList::parse(&config.exclude).unwrap_or_default();
+    
+    let results = observations_to_results(&observations, config, scope.as_ref(), &exclude);
+    for result in &results {
+        if let Ok(ip) = result.host.parse::<IpAddr>() {
+            progress.on_host_started(&ip);
+            for port in &result.open_ports {
+                progress.on_port_open(&ip, port.port);
+            }
+        }
+        progress.on_host_completed(result);
+    }
+    
+    results
+}
@@ -33,6 +33,10 @@ impl VulnerabilityDetectorPlugin for CirclDetectorPlugin {
         self.enabled
     }
     
+    fn requires_network(&self) -> bool {
+        true
+    }
+    
     fn detect_vulnerabilities(&self, 
                              _service: &str, 
                              _banner: &str, 
@@ -0,0 +1,370 @@
+// HTTP Basic/Digest Authentication Default Credentials Plugin
+
+use std::error::Error;
+use std::net::IpAddr;
+use std::time::Duration;
+use rand::{thread_rng, Rng};
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::redirect::Policy;
+use crate::credentials;
+use crate::cveapi;
+use crate::models::{Vulnerability, ScanConfig};
+use crate::plugins::VulnerabilityDetectorPlugin;
+use crate::proxy;
+use crate::utils;
+
+// Authentication scheme offered in a WWW-Authenticate challenge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthScheme {
+    Basic,
+    Digest,
+}
+
+// A single challenge from a WWW-Authenticate header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthChallenge {
+    pub scheme: AuthScheme,
+    pub realm: String,
+    pub nonce: Option<String>,     // Digest only
+    pub opaque: Option<String>,    // Digest only, echoed back unchanged
+    pub qop: Option<String>,       // Digest quality of protection options, e.g. "auth,auth-int"
+    pub algorithm: Option<String>, // Digest hash algorithm, MD5 when absent
+}
+
+pub struct HttpAuthPlugin {
+    enabled: bool,
+}
+
+impl Default for HttpAuthPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpAuthPlugin {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+        }
+    }
+    
+    /// Parse the Basic and Digest challenges out of WWW-Authenticate header values
+    pub fn parse_challenges(headers: &[&str]) -> Vec<AuthChallenge> {
+        lazy_static::lazy_static! {
+            static ref SCHEME: Regex = Regex::new(r"(?i)(?:^|,)\s*(basic|digest)\b").unwrap();
+            static ref PARAM: Regex = Regex::new(r#"([A-Za-z-]+)\s*=\s*(?:"((?:[^"\\]|\\.)*)"|([^\s,]+))"#).unwrap();
+        }
+        
+        let mut challenges = Vec::new();
+        
+        for header in headers {
+            // A header may carry several challenges, each starting with its scheme name
+            let starts: Vec<(usize, usize, AuthScheme)> = SCHEME.captures_iter(header)
+                .filter_map(|caps| {
+                    let name = caps.get(1)?;
+                    let scheme = if name.as_str().eq_ignore_ascii_case("basic") { AuthScheme::Basic } else { AuthScheme::Digest };
+                    Some((name.start(), name.end(), scheme))
+                })
+                .collect();
+            
+            for (index, (_, params_start, scheme)) in starts.iter().enumerate() {
+                let params_end = starts.get(index + 1).map(|(start, _, _)| *start).unwrap_or(header.len());
+                let mut challenge = AuthChallenge {
+                    scheme: *scheme,
+                    realm: String::new(),
+                    nonce: None,
+                    opaque: None,
+                    qop: None,
+                    algorithm: None,
+                };
+                
+                for caps in PARAM.captures_iter(&header[*params_start..params_end]) {
+                    let value = caps.get(2)
+                        .or_else(|| caps.get(3))
+                        .map(|m| m.as_str().replace("\\\"", "\""))
+                        .unwrap_or_default();
+                    match caps[1].to_lowercase().as_str() {
+                        "realm" => challenge.realm = value,
+                        "nonce" => challenge.nonce = Some(value),
+                        "opaque" => challenge.opaque = Some(value),
+                        "qop" => challenge.qop = Some(value),
+                        "algorithm" => challenge.algorithm = Some(value),
+                        _ => {},
+                    }
+                }
+                
+                challenges.push(challenge);
+            }
+        }
+        
+        challenges
+    }
+    
+    /// Build the Authorization header answering a challenge for a request to `uri`
+    ///
+    /// Returns `None` for Digest challenges without a nonce or using an
+    /// algorithm other than MD5 or MD5-sess.
+    pub fn authorization(challenge: &AuthChallenge, uri: &str, username: &str, password: &str) -> Option<String> {
+        match challenge.scheme {
+            AuthScheme::Basic => {
+                Some(format!("Basic {}", base64_encode(format!("{}:{}", username, password).as_bytes())))
+            },
+            AuthScheme::Digest => {
+                let nonce = challenge.nonce.as_deref()?;
+                let algorithm = challenge.algorithm.as_deref().unwrap_or("MD5");
+                let cnonce = format!("{:016x}", thread_rng().gen::<u64>());
+                let nc = "00000001";
+                
+                let mut ha1 = md5_hex(format!("{}:{}:{}", username, challenge.realm, password).as_bytes());
+                if algorithm.eq_ignore_ascii_case("MD5-sess") {
+                    ha1 = md5_hex(format!("{}:{}:{}", ha1, nonce, cnonce).as_bytes());
+                } else if !algorithm.eq_ignore_ascii_case("MD5") {
+                    return None;
+                }
+                let ha2 = md5_hex(format!("GET:{}", uri).as_bytes());
+                
+                // Only the "auth" quality of protection is used; "auth-int" would need the body hashed
+                let qop_auth = challenge.qop.as_deref()
+                    .map(|qop| qop.split(',').any(|option| option.trim().eq_ignore_ascii_case("auth")))
+                    .unwrap_or(false);
+                let response = if qop_auth {
+                    md5_hex(format!("{}:{}:{}:{}:auth:{}", ha1, nonce, nc, cnonce, ha2).as_bytes())
+                } else {
+                    md5_hex(format!("{}:{}:{}", ha1, nonce, ha2).as_bytes())
+                };
+                
+                let mut header = format!(
+                    "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+                    username, challenge.realm, nonce, uri, algorithm, response
+                );
+                if qop_auth {
+                    header.push_str(&format!(", qop=auth, nc={}, cnonce=\"{}\"", nc, cnonce));
+                }
+                if let Some(opaque) = &challenge.opaque {
+                    header.push_str(&format!(", opaque=\"{}\"", opaque));
+                }
+                Some(header)
+            },
+        }
+    }
+    
+    /// Try each credential pair against the realm protecting `url`, returning the first that is accepted
+    fn find_working_credentials<'a>(client: &Client, url: &str, credentials: &[(&'a str, &'a str)]) -> Option<(AuthChallenge, &'a str, &'a str)> {
+        utils::wait_for_connection_slot();
+        let response = client.get(url).send().ok()?;
+        if response.status().as_u16() != 401 {
+            return None;
+        }
+        
+        let headers: Vec<&str> = response.headers()
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect();
+        
+        // Digest is the stronger scheme, so it is answered when a server offers both
+        let mut challenges = Self::parse_challenges(&headers);
+        challenges.sort_by_key(|challenge| challenge.scheme != AuthScheme::Digest);
+        let challenge = challenges.into_iter().next()?;
+        
+        let uri = reqwest::Url::parse(url).ok()?.path().to_string();
+        
+        for (username, password) in credentials {
+            // Digest nonces may be single-use, so each attempt answers a fresh challenge
+            let current = if challenge.scheme == AuthScheme::Digest {
+                utils::wait_for_connection_slot();
+                let response = client.get(url).send().ok()?;
+                let headers: Vec<&str> = response.headers()
+                    .get_all(WWW_AUTHENTICATE)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .collect();
+                Self::parse_challenges(&headers).into_iter()
+                    .find(|c| c.scheme == AuthScheme::Digest)
+                    .unwrap_or_else(|| challenge.clone())
+            } else {
+                challenge.clone()
+            };
+            
+            let authorization = Self::authorization(&current, &uri, username, password)?;
+            
+            utils::wait_for_connection_slot();
+            let status = match client.get(url).header(AUTHORIZATION, authorization).send() {
+                Ok(response) => response.status().as_u16(),
+                Err(_) => continue,
+            };
+            if status != 401 && status != 403 && status < 500 {
+                return Some((current, username, password));
+            }
+        }
+        
+        None
+    }
+    
+    /// Build the finding reported when an HTTP authentication realm accepts default credentials
+    pub fn create_vulnerability(url: &str, challenge: &AuthChallenge, username: &str, password: &str) -> Vulnerability {
+        let scheme = match challenge.scheme {
+            AuthScheme::Basic => "Basic",
+            AuthScheme::Digest => "Digest",
+        };
+        
+        cveapi::create_full_vulnerability(
+            "DEFAULT-CREDS-HTTP-AUTH".to_string(),
+            format!("HTTP {} authentication realm \"{}\" at {} accepts default credentials {}:{}",
+                    scheme, challenge.realm, url, username, password),
+            Some("CRITICAL".to_string()),
+            Some(9.8),
+            Some(vec!["https://cwe.mitre.org/data/definitions/1392.html".to_string()]),
+            Some(false),
+            Some(true),
+            Some(format!("Change the default credentials for the \"{}\" realm", challenge.realm)),
+            Some("Authentication".to_string()),
+            Some("CWE-1392".to_string()), // Use of Default Credentials
+            Some("Web".to_string()),
+            Some(vec!["Initial Access".to_string(), "Persistence".to_string()]),
+            Some(vec!["T1078.001".to_string()]), // Valid Accounts: Default Accounts
+        )
+    }
+}
+
+impl VulnerabilityDetectorPlugin for HttpAuthPlugin {
+    fn name(&self) -> &str {
+        "HTTP Authentication"
+    }
+    
+    fn description(&self) -> &str {
+        "Tries default credentials against HTTP Basic and Digest authentication realms"
+    }
+    
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+    
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    
+    fn is_intrusive(&self) -> bool {
+        true
+    }
+    
+    fn detect_vulnerabilities(&self, 
+                             _service: &str, 
+                             _banner: &str,
+                             _config: &ScanConfig) -> Result<Vec<Vulnerability>, Box<dyn Error>> {
+        // Logins need the host and port, so they are attempted in detect_by_port
+        Ok(Vec::new())
+    }
+    
+    fn lookup_vulnerability(&self, 
+                           _identifier: &str) -> Result<Option<Vulnerability>, Box<dyn Error>> {
+        Ok(None)
+    }
+    
+    fn detect_by_port(&self, 
+                     ip: &IpAddr, 
+                     port: u16,
+                     service: &str, 
+                     banner: &str,
+                     config: &ScanConfig) -> Result<Vec<Vulnerability>, Box<dyn Error>> {
+        // Login attempts are intrusive and strictly opt-in
+        if !config.check_default_credentials || config.non_intrusive {
+            return Ok(Vec::new());
+        }
+        if !service.to_lowercase().contains("http") && !banner.starts_with("HTTP/") {
+            return Ok(Vec::new());
+        }
+        
+        let url = format!("{}/", credentials::web_base_url(ip, port, service));
+        let client = proxy::client_builder()
+            .timeout(Duration::from_millis(config.timeout_ms.max(1000)))
+            .redirect(Policy::none())
+            .danger_accept_invalid_certs(true)
+            .build()?;
+        
+        let pairs = credentials::web_credentials_for_port(port);
+        Ok(Self::find_working_credentials(&client, &url, &pairs)
+            .map(|(challenge, username, password)| Self::create_vulnerability(&url, &challenge, username, password))
+            .into_iter()
+            .collect())
+    }
+}
+
+/// Encode bytes as standard padded base64
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len() * 4 / 3 + 4);
+    
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let group = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((group >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    
+    encoded
+}
+
+/// Lowercase hex MD5 digest, as used by HTTP Digest authentication (RFC 1321)
+fn md5_hex(data: &[u8]) -> String {
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+        5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+        4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+        6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    let constants: Vec<u32> = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32)
+        .collect();
+    
+    // Pad to a multiple of 64 bytes, ending with the message length in bits
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+    
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    
+    for block in message.chunks(64) {
+        let words: Vec<u32> = block.chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a.wrapping_add(f)
+                .wrapping_add(constants[i])
+                .wrapping_add(words[g])
+                .rotate_left(SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+    }
+    
+    state.iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -45,6 +45,10 @@ impl VulnerabilityDetectorPlugin for IcsCertDetectorPlugin {
         self.enabled
     }
     
+    fn requires_network(&self) -> bool {
+        true
+    }
+    
     fn detect_vulnerabilities(&self, 
                              service: &str, 
                              _banner: &str, 
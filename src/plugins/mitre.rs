@@ -34,6 +34,10 @@ impl VulnerabilityDetectorPlugin for MitreAttackPlugin {
         self.enabled
     }
     
+    fn requires_network(&self) -> bool {
+        true
+    }
+    
     fn detect_vulnerabilities(&self, 
                              _service: &str, 
                              _banner: &str, 
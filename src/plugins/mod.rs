@@ -2,6 +2,7 @@
 // This module provides a pluggable architecture for vulnerability detection
 
 use std::error::Error;
+use std::net::IpAddr;
 use crate::models::{Vulnerability, ScanConfig};
 
 /// Trait defining the interface for vulnerability detector plugins
@@ -18,6 +19,16 @@ pub trait VulnerabilityDetectorPlugin {
     /// Returns true if the plugin is enabled
     fn is_enabled(&self) -> bool;
     
+    /// Returns true if the plugin only works with internet access, so finds nothing in offline mode
+    fn requires_network(&self) -> bool {
+        false
+    }
+    
+    /// Returns true if the plugin does more to targets than a benign request, such as attempting logins
+    fn is_intrusive(&self) -> bool {
+        false
+    }
+    
     /// Detects vulnerabilities based on service information and banner
     fn detect_vulnerabilities(&self, 
                              service: &str, 
@@ -27,11 +38,32 @@ pub trait VulnerabilityDetectorPlugin {
     /// Performs direct vulnerability lookup by identifier (e.g., CVE ID)
     fn lookup_vulnerability(&self, 
                            identifier: &str) -> Result<Option<Vulnerability>, Box<dyn Error>>;
+    
+    /// Detects vulnerabilities reported for a host as a whole rather than a single service
+    fn detect_by_host(&self, 
+                     _ip: &IpAddr, 
+                     _config: &ScanConfig) -> Result<Vec<Vulnerability>, Box<dyn Error>> {
+        Ok(Vec::new())
+    }
+    
+    /// Detects vulnerabilities that need a live connection to a specific open port
+    fn detect_by_port(&self, 
+                     _ip: &IpAddr, 
+                     _port: u16, 
+                     _service: &str, 
+                     _banner: &str, 
+                     _config: &ScanConfig) -> Result<Vec<Vulnerability>, Box<dyn Error>> {
+        Ok(Vec::new())
+    }
 }
 
 // Re-export specific plugin modules
 pub mod nvd;
 pub mod circl;
+pub mod osv;
+pub mod ghsa;
+pub mod shodan;
+pub mod http_auth;
 pub mod ics_cert;
 pub mod mitre;
 pub mod pattern_matching;
@@ -39,6 +71,9 @@ pub mod pattern_matching;
 // Plugin registry to manage available detector plugins
 pub struct PluginRegistry {
     plugins: Vec<Box<dyn VulnerabilityDetectorPlugin>>,
+    enabled_plugins: Option<Vec<String>>, // Names allowed to run (None allows all)
+    disabled_plugins: Vec<String>,        // Names never run
+    non_intrusive: bool,                  // Leave out intrusive plugins
 }
 
 impl PluginRegistry {
@@ -46,11 +81,18 @@ impl PluginRegistry {
     pub fn new() -> Self {
         let mut registry = Self { 
             plugins: Vec::new(),
+            enabled_plugins: None,
+            disabled_plugins: Vec::new(),
+            non_intrusive: false,
         };
         
         // Register default plugins
         registry.register_plugin(Box::new(nvd::NvdDetectorPlugin::new()));
         registry.register_plugin(Box::new(circl::CirclDetectorPlugin::new()));
+        registry.register_plugin(Box::new(osv::OsvDetectorPlugin::new()));
+        registry.register_plugin(Box::new(ghsa::GhsaDetectorPlugin::new()));
+        registry.register_plugin(Box::new(shodan::ShodanDetectorPlugin::new()));
+        registry.register_plugin(Box::new(http_auth::HttpAuthPlugin::new()));
         registry.register_plugin(Box::new(pattern_matching::PatternMatchingPlugin::new()));
         
         // Optional plugins based on configuration
@@ -60,6 +102,17 @@ impl PluginRegistry {
         registry
     }
     
+    /// Create a registry with default plugins, filtered by the configuration's
+    /// `enabled_plugins` and `disabled_plugins`, and without intrusive plugins
+    /// when `non_intrusive` is set
+    pub fn with_config(config: &ScanConfig) -> Self {
+        let mut registry = Self::new();
+        registry.enabled_plugins = config.enabled_plugins.clone();
+        registry.disabled_plugins = config.disabled_plugins.clone();
+        registry.non_intrusive = config.non_intrusive;
+        registry
+    }
+    
     /// Register a new plugin
     pub fn register_plugin(&mut self, plugin: Box<dyn VulnerabilityDetectorPlugin>) {
         self.plugins.push(plugin);
@@ -70,13 +123,20 @@ impl PluginRegistry {
         &self.plugins
     }
     
-    /// Get enabled plugins
+    /// Get plugins that are enabled themselves and allowed by the configuration
     pub fn get_enabled_plugins(&self) -> Vec<&Box<dyn VulnerabilityDetectorPlugin>> {
         self.plugins.iter()
-            .filter(|p| p.is_enabled())
+            .filter(|p| p.is_enabled() && self.is_allowed(p.name()) && !(self.non_intrusive && p.is_intrusive()))
             .collect()
     }
     
+    /// Whether the configuration lets the plugin with this name run (names match case-insensitively)
+    fn is_allowed(&self, name: &str) -> bool {
+        let listed = |names: &[String]| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+        
+        self.enabled_plugins.as_deref().map(listed).unwrap_or(true) && !listed(&self.disabled_plugins)
+    }
+    
     /// Detect vulnerabilities using all enabled plugins
     pub fn detect_vulnerabilities(&self, 
                                  service: &str, 
@@ -90,11 +150,41 @@ impl PluginRegistry {
             }
         }
         
-        // Deduplicate vulnerabilities by ID
-        results.sort_by(|a, b| a.id.cmp(&b.id));
-        results.dedup_by(|a, b| a.id == b.id);
+        // Combine findings for the same ID reported by different plugins
+        crate::cveapi::merge_vulnerabilities(results)
+    }
+    
+    /// Detect host-level vulnerabilities using all enabled plugins
+    pub fn detect_by_host(&self, 
+                         ip: &IpAddr, 
+                         config: &ScanConfig) -> Vec<Vulnerability> {
+        let mut results = Vec::new();
+        
+        for plugin in self.get_enabled_plugins() {
+            if let Ok(vulnerabilities) = plugin.detect_by_host(ip, config) {
+                results.extend(vulnerabilities);
+            }
+        }
+        
+        crate::cveapi::merge_vulnerabilities(results)
+    }
+    
+    /// Detect port-level vulnerabilities using all enabled plugins
+    pub fn detect_by_port(&self, 
+                         ip: &IpAddr, 
+                         port: u16, 
+                         service: &str, 
+                         banner: &str, 
+                         config: &ScanConfig) -> Vec<Vulnerability> {
+        let mut results = Vec::new();
+        
+        for plugin in self.get_enabled_plugins() {
+            if let Ok(vulnerabilities) = plugin.detect_by_port(ip, port, service, banner, config) {
+                results.extend(vulnerabilities);
+            }
+        }
         
-        results
+        crate::cveapi::merge_vulnerabilities(results)
     }
     
     /// Lookup vulnerability using all enabled plugins
@@ -38,9 +38,9 @@ impl VulnerabilityDetectorPlugin for NvdDetectorPlugin {
                              service: &str, 
                              banner: &str, 
                              config: &ScanConfig) -> Result<Vec<Vulnerability>, Box<dyn Error>> {
-        // If offline mode is enabled, don't perform NVD lookups
+        // If offline mode is enabled, use the imported NVD feeds instead of the API
         if config.offline_mode {
-            return Ok(Vec::new());
+            return Ok(cveapi::find_offline_vulnerabilities(banner));
         }
         
         // Use the existing cveapi functionality to detect vulnerabilities
@@ -51,6 +51,6 @@ impl VulnerabilityDetectorPlugin for NvdDetectorPlugin {
     fn lookup_vulnerability(&self, 
                            identifier: &str) -> Result<Option<Vulnerability>, Box<dyn Error>> {
         // Use the existing cveapi functionality to lookup a vulnerability
-        cveapi::lookup_vulnerability(identifier)
+        Ok(cveapi::lookup_vulnerability(identifier)?)
     }
 }
@@ -0,0 +1,89 @@
+// OSV.dev (Open Source Vulnerabilities) Vulnerability Detector Plugin
+
+use std::error::Error;
+use std::time::Duration;
+use reqwest::blocking::Client;
+use crate::constants::OSV_PACKAGES;
+use crate::models::{Vulnerability, ScanConfig};
+use crate::plugins::VulnerabilityDetectorPlugin;
+use crate::proxy;
+use crate::cveapi;
+
+pub struct OsvDetectorPlugin {
+    enabled: bool,
+}
+
+impl OsvDetectorPlugin {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+        }
+    }
+    
+    fn client() -> Result<Client, Box<dyn Error>> {
+        Ok(proxy::client_builder()
+            .timeout(Duration::from_secs(10))
+            .build()?)
+    }
+}
+
+impl Default for OsvDetectorPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VulnerabilityDetectorPlugin for OsvDetectorPlugin {
+    fn name(&self) -> &str {
+        "OSV Vulnerability Detector"
+    }
+    
+    fn description(&self) -> &str {
+        "Detects vulnerabilities in banner product versions using the OSV.dev API"
+    }
+    
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+    
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    
+    fn requires_network(&self) -> bool {
+        true
+    }
+    
+    fn detect_vulnerabilities(&self, 
+                             _service: &str, 
+                             banner: &str, 
+                             config: &ScanConfig) -> Result<Vec<Vulnerability>, Box<dyn Error>> {
+        // If offline mode is enabled, don't perform OSV lookups
+        if config.offline_mode {
+            return Ok(Vec::new());
+        }
+        
+        let products = cveapi::extract_product_versions(banner);
+        if products.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let client = Self::client()?;
+        let mut vulnerabilities = Vec::new();
+        
+        for (product, version) in products {
+            // Only query products we know the OSV package name for
+            if let Some((_, package, ecosystem)) = OSV_PACKAGES.iter().find(|(p, _, _)| *p == product) {
+                vulnerabilities.extend(cveapi::query_osv_package(&client, package, ecosystem, &version)?);
+            }
+        }
+        
+        Ok(vulnerabilities)
+    }
+    
+    fn lookup_vulnerability(&self, 
+                           identifier: &str) -> Result<Option<Vulnerability>, Box<dyn Error>> {
+        let client = Self::client()?;
+        Ok(cveapi::lookup_vulnerability_osv(&client, identifier)?)
+    }
+}
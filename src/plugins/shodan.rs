@@ -0,0 +1,168 @@
+// Shodan InternetDB Vulnerability Detector Plugin
+
+use std::error::Error;
+use std::net::IpAddr;
+use std::time::Duration;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use crate::constants::SHODAN_INTERNETDB_URL;
+use crate::models::{Vulnerability, ScanConfig, Severity};
+use crate::plugins::VulnerabilityDetectorPlugin;
+use crate::proxy;
+use crate::cveapi;
+
+// Host record returned by InternetDB
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct InternetDbHost {
+    pub ip: String,
+    pub ports: Vec<u16>,          // Ports Shodan has seen open
+    pub cpes: Vec<String>,        // Products identified on the host
+    pub hostnames: Vec<String>,
+    pub tags: Vec<String>,        // e.g. "cloud", "vpn", "self-signed"
+    pub vulns: Vec<String>,       // CVE IDs Shodan associates with the host
+}
+
+pub struct ShodanDetectorPlugin {
+    enabled: bool,
+}
+
+impl ShodanDetectorPlugin {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+        }
+    }
+    
+    /// Fetch the InternetDB record for an IP, or `None` if Shodan has no data on it
+    pub fn fetch_host(ip: &IpAddr) -> Result<Option<InternetDbHost>, Box<dyn Error>> {
+        let client = proxy::client_builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        
+        let response = client.get(format!("{}/{}", SHODAN_INTERNETDB_URL, ip)).send()?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(format!("InternetDB returned {}", response.status()).into());
+        }
+        
+        Ok(Some(response.json()?))
+    }
+    
+    /// Turn an InternetDB record into vulnerabilities plus an informational context entry
+    pub fn host_to_vulnerabilities(host: &InternetDbHost) -> Vec<Vulnerability> {
+        let shodan_link = format!("https://www.shodan.io/host/{}", host.ip);
+        
+        let mut results: Vec<Vulnerability> = host.vulns.iter()
+            .map(|cve_id| {
+                cveapi::create_vulnerability(
+                    cve_id.to_uppercase(),
+                    format!("{} reported for {} by Shodan InternetDB", cve_id.to_uppercase(), host.ip),
+                    None,
+                    None,
+                    Some(vec![
+                        format!("https://nvd.nist.gov/vuln/detail/{}", cve_id.to_uppercase()),
+                        shodan_link.clone(),
+                    ]),
+                )
+            })
+            .collect();
+        
+        // Keep what Shodan knows about the host as context for the report
+        if !host.cpes.is_empty() || !host.tags.is_empty() || !host.ports.is_empty() {
+            let mut context = Vec::new();
+            if !host.ports.is_empty() {
+                let ports: Vec<String> = host.ports.iter().map(|p| p.to_string()).collect();
+                context.push(format!("ports seen: {}", ports.join(", ")));
+            }
+            if !host.cpes.is_empty() {
+                context.push(format!("products: {}", host.cpes.join(", ")));
+            }
+            if !host.tags.is_empty() {
+                context.push(format!("tags: {}", host.tags.join(", ")));
+            }
+            if !host.hostnames.is_empty() {
+                context.push(format!("hostnames: {}", host.hostnames.join(", ")));
+            }
+            
+            results.push(cveapi::create_vulnerability(
+                "SHODAN-INTERNETDB".to_string(),
+                format!("Shodan InternetDB has indexed this host ({})", context.join("; ")),
+                Some(Severity::Info.to_string()),
+                None,
+                Some(vec![shodan_link]),
+            ));
+        }
+        
+        results
+    }
+}
+
+impl Default for ShodanDetectorPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check whether an address can appear in Shodan's Internet-wide scans
+fn is_internet_routable(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => !(v4.is_private() || v4.is_loopback() || v4.is_link_local()
+            || v4.is_broadcast() || v4.is_unspecified() || v4.is_multicast()),
+        IpAddr::V6(v6) => !(v6.is_loopback() || v6.is_unspecified() || v6.is_multicast()
+            // Unique local (fc00::/7) and link-local (fe80::/10) addresses
+            || (v6.segments()[0] & 0xfe00) == 0xfc00
+            || (v6.segments()[0] & 0xffc0) == 0xfe80),
+    }
+}
+
+impl VulnerabilityDetectorPlugin for ShodanDetectorPlugin {
+    fn name(&self) -> &str {
+        "Shodan InternetDB Detector"
+    }
+    
+    fn description(&self) -> &str {
+        "Reports known CVEs, products and tags for Internet-facing hosts from Shodan InternetDB"
+    }
+    
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+    
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    
+    fn requires_network(&self) -> bool {
+        true
+    }
+    
+    fn detect_vulnerabilities(&self, 
+                             _service: &str, 
+                             _banner: &str, 
+                             _config: &ScanConfig) -> Result<Vec<Vulnerability>, Box<dyn Error>> {
+        // InternetDB is queried per host in detect_by_host
+        Ok(Vec::new())
+    }
+    
+    fn lookup_vulnerability(&self, 
+                           _identifier: &str) -> Result<Option<Vulnerability>, Box<dyn Error>> {
+        Ok(None)
+    }
+    
+    fn detect_by_host(&self, 
+                     ip: &IpAddr, 
+                     config: &ScanConfig) -> Result<Vec<Vulnerability>, Box<dyn Error>> {
+        // Shodan only knows about Internet-facing addresses
+        if config.offline_mode || !is_internet_routable(ip) {
+            return Ok(Vec::new());
+        }
+        
+        match Self::fetch_host(ip)? {
+            Some(host) => Ok(Self::host_to_vulnerabilities(&host)),
+            None => Ok(Vec::new()),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// Author: CyberCraft Alchemist
+// Preset scan configurations selected by name with --profile
+
+use crate::constants::{COMMON_PORTS, OT_PROTOCOLS};
+use crate::models::ScanConfig;
+
+// Names of the preset profiles, as accepted by `by_name`
+pub const NAMES: &[&str] = &["quick", "full", "stealth", "ot", "compliance"];
+
+/// The preset configuration with the given name, or None if there is no such profile
+pub fn by_name(name: &str) -> Option<ScanConfig> {
+    match name.to_lowercase().as_str() {
+        "quick" => Some(quick()),
+        "full" => Some(full()),
+        "stealth" => Some(stealth()),
+        "ot" => Some(ot()),
+        "compliance" => Some(compliance()),
+        _ => None,
+    }
+}
+
+/// Fast inventory of the 20 most common ports using only local vulnerability data
+pub fn quick() -> ScanConfig {
+    ScanConfig {
+        top_ports: Some(20),
+        offline_mode: true,
+        retries: 0,
+        ..ScanConfig::default()
+    }
+}
+
+/// Every common and OT port with all plugins, TLS inspection and SRV enumeration
+pub fn full() -> ScanConfig {
+    ScanConfig {
+        ports: sorted_ports(COMMON_PORTS.keys()),
+        enabled_plugins: None,
+        enhanced_vuln_detection: true,
+        assess_attack_surface: true,
+        check_misconfigurations: true,
+        inspect_tls: true,
+        mitre_mapping: true,
+        attack_path_analysis: true,
+        enumerate_srv: true,
+        ..ScanConfig::default()
+    }
+}
+
+/// Randomized, rate-limited scan with long timeouts that only grabs banners and
+/// makes no misconfiguration probes, TLS handshakes or login attempts
+pub fn stealth() -> ScanConfig {
+    ScanConfig {
+        threads: 5,
+        timeout_ms: 3000,
+        max_pps: Some(10),
+        retries: 0,
+        randomize_scan: true,
+        check_misconfigurations: false,
+        inspect_tls: false,
+        check_default_credentials: false,
+        ..ScanConfig::default()
+    }
+}
+
+/// Industrial protocol ports only, with few threads and a low connection rate so
+/// fragile controllers aren't overwhelmed
+pub fn ot() -> ScanConfig {
+    ScanConfig {
+        ports: sorted_ports(OT_PROTOCOLS.keys()),
+        threads: 4,
+        timeout_ms: 2000,
+        max_pps: Some(20),
+        retries: 0,
+        inspect_tls: false,
+        check_default_credentials: false,
+        ..ScanConfig::default()
+    }
+}
+
+/// Common ports with certificate, protocol version, security header and
+/// misconfiguration checks, leaving out attack path analysis
+pub fn compliance() -> ScanConfig {
+    ScanConfig {
+        ports: sorted_ports(COMMON_PORTS.keys()),
+        check_misconfigurations: true,
+        inspect_tls: true,
+        enhanced_vuln_detection: true,
+        assess_attack_surface: false,
+        attack_path_analysis: false,
+        ..ScanConfig::default()
+    }
+}
+
+/// Collect port numbers in ascending order
+fn sorted_ports<'a>(ports: impl Iterator<Item = &'a u16>) -> Vec<u16> {
+    let mut ports: Vec<u16> = ports.cloned().collect();
+    ports.sort_unstable();
+    ports
+}
@@ -0,0 +1,110 @@
+// Author: CyberCraft Alchemist
+// Progress reporting hooks invoked while a scan is running
+
+use std::io::Write;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use crate::models::{ReportFilter, ScanResult};
+use crate::report;
+
+/// Receives progress events from a running scan
+///
+/// Hooks are called from Rayon worker threads, so implementations must be
+/// thread-safe. Every hook defaults to doing nothing.
+pub trait ScanProgress: Send + Sync {
+    /// Called when scanning of a host begins
+    fn on_host_started(&self, _ip: &IpAddr) {}
+    
+    /// Called when a host has been fully scanned, whether or not it had open ports
+    fn on_host_completed(&self, _result: &ScanResult) {}
+    
+    /// Called as soon as an open port is found
+    fn on_port_open(&self, _ip: &IpAddr, _port: u16) {}
+}
+
+impl<P: ScanProgress + ?Sized> ScanProgress for Box<P> {
+    fn on_host_started(&self, ip: &IpAddr) {
+        (**self).on_host_started(ip);
+    }
+    
+    fn on_host_completed(&self, result: &ScanResult) {
+        (**self).on_host_completed(result);
+    }
+    
+    fn on_port_open(&self, ip: &IpAddr, port: u16) {
+        (**self).on_port_open(ip, port);
+    }
+}
+
+impl<P: ScanProgress + ?Sized> ScanProgress for &P {
+    fn on_host_started(&self, ip: &IpAddr) {
+        (**self).on_host_started(ip);
+    }
+    
+    fn on_host_completed(&self, result: &ScanResult) {
+        (**self).on_host_completed(result);
+    }
+    
+    fn on_port_open(&self, ip: &IpAddr, port: u16) {
+        (**self).on_port_open(ip, port);
+    }
+}
+
+/// Progress reporter that ignores all events
+pub struct NoProgress;
+
+impl ScanProgress for NoProgress {}
+
+/// Streams each completed host with open ports as a JSON line, then forwards events to `inner`
+///
+/// The writer sits behind a mutex so results completing on different threads
+/// are written as whole lines.
+pub struct JsonlProgress<W: Write + Send, P: ScanProgress> {
+    writer: Mutex<W>,
+    inner: P,
+    filter: ReportFilter,
+}
+
+impl<W: Write + Send, P: ScanProgress> JsonlProgress<W, P> {
+    pub fn new(writer: W, inner: P) -> Self {
+        JsonlProgress {
+            writer: Mutex::new(writer),
+            inner,
+            filter: ReportFilter::default(),
+        }
+    }
+    
+    /// Only write the vulnerabilities that pass `filter`
+    pub fn with_filter(mut self, filter: ReportFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+    
+    /// Return the wrapped writer once the scan has finished
+    pub fn into_writer(self) -> W {
+        self.writer.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<W: Write + Send, P: ScanProgress> ScanProgress for JsonlProgress<W, P> {
+    fn on_host_started(&self, ip: &IpAddr) {
+        self.inner.on_host_started(ip);
+    }
+    
+    fn on_host_completed(&self, result: &ScanResult) {
+        // Only hosts with open ports or UDP findings are kept in the scan results
+        if result.is_reportable() {
+            let (shown, _) = report::filter_results(std::slice::from_ref(result), Some(&self.filter));
+            let mut writer = self.writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Err(e) = report::stream_jsonl(&shown[0], &mut *writer) {
+                log::error!("Failed to write result for {}: {}", result.host, e);
+            }
+        }
+        self.inner.on_host_completed(result);
+    }
+    
+    fn on_port_open(&self, ip: &IpAddr, port: u16) {
+        self.inner.on_port_open(ip, port);
+    }
+}
@@ -1,14 +1,23 @@
 // Author: CyberCraft Alchemist
 // Report generation functionalities in multiple formats
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Write};
 use chrono::Local;
+use log::warn;
 
-use crate::models::ScanResult;
+use serde_json::{json, Value};
+
+use crate::constants;
+use crate::models::{ScanResult, ScanMetadata, ScanReport, Severity, Vulnerability, VulnerabilitySummary, HttpTechnology,
+                    PortResult, ScanDiff, PortChange, ServiceChange, VulnerabilityChange, SmbInfo, SshAlgorithms, IdSource, ReportFilter, HostInfo};
+use crate::scanner;
+use crate::utils;
 
 /// Generate a text report of the scanning results
-pub fn generate_text_report(results: &[ScanResult], filename: &str) -> io::Result<()> {
+pub fn generate_text_report(results: &[ScanResult], filename: &str, metadata: Option<&ScanMetadata>, filter: Option<&ReportFilter>) -> io::Result<()> {
     let mut file = fs::File::create(filename)?;
     
     // Header
@@ -18,25 +27,49 @@ pub fn generate_text_report(results: &[ScanResult], filename: &str) -> io::Resul
     writeln!(file, "{}", "=".repeat(80))?;
     writeln!(file)?;
     
+    if let Some(reason) = metadata.and_then(|m| m.truncated.as_ref()) {
+        writeln!(file, "WARNING: SCAN TRUNCATED - {}; results are partial", reason)?;
+        writeln!(file)?;
+    }
+    
+    let network = aggregate_summary(results);
+    if summary_total(&network) > 0 {
+        writeln!(file, "EXECUTIVE SUMMARY")?;
+        writeln!(file, "Network Risk Score: {:.1}/10", network.overall_risk_score)?;
+        writeln!(file, "Hosts with critical or high findings: {} of {}", exposed_hosts(results), results.len())?;
+        write_text_summary(&mut file, &network, "")?;
+        writeln!(file)?;
+    }
+    
+    // Scan parameters
+    if let Some(metadata) = metadata {
+        writeln!(file, "SCAN PARAMETERS")?;
+        for (label, value) in metadata_rows(metadata) {
+            writeln!(file, "{:<28} {}", format!("{}:", label), value)?;
+        }
+        writeln!(file)?;
+    }
+    
     // Summary
     let total_hosts = results.len();
     let total_ports = results.iter().map(|r| r.open_ports.len()).sum::<usize>();
-    let total_vulns = results.iter()
-        .flat_map(|r| &r.open_ports)
-        .map(|p| p.vulnerabilities.len())
-        .sum::<usize>();
+    let total_vulns = results.iter().map(count_vulnerabilities).sum::<usize>();
     
     writeln!(file, "SUMMARY")?;
     writeln!(file, "Total hosts scanned: {}", total_hosts)?;
     writeln!(file, "Total open ports found: {}", total_ports)?;
     writeln!(file, "Total potential vulnerabilities detected: {}", total_vulns)?;
+    let (shown, hidden) = filter_results(results, filter);
+    if let Some(filter) = filter.filter(|f| f.is_active()) {
+        writeln!(file, "Hidden by report filter ({}): {}", filter, hidden)?;
+    }
     writeln!(file)?;
     
     // Detailed results
     writeln!(file, "DETAILED RESULTS")?;
     writeln!(file)?;
     
-    for result in results {
+    for result in shown.iter() {
         writeln!(file, "{}", "-".repeat(80))?;
         
         // Include hostname if different from IP
@@ -45,15 +78,79 @@ pub fn generate_text_report(results: &[ScanResult], filename: &str) -> io::Resul
         } else {
             writeln!(file, "Host: {}", result.host)?;
         }
+        if result.hostnames.len() > 1 {
+            writeln!(file, "PTR Names: {}", result.hostnames.join(", "))?;
+        }
         
+        if let Some(mac) = mac_info(result) {
+            writeln!(file, "MAC Address: {}", mac)?;
+        }
+        if let Some(ike) = ike_details(result) {
+            writeln!(file, "IPsec VPN (500/udp): {}", ike)?;
+        }
         writeln!(file, "Scan Time: {}", result.scan_time)?;
         writeln!(file, "Open Ports: {}", result.open_ports.len())?;
+        writeln!(file, "Closed Ports: {}", result.closed_port_count)?;
+        writeln!(file, "Filtered Ports: {}", result.filtered_port_count)?;
         writeln!(file)?;
         
+        let summary = host_summary(result);
+        if summary_total(&summary) > 0 {
+            writeln!(file, "  Risk Score: {:.1}/10", summary.overall_risk_score)?;
+            write_text_summary(&mut file, &summary, "  ")?;
+            writeln!(file)?;
+        }
+        
         for port_result in &result.open_ports {
-            writeln!(file, "  Port: {} ({})", port_result.port, port_result.service)?;
+            writeln!(file, "  Port: {} ({})", port_result.port, service_label(port_result))?;
             writeln!(file, "  Banner: {}", port_result.banner)?;
             
+            if let Some(cert) = &port_result.tls_certificate {
+                writeln!(file, "  TLS Certificate: {}", cert.subject_cn.as_deref().unwrap_or(&cert.subject))?;
+                writeln!(file, "    Issuer: {}", cert.issuer)?;
+                writeln!(file, "    Valid: {} to {}", cert.not_before, cert.not_after)?;
+                writeln!(file, "    Signature: {}", cert.signature_algorithm)?;
+                if !cert.subject_alt_names.is_empty() {
+                    writeln!(file, "    Alternative Names: {}", cert.subject_alt_names.join(", "))?;
+                }
+            }
+            
+            if !port_result.tls_versions.is_empty() {
+                let versions: Vec<String> = port_result.tls_versions.iter().map(|v| v.to_string()).collect();
+                writeln!(file, "  TLS Versions: {}", versions.join(", "))?;
+            }
+            
+            if let Some(smb) = &port_result.smb_info {
+                writeln!(file, "  SMB Dialects: {}", smb.dialects.join(", "))?;
+                writeln!(file, "    Signing: {}", smb_signing(smb))?;
+                for (label, value) in smb_details(smb) {
+                    writeln!(file, "    {}: {}", label, value)?;
+                }
+            }
+            
+            if let Some(ssh) = &port_result.ssh_info {
+                writeln!(file, "  SSH Version: {}", ssh.version)?;
+                for (label, names) in ssh_details(ssh) {
+                    writeln!(file, "    {}: {}", label, names.join(", "))?;
+                }
+            }
+            
+            if let Some(http2) = &port_result.http2_info {
+                writeln!(file, "  HTTP/2: {}{}", http2.protocol, if http2.grpc { " (gRPC)" } else { "" })?;
+                if !http2.grpc_services.is_empty() {
+                    writeln!(file, "    gRPC Services: {}", http2.grpc_services.join(", "))?;
+                }
+            }
+            
+            if let Some(http_info) = &port_result.http_info {
+                if let Some(title) = &http_info.title {
+                    writeln!(file, "  Page Title: {}", title)?;
+                }
+                if !http_info.technologies.is_empty() {
+                    writeln!(file, "  Technologies: {}", technology_list(&http_info.technologies))?;
+                }
+            }
+            
             if !port_result.vulnerabilities.is_empty() {
                 writeln!(file, "  Potential Vulnerabilities:")?;
                 for vuln in &port_result.vulnerabilities {
@@ -61,7 +158,10 @@ pub fn generate_text_report(results: &[ScanResult], filename: &str) -> io::Resul
                     let severity_info = match &vuln.severity {
                         Some(severity) => {
                             if let Some(score) = vuln.cvss_score {
-                                format!(" [{}] (CVSS: {:.1})", severity, score)
+                                match &vuln.cvss_vector {
+                                    Some(vector) => format!(" [{}] (CVSS: {:.1} {})", severity, score, vector),
+                                    None => format!(" [{}] (CVSS: {:.1})", severity, score),
+                                }
                             } else {
                                 format!(" [{}]", severity)
                             }
@@ -69,7 +169,7 @@ pub fn generate_text_report(results: &[ScanResult], filename: &str) -> io::Resul
                         None => "".to_string()
                     };
                     
-                    writeln!(file, "    - {}{}: {}", vuln.id, severity_info, vuln.description)?;
+                    writeln!(file, "    - {}{}{}: {}", vuln.id, severity_info, epss_info(vuln), vuln.description)?;
                     
                     // Include references if available
                     if let Some(refs) = &vuln.references {
@@ -87,6 +187,46 @@ pub fn generate_text_report(results: &[ScanResult], filename: &str) -> io::Resul
             
             writeln!(file)?;
         }
+        
+        if !result.host_vulnerabilities.is_empty() {
+            writeln!(file, "Host Vulnerabilities:")?;
+            for vuln in &result.host_vulnerabilities {
+                let severity_info = vuln.severity.as_ref().map(|s| format!(" [{}]", s)).unwrap_or_default();
+                writeln!(file, "  - {}{}{}: {}", vuln.id, severity_info, epss_info(vuln), vuln.description)?;
+            }
+            writeln!(file)?;
+        }
+        
+        if !result.misconfigurations.is_empty() {
+            writeln!(file, "Misconfigurations:")?;
+            for misconfig in &result.misconfigurations {
+                writeln!(file, "  - {} [{}] (port {}): {}", misconfig.id, misconfig.severity, misconfig.port, misconfig.description)?;
+                writeln!(file, "    Recommendation: {}", misconfig.recommendation)?;
+            }
+            writeln!(file)?;
+        }
+        
+        if let Some(attack_paths) = result.attack_paths.as_ref().filter(|paths| !paths.is_empty()) {
+            writeln!(file, "Attack Paths:")?;
+            for (index, path) in attack_paths.iter().enumerate() {
+                writeln!(file, "  {}. Entry point: {}", index + 1, path.entry_point)?;
+                writeln!(file, "     Likelihood: {} ({:.2})  Impact: {}", path.likelihood, path.likelihood_score, path.impact)?;
+                writeln!(file, "     Steps:")?;
+                for (step_index, step) in path.steps.iter().enumerate() {
+                    match &step.mitre_technique {
+                        Some(technique) => writeln!(file, "       {}. {} [{}]", step_index + 1, step.description, technique)?,
+                        None => writeln!(file, "       {}. {}", step_index + 1, step.description)?,
+                    }
+                }
+                if !path.mitigations.is_empty() {
+                    writeln!(file, "     Mitigations:")?;
+                    for mitigation in &path.mitigations {
+                        writeln!(file, "       - {}", mitigation)?;
+                    }
+                }
+            }
+            writeln!(file)?;
+        }
     }
     
     // Footer
@@ -98,7 +238,7 @@ pub fn generate_text_report(results: &[ScanResult], filename: &str) -> io::Resul
 }
 
 /// Generate an HTML report of the scanning results
-pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Result<()> {
+pub fn generate_html_report(results: &[ScanResult], filename: &str, metadata: Option<&ScanMetadata>, filter: Option<&ReportFilter>) -> io::Result<()> {
     let mut file = fs::File::create(filename)?;
     
     // Begin HTML with enhanced styling for vulnerabilities
@@ -114,6 +254,12 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
         .host {{ background-color: #f8f9fa; padding: 15px; border-radius: 5px; margin-bottom: 15px; }}
         .port {{ background-color: #ffffff; padding: 10px; border: 1px solid #dee2e6; border-radius: 5px; margin-bottom: 10px; }}
         .vulnerability {{ background-color: #fff3cd; padding: 10px; border-radius: 5px; margin-top: 10px; }}
+        .misconfiguration {{ background-color: #f8d7da; padding: 10px; border-radius: 5px; margin-top: 10px; }}
+        .host-summary {{ background-color: #ffffff; padding: 10px; border: 1px solid #dee2e6; border-radius: 5px; margin-bottom: 10px; }}
+        .breakdown th {{ width: 25%; }}
+        .bar {{ background-color: #17a2b8; height: 12px; border-radius: 2px; }}
+        .attack-paths {{ background-color: #e2e3e5; padding: 10px; border-radius: 5px; margin-top: 10px; }}
+        .attack-path {{ background-color: #ffffff; padding: 10px; border: 1px solid #dee2e6; border-radius: 5px; margin-bottom: 10px; }}
         h1, h2, h3 {{ color: #343a40; }}
         table {{ width: 100%; border-collapse: collapse; margin-bottom: 20px; }}
         th, td {{ padding: 8px; text-align: left; border-bottom: 1px solid #dee2e6; }}
@@ -130,6 +276,10 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
         .cve-id {{ font-family: monospace; font-weight: bold; }}
         .vuln-details {{ margin-left: 20px; margin-top: 5px; }}
         .references {{ font-size: 0.9em; margin-top: 5px; color: #6c757d; }}
+        .scan-parameters {{ background-color: #f8f9fa; padding: 10px 15px; border-radius: 5px; margin-bottom: 20px; }}
+        .scan-parameters summary {{ cursor: pointer; font-weight: bold; color: #343a40; }}
+        .scan-parameters td {{ font-family: monospace; word-break: break-all; }}
+        .truncated {{ background-color: #fff3cd; color: #856404; border: 1px solid #ffeeba; padding: 10px 15px; border-radius: 5px; margin-bottom: 20px; }}
     </style>
 </head>
 <body>
@@ -140,18 +290,94 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
         </div>
 "#, Local::now().format("%Y-%m-%d %H:%M:%S").to_string())?;
     
+    if let Some(reason) = metadata.and_then(|m| m.truncated.as_ref()) {
+        write!(file, r#"
+        <div class="truncated"><strong>Scan truncated:</strong> {}; results are partial.</div>
+"#, html_escape(reason))?;
+    }
+    
+    let network = aggregate_summary(results);
+    if summary_total(&network) > 0 {
+        write!(file, r#"
+        <div class="summary">
+            <h2>Executive Summary</h2>
+            <p><strong>Network Risk Score:</strong> <span class="{}">{:.1}/10</span></p>
+            <p>Hosts with critical or high findings: {} of {}</p>
+            <p>Actively exploited: {}. With public exploits: {}.</p>
+"#, severity_css_class(&Severity::from_cvss(network.overall_risk_score).to_string()), network.overall_risk_score,
+    exposed_hosts(results), results.len(), network.actively_exploited_count, network.exploit_available_count)?;
+        
+        for (title, counts) in [("Categories", &network.categories), ("Attack Vectors", &network.attack_vectors), ("MITRE Tactics", &network.mitre_tactics)] {
+            let counts = sorted_counts(counts);
+            if let Some(max) = counts.first().map(|(_, count)| *count) {
+                write!(file, r#"
+            <h3>{}</h3>
+            <table class="breakdown">
+"#, title)?;
+                for (name, count) in counts {
+                    write!(file, r#"
+                <tr><th>{}</th><td><div class="bar" style="width: {}%"></div></td><td>{}</td></tr>
+"#, html_escape(name), count * 100 / max, count)?;
+                }
+                write!(file, r#"
+            </table>
+"#)?;
+            }
+        }
+        
+        if !network.top_recommendations.is_empty() {
+            write!(file, r#"
+            <h3>Top Recommendations</h3>
+            <ul>
+"#)?;
+            for recommendation in &network.top_recommendations {
+                write!(file, r#"
+                <li>{}</li>
+"#, html_escape(recommendation))?;
+            }
+            write!(file, r#"
+            </ul>
+"#)?;
+        }
+        
+        write!(file, r#"
+        </div>
+"#)?;
+    }
+    
+    // Collapsible scan parameters panel
+    if let Some(metadata) = metadata {
+        write!(file, r#"
+        <details class="scan-parameters">
+            <summary>Scan parameters</summary>
+            <table>
+"#)?;
+        
+        for (label, value) in metadata_rows(metadata) {
+            write!(file, r#"
+                <tr><th>{}</th><td>{}</td></tr>
+"#, html_escape(&label), html_escape(&value))?;
+        }
+        
+        write!(file, r#"
+            </table>
+        </details>
+"#)?;
+    }
+    
     // Summary
     let total_hosts = results.len();
     let total_ports = results.iter().map(|r| r.open_ports.len()).sum::<usize>();
-    let total_vulns = results.iter()
-        .flat_map(|r| &r.open_ports)
-        .map(|p| p.vulnerabilities.len())
-        .sum::<usize>();
+    let total_vulns = results.iter().map(count_vulnerabilities).sum::<usize>();
     
-    let critical_vulns = count_vulnerabilities_by_severity(results, "critical");
-    let high_vulns = count_vulnerabilities_by_severity(results, "high");
-    let medium_vulns = count_vulnerabilities_by_severity(results, "medium");
-    let low_vulns = count_vulnerabilities_by_severity(results, "low");
+    let summaries: Vec<Cow<VulnerabilitySummary>> = results.iter().map(host_summary).collect();
+    
+    let (shown, hidden) = filter_results(results, filter);
+    let filter_row = match filter.filter(|f| f.is_active()) {
+        Some(filter) => format!(r#"
+                <tr><th>Hidden by report filter ({})</th><td>{}</td></tr>"#, html_escape(&filter.to_string()), hidden),
+        None => String::new(),
+    };
     
     write!(file, r#"
         <div class="summary">
@@ -159,7 +385,7 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
             <table>
                 <tr><th>Total hosts scanned</th><td>{}</td></tr>
                 <tr><th>Total open ports found</th><td>{}</td></tr>
-                <tr><th>Total vulnerabilities detected</th><td>{}</td></tr>
+                <tr><th>Total vulnerabilities detected</th><td>{}</td></tr>{}
             </table>
             
             <h3>Vulnerability Breakdown</h3>
@@ -168,17 +394,16 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
                 <tr><th>High</th><td><span class="high-severity">{}</span></td></tr>
                 <tr><th>Medium</th><td><span class="medium-severity">{}</span></td></tr>
                 <tr><th>Low</th><td><span class="low-severity">{}</span></td></tr>
-                <tr><th>Unknown</th><td><span class="unknown-severity">{}</span></td></tr>
+                <tr><th>Info</th><td><span class="unknown-severity">{}</span></td></tr>
             </table>
         </div>
         
         <h2>Detailed Results</h2>
-"#, total_hosts, total_ports, total_vulns, 
-    critical_vulns, high_vulns, medium_vulns, low_vulns, 
-    total_vulns - (critical_vulns + high_vulns + medium_vulns + low_vulns))?;
+"#, total_hosts, total_ports, total_vulns, filter_row,
+    network.critical_count, network.high_count, network.medium_count, network.low_count, network.info_count)?;
     
     // Detailed results
-    for result in results {
+    for (index, result) in shown.iter().enumerate() {
         write!(file, r#"
         <div class="host">
 "#)?;
@@ -194,18 +419,158 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
 "#, html_escape(&result.host))?;
         }
 
+        if let Some(mac) = mac_info(result) {
+            write!(file, r#"
+            <p>MAC Address: {}</p>"#, html_escape(&mac))?;
+        }
+        if let Some(ike) = ike_details(result) {
+            write!(file, r#"
+            <p>IPsec VPN (500/udp): {}</p>"#, html_escape(&ike))?;
+        }
+
         write!(file, r#"
             <p>Scan Time: {}</p>
             <p>Open Ports: {}</p>
+            <p>Closed Ports: {}</p>
+            <p>Filtered Ports: {}</p>
+            
+"#, result.scan_time, result.open_ports.len(), result.closed_port_count, result.filtered_port_count)?;
+        
+        let summary = &summaries[index];
+        if summary_total(summary) > 0 {
+            write!(file, r#"
+            <div class="host-summary">
+                <p><strong>Risk Score:</strong> <span class="{}">{:.1}/10</span></p>
+                <p>
+                    <span class="critical-severity">{} critical</span>
+                    <span class="high-severity">{} high</span>
+                    <span class="medium-severity">{} medium</span>
+                    <span class="low-severity">{} low</span>
+                    <span class="unknown-severity">{} info</span>
+                </p>
+"#, severity_css_class(&Severity::from_cvss(summary.overall_risk_score).to_string()), summary.overall_risk_score,
+    summary.critical_count, summary.high_count, summary.medium_count, summary.low_count, summary.info_count)?;
+            
+            for (title, counts) in [("Categories", &summary.categories), ("Attack Vectors", &summary.attack_vectors), ("MITRE Tactics", &summary.mitre_tactics)] {
+                let counts = sorted_counts(counts);
+                if let Some(max) = counts.first().map(|(_, count)| *count) {
+                    write!(file, r#"
+                <h4>{}</h4>
+                <table class="breakdown">
+"#, title)?;
+                    for (name, count) in counts {
+                        write!(file, r#"
+                    <tr><th>{}</th><td><div class="bar" style="width: {}%"></div></td><td>{}</td></tr>
+"#, html_escape(name), count * 100 / max, count)?;
+                    }
+                    write!(file, r#"
+                </table>
+"#)?;
+                }
+            }
+            
+            if !summary.top_recommendations.is_empty() {
+                write!(file, r#"
+                <h4>Top Recommendations</h4>
+                <ul>
+"#)?;
+                for recommendation in &summary.top_recommendations {
+                    write!(file, r#"
+                    <li>{}</li>
+"#, html_escape(recommendation))?;
+                }
+                write!(file, r#"
+                </ul>
+"#)?;
+            }
             
-"#, result.scan_time, result.open_ports.len())?;
+            write!(file, r#"
+            </div>
+"#)?;
+        }
         
         for port_result in &result.open_ports {
             write!(file, r#"
             <div class="port">
                 <strong>Port: {} ({})</strong>
                 <p>Banner: {}</p>
-"#, port_result.port, html_escape(&port_result.service), html_escape(&port_result.banner))?;
+"#, port_result.port, html_escape(&service_label(port_result)), html_escape(&port_result.banner))?;
+            
+            if let Some(cert) = &port_result.tls_certificate {
+                write!(file, r#"
+                <div class="tls-certificate">
+                    <p>TLS Certificate: {}</p>
+                    <p>Issuer: {}</p>
+                    <p>Valid: {} to {}</p>
+                    <p>Signature: {}</p>
+                    <p>Alternative Names: {}</p>
+                </div>
+"#, html_escape(cert.subject_cn.as_deref().unwrap_or(&cert.subject)), html_escape(&cert.issuer),
+    html_escape(&cert.not_before), html_escape(&cert.not_after), html_escape(&cert.signature_algorithm),
+    html_escape(&cert.subject_alt_names.join(", ")))?;
+            }
+            
+            if !port_result.tls_versions.is_empty() {
+                let versions: Vec<String> = port_result.tls_versions.iter().map(|v| v.to_string()).collect();
+                write!(file, r#"
+                <p>TLS Versions: {}</p>
+"#, versions.join(", "))?;
+            }
+            
+            if let Some(smb) = &port_result.smb_info {
+                write!(file, r#"
+                <div class="smb-info">
+                    <p>SMB Dialects: {}</p>
+                    <p>Signing: {}</p>
+"#, html_escape(&smb.dialects.join(", ")), smb_signing(smb))?;
+                for (label, value) in smb_details(smb) {
+                    write!(file, r#"
+                    <p>{}: {}</p>
+"#, label, html_escape(value))?;
+                }
+                write!(file, r#"
+                </div>
+"#)?;
+            }
+            
+            if let Some(ssh) = &port_result.ssh_info {
+                write!(file, r#"
+                <div class="ssh-info">
+                    <p>SSH Version: {}</p>
+"#, html_escape(&ssh.version))?;
+                for (label, names) in ssh_details(ssh) {
+                    write!(file, r#"
+                    <p>{}: {}</p>
+"#, label, html_escape(&names.join(", ")))?;
+                }
+                write!(file, r#"
+                </div>
+"#)?;
+            }
+            
+            if let Some(http2) = &port_result.http2_info {
+                write!(file, r#"
+                <p>HTTP/2: {}{}</p>
+"#, html_escape(&http2.protocol), if http2.grpc { " (gRPC)" } else { "" })?;
+                if !http2.grpc_services.is_empty() {
+                    write!(file, r#"
+                <p>gRPC Services: {}</p>
+"#, html_escape(&http2.grpc_services.join(", ")))?;
+                }
+            }
+            
+            if let Some(http_info) = &port_result.http_info {
+                if let Some(title) = &http_info.title {
+                    write!(file, r#"
+                <p>Page Title: {}</p>
+"#, html_escape(title))?;
+                }
+                if !http_info.technologies.is_empty() {
+                    write!(file, r#"
+                <p>Technologies: {}</p>
+"#, html_escape(&technology_list(&http_info.technologies)))?;
+                }
+            }
             
             if !port_result.vulnerabilities.is_empty() {
                 write!(file, r#"
@@ -216,20 +581,24 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
                 
                 for vuln in &port_result.vulnerabilities {
                     // Determine severity class
-                    let severity_class = match &vuln.severity {
-                        Some(sev) if sev.to_lowercase() == "critical" => "critical-severity",
-                        Some(sev) if sev.to_lowercase() == "high" => "high-severity",
-                        Some(sev) if sev.to_lowercase() == "medium" => "medium-severity",
-                        Some(sev) if sev.to_lowercase() == "low" => "low-severity",
-                        _ => "unknown-severity",
+                    let severity_class = match (&vuln.severity, vuln.severity_level()) {
+                        (None, _) => "unknown-severity",
+                        (_, Severity::Critical) => "critical-severity",
+                        (_, Severity::High) => "high-severity",
+                        (_, Severity::Medium) => "medium-severity",
+                        (_, Severity::Low) => "low-severity",
+                        (_, Severity::Info) => "unknown-severity",
                     };
                     
                     // Format severity and CVSS information
                     let severity_info = match &vuln.severity {
                         Some(severity) => {
                             if let Some(score) = vuln.cvss_score {
-                                format!("<span class=\"{}\">{}:</span> (CVSS: {:.1})", 
-                                        severity_class, severity, score)
+                                let vector = vuln.cvss_vector.as_ref()
+                                    .map(|v| format!(" <code class=\"cvss-vector\">{}</code>", html_escape(v)))
+                                    .unwrap_or_default();
+                                format!("<span class=\"{}\">{}:</span> (CVSS: {:.1}{})", 
+                                        severity_class, severity, score, vector)
                             } else {
                                 format!("<span class=\"{}\">{}:</span>", severity_class, severity)
                             }
@@ -239,9 +608,9 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
                     
                     write!(file, r#"
                         <li>
-                            <div><strong class="cve-id">{}</strong> {}</div>
+                            <div><strong class="cve-id">{}</strong> {}{}</div>
                             <div class="vuln-details">{}</div>
-"#, html_escape(&vuln.id), severity_info, html_escape(&vuln.description))?;
+"#, html_escape(&vuln.id), severity_info, epss_info(vuln), html_escape(&vuln.description))?;
                     
                     // Include references if available
                     if let Some(refs) = &vuln.references {
@@ -285,6 +654,108 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
 "#)?;
         }
         
+        if !result.host_vulnerabilities.is_empty() {
+            write!(file, r#"
+            <div class="vulnerability">
+                <h4>Host Vulnerabilities:</h4>
+                <ul>
+"#)?;
+            
+            for vuln in &result.host_vulnerabilities {
+                let severity = vuln.severity.as_deref().unwrap_or("UNKNOWN");
+                write!(file, r#"
+                    <li>
+                        <div><strong class="cve-id">{}</strong> <span class="{}">{}</span>{}</div>
+                        <div class="vuln-details">{}</div>
+                    </li>
+"#, html_escape(&vuln.id), severity_css_class(severity), html_escape(severity), epss_info(vuln),
+    html_escape(&vuln.description))?;
+            }
+            
+            write!(file, r#"
+                </ul>
+            </div>
+"#)?;
+        }
+        
+        if !result.misconfigurations.is_empty() {
+            write!(file, r#"
+            <div class="misconfiguration">
+                <h4>Misconfigurations:</h4>
+                <ul>
+"#)?;
+            
+            for misconfig in &result.misconfigurations {
+                let severity_class = severity_css_class(&misconfig.severity);
+                write!(file, r#"
+                    <li>
+                        <div><strong class="cve-id">{}</strong> <span class="{}">{}</span> (port {})</div>
+                        <div class="vuln-details">{}</div>
+                        <div class="vuln-details">Recommendation: {}</div>
+                    </li>
+"#, html_escape(&misconfig.id), severity_class, html_escape(&misconfig.severity), misconfig.port,
+    html_escape(&misconfig.description), html_escape(&misconfig.recommendation))?;
+            }
+            
+            write!(file, r#"
+                </ul>
+            </div>
+"#)?;
+        }
+        
+        if let Some(attack_paths) = result.attack_paths.as_ref().filter(|paths| !paths.is_empty()) {
+            write!(file, r#"
+            <div class="attack-paths">
+                <h4>Attack Paths:</h4>
+"#)?;
+            
+            for path in attack_paths {
+                let likelihood_class = severity_css_class(&path.likelihood);
+                write!(file, r#"
+                <div class="attack-path">
+                    <p><strong>Entry point:</strong> {}</p>
+                    <p>Likelihood: <span class="{}">{} ({:.2})</span> Impact: {}</p>
+                    <ol>
+"#, html_escape(&path.entry_point), likelihood_class, html_escape(&path.likelihood), path.likelihood_score, html_escape(&path.impact))?;
+                
+                for step in &path.steps {
+                    let technique = step.mitre_technique.as_ref()
+                        .map(|t| format!(" <code>{}</code>", html_escape(t)))
+                        .unwrap_or_default();
+                    write!(file, r#"
+                        <li>{}{}</li>
+"#, html_escape(&step.description), technique)?;
+                }
+                
+                write!(file, r#"
+                    </ol>
+"#)?;
+                
+                if !path.mitigations.is_empty() {
+                    write!(file, r#"
+                    <p>Mitigations:</p>
+                    <ul>
+"#)?;
+                    for mitigation in &path.mitigations {
+                        write!(file, r#"
+                        <li>{}</li>
+"#, html_escape(mitigation))?;
+                    }
+                    write!(file, r#"
+                    </ul>
+"#)?;
+                }
+                
+                write!(file, r#"
+                </div>
+"#)?;
+            }
+            
+            write!(file, r#"
+            </div>
+"#)?;
+        }
+        
         write!(file, r#"
         </div>
 "#)?;
@@ -304,26 +775,1263 @@ pub fn generate_html_report(results: &[ScanResult], filename: &str) -> io::Resul
 }
 
 /// Generate a JSON report of the scanning results
-pub fn generate_json_report(results: &[ScanResult], filename: &str) -> io::Result<()> {
-    let json = serde_json::to_string_pretty(results)?;
+pub fn generate_json_report(results: &[ScanResult], filename: &str, metadata: Option<&ScanMetadata>, filter: Option<&ReportFilter>) -> io::Result<()> {
+    let (shown, hidden) = filter_results(results, filter);
+    let report = ScanReport {
+        schema_version: constants::REPORT_SCHEMA_VERSION.to_string(),
+        tool: constants::TOOL_NAME.to_string(),
+        tool_version: constants::VERSION.to_string(),
+        generated_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        metadata: metadata.cloned(),
+        results: shown.into_owned(),
+        filter: filter.filter(|f| f.is_active()).cloned(),
+        hidden_vulnerabilities: hidden,
+        summary: Some(aggregate_summary(results)),
+    };
+    let json = serde_json::to_string_pretty(&report)?;
     fs::write(filename, json)?;
     Ok(())
 }
 
-/// Count vulnerabilities by severity level
-fn count_vulnerabilities_by_severity(results: &[ScanResult], severity: &str) -> usize {
-    results.iter()
-        .flat_map(|r| &r.open_ports)
-        .flat_map(|p| &p.vulnerabilities)
-        .filter(|v| v.severity.as_ref().map_or(false, |s| s.to_lowercase() == severity))
-        .count()
+/// Generate a JSON-lines report with one compact scan result per line
+pub fn generate_jsonl_report(results: &[ScanResult], filename: &str, filter: Option<&ReportFilter>) -> io::Result<()> {
+    let (shown, _) = filter_results(results, filter);
+    let mut file = io::BufWriter::new(fs::File::create(filename)?);
+    for result in shown.iter() {
+        stream_jsonl(result, &mut file)?;
+    }
+    Ok(())
 }
 
-/// Escape HTML special characters
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-     .replace('<', "&lt;")
-     .replace('>', "&gt;")
-     .replace('"', "&quot;")
-     .replace('\'', "&#39;")
+/// Write a scan result as a single line of compact JSON and flush it immediately
+///
+/// Callers sharing a writer across scan threads must serialize calls so that
+/// lines from different hosts are not interleaved.
+pub fn stream_jsonl<W: Write>(result: &ScanResult, writer: &mut W) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, result)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+/// Generate a SARIF 2.1.0 log of the scanning results for CI code scanning
+pub fn generate_sarif_report(results: &[ScanResult], filename: &str, metadata: Option<&ScanMetadata>, filter: Option<&ReportFilter>) -> io::Result<()> {
+    let mut rules: Vec<Value> = Vec::new();
+    let mut rule_ids: Vec<String> = Vec::new();
+    let mut sarif_results: Vec<Value> = Vec::new();
+    
+    let (shown, hidden) = filter_results(results, filter);
+    for result in shown.iter() {
+        // Host-level findings are located at the host rather than a port
+        let port_findings = result.open_ports.iter()
+            .flat_map(|p| p.vulnerabilities.iter().map(move |v| (utils::format_endpoint(&result.host, p.port), v)));
+        let host_findings = result.host_vulnerabilities.iter()
+            .map(|v| (result.host.clone(), v));
+        
+        for (location, vuln) in port_findings.chain(host_findings) {
+            // One rule per unique vulnerability ID
+            let rule_index = match rule_ids.iter().position(|id| id == &vuln.id) {
+                Some(index) => index,
+                None => {
+                    rule_ids.push(vuln.id.clone());
+                    rules.push(sarif_rule(vuln));
+                    rule_ids.len() - 1
+                }
+            };
+            
+            sarif_results.push(json!({
+                "ruleId": vuln.id,
+                "ruleIndex": rule_index,
+                "level": sarif_level(vuln),
+                "message": { "text": vuln.description },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": location
+                        }
+                    }
+                }]
+            }));
+        }
+    }
+    
+    let mut run = json!({
+        "tool": {
+            "driver": {
+                "name": constants::TOOL_NAME,
+                "version": constants::VERSION,
+                "rules": rules
+            }
+        },
+        "results": sarif_results
+    });
+    
+    run["properties"] = json!({ "executiveSummary": aggregate_summary(results) });
+    if let Some(filter) = filter.filter(|f| f.is_active()) {
+        run["properties"]["reportFilter"] = json!(filter.to_string());
+        run["properties"]["hiddenResults"] = json!(hidden);
+    }
+    
+    if let Some(metadata) = metadata {
+        run["invocations"] = json!([{
+            "commandLine": metadata.command_line,
+            "executionSuccessful": metadata.truncated.is_none(),
+            "properties": {
+                "startTime": metadata.start_time,
+                "endTime": metadata.end_time,
+                "truncated": metadata.truncated
+            }
+        }]);
+    }
+    
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [run]
+    });
+    
+    let json = serde_json::to_string_pretty(&sarif)?;
+    fs::write(filename, json)?;
+    Ok(())
+}
+
+/// Generate an Nmap-compatible XML report for tools that import Nmap scans
+///
+/// Hosts, addresses, hostnames and open ports follow Nmap's `nmaprun` layout;
+/// each vulnerability becomes a `<script>` element under its port, or under
+/// `<hostscript>` for host-level findings.
+pub fn generate_nmap_xml_report(results: &[ScanResult], filename: &str, metadata: Option<&ScanMetadata>, filter: Option<&ReportFilter>) -> io::Result<()> {
+    let mut xml = XmlWriter::new(io::BufWriter::new(fs::File::create(filename)?));
+    
+    let start = metadata.map(|m| m.start_time.clone())
+        .unwrap_or_else(|| Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+    let end = metadata.map(|m| m.end_time.clone())
+        .unwrap_or_else(|| Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+    let args = metadata.map(|m| m.command_line.clone()).unwrap_or_else(|| constants::TOOL_NAME.to_string());
+    
+    let mut ports: Vec<u16> = results.iter().flat_map(|r| r.open_ports.iter().map(|p| p.port)).collect();
+    ports.sort_unstable();
+    ports.dedup();
+    
+    xml.declaration()?;
+    xml.start("nmaprun", &[
+        ("scanner", constants::TOOL_NAME),
+        ("args", &args),
+        ("start", &epoch_seconds(&start)),
+        ("startstr", &start),
+        ("version", constants::VERSION),
+        ("xmloutputversion", "1.05"),
+    ])?;
+    xml.empty("scaninfo", &[
+        ("type", "connect"),
+        ("protocol", "tcp"),
+        ("numservices", &ports.len().to_string()),
+        ("services", &ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",")),
+    ])?;
+    
+    let (shown, _) = filter_results(results, filter);
+    for result in shown.iter() {
+        let addrtype = if result.host.contains(':') { "ipv6" } else { "ipv4" };
+        let scan_time = epoch_seconds(&result.scan_time);
+        
+        xml.start("host", &[("starttime", &scan_time), ("endtime", &scan_time)])?;
+        xml.empty("status", &[("state", "up"), ("reason", "syn-ack"), ("reason_ttl", "0")])?;
+        xml.empty("address", &[("addr", &result.host), ("addrtype", addrtype)])?;
+        if let Some(mac) = &result.mac {
+            match &result.vendor {
+                Some(vendor) => xml.empty("address", &[("addr", &mac.to_uppercase()), ("addrtype", "mac"), ("vendor", vendor)])?,
+                None => xml.empty("address", &[("addr", &mac.to_uppercase()), ("addrtype", "mac")])?,
+            }
+        }
+        
+        xml.start("hostnames", &[])?;
+        if !result.hostname.is_empty() && result.hostname != result.host && result.hostname != "Unknown" {
+            xml.empty("hostname", &[("name", &result.hostname), ("type", "PTR")])?;
+        }
+        for name in result.hostnames.iter().filter(|name| **name != result.hostname) {
+            xml.empty("hostname", &[("name", name), ("type", "PTR")])?;
+        }
+        xml.end("hostnames")?;
+        
+        xml.start("ports", &[])?;
+        for (state, count, reason) in [("closed", result.closed_port_count, "conn-refused"), ("filtered", result.filtered_port_count, "no-response")] {
+            if count > 0 {
+                xml.start("extraports", &[("state", state), ("count", &count.to_string())])?;
+                xml.empty("extrareasons", &[("reason", reason), ("count", &count.to_string())])?;
+                xml.end("extraports")?;
+            }
+        }
+        for port in &result.open_ports {
+            xml.start("port", &[("protocol", "tcp"), ("portid", &port.port.to_string())])?;
+            xml.empty("state", &[("state", "open"), ("reason", "syn-ack"), ("reason_ttl", "0")])?;
+            xml.empty("service", &nmap_service_attributes(port).iter().map(|(k, v)| (*k, v.as_str())).collect::<Vec<_>>())?;
+            for vuln in &port.vulnerabilities {
+                write_nmap_script(&mut xml, vuln)?;
+            }
+            xml.end("port")?;
+        }
+        xml.end("ports")?;
+        
+        if !result.host_vulnerabilities.is_empty() {
+            xml.start("hostscript", &[])?;
+            for vuln in &result.host_vulnerabilities {
+                write_nmap_script(&mut xml, vuln)?;
+            }
+            xml.end("hostscript")?;
+        }
+        
+        if let Some(os) = &result.os_info {
+            xml.start("os", &[])?;
+            xml.empty("osmatch", &[("name", os), ("accuracy", "50")])?;
+            xml.end("os")?;
+        }
+        
+        xml.end("host")?;
+    }
+    
+    let up = results.len().to_string();
+    xml.start("runstats", &[])?;
+    xml.empty("finished", &[
+        ("time", &epoch_seconds(&end)),
+        ("timestr", &end),
+        ("summary", &format!("{} scan done; {} hosts with open ports", constants::TOOL_NAME, up)),
+        ("exit", "success"),
+    ])?;
+    xml.empty("hosts", &[("up", &up), ("down", "0"), ("total", &up)])?;
+    xml.end("runstats")?;
+    xml.end("nmaprun")?;
+    
+    xml.finish()?.flush()
+}
+
+/// Nmap `<service>` attributes for an open port
+fn nmap_service_attributes(port: &PortResult) -> Vec<(&'static str, String)> {
+    let mut attributes = vec![("name", port.service.to_lowercase().replace(' ', "-"))];
+    
+    if let Some((product, version)) = crate::cveapi::extract_product_versions(&port.banner).into_iter().next() {
+        attributes.push(("product", product.to_string()));
+        attributes.push(("version", version));
+    } else if let Some(server) = port.http_info.as_ref().and_then(|h| h.server.clone()) {
+        attributes.push(("product", server));
+    }
+    if port.tls_certificate.is_some() {
+        attributes.push(("tunnel", "ssl".to_string()));
+    }
+    // Nmap reports port-table guesses as method "table"
+    let method = if port.service_source == Some(IdSource::Port) { "table" } else { "probed" };
+    attributes.push(("method", method.to_string()));
+    let conf = match port.service_source {
+        Some(_) => (port.service_confidence * 10.0).round() as u8,
+        None => 10,
+    };
+    attributes.push(("conf", conf.to_string()));
+    
+    attributes
+}
+
+/// Write a vulnerability as an Nmap `<script>` element with a table of its details
+fn write_nmap_script<W: Write>(xml: &mut XmlWriter<W>, vuln: &Vulnerability) -> io::Result<()> {
+    let severity = vuln.severity_level().to_string();
+    let output = format!("{} ({}): {}", vuln.id, severity, vuln.description);
+    
+    xml.start("script", &[("id", "rustnet-vuln"), ("output", &output)])?;
+    xml.start("table", &[("key", &vuln.id)])?;
+    xml.element("elem", &[("key", "id")], &vuln.id)?;
+    xml.element("elem", &[("key", "severity")], &severity)?;
+    if let Some(score) = vuln.cvss_score {
+        xml.element("elem", &[("key", "cvss")], &format!("{:.1}", score))?;
+    }
+    if let Some(cwe) = &vuln.cwe_id {
+        xml.element("elem", &[("key", "cwe")], cwe)?;
+    }
+    xml.element("elem", &[("key", "description")], &vuln.description)?;
+    if vuln.exploit_available == Some(true) {
+        xml.element("elem", &[("key", "is_exploit")], "true")?;
+    }
+    xml.end("table")?;
+    xml.end("script")
+}
+
+/// Convert a "%Y-%m-%d %H:%M:%S" local timestamp to Unix seconds, or "0" if it can't be parsed
+fn epoch_seconds(timestamp: &str) -> String {
+    use chrono::TimeZone;
+    
+    chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S").ok()
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .map(|time| time.timestamp().to_string())
+        .unwrap_or_else(|| "0".to_string())
+}
+
+// Minimal XML writer that escapes text and attributes and rejects mis-nested elements
+struct XmlWriter<W: Write> {
+    writer: W,
+    open: Vec<&'static str>,
+}
+
+impl<W: Write> XmlWriter<W> {
+    fn new(writer: W) -> Self {
+        XmlWriter { writer, open: Vec::new() }
+    }
+    
+    fn declaration(&mut self) -> io::Result<()> {
+        writeln!(self.writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(self.writer, "<!DOCTYPE nmaprun>")
+    }
+    
+    /// Open an element; it must be closed with a matching `end`
+    fn start(&mut self, name: &'static str, attributes: &[(&str, &str)]) -> io::Result<()> {
+        self.indent()?;
+        write!(self.writer, "<{}{}>", name, xml_attributes(attributes))?;
+        writeln!(self.writer)?;
+        self.open.push(name);
+        Ok(())
+    }
+    
+    /// Write an element with no children
+    fn empty(&mut self, name: &str, attributes: &[(&str, &str)]) -> io::Result<()> {
+        self.indent()?;
+        writeln!(self.writer, "<{}{}/>", name, xml_attributes(attributes))
+    }
+    
+    /// Write an element containing only text
+    fn element(&mut self, name: &str, attributes: &[(&str, &str)], text: &str) -> io::Result<()> {
+        self.indent()?;
+        writeln!(self.writer, "<{}{}>{}</{}>", name, xml_attributes(attributes), xml_escape(text), name)
+    }
+    
+    /// Close the innermost open element, which must be `name`
+    fn end(&mut self, name: &str) -> io::Result<()> {
+        match self.open.pop() {
+            Some(open) if open == name => {
+                self.indent()?;
+                writeln!(self.writer, "</{}>", name)
+            },
+            open => Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("XML nesting error: closing <{}> while <{}> is open", name, open.unwrap_or("none")))),
+        }
+    }
+    
+    /// Check that every element was closed and return the underlying writer
+    fn finish(self) -> io::Result<W> {
+        match self.open.last() {
+            Some(open) => Err(io::Error::new(io::ErrorKind::InvalidData, format!("XML nesting error: <{}> was never closed", open))),
+            None => Ok(self.writer),
+        }
+    }
+    
+    fn indent(&mut self) -> io::Result<()> {
+        write!(self.writer, "{}", "  ".repeat(self.open.len()))
+    }
+}
+
+/// Format attributes as ` key="value"` pairs with escaped values
+fn xml_attributes(attributes: &[(&str, &str)]) -> String {
+    attributes.iter()
+        .map(|(key, value)| format!(" {}=\"{}\"", key, xml_escape(value)))
+        .collect()
+}
+
+/// Escape XML special characters and drop control characters XML 1.0 does not allow
+fn xml_escape(s: &str) -> String {
+    s.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&apos;".to_string(),
+            // Keep line breaks in attribute values from being normalized to spaces
+            '\n' => "&#10;".to_string(),
+            '\r' => "&#13;".to_string(),
+            '\t' => "&#9;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Generate a GitHub-flavored Markdown report of the scanning results
+pub fn generate_markdown_report(results: &[ScanResult], filename: &str, metadata: Option<&ScanMetadata>, filter: Option<&ReportFilter>) -> io::Result<()> {
+    let mut file = fs::File::create(filename)?;
+    
+    writeln!(file, "# Network Vulnerability Scan Report")?;
+    writeln!(file)?;
+    writeln!(file, "Generated on: {}", Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+    writeln!(file)?;
+    
+    if let Some(reason) = metadata.and_then(|m| m.truncated.as_ref()) {
+        writeln!(file, "> **Scan truncated:** {}; results are partial.", markdown_escape(reason))?;
+        writeln!(file)?;
+    }
+    
+    let network = aggregate_summary(results);
+    if summary_total(&network) > 0 {
+        writeln!(file, "## Executive Summary")?;
+        writeln!(file)?;
+        writeln!(file, "**Network risk score: {:.1}/10.** Hosts with critical or high findings: {} of {}.",
+                 network.overall_risk_score, exposed_hosts(results), results.len())?;
+        writeln!(file)?;
+        writeln!(file, "| Critical | High | Medium | Low | Info | Actively Exploited | Public Exploits |")?;
+        writeln!(file, "| --- | --- | --- | --- | --- | --- | --- |")?;
+        writeln!(file, "| {} | {} | {} | {} | {} | {} | {} |", network.critical_count, network.high_count, network.medium_count,
+                 network.low_count, network.info_count, network.actively_exploited_count, network.exploit_available_count)?;
+        writeln!(file)?;
+        
+        for (title, counts) in [("Categories", &network.categories), ("Attack Vectors", &network.attack_vectors), ("MITRE Tactics", &network.mitre_tactics)] {
+            let counts = sorted_counts(counts);
+            if !counts.is_empty() {
+                let counts: Vec<String> = counts.iter().map(|(name, count)| format!("{} ({})", markdown_escape(name), count)).collect();
+                writeln!(file, "**{}:** {}", title, counts.join(", "))?;
+                writeln!(file)?;
+            }
+        }
+        
+        if !network.top_recommendations.is_empty() {
+            writeln!(file, "### Top Recommendations")?;
+            writeln!(file)?;
+            for recommendation in &network.top_recommendations {
+                writeln!(file, "- {}", markdown_escape(recommendation))?;
+            }
+            writeln!(file)?;
+        }
+    }
+    
+    // Scan parameters
+    if let Some(metadata) = metadata {
+        writeln!(file, "## Scan Parameters")?;
+        writeln!(file)?;
+        writeln!(file, "| Parameter | Value |")?;
+        writeln!(file, "| --- | --- |")?;
+        for (label, value) in metadata_rows(metadata) {
+            writeln!(file, "| {} | {} |", markdown_escape(&label), markdown_escape(&value))?;
+        }
+        writeln!(file)?;
+    }
+    
+    // Summary
+    let total_ports = results.iter().map(|r| r.open_ports.len()).sum::<usize>();
+    let total_vulns = results.iter().map(count_vulnerabilities).sum::<usize>();
+    
+    writeln!(file, "## Summary")?;
+    writeln!(file)?;
+    writeln!(file, "| Hosts | Open Ports | Vulnerabilities |")?;
+    writeln!(file, "| --- | --- | --- |")?;
+    writeln!(file, "| {} | {} | {} |", results.len(), total_ports, total_vulns)?;
+    writeln!(file)?;
+    
+    let (shown, hidden) = filter_results(results, filter);
+    if let Some(filter) = filter.filter(|f| f.is_active()) {
+        writeln!(file, "_{} vulnerabilities hidden by report filter ({})._", hidden, markdown_escape(&filter.to_string()))?;
+        writeln!(file)?;
+    }
+    
+    // Detailed results
+    for result in shown.iter() {
+        if result.hostname != result.host {
+            writeln!(file, "## {} ({})", result.hostname, result.host)?;
+        } else {
+            writeln!(file, "## {}", result.host)?;
+        }
+        writeln!(file)?;
+        if let Some(mac) = mac_info(result) {
+            writeln!(file, "MAC address: {}", markdown_escape(&mac))?;
+            writeln!(file)?;
+        }
+        if let Some(ike) = ike_details(result) {
+            writeln!(file, "IPsec VPN (500/udp): {}", markdown_escape(&ike))?;
+            writeln!(file)?;
+        }
+        writeln!(file, "Scan time: {}. Open: {}, closed: {}, filtered: {}.",
+                 result.scan_time, result.open_ports.len(), result.closed_port_count, result.filtered_port_count)?;
+        writeln!(file)?;
+        
+        if !result.host_vulnerabilities.is_empty() {
+            writeln!(file, "### Host Vulnerabilities")?;
+            writeln!(file)?;
+            for vuln in &result.host_vulnerabilities {
+                let severity = vuln.severity.as_deref().unwrap_or("UNKNOWN");
+                writeln!(file, "- **{}** `{}`{}: {}", severity, vuln.id, epss_info(vuln), vuln.description.trim())?;
+            }
+            writeln!(file)?;
+        }
+        
+        if result.open_ports.is_empty() {
+            continue;
+        }
+        
+        writeln!(file, "| Port | Service | Banner |")?;
+        writeln!(file, "| --- | --- | --- |")?;
+        for port_result in &result.open_ports {
+            writeln!(file, "| {} | {} | {} |", port_result.port,
+                     markdown_escape(&service_label(port_result)), markdown_escape(&port_result.banner))?;
+        }
+        writeln!(file)?;
+        
+        let vulnerable_ports: Vec<_> = result.open_ports.iter()
+            .filter(|p| !p.vulnerabilities.is_empty())
+            .collect();
+        if vulnerable_ports.is_empty() {
+            continue;
+        }
+        
+        writeln!(file, "### Vulnerabilities")?;
+        writeln!(file)?;
+        for port_result in vulnerable_ports {
+            for vuln in &port_result.vulnerabilities {
+                let severity = vuln.severity.as_deref().unwrap_or("UNKNOWN");
+                let score = vuln.cvss_score.map(|s| format!(" (CVSS {:.1})", s)).unwrap_or_default();
+                writeln!(file, "- **{}** `{}` on port {}{}{}: {}", severity, vuln.id, port_result.port, score, epss_info(vuln), vuln.description.trim())?;
+                
+                if let Some(refs) = &vuln.references {
+                    for reference in refs.iter().take(3) {  // Limit to first 3 references
+                        writeln!(file, "  - <{}>", reference)?;
+                    }
+                }
+            }
+        }
+        writeln!(file)?;
+    }
+    
+    Ok(())
+}
+
+/// Load the scan results from a JSON report written by `generate_json_report`
+///
+/// Only the results are read, so reports written by older versions whose scan
+/// parameters no longer match the current configuration still load.
+pub fn load_json_report(filename: &str) -> io::Result<Vec<ScanResult>> {
+    let content = fs::read_to_string(filename)?;
+    Ok(parse_report(&content)?)
+}
+
+/// Parse the scan results out of a JSON report
+///
+/// Reports are a versioned {schema_version, ..., results} object. Unversioned
+/// {metadata, results} objects and bare arrays of results from older releases
+/// are also accepted. Reports from a newer schema are read on a best-effort basis.
+pub fn parse_report(json: &str) -> Result<Vec<ScanResult>, serde_json::Error> {
+    let mut value: Value = serde_json::from_str(json)?;
+    
+    let schema_version = value.get("schema_version").and_then(|v| v.as_str()).unwrap_or("");
+    if is_newer_schema(schema_version) {
+        warn!("Report uses schema version {} (this build reads {}), some fields may be ignored",
+            schema_version, constants::REPORT_SCHEMA_VERSION);
+    }
+    
+    let results = match value.get_mut("results") {
+        Some(results) => results.take(),
+        None => value,
+    };
+    serde_json::from_value(results)
+}
+
+/// Whether a report schema version is newer than the one this build writes
+fn is_newer_schema(version: &str) -> bool {
+    match (version.parse::<u32>(), constants::REPORT_SCHEMA_VERSION.parse::<u32>()) {
+        (Ok(version), Ok(current)) => version > current,
+        _ => false,
+    }
+}
+
+/// Compare two scans and report what changed, keyed by host and port
+///
+/// Ports and vulnerabilities on hosts that only appear in the new scan are
+/// reported as opened and new. Hosts missing from the new scan are only listed
+/// in `removed_hosts`, since their absence doesn't show their ports were closed.
+pub fn diff_reports(old: &[ScanResult], new: &[ScanResult]) -> ScanDiff {
+    let old_hosts: HashMap<&str, &ScanResult> = old.iter().map(|r| (r.host.as_str(), r)).collect();
+    let new_hosts: HashMap<&str, &ScanResult> = new.iter().map(|r| (r.host.as_str(), r)).collect();
+    let mut diff = ScanDiff::default();
+    
+    for result in old {
+        if !new_hosts.contains_key(result.host.as_str()) {
+            diff.removed_hosts.push(result.host.clone());
+        }
+    }
+    
+    for result in new {
+        let old_ports: Vec<&PortResult> = match old_hosts.get(result.host.as_str()) {
+            Some(old_result) => old_result.open_ports.iter().collect(),
+            None => {
+                diff.new_hosts.push(result.host.clone());
+                Vec::new()
+            },
+        };
+        
+        for port in &result.open_ports {
+            let old_port = old_ports.iter().find(|p| p.port == port.port);
+            match old_port {
+                Some(old_port) => {
+                    if old_port.service != port.service || old_port.banner != port.banner {
+                        diff.changed_services.push(ServiceChange {
+                            host: result.host.clone(),
+                            port: port.port,
+                            old_service: old_port.service.clone(),
+                            new_service: port.service.clone(),
+                            old_banner: old_port.banner.clone(),
+                            new_banner: port.banner.clone(),
+                        });
+                    }
+                },
+                None => diff.opened_ports.push(PortChange {
+                    host: result.host.clone(),
+                    port: port.port,
+                    service: port.service.clone(),
+                }),
+            }
+            
+            let old_vulnerabilities = old_port.map(|p| p.vulnerabilities.as_slice()).unwrap_or(&[]);
+            for vuln in &port.vulnerabilities {
+                if !old_vulnerabilities.iter().any(|v| v.id == vuln.id) {
+                    diff.new_vulnerabilities.push(vulnerability_change(&result.host, port.port, vuln));
+                }
+            }
+        }
+        
+        for old_port in old_ports {
+            let new_port = result.open_ports.iter().find(|p| p.port == old_port.port);
+            if new_port.is_none() {
+                diff.closed_ports.push(PortChange {
+                    host: result.host.clone(),
+                    port: old_port.port,
+                    service: old_port.service.clone(),
+                });
+            }
+            
+            let new_vulnerabilities = new_port.map(|p| p.vulnerabilities.as_slice()).unwrap_or(&[]);
+            for vuln in &old_port.vulnerabilities {
+                if !new_vulnerabilities.iter().any(|v| v.id == vuln.id) {
+                    diff.resolved_vulnerabilities.push(vulnerability_change(&result.host, old_port.port, vuln));
+                }
+            }
+        }
+    }
+    
+    diff
+}
+
+/// Describe a vulnerability found on a host and port for a scan diff
+fn vulnerability_change(host: &str, port: u16, vuln: &Vulnerability) -> VulnerabilityChange {
+    VulnerabilityChange {
+        host: host.to_string(),
+        port,
+        id: vuln.id.clone(),
+        severity: vuln.severity.clone(),
+        description: vuln.description.clone(),
+    }
+}
+
+/// Write a scan diff in the given format (TEXT, HTML or JSON)
+pub fn generate_diff_report(diff: &ScanDiff, filename: &str, format: &str) -> io::Result<()> {
+    match format.to_uppercase().as_str() {
+        "TEXT" => generate_text_diff_report(diff, filename),
+        "HTML" => generate_html_diff_report(diff, filename),
+        "JSON" => {
+            let json = serde_json::to_string_pretty(diff)?;
+            fs::write(filename, json)
+        },
+        other => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unsupported diff report format: {}", other))),
+    }
+}
+
+/// Write the live hosts found by a discovery sweep as TEXT, JSON or CSV
+pub fn generate_host_discovery_report(hosts: &[HostInfo], filename: &str, format: &str) -> io::Result<()> {
+    match format.to_uppercase().as_str() {
+        "TEXT" => generate_text_host_discovery_report(hosts, filename),
+        "JSON" => {
+            let json = serde_json::to_string_pretty(hosts)?;
+            fs::write(filename, json)
+        },
+        "CSV" => {
+            let mut file = fs::File::create(filename)?;
+            writeln!(file, "ip,hostname,online,mac,vendor")?;
+            for host in hosts {
+                writeln!(file, "{},{},{},{},{}",
+                    csv_field(&host.ip),
+                    csv_field(&host.hostname),
+                    host.is_online,
+                    csv_field(host.mac.as_deref().unwrap_or("")),
+                    csv_field(host.vendor.as_deref().unwrap_or("")))?;
+            }
+            Ok(())
+        },
+        other => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unsupported host discovery report format: {}", other))),
+    }
+}
+
+/// Generate a text list of the live hosts found by a discovery sweep
+fn generate_text_host_discovery_report(hosts: &[HostInfo], filename: &str) -> io::Result<()> {
+    let mut file = fs::File::create(filename)?;
+    
+    writeln!(file, "{}", "=".repeat(80))?;
+    writeln!(file, "{:^80}", "HOST DISCOVERY REPORT")?;
+    writeln!(file, "{:^80}", Local::now().format("%Y-%m-%d %H:%M:%S").to_string())?;
+    writeln!(file, "{}", "=".repeat(80))?;
+    writeln!(file)?;
+    
+    writeln!(file, "Live Hosts ({})", hosts.len())?;
+    for host in hosts {
+        let mut line = format!("  {:<40}", host.ip);
+        if host.hostname != host.ip {
+            line.push_str(&format!(" {}", host.hostname));
+        }
+        if let Some(mac) = &host.mac {
+            match &host.vendor {
+                Some(vendor) => line.push_str(&format!(" [{} {}]", mac, vendor)),
+                None => line.push_str(&format!(" [{}]", mac)),
+            }
+        }
+        writeln!(file, "{}", line.trim_end())?;
+    }
+    writeln!(file)?;
+    
+    writeln!(file, "{}", "=".repeat(80))?;
+    writeln!(file, "End of Report")?;
+    writeln!(file, "{}", "=".repeat(80))?;
+    
+    Ok(())
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Generate a text report of the differences between two scans
+fn generate_text_diff_report(diff: &ScanDiff, filename: &str) -> io::Result<()> {
+    let mut file = fs::File::create(filename)?;
+    
+    writeln!(file, "{}", "=".repeat(80))?;
+    writeln!(file, "{:^80}", "NETWORK SCAN DIFF REPORT")?;
+    writeln!(file, "{:^80}", Local::now().format("%Y-%m-%d %H:%M:%S").to_string())?;
+    writeln!(file, "{}", "=".repeat(80))?;
+    writeln!(file)?;
+    
+    for (title, rows) in diff_sections(diff) {
+        writeln!(file, "{} ({})", title, rows.len())?;
+        for row in rows {
+            writeln!(file, "  {}", row)?;
+        }
+        writeln!(file)?;
+    }
+    
+    writeln!(file, "{}", "=".repeat(80))?;
+    writeln!(file, "End of Report")?;
+    writeln!(file, "{}", "=".repeat(80))?;
+    
+    Ok(())
+}
+
+/// Generate an HTML report of the differences between two scans
+fn generate_html_diff_report(diff: &ScanDiff, filename: &str) -> io::Result<()> {
+    let mut file = fs::File::create(filename)?;
+    
+    write!(file, r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Network Scan Diff Report</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; margin: 0; padding: 20px; }}
+        .container {{ max-width: 1200px; margin: 0 auto; }}
+        .header {{ background-color: #f8f9fa; padding: 20px; border-radius: 5px; margin-bottom: 20px; }}
+        .section {{ background-color: #f8f9fa; padding: 15px; border-radius: 5px; margin-bottom: 15px; }}
+        h1, h2 {{ color: #343a40; }}
+        li {{ font-family: monospace; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>Network Scan Diff Report</h1>
+            <p>Generated on: {}</p>
+        </div>
+"#, Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+    
+    for (title, rows) in diff_sections(diff) {
+        write!(file, r#"
+        <div class="section">
+            <h2>{} ({})</h2>
+            <ul>
+"#, html_escape(title), rows.len())?;
+        
+        for row in rows {
+            write!(file, r#"
+                <li>{}</li>
+"#, html_escape(&row))?;
+        }
+        
+        write!(file, r#"
+            </ul>
+        </div>
+"#)?;
+    }
+    
+    write!(file, r#"
+    </div>
+</body>
+</html>
+"#)?;
+    
+    Ok(())
+}
+
+/// Format each category of a scan diff as a titled list of lines
+fn diff_sections(diff: &ScanDiff) -> Vec<(&'static str, Vec<String>)> {
+    let port_rows = |changes: &[PortChange]| -> Vec<String> {
+        changes.iter().map(|c| format!("{} ({})", utils::format_endpoint(&c.host, c.port), c.service)).collect()
+    };
+    let vulnerability_rows = |changes: &[VulnerabilityChange]| -> Vec<String> {
+        changes.iter()
+            .map(|c| format!("{} {} [{}] {}", utils::format_endpoint(&c.host, c.port), c.id, c.severity.as_deref().unwrap_or("UNKNOWN"), c.description))
+            .collect()
+    };
+    
+    vec![
+        ("New Hosts", diff.new_hosts.clone()),
+        ("Removed Hosts", diff.removed_hosts.clone()),
+        ("Newly Opened Ports", port_rows(&diff.opened_ports)),
+        ("Newly Closed Ports", port_rows(&diff.closed_ports)),
+        ("Changed Services", diff.changed_services.iter()
+            .map(|c| format!("{} {} -> {} (banner: {:?} -> {:?})", utils::format_endpoint(&c.host, c.port), c.old_service, c.new_service, c.old_banner, c.new_banner))
+            .collect()),
+        ("New Vulnerabilities", vulnerability_rows(&diff.new_vulnerabilities)),
+        ("Resolved Vulnerabilities", vulnerability_rows(&diff.resolved_vulnerabilities)),
+    ]
+}
+
+/// Build a SARIF rule descriptor for a vulnerability
+fn sarif_rule(vuln: &Vulnerability) -> Value {
+    let mut rule = json!({
+        "id": vuln.id,
+        "shortDescription": { "text": vuln.description },
+        "properties": {
+            "tags": ["security"]
+        }
+    });
+    
+    if let Some(reference) = vuln.references.as_ref().and_then(|refs| refs.first()) {
+        rule["helpUri"] = json!(reference);
+    }
+    
+    if let Some(category) = &vuln.category {
+        rule["properties"]["tags"] = json!(["security", category]);
+    }
+    
+    // GitHub code scanning ranks alerts by this CVSS-style score
+    if let Some(score) = vuln.cvss_score {
+        rule["properties"]["security-severity"] = json!(format!("{:.1}", score));
+    }
+    
+    if let Some(vector) = &vuln.cvss_vector {
+        rule["properties"]["cvssVector"] = json!(vector);
+    }
+    
+    if let Some(epss) = vuln.epss_score {
+        rule["properties"]["epss"] = json!(epss);
+    }
+    
+    rule
+}
+
+/// Format detected web technologies as "name version" entries
+fn technology_list(technologies: &[HttpTechnology]) -> String {
+    technologies.iter()
+        .map(|t| match &t.version {
+            Some(version) => format!("{} {}", t.name, version),
+            None => t.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Count the vulnerabilities found on a host's ports and on the host itself
+fn count_vulnerabilities(result: &ScanResult) -> usize {
+    result.open_ports.iter().map(|p| p.vulnerabilities.len()).sum::<usize>() + result.host_vulnerabilities.len()
+}
+
+/// Format a vulnerability's EPSS probability for display next to its CVSS score
+fn epss_info(vuln: &Vulnerability) -> String {
+    vuln.epss_score
+        .map(|epss| format!(" (EPSS: {:.1}%)", epss * 100.0))
+        .unwrap_or_default()
+}
+
+/// Map a vulnerability severity to a SARIF result level
+fn sarif_level(vuln: &Vulnerability) -> &'static str {
+    match vuln.severity_level() {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note",
+    }
+}
+
+/// Flatten scan metadata into labelled rows for display
+fn metadata_rows(metadata: &ScanMetadata) -> Vec<(String, String)> {
+    let mut rows = vec![
+        ("Tool".to_string(), format!("{} v{}", metadata.tool_name, metadata.version)),
+        ("Command".to_string(), metadata.command_line.clone()),
+        ("Started".to_string(), metadata.start_time.clone()),
+        ("Finished".to_string(), metadata.end_time.clone()),
+    ];
+    if let Some(reason) = &metadata.truncated {
+        rows.push(("Truncated".to_string(), reason.clone()));
+    }
+    
+    // List every configuration field so new options show up automatically
+    if let Ok(Value::Object(parameters)) = serde_json::to_value(&metadata.parameters) {
+        for (name, value) in parameters {
+            rows.push((name, format_parameter_value(&value)));
+        }
+    }
+    
+    rows
+}
+
+/// Format a configuration value for display, collapsing runs of numbers into ranges
+fn format_parameter_value(value: &Value) -> String {
+    match value {
+        Value::Null => "none".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => {
+            let numbers: Vec<u64> = items.iter().filter_map(|v| v.as_u64()).collect();
+            
+            if !items.is_empty() && numbers.len() == items.len() {
+                let mut ranges: Vec<String> = Vec::new();
+                let mut start = numbers[0];
+                let mut end = numbers[0];
+                
+                for &n in &numbers[1..] {
+                    if n == end + 1 {
+                        end = n;
+                    } else {
+                        ranges.push(if start == end { start.to_string() } else { format!("{}-{}", start, end) });
+                        start = n;
+                        end = n;
+                    }
+                }
+                ranges.push(if start == end { start.to_string() } else { format!("{}-{}", start, end) });
+                
+                ranges.join(",")
+            } else if items.is_empty() {
+                "default".to_string()
+            } else {
+                items.iter().map(format_parameter_value).collect::<Vec<String>>().join(", ")
+            }
+        },
+        other => other.to_string(),
+    }
+}
+
+/// CSS class for a severity-like label such as "High" or "CRITICAL"
+fn severity_css_class(label: &str) -> &'static str {
+    match label.parse::<Severity>() {
+        Ok(Severity::Critical) => "critical-severity",
+        Ok(Severity::High) => "high-severity",
+        Ok(Severity::Medium) => "medium-severity",
+        Ok(Severity::Low) => "low-severity",
+        _ => "unknown-severity",
+    }
+}
+
+/// Apply a report filter, returning the results to show and how many vulnerabilities it hid
+///
+/// Hosts and ports are kept even when all of their vulnerabilities are filtered
+/// out, and each host's precomputed summary still counts everything found.
+pub fn filter_results<'a>(results: &'a [ScanResult], filter: Option<&ReportFilter>) -> (Cow<'a, [ScanResult]>, usize) {
+    let filter = match filter {
+        Some(filter) if filter.is_active() => filter,
+        _ => return (Cow::Borrowed(results), 0),
+    };
+    
+    let mut hidden = 0;
+    let mut retain = |vulnerabilities: &mut Vec<Vulnerability>| {
+        let before = vulnerabilities.len();
+        vulnerabilities.retain(|v| filter.matches(v));
+        hidden += before - vulnerabilities.len();
+    };
+    
+    let shown = results.iter().cloned()
+        .map(|mut result| {
+            for port in &mut result.open_ports {
+                retain(&mut port.vulnerabilities);
+            }
+            retain(&mut result.host_vulnerabilities);
+            result
+        })
+        .collect();
+    
+    (Cow::Owned(shown), hidden)
+}
+
+/// Get the vulnerability summary for a host, computing it if the scan didn't
+fn host_summary(result: &ScanResult) -> Cow<'_, VulnerabilitySummary> {
+    match &result.vulnerabilities_summary {
+        Some(summary) => Cow::Borrowed(summary),
+        None => Cow::Owned(scanner::generate_vulnerability_summary(&result.open_ports, &result.host_vulnerabilities)),
+    }
+}
+
+/// Combine every host's vulnerability summary into one for the whole network
+///
+/// Counts and breakdowns are summed. The risk score averages the worst few
+/// hosts, so a single badly exposed host isn't diluted by many clean ones, and
+/// adds up to a point for the share of hosts with critical or high findings.
+/// Recommendations shared by the most hosts come first.
+pub fn aggregate_summary(results: &[ScanResult]) -> VulnerabilitySummary {
+    let summaries: Vec<Cow<VulnerabilitySummary>> = results.iter().map(host_summary).collect();
+    let mut total = VulnerabilitySummary::default();
+    let mut recommendations: Vec<(&String, usize)> = Vec::new();
+    
+    for summary in &summaries {
+        total.critical_count += summary.critical_count;
+        total.high_count += summary.high_count;
+        total.medium_count += summary.medium_count;
+        total.low_count += summary.low_count;
+        total.info_count += summary.info_count;
+        total.actively_exploited_count += summary.actively_exploited_count;
+        total.exploit_available_count += summary.exploit_available_count;
+        
+        for (totals, counts) in [(&mut total.categories, &summary.categories), (&mut total.attack_vectors, &summary.attack_vectors), (&mut total.mitre_tactics, &summary.mitre_tactics)] {
+            for (name, count) in counts {
+                *totals.entry(name.clone()).or_insert(0) += count;
+            }
+        }
+        
+        for recommendation in &summary.top_recommendations {
+            match recommendations.iter_mut().find(|(r, _)| *r == recommendation) {
+                Some((_, hosts)) => *hosts += 1,
+                None => recommendations.push((recommendation, 1)),
+            }
+        }
+    }
+    
+    // Stable sort, so ties keep the order they were first seen in
+    recommendations.sort_by_key(|r| std::cmp::Reverse(r.1));
+    total.top_recommendations = recommendations.into_iter()
+        .take(5)  // Same limit as each host's list
+        .map(|(recommendation, _)| recommendation.clone())
+        .collect();
+    
+    let mut scores: Vec<f32> = summaries.iter().map(|s| s.overall_risk_score).collect();
+    scores.sort_by(|a, b| b.total_cmp(a));
+    let worst = &scores[..scores.len().min(constants::NETWORK_RISK_WORST_HOSTS)];
+    if !worst.is_empty() {
+        let worst_average = worst.iter().sum::<f32>() / worst.len() as f32;
+        let exposed_share = exposed_hosts(results) as f32 / results.len() as f32;
+        total.overall_risk_score = (worst_average + exposed_share).min(10.0);
+    }
+    
+    total
+}
+
+/// Number of hosts with at least one critical or high severity finding
+fn exposed_hosts(results: &[ScanResult]) -> usize {
+    results.iter()
+        .map(host_summary)
+        .filter(|s| s.critical_count + s.high_count > 0)
+        .count()
+}
+
+/// Write a summary's severity counts, breakdowns and recommendations as indented text
+fn write_text_summary(file: &mut fs::File, summary: &VulnerabilitySummary, indent: &str) -> io::Result<()> {
+    writeln!(file, "{}Severity: {} critical, {} high, {} medium, {} low, {} info", indent,
+             summary.critical_count, summary.high_count, summary.medium_count, summary.low_count, summary.info_count)?;
+    if summary.actively_exploited_count + summary.exploit_available_count > 0 {
+        writeln!(file, "{}Exploits: {} actively exploited, {} with public exploits", indent,
+                 summary.actively_exploited_count, summary.exploit_available_count)?;
+    }
+    
+    for (title, counts) in [("Categories", &summary.categories), ("Attack Vectors", &summary.attack_vectors), ("MITRE Tactics", &summary.mitre_tactics)] {
+        let counts = sorted_counts(counts);
+        if let Some(max) = counts.first().map(|(_, count)| *count) {
+            writeln!(file, "{}{}:", indent, title)?;
+            for (name, count) in counts {
+                writeln!(file, "{}  {:<24} {:<30} {}", indent, name, text_bar(count, max), count)?;
+            }
+        }
+    }
+    
+    if !summary.top_recommendations.is_empty() {
+        writeln!(file, "{}Top Recommendations:", indent)?;
+        for recommendation in &summary.top_recommendations {
+            writeln!(file, "{}  - {}", indent, recommendation)?;
+        }
+    }
+    Ok(())
+}
+
+/// Total number of vulnerabilities counted in a summary
+fn summary_total(summary: &VulnerabilitySummary) -> usize {
+    summary.critical_count + summary.high_count + summary.medium_count + summary.low_count + summary.info_count
+}
+
+/// Sort a breakdown by descending count, then by name
+fn sorted_counts(counts: &HashMap<String, usize>) -> Vec<(&String, usize)> {
+    let mut sorted: Vec<(&String, usize)> = counts.iter().map(|(name, count)| (name, *count)).collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    sorted
+}
+
+/// Draw a text bar scaled against the largest count in a breakdown
+fn text_bar(count: usize, max: usize) -> String {
+    "#".repeat((count * 30 / max.max(1)).max(1))
+}
+
+/// Service name of a port, with how it was identified and how confidently
+fn service_label(port: &PortResult) -> String {
+    match port.service_source {
+        Some(source) => format!("{}, {} {:.0}%", port.service, source, port.service_confidence * 100.0),
+        None => port.service.clone(),
+    }
+}
+
+/// Describe an SMB server's signing policy
+fn smb_signing(smb: &SmbInfo) -> &'static str {
+    match (smb.signing_required, smb.signing_enabled) {
+        (true, _) => "required",
+        (false, true) => "enabled, not required",
+        (false, false) => "disabled",
+    }
+}
+
+/// The OS, LAN manager, domain and server name an SMB server reported, labelled
+fn smb_details(smb: &SmbInfo) -> Vec<(&'static str, &str)> {
+    [
+        ("Native OS", &smb.native_os),
+        ("LAN Manager", &smb.native_lan_manager),
+        ("Domain", &smb.domain),
+        ("Server Name", &smb.server_name),
+    ].into_iter()
+        .filter_map(|(label, value)| value.as_deref().map(|value| (label, value)))
+        .collect()
+}
+
+/// The algorithm lists an SSH server offered, labelled, skipping empty ones
+fn ssh_details(ssh: &SshAlgorithms) -> Vec<(&'static str, &[String])> {
+    [
+        ("Key Exchange", &ssh.kex),
+        ("Host Keys", &ssh.host_key),
+        ("Ciphers", &ssh.ciphers),
+        ("MACs", &ssh.macs),
+    ].into_iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(label, names)| (label, names.as_slice()))
+        .collect()
+}
+
+/// MAC address of a host, followed by its vendor when known
+fn mac_info(result: &ScanResult) -> Option<String> {
+    let mac = result.mac.as_ref()?;
+    
+    Some(match &result.vendor {
+        Some(vendor) => format!("{} ({})", mac, vendor),
+        None => mac.clone(),
+    })
+}
+
+/// IKE versions, aggressive mode, accepted transforms and vendor IDs of a host's IPsec VPN
+fn ike_details(result: &ScanResult) -> Option<String> {
+    let ike = result.ike_info.as_ref()?;
+    
+    let mut details = ike.versions.join(", ");
+    if ike.aggressive_mode {
+        details.push_str(", aggressive mode");
+    }
+    if !ike.transforms.is_empty() {
+        let transforms: Vec<String> = ike.transforms.iter().map(|t| t.to_string()).collect();
+        details.push_str(&format!("; transforms: {}", transforms.join(", ")));
+    }
+    if !ike.vendor_ids.is_empty() {
+        details.push_str(&format!("; vendor IDs: {}", ike.vendor_ids.join(", ")));
+    }
+    Some(details)
+}
+
+/// Escape text for use inside a Markdown table cell
+fn markdown_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+     .replace('|', "\\|")
+     .replace("\r\n", " ")
+     .replace(['\r', '\n'], " ")
+}
+
+/// Escape HTML special characters
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+     .replace('<', "&lt;")
+     .replace('>', "&gt;")
+     .replace('"', "&quot;")
+     .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Host with no ports whose findings are described entirely by `summary`
+    fn host(ip: &str, summary: VulnerabilitySummary) -> ScanResult {
+        let mut result: ScanResult = serde_json::from_value(serde_json::json!({
+            "host": ip,
+            "hostname": ip,
+            "is_online": true,
+            "open_ports": [],
+            "closed_port_count": 0,
+            "filtered_port_count": 0,
+            "scan_time": "2024-01-01 00:00:00",
+        })).unwrap();
+        result.vulnerabilities_summary = Some(summary);
+        result
+    }
+    
+    fn counts(entries: &[(&str, usize)]) -> HashMap<String, usize> {
+        entries.iter().map(|(name, count)| (name.to_string(), *count)).collect()
+    }
+    
+    #[test]
+    fn aggregate_summary_sums_hosts_and_deduplicates_recommendations() {
+        let web_server = host("10.0.0.1", VulnerabilitySummary {
+            critical_count: 1,
+            high_count: 2,
+            low_count: 1,
+            actively_exploited_count: 1,
+            overall_risk_score: 8.0,
+            top_recommendations: vec!["Patch SMB".to_string(), "Enable HSTS".to_string()],
+            categories: counts(&[("web", 2), ("smb", 1)]),
+            attack_vectors: counts(&[("NETWORK", 3)]),
+            mitre_tactics: counts(&[("Initial Access", 2)]),
+            ..VulnerabilitySummary::default()
+        });
+        let mail_server = host("10.0.0.2", VulnerabilitySummary {
+            high_count: 1,
+            medium_count: 3,
+            overall_risk_score: 4.0,
+            top_recommendations: vec!["Enable HSTS".to_string(), "Disable TLS 1.0".to_string()],
+            categories: counts(&[("web", 1)]),
+            attack_vectors: counts(&[("NETWORK", 1)]),
+            mitre_tactics: counts(&[("Initial Access", 1), ("Credential Access", 1)]),
+            ..VulnerabilitySummary::default()
+        });
+        
+        let total = aggregate_summary(&[web_server, mail_server]);
+        
+        assert_eq!(total.critical_count, 1);
+        assert_eq!(total.high_count, 3);
+        assert_eq!(total.medium_count, 3);
+        assert_eq!(total.low_count, 1);
+        assert_eq!(total.actively_exploited_count, 1);
+        assert_eq!(total.categories, counts(&[("web", 3), ("smb", 1)]));
+        assert_eq!(total.attack_vectors, counts(&[("NETWORK", 4)]));
+        assert_eq!(total.mitre_tactics, counts(&[("Initial Access", 3), ("Credential Access", 1)]));
+        
+        // The recommendation both hosts share comes first and appears once
+        assert_eq!(total.top_recommendations, vec!["Enable HSTS", "Patch SMB", "Disable TLS 1.0"]);
+        
+        // Average of the two hosts' scores plus a point since both have high findings
+        assert!((total.overall_risk_score - 7.0).abs() < 0.001);
+    }
 }
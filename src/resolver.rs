@@ -1,13 +1,22 @@
 // Author: CyberCraft Alchemist
 // Hostname resolution and network target expansion functionalities
 
-use std::net::{IpAddr, Ipv4Addr, ToSocketAddrs};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, ToSocketAddrs};
+use std::path::Path;
+use log::debug;
 use std::str::FromStr;
+use std::sync::RwLock;
 use std::time::Duration;
-use trust_dns_resolver::config::{ResolverConfig, ResolverOpts};
+use trust_dns_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::proto::rr::rdata::{MX, SRV};
 use trust_dns_resolver::Resolver;
 use trust_dns_resolver::error::ResolveError;
 
+use crate::constants::{GENERIC_PTR_SUFFIXES, MAX_RANGE_SIZE, MIN_IPV6_PREFIX_LEN, OUI_VENDORS};
+
 #[cfg(target_os = "windows")]
 use winreg::enums::*;
 #[cfg(target_os = "windows")]
@@ -15,28 +24,155 @@ use winreg::RegKey;
 
 /// Resolves a hostname or IP range to a list of IP addresses
 pub fn resolve_targets(target_spec: &str) -> Vec<IpAddr> {
-    let mut ips = Vec::new();
+    TargetIterator::new(target_spec).collect()
+}
+
+/// Why a target specification yielded no addresses to scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetError {
+    Unresolvable(String),                          // Hostname that neither DNS nor the system resolver knows
+    InvalidCidr { spec: String, reason: String },  // CIDR block that can't be expanded
+    InvalidRange { spec: String, reason: String }, // IP range that can't be expanded
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TargetError::Unresolvable(host) => write!(f, "could not resolve {}", host),
+            TargetError::InvalidCidr { spec, reason } => write!(f, "invalid CIDR {}: {}", spec, reason),
+            TargetError::InvalidRange { spec, reason } => write!(f, "invalid range {}: {}", spec, reason),
+        }
+    }
+}
+
+impl std::error::Error for TargetError {}
+
+/// Lazily yields the IP addresses described by a target specification
+///
+/// CIDR blocks and IP ranges are generated one address at a time, so large
+/// networks don't have to be held in memory before scanning starts.
+pub struct TargetIterator {
+    source: TargetSource,
+}
+
+// Where a TargetIterator draws its addresses from
+enum TargetSource {
+    // Inclusive numeric range of IPv4 (stored widened) or IPv6 addresses
+    Range { next: u128, end: u128, is_ipv4: bool, exhausted: bool },
+    // Addresses that were resolved up front, such as DNS results
+    List(std::vec::IntoIter<IpAddr>),
+}
+
+impl TargetIterator {
+    /// Create an iterator for a CIDR block, IP range, IP address or hostname
+    ///
+    /// Specifications that yield no addresses produce an empty iterator; `try_new` says why.
+    pub fn new(target_spec: &str) -> Self {
+        TargetIterator::try_new(target_spec).unwrap_or_else(|e| {
+            debug!("Skipping target: {}", e);
+            TargetIterator::from_list(Vec::new())
+        })
+    }
+    
+    /// Create an iterator for a CIDR block, IP range, IP address or hostname,
+    /// saying why when the specification yields no addresses
+    pub fn try_new(target_spec: &str) -> Result<Self, TargetError> {
+        // Check if the target is a CIDR notation (e.g., 192.168.1.0/24)
+        if target_spec.contains('/') {
+            return cidr_targets(target_spec).map_err(|reason| TargetError::InvalidCidr {
+                spec: target_spec.to_string(),
+                reason,
+            });
+        }
+        
+        // Check if the target is an IP range (e.g., 192.168.1.1-192.168.1.254);
+        // hostnames may contain '-' too, so only specs that start with an address count
+        if let Some((start, _)) = target_spec.split_once("..").or_else(|| target_spec.split_once('-')) {
+            if IpAddr::from_str(start.trim()).is_ok() {
+                return range_targets(target_spec).map_err(|reason| TargetError::InvalidRange {
+                    spec: target_spec.to_string(),
+                    reason,
+                });
+            }
+        }
+        
+        resolve_single_target(target_spec).map(TargetIterator::from_list)
+    }
     
-    // Check if the target is a CIDR notation (e.g., 192.168.1.0/24)
-    if target_spec.contains('/') {
-        if let Some(cidr_ips) = expand_cidr(target_spec) {
-            ips.extend(cidr_ips);
-            return ips;
+    /// Create an iterator over an already-resolved list of addresses
+    pub fn from_list(ips: Vec<IpAddr>) -> Self {
+        TargetIterator {
+            source: TargetSource::List(ips.into_iter()),
         }
     }
     
-    // Check if the target is an IP range (e.g., 192.168.1.1-192.168.1.254)
-    if target_spec.contains('-') {
-        if let Some(range_ips) = expand_ip_range(target_spec) {
-            ips.extend(range_ips);
-            return ips;
+    /// The addresses an IP address or hostname resolved to
+    ///
+    /// `None` for CIDR blocks and IP ranges, which are generated lazily instead.
+    pub fn resolved(&self) -> Option<Vec<IpAddr>> {
+        match &self.source {
+            TargetSource::List(ips) => Some(ips.as_slice().to_vec()),
+            TargetSource::Range { .. } => None,
         }
     }
     
+    /// Create an iterator over an inclusive numeric address range
+    fn from_range(start: u128, end: u128, is_ipv4: bool) -> Self {
+        TargetIterator {
+            source: TargetSource::Range { next: start, end, is_ipv4, exhausted: start > end },
+        }
+    }
+}
+
+impl Iterator for TargetIterator {
+    type Item = IpAddr;
+    
+    fn next(&mut self) -> Option<IpAddr> {
+        match &mut self.source {
+            TargetSource::Range { next, end, is_ipv4, exhausted } => {
+                if *exhausted {
+                    return None;
+                }
+                
+                let current = *next;
+                
+                // Stop at the end address rather than incrementing past the maximum
+                if current == *end {
+                    *exhausted = true;
+                } else {
+                    *next += 1;
+                }
+                
+                if *is_ipv4 {
+                    Some(IpAddr::V4(Ipv4Addr::from(current as u32)))
+                } else {
+                    Some(IpAddr::V6(Ipv6Addr::from(current)))
+                }
+            },
+            TargetSource::List(ips) => ips.next(),
+        }
+    }
+    
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.source {
+            TargetSource::Range { exhausted: true, .. } => (0, Some(0)),
+            TargetSource::Range { next, end, .. } => {
+                let remaining = usize::try_from(end - next).ok().and_then(|n| n.checked_add(1));
+                (remaining.unwrap_or(usize::MAX), remaining)
+            },
+            TargetSource::List(ips) => ips.size_hint(),
+        }
+    }
+}
+
+/// Resolve a single IP address or hostname
+fn resolve_single_target(target_spec: &str) -> Result<Vec<IpAddr>, TargetError> {
+    let mut ips = Vec::new();
+    
     // Try to parse as an IP address first
     if let Ok(ip) = IpAddr::from_str(target_spec) {
         ips.push(ip);
-        return ips;
+        return Ok(ips);
     }
     
     // Otherwise, try DNS resolution
@@ -46,7 +182,8 @@ pub fn resolve_targets(target_spec: &str) -> Vec<IpAddr> {
                 ips.extend(resolved_ips);
             }
         },
-        Err(_) => {
+        Err(e) => {
+            debug!("DNS lookup for {} failed ({}), trying the system resolver", target_spec, e);
             // If regular DNS resolution fails, try additional methods
             if let Some(hostname) = target_spec.to_socket_addrs().ok().and_then(|mut addrs| {
                 addrs.next().map(|socket_addr| socket_addr.ip())
@@ -56,7 +193,50 @@ pub fn resolve_targets(target_spec: &str) -> Vec<IpAddr> {
         }
     }
     
-    ips
+    if ips.is_empty() {
+        return Err(TargetError::Unresolvable(target_spec.to_string()));
+    }
+    
+    Ok(ips)
+}
+
+/// Read target specifications from a file, one per line
+///
+/// Blank lines and lines starting with `#` are ignored, as is anything after
+/// a `#` on a line.
+pub fn read_target_file(path: &Path) -> io::Result<Vec<String>> {
+    let contents = fs::read_to_string(path)?;
+    
+    Ok(contents.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
+
+lazy_static::lazy_static! {
+    static ref DNS_SERVERS: RwLock<Vec<IpAddr>> = RwLock::new(Vec::new());
+}
+
+/// Send subsequent DNS queries to `servers`, or to the default resolvers when empty
+pub fn set_dns_servers(servers: &[IpAddr]) {
+    *DNS_SERVERS.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = servers.to_vec();
+}
+
+/// The DNS servers queries are currently sent to (empty means the defaults)
+pub fn dns_servers() -> Vec<IpAddr> {
+    DNS_SERVERS.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Build a resolver configuration that queries `servers` on port 53
+///
+/// An empty list gives `ResolverConfig::default()`.
+pub fn resolver_config(servers: &[IpAddr]) -> ResolverConfig {
+    if servers.is_empty() {
+        return ResolverConfig::default();
+    }
+    
+    ResolverConfig::from_parts(None, Vec::new(), NameServerConfigGroup::from_ips_clear(servers, 53, true))
 }
 
 /// Resolves a hostname to IP addresses using DNS
@@ -66,7 +246,7 @@ pub fn resolve_hostname(hostname: &str) -> Result<Vec<IpAddr>, ResolveError> {
     opts.timeout = Duration::from_secs(5);
     opts.attempts = 2;
     
-    let resolver = Resolver::new(ResolverConfig::default(), opts)?;
+    let resolver = Resolver::new(resolver_config(&dns_servers()), opts)?;
     
     let response = resolver.lookup_ip(hostname)?;
     let ips: Vec<IpAddr> = response.iter().collect();
@@ -74,22 +254,140 @@ pub fn resolve_hostname(hostname: &str) -> Result<Vec<IpAddr>, ResolveError> {
     Ok(ips)
 }
 
+/// Look up the `host:port` pairs a domain publishes for a service such as `_ldap._tcp`
+///
+/// Records are ordered by priority, then by descending weight.
+pub fn lookup_srv(domain: &str, service: &str) -> Result<Vec<(String, u16)>, ResolveError> {
+    let resolver = record_resolver()?;
+    let response = resolver.srv_lookup(format!("{}.{}.", service, domain.trim_end_matches('.')))?;
+    let records: Vec<SRV> = response.iter().cloned().collect();
+    
+    Ok(srv_targets(&records))
+}
+
+/// Look up a domain's mail exchangers, most preferred first
+pub fn lookup_mx(domain: &str) -> Result<Vec<String>, ResolveError> {
+    let resolver = record_resolver()?;
+    let response = resolver.mx_lookup(format!("{}.", domain.trim_end_matches('.')))?;
+    let records: Vec<MX> = response.iter().cloned().collect();
+    
+    Ok(mx_hosts(&records))
+}
+
+/// Resolver for SRV and MX queries, using the configured DNS servers
+fn record_resolver() -> Result<Resolver, ResolveError> {
+    let mut opts = ResolverOpts::default();
+    opts.timeout = Duration::from_secs(3);
+    opts.attempts = 1;
+    
+    Ok(Resolver::new(resolver_config(&dns_servers()), opts)?)
+}
+
+/// Turn SRV records into `host:port` pairs
+///
+/// A target of "." means the service is explicitly unavailable and is skipped.
+pub fn srv_targets(records: &[SRV]) -> Vec<(String, u16)> {
+    let mut sorted: Vec<&SRV> = records.iter().collect();
+    sorted.sort_by(|a, b| a.priority().cmp(&b.priority()).then(b.weight().cmp(&a.weight())));
+    
+    let mut targets: Vec<(String, u16)> = Vec::new();
+    for record in sorted {
+        let host = record.target().to_utf8().trim_end_matches('.').to_string();
+        if host.is_empty() || targets.iter().any(|(h, p)| *h == host && *p == record.port()) {
+            continue;
+        }
+        targets.push((host, record.port()));
+    }
+    
+    targets
+}
+
+/// Turn MX records into exchanger hostnames, most preferred first
+///
+/// A null MX (RFC 7505) has "." as its exchanger and is skipped.
+pub fn mx_hosts(records: &[MX]) -> Vec<String> {
+    let mut sorted: Vec<&MX> = records.iter().collect();
+    sorted.sort_by_key(|record| record.preference());
+    
+    let mut hosts: Vec<String> = Vec::new();
+    for record in sorted {
+        let host = record.exchange().to_utf8().trim_end_matches('.').to_string();
+        if !host.is_empty() && !hosts.contains(&host) {
+            hosts.push(host);
+        }
+    }
+    
+    hosts
+}
+
+/// Whether a target specification is a domain name rather than an address, CIDR block or range
+pub fn is_domain_name(target_spec: &str) -> bool {
+    IpAddr::from_str(target_spec).is_err()
+        && !target_spec.contains('/')
+        && parse_range(target_spec).is_none()
+        && target_spec.contains('.')
+        && target_spec.trim_end_matches('.').rsplit('.').next().unwrap_or("").chars().any(|c| c.is_ascii_alphabetic())
+        && target_spec.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_')
+}
+
 /// Perform a reverse DNS lookup to get a hostname from an IP
+///
+/// When the address has several PTR records, a specific name is preferred
+/// over a provider's auto-generated one.
 pub fn reverse_lookup(ip: &IpAddr) -> Option<String> {
+    preferred_hostname(ip, &reverse_lookup_all(ip)).map(str::to_string)
+}
+
+/// Perform a reverse DNS lookup returning every PTR record for an IP, in answer order
+pub fn reverse_lookup_all(ip: &IpAddr) -> Vec<String> {
     // Configure DNS resolver with reasonable timeouts
     let mut opts = ResolverOpts::default();
     opts.timeout = Duration::from_secs(3);
     opts.attempts = 1;
     
-    if let Ok(resolver) = Resolver::new(ResolverConfig::default(), opts) {
+    let mut names: Vec<String> = Vec::new();
+    if let Ok(resolver) = Resolver::new(resolver_config(&dns_servers()), opts) {
         if let Ok(response) = resolver.reverse_lookup(*ip) {
-            if let Some(name) = response.iter().next() {
-                return Some(name.to_utf8());
+            for name in response.iter() {
+                let name = name.to_utf8();
+                if !names.contains(&name) {
+                    names.push(name);
+                }
             }
         }
     }
     
-    None
+    names
+}
+
+/// Whether a PTR name was generated by a hosting provider or ISP rather than
+/// chosen for the host, e.g. `ec2-1-2-3-4.compute-1.amazonaws.com`
+pub fn is_generic_hostname(ip: &IpAddr, hostname: &str) -> bool {
+    let hostname = hostname.trim_end_matches('.').to_ascii_lowercase();
+    if GENERIC_PTR_SUFFIXES.iter().any(|suffix| hostname.ends_with(suffix)) {
+        return true;
+    }
+    
+    // Names that spell out the address, like 4-3-2-1.static.isp.net or host-1-2-3-4
+    let first_label = hostname.split('.').next().unwrap_or("");
+    let octets: Vec<String> = match ip {
+        IpAddr::V4(v4) => v4.octets().iter().map(|o| o.to_string()).collect(),
+        IpAddr::V6(_) => return false,
+    };
+    let mut reversed = octets.clone();
+    reversed.reverse();
+    
+    [octets, reversed].iter().any(|parts| {
+        first_label.contains(&parts.join("-")) || hostname.starts_with(&format!("{}.", parts.join(".")))
+    })
+}
+
+/// The best name among an IP's PTR records: the first specific one, or the first one
+pub fn preferred_hostname<'a>(ip: &IpAddr, names: &'a [String]) -> Option<&'a str> {
+    names.iter()
+        .find(|name| !is_generic_hostname(ip, name))
+        .or_else(|| names.first())
+        .map(String::as_str)
 }
 
 /// Get NetBIOS name for an IP (Windows)
@@ -145,6 +443,25 @@ pub fn get_netbios_name(ip: &IpAddr) -> Option<String> {
     None
 }
 
+/// Look up the vendor a MAC address's OUI prefix is assigned to
+///
+/// Accepts colon, dash or dot separated addresses (`00:1c:06:..`,
+/// `00-1C-06-..`, `001c.06..`) or a bare prefix. Only vendors in the
+/// embedded table are known.
+pub fn oui_lookup(mac: &str) -> Option<String> {
+    let digits: String = mac.chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if digits.len() < 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    
+    let prefix = digits[..6].to_ascii_uppercase();
+    OUI_VENDORS.iter()
+        .find(|(oui, _)| *oui == prefix)
+        .map(|(_, vendor)| vendor.to_string())
+}
+
 /// Get the local hostname of the system
 pub fn get_local_hostname() -> Option<String> {
     std::env::var("HOSTNAME")
@@ -206,20 +523,32 @@ pub fn get_local_domain() -> Option<String> {
 }
 
 /// Expand a CIDR notation into individual IP addresses
+///
+/// IPv6 networks are only expanded for prefixes of at least `MIN_IPV6_PREFIX_LEN`.
 pub fn expand_cidr(cidr: &str) -> Option<Vec<IpAddr>> {
+    cidr_targets(cidr).ok().map(|targets| targets.collect())
+}
+
+/// Build a lazy iterator over the hosts of a CIDR block, or say why it is invalid
+fn cidr_targets(cidr: &str) -> Result<TargetIterator, String> {
     let parts: Vec<&str> = cidr.split('/').collect();
     if parts.len() != 2 {
-        return None;
+        return Err("expected an address and a prefix length such as 10.0.0.0/24".to_string());
     }
     
     let ip_str = parts[0];
-    let prefix_len = parts[1].parse::<u8>().ok()?;
-    
-    // Only support IPv4 CIDR for now
-    let ip = Ipv4Addr::from_str(ip_str).ok()?;
+    let prefix_len = parts[1].parse::<u8>().map_err(|_| format!("prefix length '{}' is not a number", parts[1]))?;
     
+    match IpAddr::from_str(ip_str).map_err(|_| format!("'{}' is not an IP address", ip_str))? {
+        IpAddr::V4(ip) => ipv4_cidr_targets(ip, prefix_len),
+        IpAddr::V6(ip) => ipv6_cidr_targets(ip, prefix_len),
+    }
+}
+
+/// Iterate over the usable host addresses of an IPv4 network
+fn ipv4_cidr_targets(ip: Ipv4Addr, prefix_len: u8) -> Result<TargetIterator, String> {
     if prefix_len > 32 {
-        return None;
+        return Err("IPv4 prefix length must be at most 32".to_string());
     }
     
     let ip_u32 = u32::from(ip);
@@ -232,63 +561,329 @@ pub fn expand_cidr(cidr: &str) -> Option<Vec<IpAddr>> {
     let network = ip_u32 & mask;
     let broadcast = network | !mask;
     
-    let mut ips = Vec::new();
-    
-    // Skip network and broadcast addresses if prefix_len <= 30
-    let start = if prefix_len <= 30 { network + 1 } else { network };
-    let end = if prefix_len <= 30 { broadcast - 1 } else { broadcast };
+    // A /32 is a single host and a /31 is a point-to-point link (RFC 3021)
+    // where both addresses are usable
+    let (start, end) = match prefix_len {
+        32 => (network, network),
+        31 => (network, broadcast),
+        // Skip network and broadcast addresses
+        _ => (network + 1, broadcast - 1),
+    };
     
-    for i in start..=end {
-        let ip = Ipv4Addr::from(i);
-        ips.push(IpAddr::V4(ip));
+    Ok(TargetIterator::from_range(u128::from(start), u128::from(end), true))
+}
+
+/// Iterate over the addresses of an IPv6 network
+fn ipv6_cidr_targets(ip: Ipv6Addr, prefix_len: u8) -> Result<TargetIterator, String> {
+    // Refuse large networks to avoid generating millions of addresses
+    if prefix_len > 128 {
+        return Err("IPv6 prefix length must be at most 128".to_string());
+    }
+    if prefix_len < MIN_IPV6_PREFIX_LEN {
+        return Err(format!("network is too large to scan (minimum IPv6 prefix is /{})", MIN_IPV6_PREFIX_LEN));
     }
     
-    Some(ips)
+    let mask = !0u128 << (128 - u32::from(prefix_len));
+    let network = u128::from(ip) & mask;
+    let last = network | !mask;
+    
+    // IPv6 has no broadcast address, but the first address of a subnet is the
+    // subnet-router anycast address (RFC 4291), so skip it unless the prefix
+    // is /127 or /128
+    let start = if prefix_len <= 126 { network + 1 } else { network };
+    
+    Ok(TargetIterator::from_range(start, last, false))
 }
 
 /// Expand an IP range into individual IP addresses
 pub fn expand_ip_range(range: &str) -> Option<Vec<IpAddr>> {
-    let parts: Vec<&str> = range.split('-').collect();
-    if parts.len() != 2 {
-        return None;
+    range_targets(range).ok().map(|targets| targets.collect())
+}
+
+/// Parse an IP range such as `10.0.0.1-10.0.0.20` or `2001:db8::1-2001:db8::ff`
+///
+/// `start..end` is accepted as well. Both ends must be the same address family
+/// and the start must not come after the end.
+pub fn parse_range(range: &str) -> Option<(IpAddr, IpAddr)> {
+    let (start, end) = range.split_once("..").or_else(|| range.split_once('-'))?;
+    let start = IpAddr::from_str(start.trim()).ok()?;
+    let end = IpAddr::from_str(end.trim()).ok()?;
+    
+    match range_bounds(&start, &end) {
+        Some((first, last, _)) if first <= last => Some((start, end)),
+        _ => None,
+    }
+}
+
+/// Numeric (start, end, is_ipv4) bounds of a range; `None` for mixed address families
+fn range_bounds(start: &IpAddr, end: &IpAddr) -> Option<(u128, u128, bool)> {
+    match (start, end) {
+        (IpAddr::V4(start), IpAddr::V4(end)) => Some((u128::from(u32::from(*start)), u128::from(u32::from(*end)), true)),
+        (IpAddr::V6(start), IpAddr::V6(end)) => Some((u128::from(*start), u128::from(*end), false)),
+        _ => None,
+    }
+}
+
+/// Build a lazy iterator over an IP range such as 10.0.0.1-10.0.0.20, or say why it is invalid
+fn range_targets(range: &str) -> Result<TargetIterator, String> {
+    let (start_ip, end_ip) = range.split_once("..").or_else(|| range.split_once('-'))
+        .ok_or_else(|| "expected two addresses separated by '-' or '..'".to_string())?;
+    let start_ip = IpAddr::from_str(start_ip.trim()).map_err(|_| format!("'{}' is not an IP address", start_ip.trim()))?;
+    let end_ip = IpAddr::from_str(end_ip.trim()).map_err(|_| format!("'{}' is not an IP address", end_ip.trim()))?;
+    let (start, end, is_ipv4) = range_bounds(&start_ip, &end_ip)
+        .ok_or_else(|| "start and end must both be IPv4 or both be IPv6".to_string())?;
+    if start > end {
+        return Err(format!("{} comes after {}", start_ip, end_ip));
     }
     
-    let start_ip = Ipv4Addr::from_str(parts[0]).ok()?;
-    let end_ip = Ipv4Addr::from_str(parts[1]).ok()?;
+    // Limit range to keep scans of mistyped ranges bounded
+    if end - start > MAX_RANGE_SIZE {
+        return Err(format!("range covers more than {} addresses", MAX_RANGE_SIZE + 1));
+    }
     
-    let start_u32 = u32::from(start_ip);
-    let end_u32 = u32::from(end_ip);
+    Ok(TargetIterator::from_range(start, end, is_ipv4))
+}
+
+/// Check a target specification for networks that are too large to expand
+pub fn validate_target_spec(target_spec: &str) -> Result<(), String> {
+    if let Some((ip_str, prefix)) = target_spec.split_once('/') {
+        let is_ipv6 = Ipv6Addr::from_str(ip_str).is_ok();
+        
+        if let Ok(prefix_len) = prefix.parse::<u8>() {
+            if is_ipv6 && prefix_len < MIN_IPV6_PREFIX_LEN {
+                return Err(format!(
+                    "IPv6 network {} is too large to scan (minimum prefix is /{})",
+                    target_spec, MIN_IPV6_PREFIX_LEN
+                ));
+            }
+        }
+    }
     
-    if end_u32 < start_u32 {
-        return None;
+    Ok(())
+}
+
+/// Set of addresses excluded from a scan, stored as inclusive numeric ranges
+#[derive(Debug, Clone, Default)]
+pub struct ExcludeList {
+    ranges: Vec<(u128, u128, bool)>, // (start, end, is_ipv4)
+}
+
+impl ExcludeList {
+    /// Parse exclusions given as IP addresses, CIDR blocks or IP ranges
+    ///
+    /// CIDR exclusions cover the whole block, including network and broadcast addresses.
+    pub fn parse(specs: &[String]) -> Result<Self, String> {
+        ExcludeList::parse_entries(specs).map_err(|spec| format!("Invalid exclusion: {}", spec))
     }
     
-    // Limit range to avoid excessive memory usage
-    if end_u32 - start_u32 > 65535 {
-        return None;
+    /// Parse addresses, CIDR blocks and ranges, returning the first entry that is none of them
+    fn parse_entries(specs: &[String]) -> Result<Self, &str> {
+        let mut ranges = Vec::new();
+        
+        for spec in specs.iter().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            ranges.push(parse_exclusion(spec).ok_or(spec)?);
+        }
+        
+        Ok(ExcludeList { ranges })
     }
     
-    let mut ips = Vec::new();
-    for i in start_u32..=end_u32 {
-        let ip = Ipv4Addr::from(i);
-        ips.push(IpAddr::V4(ip));
+    /// Check whether an address is excluded
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        let (value, is_ipv4) = match ip {
+            IpAddr::V4(ip) => (u128::from(u32::from(*ip)), true),
+            IpAddr::V6(ip) => (u128::from(*ip), false),
+        };
+        
+        self.ranges.iter()
+            .any(|(start, end, range_is_ipv4)| *range_is_ipv4 == is_ipv4 && value >= *start && value <= *end)
+    }
+    
+    /// Check whether there are no exclusions
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+/// Parse a single exclusion into an inclusive (start, end, is_ipv4) range
+fn parse_exclusion(spec: &str) -> Option<(u128, u128, bool)> {
+    if let Some((ip_str, prefix)) = spec.split_once('/') {
+        let prefix_len = prefix.parse::<u32>().ok()?;
+        
+        return match IpAddr::from_str(ip_str).ok()? {
+            IpAddr::V4(ip) if prefix_len <= 32 => {
+                let mask = if prefix_len == 0 { 0 } else { !0u32 << (32 - prefix_len) };
+                let network = u32::from(ip) & mask;
+                Some((u128::from(network), u128::from(network | !mask), true))
+            },
+            IpAddr::V6(ip) if prefix_len <= 128 => {
+                let mask = if prefix_len == 0 { 0 } else { !0u128 << (128 - prefix_len) };
+                let network = u128::from(ip) & mask;
+                Some((network, network | !mask, false))
+            },
+            _ => None,
+        };
     }
     
-    Some(ips)
+    if spec.contains('-') || spec.contains("..") {
+        let (start, end) = parse_range(spec)?;
+        return range_bounds(&start, &end);
+    }
+    
+    match IpAddr::from_str(spec).ok()? {
+        IpAddr::V4(ip) => Some((u128::from(u32::from(ip)), u128::from(u32::from(ip)), true)),
+        IpAddr::V6(ip) => Some((u128::from(ip), u128::from(ip), false)),
+    }
+}
+
+/// Parse the IP addresses, CIDR blocks and ranges a scan is allowed to touch
+pub fn parse_scope(specs: &[String]) -> Result<ExcludeList, String> {
+    ExcludeList::parse_entries(specs).map_err(|spec| format!("Invalid scope entry: {}", spec))
+}
+
+/// Resolve a target specification, dropping any excluded addresses
+pub fn resolve_targets_excluding(target_spec: &str, exclude: &ExcludeList) -> Vec<IpAddr> {
+    TargetIterator::new(target_spec)
+        .filter(|ip| !exclude.contains(ip))
+        .collect()
 }
 
 /// Comprehensive hostname resolution that tries multiple methods
 pub fn resolve_hostname_comprehensive(ip: &IpAddr) -> String {
+    resolve_hostnames_comprehensive(ip).0
+}
+
+/// Comprehensive hostname resolution returning the preferred name along with every PTR name found
+pub fn resolve_hostnames_comprehensive(ip: &IpAddr) -> (String, Vec<String>) {
     // First try reverse DNS
-    if let Some(hostname) = reverse_lookup(ip) {
-        return hostname;
+    let names = reverse_lookup_all(ip);
+    if let Some(hostname) = preferred_hostname(ip, &names) {
+        debug!("Reverse DNS for {}: {} (of {})", ip, hostname, names.join(", "));
+        return (hostname.to_string(), names);
     }
     
     // Then try NetBIOS name
     if let Some(netbios_name) = get_netbios_name(ip) {
-        return netbios_name;
+        debug!("NetBIOS name for {}: {}", ip, netbios_name);
+        return (netbios_name, names);
     }
     
     // Fall back to IP address string
-    ip.to_string()
+    (ip.to_string(), names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn specs(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+    
+    fn ip(addr: &str) -> IpAddr {
+        IpAddr::from_str(addr).unwrap()
+    }
+    
+    #[test]
+    fn excluding_a_subnet_removes_exactly_its_addresses() {
+        let everything = resolve_targets_excluding("192.168.0.0/16", &ExcludeList::default());
+        let exclude = ExcludeList::parse(&specs(&["192.168.1.0/24"])).unwrap();
+        let remaining = resolve_targets_excluding("192.168.0.0/16", &exclude);
+        
+        assert_eq!(everything.len(), 65534);
+        assert_eq!(everything.len() - remaining.len(), 256);
+        assert!(remaining.contains(&ip("192.168.0.255")));
+        assert!(remaining.contains(&ip("192.168.2.0")));
+    }
+    
+    #[test]
+    fn excluded_addresses_and_subnets_are_not_scanned() {
+        let exclude = ExcludeList::parse(&specs(&["10.0.0.5", "10.0.0.64/26"])).unwrap();
+        let remaining = resolve_targets_excluding("10.0.0.0/24", &exclude);
+        
+        assert!(!remaining.contains(&ip("10.0.0.5")));
+        assert!((64..128).all(|host| !remaining.contains(&ip(&format!("10.0.0.{}", host)))));
+        assert_eq!(remaining.len(), 254 - 1 - 64);
+    }
+    
+    fn addrs(spec: &str) -> Vec<String> {
+        resolve_targets(spec).iter().map(|addr| addr.to_string()).collect()
+    }
+    
+    #[test]
+    fn ipv4_cidr_blocks_skip_network_and_broadcast_except_for_31_and_32() {
+        assert_eq!(addrs("10.1.2.3/32"), vec!["10.1.2.3"]);
+        assert_eq!(addrs("10.1.2.3/31"), vec!["10.1.2.2", "10.1.2.3"]);
+        assert_eq!(addrs("10.1.2.3/30"), vec!["10.1.2.1", "10.1.2.2"]);
+        
+        let block = addrs("192.168.7.99/24");
+        assert_eq!(block.len(), 254);
+        assert_eq!(block.first().unwrap(), "192.168.7.1");
+        assert_eq!(block.last().unwrap(), "192.168.7.254");
+    }
+    
+    #[test]
+    fn ipv4_cidr_blocks_at_the_top_of_the_address_space_stop_there() {
+        assert_eq!(addrs("255.255.255.255/32"), vec!["255.255.255.255"]);
+        assert_eq!(addrs("255.255.255.254/31"), vec!["255.255.255.254", "255.255.255.255"]);
+        assert_eq!(addrs("255.255.255.252/30"), vec!["255.255.255.253", "255.255.255.254"]);
+        assert_eq!(TargetIterator::new("255.255.255.255/32").size_hint(), (1, Some(1)));
+    }
+    
+    #[test]
+    fn ipv6_cidr_blocks_and_ranges_expand() {
+        // The subnet-router anycast address at the start of a /126 is skipped
+        assert_eq!(addrs("fe80::/126"), vec!["fe80::1", "fe80::2", "fe80::3"]);
+        assert_eq!(addrs("fe80::/127"), vec!["fe80::", "fe80::1"]);
+        assert_eq!(addrs("::1-::5"), vec!["::1", "::2", "::3", "::4", "::5"]);
+        assert_eq!(addrs("::1..::3"), vec!["::1", "::2", "::3"]);
+        assert_eq!(addrs("2001:db8::/112").len(), 65535);
+    }
+    
+    #[test]
+    fn ipv6_networks_larger_than_a_112_are_rejected() {
+        let error = TargetIterator::try_new("2001:db8::/111").err().unwrap();
+        assert_eq!(error, TargetError::InvalidCidr {
+            spec: "2001:db8::/111".to_string(),
+            reason: format!("network is too large to scan (minimum IPv6 prefix is /{})", MIN_IPV6_PREFIX_LEN),
+        });
+        assert!(validate_target_spec("2001:db8::/64").is_err());
+        assert!(validate_target_spec("2001:db8::/112").is_ok());
+        assert!(matches!(TargetIterator::try_new("::1-::2:0"), Err(TargetError::InvalidRange { .. })));
+    }
+    
+    #[test]
+    fn invalid_cidr_blocks_say_why() {
+        let error = TargetIterator::try_new("10.0.0.0/33").err().unwrap();
+        assert_eq!(error, TargetError::InvalidCidr {
+            spec: "10.0.0.0/33".to_string(),
+            reason: "IPv4 prefix length must be at most 32".to_string(),
+        });
+        assert_eq!(error.to_string(), "invalid CIDR 10.0.0.0/33: IPv4 prefix length must be at most 32");
+        assert_eq!(TargetIterator::new("10.0.0.0/33").count(), 0);
+    }
+    
+    #[test]
+    fn unresolvable_hostnames_say_why() {
+        // The .invalid TLD never resolves (RFC 6761)
+        let error = TargetIterator::try_new("nonexistent.invalid").err().unwrap();
+        assert_eq!(error, TargetError::Unresolvable("nonexistent.invalid".to_string()));
+        assert_eq!(error.to_string(), "could not resolve nonexistent.invalid");
+    }
+    
+    #[test]
+    fn only_addresses_and_hostnames_are_resolved_up_front() {
+        assert_eq!(TargetIterator::try_new("10.0.0.7").unwrap().resolved(), Some(vec![ip("10.0.0.7")]));
+        assert_eq!(TargetIterator::try_new("10.0.0.0/24").unwrap().resolved(), None);
+        assert_eq!(TargetIterator::try_new("10.0.0.1-10.0.0.9").unwrap().resolved(), None);
+    }
+    
+    #[test]
+    fn parse_scope_names_the_invalid_entry() {
+        let scope = parse_scope(&specs(&["10.0.0.0/8", " 192.168.1.1-192.168.1.9 "])).unwrap();
+        assert!(scope.contains(&ip("10.20.30.40")));
+        assert!(scope.contains(&ip("192.168.1.9")));
+        
+        assert_eq!(parse_scope(&specs(&["10.0.0.0/8", "10.0.0.0/40"])).unwrap_err(), "Invalid scope entry: 10.0.0.0/40");
+        assert_eq!(ExcludeList::parse(&specs(&["bogus"])).unwrap_err(), "Invalid exclusion: bogus");
+    }
 }
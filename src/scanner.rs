@@ -1,152 +1,661 @@
 // Author: CyberCraft Alchemist
 // Core network scanning and vulnerability detection engine
 
-use std::net::IpAddr;
-use std::sync::{Arc, Mutex};
+use std::collections::HashSet;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Instant;
 use rayon::prelude::*;
 use chrono::Local;
+use log::{debug, error, info, warn};
 
-use crate::models::{ScanConfig, ScanResult, PortResult, Vulnerability, HostInfo};
+use crate::models::{ScanConfig, ScanPlan, ScanResult, PortResult, PortState, IdSource, Severity, Vulnerability, HostInfo, IkeInfo, Misconfiguration, ServiceProbeResult};
 use crate::utils;
+use crate::proxy;
 use crate::resolver;
 use crate::cveapi;
 use crate::constants;
+use crate::credentials;
+use crate::schedule;
 use crate::plugins::PluginRegistry;
+use crate::progress::{NoProgress, ScanProgress};
+use crate::notify::WebhookProgress;
+
+// Set when the most recent scan stopped early because it ran past its time budget
+static TIME_BUDGET_EXCEEDED: AtomicBool = AtomicBool::new(false);
 
 /// Main scanner function that orchestrates the entire scanning process
-pub fn scan(config: ScanConfig) -> Vec<ScanResult> {
-    let _start_time = Instant::now();
+///
+/// Fails with `InvalidInput` when a target lies outside the allowed scope.
+pub fn scan(config: ScanConfig) -> io::Result<Vec<ScanResult>> {
+    scan_with_progress(config, &NoProgress)
+}
+
+/// Scan all targets, reporting progress to the given reporter as hosts and ports are found
+pub fn scan_with_progress(config: ScanConfig, progress: &dyn ScanProgress) -> io::Result<Vec<ScanResult>> {
+    run_scan(config, progress, &Arc::new(AtomicBool::new(false)))
+}
+
+/// Scan all targets until finished or until `cancel` is set
+///
+/// Once the flag is set no new hosts or ports are probed and the results
+/// collected so far are returned. Connection attempts already in flight are
+/// not interrupted and still wait for their timeout.
+pub fn scan_cancellable(config: ScanConfig, cancel: Arc<AtomicBool>) -> io::Result<Vec<ScanResult>> {
+    run_scan(config, &NoProgress, &cancel)
+}
+
+/// Scan all targets with both a progress reporter and a cancel flag
+///
+/// Nothing is sent, and the error is `InvalidInput`, when a target lies
+/// outside the allowed scope, so an aborted scan isn't mistaken for an empty one.
+pub fn run_scan(config: ScanConfig, progress: &dyn ScanProgress, cancel: &Arc<AtomicBool>) -> io::Result<Vec<ScanResult>> {
+    TIME_BUDGET_EXCEEDED.store(false, Ordering::SeqCst);
     
-    // Resolve targets to IP addresses
-    let mut targets = resolve_targets(&config);
+    // Use the configured NVD API key for CVE lookups
+    if config.nvd_api_key.is_some() {
+        cveapi::set_nvd_api_key(config.nvd_api_key.clone());
+    }
+    cveapi::set_cve_source_order(config.cve_source_order.clone());
     
-    // Randomize targets if requested
-    if config.randomize_scan {
-        utils::randomize_hosts(&mut targets);
+    // Post serious findings to the webhook as each host completes
+    let webhook_progress;
+    let progress: &dyn ScanProgress = match &config.webhook_url {
+        Some(url) => {
+            webhook_progress = WebhookProgress::new(url, config.webhook_min_severity, progress);
+            &webhook_progress
+        },
+        None => progress,
+    };
+    
+    // Detect services from captured traffic without sending anything to the targets
+    #[cfg(feature = "passive")]
+    {
+        if config.is_passive() {
+            return Ok(crate::passive::run(&config, progress, cancel));
+        }
+    }
+    
+    let config = prepare_targets(&config);
+    check_scope(&config).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    
+    // Bound host and port parallelism to the configured thread count
+    Ok(run_in_pool(&config, || {
+        // Sweep directly-connected subnets with ARP so hosts that ignore ping are still found
+        if config.arp_discovery {
+            utils::set_arp_sweeps(arp_sweep_targets(&config));
+        }
+        
+        let results = scan_targets(&config, progress, cancel);
+        utils::set_arp_sweeps(Vec::new());
+        results
+    }))
+}
+
+/// Whether the most recent scan was cut short by `max_duration_secs`
+pub fn time_budget_exceeded() -> bool {
+    TIME_BUDGET_EXCEEDED.load(Ordering::SeqCst)
+}
+
+/// Cancel the scan once it has run past the configured time budget
+///
+/// Returns true when the budget is spent. Hosts already being scanned stop
+/// probing new ports, and no new hosts are started.
+pub(crate) fn enforce_time_budget(config: &ScanConfig, start_time: Instant, cancel: &AtomicBool) -> bool {
+    let budget = match config.max_duration() {
+        Some(budget) => budget,
+        None => return false,
+    };
+    if start_time.elapsed() < budget {
+        return false;
+    }
+    
+    if !TIME_BUDGET_EXCEEDED.swap(true, Ordering::SeqCst) {
+        warn!("Time budget of {}s exceeded, stopping the scan", budget.as_secs());
     }
+    cancel.store(true, Ordering::SeqCst);
+    true
+}
+
+/// ARP sweep every target specification that lies on a directly-connected subnet
+///
+/// Other targets, and all of them when raw sockets are unavailable, are
+/// checked with ping and TCP probing as usual.
+fn arp_sweep_targets(config: &ScanConfig) -> Vec<utils::ArpSweep> {
+    target_specs(config).iter()
+        .filter_map(|spec| match utils::arp_sweep(None, spec) {
+            Ok(sweep) => {
+                info!("ARP found {} hosts on {} via {}", sweep.hosts.len(), sweep.network, sweep.interface);
+                Some(sweep)
+            },
+            Err(e) => {
+                warn!("{}; using ping for {}", e, spec);
+                None
+            },
+        })
+        .collect()
+}
+
+/// Scan every target in the configuration
+fn scan_targets(config: &ScanConfig, progress: &dyn ScanProgress, cancel: &Arc<AtomicBool>) -> Vec<ScanResult> {
+    let start_time = Instant::now();
     
-    // Create a thread-safe container for results
-    let results = Arc::new(Mutex::new(Vec::new()));
+    // Hand off to the async backend when requested
+    #[cfg(feature = "async")]
+    {
+        if config.use_async {
+            match crate::async_scanner::run_scan(config, progress, cancel) {
+                Ok(results) => return results,
+                Err(e) => warn!("Async backend unavailable ({}), using the threaded scanner", e),
+            }
+        }
+    }
     
-    // Scan each target in parallel
-    targets.par_iter().for_each(|ip| {
-        let host_result = scan_host(ip, &config);
+    let scan_target = |ip: IpAddr| -> Option<ScanResult> {
+        // Skip remaining hosts once the scan has been cancelled or its time budget is spent
+        if enforce_time_budget(config, start_time, cancel) || cancel.load(Ordering::Relaxed) {
+            return None;
+        }
         
-        // If we found any open ports, add the result
-        if !host_result.open_ports.is_empty() {
-            let mut results_guard = results.lock().unwrap();
-            results_guard.push(host_result);
+        // Hold new hosts until the scan window is open; in-flight hosts finish
+        if let Some(window) = &config.scan_window {
+            if !schedule::wait_for_window_cancellable(window, cancel) {
+                return None;
+            }
         }
-    });
+        
+        progress.on_host_started(&ip);
+        
+        // A panic while scanning one host loses only that host; release builds unwind for this
+        let host_result = match panic::catch_unwind(AssertUnwindSafe(|| scan_host(&ip, config, progress, cancel))) {
+            Ok(host_result) => host_result,
+            Err(_) => {
+                error!("Scanning {} failed unexpectedly, skipping it", ip);
+                progress.on_host_completed(&unscanned_host_result(&ip, String::new(), false));
+                return None;
+            },
+        };
+        progress.on_host_completed(&host_result);
+        
+        // If we found any open ports (or a UDP-only service such as SNMP or IKE), keep the result
+        if host_result.is_reportable() {
+            Some(host_result)
+        } else {
+            None
+        }
+    };
     
-    // Return the results
-    let final_results = Arc::try_unwrap(results)
-        .unwrap()
-        .into_inner()
-        .unwrap();
+    if config.randomize_scan {
+        // Shuffling needs every target up front
+        let mut targets = resolve_targets(config);
+        utils::randomize_hosts(&mut targets, &mut utils::scan_rng(config.random_seed, 0));
+        targets.into_par_iter().filter_map(scan_target).collect()
+    } else {
+        // Stream targets so large ranges are never fully materialized
+        target_stream(config)
+            .par_bridge()
+            .filter_map(scan_target)
+            .collect()
+    }
+}
+
+/// Run an operation inside a Rayon pool sized to the configured thread count
+///
+/// Nested parallel iterators (such as the per-port scan inside `scan_host`)
+/// run on the same pool, so both levels of parallelism honor the limit. The
+/// configured connection rate limit is applied for the whole operation.
+fn run_in_pool<T, F>(config: &ScanConfig, operation: F) -> T
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    utils::set_max_connection_rate(config.max_pps);
+    utils::set_probe_retries(config.retries);
+    utils::set_max_banner_bytes(config.max_banner_bytes);
+    proxy::set_proxy(config.proxy.as_deref().and_then(|url| proxy::ProxyConfig::parse(url).ok()));
+    proxy::set_source_addresses(config.source_addresses().unwrap_or_default());
+    resolver::set_dns_servers(&config.dns_servers);
     
-    final_results
+    match rayon::ThreadPoolBuilder::new().num_threads(config.threads.max(1)).build() {
+        Ok(pool) => pool.install(operation),
+        Err(_) => operation(),
+    }
 }
 
 /// Scan a single host for open ports and vulnerabilities
-fn scan_host(ip: &IpAddr, config: &ScanConfig) -> ScanResult {
+fn scan_host(ip: &IpAddr, config: &ScanConfig, progress: &dyn ScanProgress, cancel: &AtomicBool) -> ScanResult {
     let _start_time = Instant::now();
+    debug!("Scanning {}", ip);
     
     // Resolve hostname
-    let hostname = resolver::resolve_hostname_comprehensive(ip);
+    let (hostname, hostnames) = resolver::resolve_hostnames_comprehensive(ip);
     
     // Ping host to check if it's online
-    let is_online = utils::ping_host(ip) || utils::tcp_ping_host(ip, config.timeout_ms);
+    let is_online = utils::host_responds(ip, config.timeout_ms);
     
     // If host is not online and we're not doing a complete scan, return early
     if !is_online && !config.scan_offline_hosts {
-        return ScanResult {
-            host: ip.to_string(),
-            hostname,
-            is_online,
-            scan_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-            open_ports: Vec::new(),
-            os_info: None,
-            vulnerabilities_summary: None,
-            attack_paths: None,
-        };
+        info!("{} did not respond to ping, skipping", ip);
+        return ScanResult { hostnames, ..unscanned_host_result(ip, hostname, is_online) };
     }
     
+    // In adaptive mode, size the port timeout for this host from its measured RTT
+    let effective_timeout_ms = if config.adaptive_timeout {
+        match utils::measure_rtt(ip, config.timeout_ms) {
+            Some(rtt) => {
+                let timeout_ms = utils::adaptive_timeout_ms(rtt, config.min_timeout_ms, config.timeout_ms);
+                info!("{} RTT {}ms, using a {}ms port timeout", ip, rtt.as_millis(), timeout_ms);
+                Some(timeout_ms)
+            },
+            None => {
+                info!("{} RTT could not be measured, using the {}ms port timeout", ip, config.timeout_ms);
+                None
+            },
+        }
+    } else {
+        None
+    };
+    
+    // With a fast filter, a host whose sentinel ports all time out is swept with a short timeout
+    let effective_timeout_ms = effective_timeout_ms.or_else(|| {
+        let timeout_ms = constants::FAST_FILTER_TIMEOUT_MS.min(config.timeout_ms);
+        if config.fast_filter && utils::sentinel_ports_filtered(ip, timeout_ms) {
+            info!("{} filtered every sentinel port, using a {}ms port timeout", ip, timeout_ms);
+            Some(timeout_ms)
+        } else {
+            None
+        }
+    });
+    let host_config = match effective_timeout_ms {
+        Some(timeout_ms) => ScanConfig { timeout_ms, ..config.clone() },
+        None => config.clone(),
+    };
+    let config = &host_config;
+    
     // Determine which ports to scan
-    let ports_to_scan: Vec<u16> = if config.ports.is_empty() {
+    let mut ports = ports_for_config(config);
+    
+    // Randomize ports if requested, in an order of their own for each host
+    if config.randomize_scan {
+        utils::randomize_ports(&mut ports, &mut utils::scan_rng(config.random_seed, utils::host_rng_stream(ip)));
+    }
+    
+    // Counters for the ports that aren't open
+    let closed_ports = AtomicUsize::new(0);
+    let filtered_ports = AtomicUsize::new(0);
+    
+    // Scan ports in parallel, keeping the open ones
+    let open_port_results: Vec<PortResult> = ports.par_iter().filter_map(|port| {
+        // Stop probing new ports once the scan has been cancelled
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        
+        match utils::probe_port(ip, *port, config.timeout_ms) {
+            PortState::Open => {
+                debug!("{} open", SocketAddr::new(*ip, *port));
+                progress.on_port_open(ip, *port);
+                
+                // Get service banner
+                let probe = utils::probe_service(ip, *port, config.timeout_ms);
+                
+                Some(analyze_open_port(ip, *port, probe, config))
+            },
+            PortState::Closed => {
+                if config.verbose {
+                    debug!("{} closed", SocketAddr::new(*ip, *port));
+                }
+                closed_ports.fetch_add(1, Ordering::Relaxed);
+                None
+            },
+            PortState::Filtered => {
+                if config.verbose {
+                    debug!("{} filtered", SocketAddr::new(*ip, *port));
+                }
+                filtered_ports.fetch_add(1, Ordering::Relaxed);
+                None
+            },
+        }
+    }).collect();
+    
+    let mut result = build_host_result(
+        ip,
+        hostname,
+        is_online,
+        open_port_results,
+        closed_ports.into_inner(),
+        filtered_ports.into_inner(),
+        config
+    );
+    result.hostnames = hostnames;
+    result.effective_timeout_ms = effective_timeout_ms;
+    result
+}
+
+/// Result for a host whose ports were not scanned
+fn unscanned_host_result(ip: &IpAddr, hostname: String, is_online: bool) -> ScanResult {
+    ScanResult {
+        host: ip.to_string(),
+        hostname,
+        hostnames: Vec::new(),
+        is_online,
+        scan_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        open_ports: Vec::new(),
+        closed_port_count: 0,
+        filtered_port_count: 0,
+        os_info: None,
+        vulnerabilities_summary: None,
+        attack_paths: None,
+        misconfigurations: Vec::new(),
+        host_vulnerabilities: Vec::new(),
+        mac: None,
+        vendor: None,
+        effective_timeout_ms: None,
+        ike_info: None,
+    }
+}
+
+/// Determine which ports to scan for a configuration
+pub(crate) fn ports_for_config(config: &ScanConfig) -> Vec<u16> {
+    if !config.ports.is_empty() {
+        // Explicit ports take precedence
+        config.ports.clone()
+    } else if let Some(count) = config.top_ports {
+        // Scan the most frequently open ports
+        constants::TOP_PORTS.iter().take(count).cloned().collect()
+    } else {
         // If no ports are specified, scan common ports
         constants::COMMON_PORTS.keys().cloned().collect()
+    }
+}
+
+/// Identify the service on an open port and detect its vulnerabilities
+pub(crate) fn analyze_open_port(ip: &IpAddr, port: u16, probe: Option<ServiceProbeResult>, config: &ScanConfig) -> PortResult {
+    let banner = probe.as_ref()
+        .map(|probe| probe.banner.clone())
+        .unwrap_or_else(|| String::from("No banner"));
+    
+    // Evidence of exactly what was exchanged, kept only on request since it grows reports
+    let (raw_probe, raw_response) = match probe.filter(|_| config.record_raw_probes) {
+        Some(probe) => (Some(probe.probe), Some(probe.response)),
+        None => (None, None),
+    };
+    
+    // Identify service
+    let identity = utils::identify_service(port, &banner);
+    let service = identity.name.clone();
+    debug!("{} identified as {} ({}, {:.2})", SocketAddr::new(*ip, port), service, identity.source, identity.confidence);
+    
+    // Inventory scans stop at the service map
+    if config.banner_only {
+        return PortResult {
+            port,
+            service,
+            banner: utils::sanitize_banner(banner.as_bytes(), constants::BANNER_MAX_LEN),
+            vulnerabilities: Vec::new(),
+            tls_certificate: None,
+            tls_versions: Vec::new(),
+            http_info: None,
+            smb_info: None,
+            http2_info: None,
+            ssh_info: None,
+            service_confidence: identity.confidence,
+            service_source: Some(identity.source),
+            raw_probe,
+            raw_response,
+        };
+    }
+    
+    // Speak HTTP/2 to silent ports and gRPC ports, since h2c and gRPC services ignore the banner probe
+    let silent = banner.is_empty() || banner == "No banner";
+    let http2_info = if silent || constants::GRPC_PORTS.contains(&port) {
+        utils::probe_http2(ip, port, is_tls_port(port, &service), config.timeout_ms)
     } else {
-        config.ports.clone()
+        None
+    };
+    let service = match &http2_info {
+        Some(http2) if http2.grpc => "grpc".to_string(),
+        Some(_) if service == "unknown" => "http2".to_string(),
+        _ => service,
     };
     
-    // Randomize ports if requested
-    let mut ports = ports_to_scan.clone();
-    if config.randomize_scan {
-        utils::randomize_ports(&mut ports);
+    // Create plugin registry
+    let plugin_registry = PluginRegistry::with_config(config);
+    
+    // Detect vulnerabilities using the appropriate method based on configuration
+    let mut vulnerabilities = if config.enhanced_vuln_detection {
+        // If enhanced vulnerability detection is enabled, use all plugins
+        plugin_registry.detect_vulnerabilities(
+            &service,
+            &banner,
+            config
+        )
+    } else {
+        // Otherwise use the legacy approach for backward compatibility
+        cveapi::check_service_vulnerabilities(
+            &service, 
+            &banner, 
+            !config.offline_mode
+        )
+    };
+    
+    // Fingerprint web services with a real request, falling back to the banner headers
+    let is_https = port == 443 || port == 8443 || service.to_lowercase().contains("https");
+    let (http_info, http_probed) = if service.to_lowercase().contains("http") || banner.starts_with("HTTP/") {
+        match utils::probe_http(ip, port, is_https, config.timeout_ms) {
+            Some(http_info) => (Some(http_info), true),
+            None => (utils::parse_http_info(&banner, is_https), false),
+        }
+    } else {
+        (None, false)
+    };
+    
+    if let Some(http_info) = &http_info {
+        // Products identified by fingerprinting may not appear in the raw banner
+        let products: Vec<String> = http_info.technologies.iter()
+            .filter_map(|t| t.version.as_ref().map(|v| format!("{}/{}", t.name, v)))
+            .collect();
+        if !products.is_empty() {
+            cveapi::check_known_service_vulnerabilities(&service, &products.join("\n"), !config.offline_mode, &mut vulnerabilities);
+            vulnerabilities = cveapi::merge_vulnerabilities(vulnerabilities);
+        }
     }
     
-    // Container for open port results
-    let open_ports = Arc::new(Mutex::new(Vec::new()));
+    // Inspect the certificate and protocol versions of TLS services
+    let (tls_certificate, tls_versions) = if config.inspect_tls && is_tls_port(port, &service) {
+        (
+            utils::inspect_tls_certificate(ip, port, config.timeout_ms),
+            utils::enumerate_tls_versions(ip, port, config.timeout_ms),
+        )
+    } else {
+        (None, Vec::new())
+    };
     
-    // Scan ports in parallel
-    ports.par_iter().for_each(|port| {
-        if utils::is_port_open(ip, *port, config.timeout_ms) {
-            // Get service banner
-            let banner = utils::get_service_banner(ip, *port, config.timeout_ms)
-                .unwrap_or_else(|| String::from("No banner"));
-            
-            // Identify service
-            let service = utils::identify_service(*port, &banner);
-            
-            // Create plugin registry
-            let plugin_registry = PluginRegistry::new();
-            
-            // Detect vulnerabilities using the appropriate method based on configuration
-            let vulnerabilities = if config.enhanced_vuln_detection {
-                // If enhanced vulnerability detection is enabled, use all plugins
-                plugin_registry.detect_vulnerabilities(
-                    &service,
-                    &banner,
-                    config
-                )
-            } else {
-                // Otherwise use the legacy approach for backward compatibility
-                cveapi::check_service_vulnerabilities(
-                    &service, 
-                    &banner, 
-                    !config.offline_mode
-                )
-            };
-            
-            // Create port result
-            let port_result = PortResult {
-                port: *port,
-                service,
-                banner,
-                vulnerabilities,
-            };
-            
-            // Add to results
-            let mut open_ports_guard = open_ports.lock().unwrap();
-            open_ports_guard.push(port_result);
+    if config.check_misconfigurations {
+        if let Some(cert) = &tls_certificate {
+            vulnerabilities.extend(cveapi::assess_tls_certificate(cert));
         }
-    });
+        vulnerabilities.extend(cveapi::assess_tls_versions(&tls_versions));
+    }
+    
+    // Negotiate with SMB services to learn their dialects and signing policy
+    let smb_info = if is_smb_port(port, &service) {
+        utils::probe_smb(ip, port, config.timeout_ms, !config.non_intrusive)
+    } else {
+        None
+    };
+    
+    if config.check_misconfigurations {
+        if let Some(smb_info) = &smb_info {
+            vulnerabilities.extend(cveapi::assess_smb(smb_info));
+        }
+    }
+    
+    // Read the version and key exchange offer of SSH services
+    let ssh_info = if is_ssh_port(port, &service, &banner) {
+        utils::probe_ssh_algorithms(ip, port, config.timeout_ms)
+    } else {
+        None
+    };
+    
+    if config.check_misconfigurations {
+        if let Some(ssh_info) = &ssh_info {
+            vulnerabilities.extend(cveapi::assess_ssh_algorithms(ssh_info));
+        }
+    }
     
-    // Collect open ports
-    let mut open_port_results = Arc::try_unwrap(open_ports)
-        .unwrap()
-        .into_inner()
-        .unwrap();
+    // Ask web services which methods they allow
+    if config.check_misconfigurations && !config.non_intrusive && http_probed {
+        if let Some(methods) = utils::probe_http_methods(ip, port, is_https, config.timeout_ms) {
+            debug!("{} allows {}", SocketAddr::new(*ip, port), methods.join(", "));
+            vulnerabilities.extend(cveapi::assess_http_methods(&methods));
+        }
+    }
+    
+    // Ask database services to list their contents without logging in
+    if config.check_misconfigurations && !config.non_intrusive {
+        if let Some((database, names)) = probe_unauthenticated_database(ip, port, &service, config.timeout_ms) {
+            vulnerabilities.push(cveapi::assess_unauthenticated_database(database, &names));
+        }
+    }
+    
+    // A successful protocol handshake confirms the service outright
+    let (service_confidence, service_source) = if http_probed || smb_info.is_some() || http2_info.is_some() || ssh_info.is_some() {
+        (constants::SERVICE_CONFIDENCE_PROBE, IdSource::Probe)
+    } else {
+        (identity.confidence, identity.source)
+    };
+    
+    // Try default credentials when explicitly enabled
+    vulnerabilities.extend(check_default_credentials(ip, port, &service, config));
+    vulnerabilities.extend(plugin_registry.detect_by_port(ip, port, &service, &banner, config));
+    
+    PortResult {
+        port,
+        service,
+        banner: utils::sanitize_banner(banner.as_bytes(), constants::BANNER_MAX_LEN),
+        vulnerabilities,
+        tls_certificate,
+        tls_versions,
+        http_info,
+        smb_info,
+        http2_info,
+        ssh_info,
+        service_confidence,
+        service_source: Some(service_source),
+        raw_probe,
+        raw_response,
+    }
+}
+
+/// Try the default credentials listed for a service with a protocol-appropriate login
+///
+/// This is intrusive and only runs when `config.check_default_credentials` is set.
+pub fn check_default_credentials(ip: &IpAddr, port: u16, service: &str, config: &ScanConfig) -> Vec<Vulnerability> {
+    if !config.check_default_credentials || config.non_intrusive {
+        return Vec::new();
+    }
     
+    let service = service.to_lowercase();
+    let timeout_ms = config.timeout_ms;
+    let mut results = Vec::new();
+    
+    // HTTP Basic/Digest realms are covered by the HTTP authentication plugin
+    if service.contains("http") {
+        results.extend(credentials::check_web_login_credentials(ip, port, &service, timeout_ms));
+    } else if service.contains("ftp") {
+        let ftp_credentials = credentials::credentials_for_service("ftp");
+        results.extend(credentials::check_ftp_credentials(ip, port, &ftp_credentials, timeout_ms));
+    } else if service.contains("snmp") {
+        let communities: Vec<&str> = credentials::credentials_for_service("snmp").into_iter()
+            .map(|(community, _)| community)
+            .collect();
+        results.extend(credentials::check_snmp_communities(ip, port, &communities, timeout_ms));
+    }
+    
+    // SSH logins need the optional `ssh` feature
+    #[cfg(feature = "ssh")]
+    if service.contains("ssh") {
+        let ssh_credentials = credentials::credentials_for_service("ssh");
+        results.extend(credentials::check_ssh_credentials(ip, port, &ssh_credentials, timeout_ms));
+    }
+    
+    results
+}
+
+/// Check whether a port is expected to speak TLS
+fn is_tls_port(port: u16, service: &str) -> bool {
+    let service = service.to_lowercase();
+    constants::TLS_PORTS.contains(&port) || service.contains("https") || service.contains("ssl") || service.contains("tls")
+}
+
+/// Whether a port is likely to speak SMB, directly (445) or over NetBIOS (139)
+fn is_smb_port(port: u16, service: &str) -> bool {
+    let service = service.to_lowercase();
+    port == 445 || port == 139 || service.contains("smb") || service.contains("microsoft-ds") || service.contains("netbios-ssn")
+}
+
+/// Whether a port is likely to speak SSH, by port number, service name or identification banner
+fn is_ssh_port(port: u16, service: &str, banner: &str) -> bool {
+    port == 22 || service.to_lowercase().contains("ssh") || banner.starts_with("SSH-")
+}
+
+/// Probe Redis, MongoDB and Elasticsearch services for access without credentials
+///
+/// Returns the database's name with the databases or indices it listed.
+fn probe_unauthenticated_database(ip: &IpAddr, port: u16, service: &str, timeout_ms: u64) -> Option<(&'static str, Vec<String>)> {
+    let service = service.to_lowercase();
+    if port == constants::REDIS_PORT || service.contains("redis") {
+        utils::probe_redis(ip, port, timeout_ms).map(|names| ("Redis", names))
+    } else if port == constants::MONGODB_PORT || service.contains("mongo") {
+        utils::probe_mongodb(ip, port, timeout_ms).map(|names| ("MongoDB", names))
+    } else if port == constants::ELASTICSEARCH_PORT || service.contains("elasticsearch") {
+        utils::probe_elasticsearch(ip, port, timeout_ms).map(|names| ("Elasticsearch", names))
+    } else {
+        None
+    }
+}
+
+/// Assemble the final result for a host from its open ports
+pub(crate) fn build_host_result(
+    ip: &IpAddr,
+    hostname: String,
+    is_online: bool,
+    mut open_port_results: Vec<PortResult>,
+    closed_port_count: usize,
+    filtered_port_count: usize,
+    config: &ScanConfig
+) -> ScanResult {
     // Sort ports for better readability
     open_port_results.sort_by_key(|p| p.port);
     
+    // Guess SNMP communities on hosts that responded; an answer also yields the device's
+    // sysDescr. Each community waits out its own timeout, and guessing them is a
+    // credential attempt, so it is opt-in like the other logins
+    let snmp = if config.check_default_credentials && is_online && !config.banner_only && !config.non_intrusive {
+        enumerate_snmp(ip, config)
+    } else {
+        None
+    };
+    
+    // Fingerprint IPsec VPNs on 500/udp, which the TCP port scan never sees; hosts that
+    // didn't respond are skipped, since a silent probe waits out a full timeout
+    let ike_info = if config.check_misconfigurations && is_online && !config.banner_only && !config.non_intrusive {
+        utils::probe_ike(ip, config.timeout_ms)
+    } else {
+        None
+    };
+    
     // Gather OS information if possible
-    let os_info = if !open_port_results.is_empty() {
+    let os_info = if !open_port_results.is_empty() || snmp.is_some() {
+        // SSH identification strings name the distribution even when the banner grab missed them
         let banners: Vec<String> = open_port_results.iter()
             .map(|p| p.banner.clone())
+            .chain(open_port_results.iter().filter_map(|p| p.ssh_info.as_ref().map(|ssh| ssh.version.clone())))
+            .chain(snmp.iter().map(|(_, sys_descr)| sys_descr.clone()))
             .collect();
         
         utils::fingerprint_os(&banners)
@@ -154,18 +663,31 @@ fn scan_host(ip: &IpAddr, config: &ScanConfig) -> ScanResult {
         None
     };
     
+    // Ask host-level sources (e.g. Shodan InternetDB) about the host itself
+    let mut host_vulnerabilities = if config.enhanced_vuln_detection && !config.offline_mode && !config.banner_only {
+        PluginRegistry::with_config(config).detect_by_host(ip, config)
+    } else {
+        Vec::new()
+    };
+    
+    // Look up EPSS scores for the host's CVEs in one batch
+    if !config.offline_mode && !config.banner_only {
+        add_epss_scores(&mut open_port_results, &mut host_vulnerabilities);
+    }
+    
     // Create vulnerability summary if enhanced detection is enabled
-    let vulnerabilities_summary = if config.enhanced_vuln_detection {
-        Some(generate_vulnerability_summary(&open_port_results))
+    let vulnerabilities_summary = if config.enhanced_vuln_detection && !config.banner_only {
+        Some(generate_vulnerability_summary(&open_port_results, &host_vulnerabilities))
     } else {
         None
     };
     
     // Generate attack paths if analysis is enabled
-    let attack_paths = if config.attack_path_analysis {
+    let attack_paths = if config.attack_path_analysis && !config.banner_only {
         // Collect all vulnerabilities from all ports
         let all_vulnerabilities: Vec<Vulnerability> = open_port_results.iter()
             .flat_map(|port| port.vulnerabilities.clone())
+            .chain(host_vulnerabilities.iter().cloned())
             .collect();
             
         if !all_vulnerabilities.is_empty() {
@@ -178,22 +700,475 @@ fn scan_host(ip: &IpAddr, config: &ScanConfig) -> ScanResult {
         None
     };
     
+    // Look for insecure service configurations
+    let mut misconfigurations = if config.check_misconfigurations && !config.banner_only {
+        let mut misconfigurations: Vec<Misconfiguration> = open_port_results.iter()
+            .flat_map(|p| check_misconfigurations(&p.service, &p.banner, ip, p.port, config))
+            .collect();
+        // Audit the security headers of web responses
+        for port in &open_port_results {
+            if let Some(http_info) = &port.http_info {
+                misconfigurations.extend(cveapi::assess_security_headers(http_info, port.port));
+            }
+        }
+        misconfigurations.extend(ike_info.as_ref().and_then(ike_misconfiguration));
+        misconfigurations
+    } else {
+        Vec::new()
+    };
+    
+    // An accepted community is reported whenever communities were guessed
+    misconfigurations.extend(snmp.as_ref().and_then(|(community, sys_descr)| snmp_misconfiguration(community, sys_descr)));
+    
+    // Hosts that answered an ARP sweep are attributed to their NIC vendor
+    let mac = utils::arp_mac(ip);
+    let vendor = mac.as_deref().and_then(resolver::oui_lookup);
+    
+    info!("Completed {}: {} open, {} closed, {} filtered ports",
+          ip, open_port_results.len(), closed_port_count, filtered_port_count);
+    
     // Create final result
     ScanResult {
         host: ip.to_string(),
         hostname,
+        hostnames: Vec::new(),
         is_online,
         scan_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
         open_ports: open_port_results,
+        closed_port_count,
+        filtered_port_count,
         os_info,
         vulnerabilities_summary,
         attack_paths,
+        misconfigurations,
+        host_vulnerabilities,
+        mac,
+        vendor,
+        effective_timeout_ms: None,
+        ike_info,
+    }
+}
+
+/// Fill in missing EPSS scores for the CVEs found on a host and its ports
+fn add_epss_scores(ports: &mut [PortResult], host_vulnerabilities: &mut [Vulnerability]) {
+    let mut cve_ids: Vec<String> = ports.iter()
+        .flat_map(|p| p.vulnerabilities.iter())
+        .chain(host_vulnerabilities.iter())
+        .filter(|v| v.epss_score.is_none() && v.id.starts_with("CVE-"))
+        .map(|v| v.id.to_uppercase())
+        .collect();
+    cve_ids.sort_unstable();
+    cve_ids.dedup();
+    
+    if cve_ids.is_empty() {
+        return;
+    }
+    
+    let cve_refs: Vec<&str> = cve_ids.iter().map(String::as_str).collect();
+    let scores = match cveapi::lookup_epss_batch(&cve_refs) {
+        Ok(scores) => scores,
+        // EPSS is best-effort enrichment
+        Err(cveapi::CveApiError::RateLimited(service)) => {
+            warn!("{} rate limited the EPSS lookup, continuing without scores", service);
+            return;
+        },
+        Err(e) => {
+            debug!("EPSS lookup failed: {}", e);
+            return;
+        },
+    };
+    
+    for vuln in ports.iter_mut().flat_map(|p| p.vulnerabilities.iter_mut()).chain(host_vulnerabilities.iter_mut()) {
+        if vuln.epss_score.is_none() {
+            vuln.epss_score = scores.get(&vuln.id.to_uppercase()).copied();
+        }
+    }
+}
+
+/// Check an open service for the misconfigurations in `SECURITY_MISCONFIGURATIONS`
+///
+/// Banners are matched against each pattern that applies to the service. DNS
+/// servers are additionally asked for a zone transfer, unless `config.non_intrusive`
+/// is set. SNMP communities are guessed once per host by `enumerate_snmp`.
+pub fn check_misconfigurations(service: &str, banner: &str, ip: &IpAddr, port: u16, config: &ScanConfig) -> Vec<Misconfiguration> {
+    let service = service.to_lowercase();
+    let mut results: Vec<Misconfiguration> = Vec::new();
+    
+    for (category, pattern, id, description, recommendation) in constants::SECURITY_MISCONFIGURATIONS.iter() {
+        let applies = match *category {
+            "ssl" => is_tls_port(port, &service),
+            category => service.contains(category),
+        };
+        if !applies {
+            continue;
+        }
+        
+        let evidence = match *category {
+            // Zone transfers go beyond a benign greeting
+            "dns" if config.non_intrusive => None,
+            "dns" => find_zone_transfer(ip, port).map(|zone| format!("zone {} transferred", zone)),
+            // SNMP and IKE are probed once per host over UDP, see `enumerate_snmp` and `ike_misconfiguration`
+            "snmp" | "ike" => None,
+            _ => pattern.find(banner).map(|m| m.as_str().trim().to_string()),
+        };
+        
+        if let Some(evidence) = evidence {
+            results.push(Misconfiguration {
+                id: id.clone(),
+                port,
+                category: category.to_string(),
+                description: format!("{} ({})", description, evidence),
+                severity: misconfiguration_severity(id).to_string(),
+                recommendation: recommendation.clone(),
+            });
+        }
+    }
+    
+    results
+}
+
+/// Look for an SNMP agent on 161/udp that accepts a default or configured community
+///
+/// Returns the accepted community and the agent's sysDescr. SNMP is UDP-only, so
+/// agents are found here even when the TCP port scan shows nothing on 161. This
+/// is the only place communities are guessed, and only with `check_default_credentials`.
+pub fn enumerate_snmp(ip: &IpAddr, config: &ScanConfig) -> Option<(String, String)> {
+    let mut communities: Vec<&str> = vec!["public", "private"];
+    let extra = credentials::credentials_for_service("snmp").into_iter()
+        .map(|(community, _)| community)
+        .chain(config.snmp_communities.iter().map(String::as_str));
+    for community in extra {
+        if !communities.contains(&community) {
+            communities.push(community);
+        }
+    }
+    
+    credentials::find_snmp_community(ip, constants::SNMP_PORT, &communities, config.timeout_ms)
+}
+
+/// Build the default-community misconfiguration for an SNMP agent found by `enumerate_snmp`
+fn snmp_misconfiguration(community: &str, sys_descr: &str) -> Option<Misconfiguration> {
+    let (_, _, id, description, recommendation) = constants::SECURITY_MISCONFIGURATIONS.iter()
+        .find(|(_, _, id, _, _)| id == "MISCONFIG-SNMP-DEFAULT-COMMUNITY")?;
+    
+    Some(Misconfiguration {
+        id: id.clone(),
+        port: constants::SNMP_PORT,
+        category: "snmp".to_string(),
+        description: format!("{} ({})", description, snmp_evidence(community, sys_descr)),
+        severity: misconfiguration_severity(id).to_string(),
+        recommendation: recommendation.clone(),
+    })
+}
+
+/// Evidence shown for an accepted SNMP community
+fn snmp_evidence(community: &str, sys_descr: &str) -> String {
+    if sys_descr.is_empty() {
+        format!("community '{}' accepted", community)
+    } else {
+        format!("community '{}' accepted, sysDescr: {}", community, sys_descr)
+    }
+}
+
+/// Build the weak-transform misconfiguration for an IKE endpoint found by `utils::probe_ike`
+fn ike_misconfiguration(ike: &IkeInfo) -> Option<Misconfiguration> {
+    let mut evidence: Vec<String> = ike.transforms.iter()
+        .filter(|t| t.is_weak())
+        .map(|t| t.to_string())
+        .collect();
+    if ike.aggressive_mode {
+        evidence.insert(0, "aggressive mode with pre-shared key".to_string());
+    }
+    if evidence.is_empty() {
+        return None;
+    }
+    
+    let (_, _, id, description, recommendation) = constants::SECURITY_MISCONFIGURATIONS.iter()
+        .find(|(_, _, id, _, _)| id == "MISCONFIG-IKE-WEAK-TRANSFORM")?;
+    
+    Some(Misconfiguration {
+        id: id.clone(),
+        port: constants::IKE_PORT,
+        category: "ike".to_string(),
+        description: format!("{} ({})", description, evidence.join(", ")),
+        severity: misconfiguration_severity(id).to_string(),
+        recommendation: recommendation.clone(),
+    })
+}
+
+/// Attempt zone transfers for the domains the DNS server is likely authoritative for
+fn find_zone_transfer(ip: &IpAddr, port: u16) -> Option<String> {
+    let mut zones = Vec::new();
+    
+    // The parent domain of the server's own name is the most likely candidate
+    if let Some(name) = resolver::reverse_lookup(ip) {
+        let name = name.trim_end_matches('.');
+        if let Some((_, parent)) = name.split_once('.') {
+            if parent.contains('.') {
+                zones.push(parent.to_string());
+            }
+        }
+    }
+    if let Some(domain) = resolver::get_local_domain() {
+        if !zones.contains(&domain) {
+            zones.push(domain);
+        }
+    }
+    
+    zones.into_iter().find(|zone| utils::attempt_zone_transfer(ip, port, zone, constants::PORT_SCAN_TIMEOUT_MS))
+}
+
+/// Severity assigned to each misconfiguration
+fn misconfiguration_severity(id: &str) -> Severity {
+    match id {
+        "MISCONFIG-DNS-ZONE-TRANSFER" | "MISCONFIG-SNMP-DEFAULT-COMMUNITY" => Severity::High,
+        "MISCONFIG-SSL-OLD-PROTOCOL" | "MISCONFIG-SSH-PASSWORD-AUTH" | "MISCONFIG-IKE-WEAK-TRANSFORM" => Severity::Medium,
+        _ => Severity::Low,
+    }
+}
+
+/// Resolve a target specification to a list of IPs, minus any excluded hosts
+pub(crate) fn resolve_targets(config: &ScanConfig) -> Vec<IpAddr> {
+    target_stream(config).collect()
+}
+
+/// Work out which hosts, ports and active checks a scan of this configuration would cover
+///
+/// Hostnames in the targets are resolved, but nothing is sent to the targets themselves.
+pub fn plan_scan(config: &ScanConfig) -> ScanPlan {
+    let config = &prepare_targets(config);
+    let targets: Vec<String> = target_stream(config).map(|ip| ip.to_string()).collect();
+    let mut ports = ports_for_config(config);
+    ports.sort_unstable();
+    
+    let mut active_checks = Vec::new();
+    if config.is_passive() {
+        active_checks.push("None: hosts and services are read from captured traffic".to_string());
+    } else if config.enumerate_srv {
+        active_checks.push("SRV and MX lookups for domain targets (DNS queries only)".to_string());
+    }
+    if config.arp_discovery {
+        active_checks.push("ARP sweep of directly-connected subnets".to_string());
+    }
+    let discovery = if config.scan_offline_hosts { "Ping and TCP probes (unresponsive hosts are still scanned)" } else { "Ping and TCP probes to find live hosts" };
+    active_checks.push(discovery.to_string());
+    if config.adaptive_timeout {
+        active_checks.push("RTT measurement for adaptive timeouts".to_string());
+    }
+    if config.fast_filter {
+        active_checks.push("Short-timeout sweep of sentinel ports to find firewalled hosts".to_string());
+    }
+    active_checks.push("Banner grabs on open ports".to_string());
+    if !config.banner_only {
+        active_checks.push("HTTP requests to web services".to_string());
+        active_checks.push(if config.non_intrusive {
+            "SMB negotiation on ports 139/445 (no null session)".to_string()
+        } else {
+            "SMB negotiation on ports 139/445".to_string()
+        });
+        active_checks.push("SSH version exchange to read the key exchange offer".to_string());
+        if config.inspect_tls {
+            active_checks.push("TLS handshakes (certificate and protocol versions)".to_string());
+        }
+        if config.check_misconfigurations && config.non_intrusive {
+            active_checks.push("Misconfiguration checks from banners and HTTP headers only (--safe)".to_string());
+        } else if config.check_misconfigurations {
+            active_checks.push("Misconfiguration checks (DNS zone transfers, IKE proposals on 500/udp of responding hosts, HTTP OPTIONS requests, unauthenticated Redis, MongoDB and Elasticsearch queries)".to_string());
+        }
+        if config.check_default_credentials && !config.non_intrusive {
+            active_checks.push("Default credential logins (FTP, HTTP, SNMP communities on 161/udp of responding hosts, SSH)".to_string());
+        }
+        if !config.offline_mode {
+            active_checks.push("Online CVE and enrichment lookups (not sent to targets)".to_string());
+        }
+    }
+    
+    ScanPlan {
+        target_specs: target_specs(config),
+        exclude: config.exclude.clone(),
+        target_count: targets.len(),
+        targets,
+        ports,
+        active_checks,
+        threads: config.threads,
+        timeout_ms: config.timeout_ms,
+        max_pps: config.max_pps,
+    }
+}
+
+/// Copy of the configuration with service record targets added and every hostname target resolved
+///
+/// Later passes over the targets (the scope check, counting and the scan
+/// itself) reuse the addresses instead of looking the names up again.
+pub(crate) fn prepare_targets(config: &ScanConfig) -> ScanConfig {
+    let mut prepared = with_service_record_targets(config);
+    check_targets(&mut prepared);
+    prepared
+}
+
+/// Copy of the configuration with the hosts and ports behind SRV and MX records added
+///
+/// Only domain targets are enumerated, and only when `enumerate_srv` is set.
+/// Discovered hosts become extra targets and their ports are scanned on every
+/// host. This also applies the configured DNS servers.
+pub(crate) fn with_service_record_targets(config: &ScanConfig) -> ScanConfig {
+    resolver::set_dns_servers(&config.dns_servers);
+    let mut expanded = config.clone();
+    if !config.enumerate_srv {
+        return expanded;
+    }
+    
+    let specs = target_specs(config);
+    let mut hosts: Vec<String> = Vec::new();
+    let mut ports = ports_for_config(config);
+    
+    for domain in specs.iter().filter(|spec| resolver::is_domain_name(spec)) {
+        for service in constants::SRV_SERVICES {
+            match resolver::lookup_srv(domain, service) {
+                Ok(targets) => {
+                    for (host, port) in targets {
+                        info!("{}.{} SRV -> {}:{}", service, domain, host, port);
+                        hosts.push(host);
+                        ports.push(port);
+                    }
+                },
+                Err(e) => debug!("No {} SRV records for {}: {}", service, domain, e),
+            }
+        }
+        
+        match resolver::lookup_mx(domain) {
+            Ok(exchangers) => {
+                for host in exchangers {
+                    info!("{} MX -> {}", domain, host);
+                    hosts.push(host);
+                    ports.push(constants::MX_SMTP_PORT);
+                }
+            },
+            Err(e) => debug!("No MX records for {}: {}", domain, e),
+        }
+    }
+    
+    for host in hosts {
+        if !specs.contains(&host) && !expanded.input_targets.contains(&host) {
+            expanded.input_targets.push(host);
+        }
+    }
+    ports.sort_unstable();
+    ports.dedup();
+    expanded.ports = ports;
+    
+    expanded
+}
+
+/// Check that every target lies inside the allowed scope, when one is configured
+///
+/// Targets are resolved and exclusions applied first, so this sees exactly the
+/// hosts a scan would probe. The error names the offending addresses.
+pub fn check_scope(config: &ScanConfig) -> Result<(), String> {
+    if config.allowed_scope.is_empty() || config.is_passive() {
+        return Ok(());
+    }
+    
+    let scope = resolver::parse_scope(&config.allowed_scope)?;
+    resolver::set_dns_servers(&config.dns_servers);
+    let outside: Vec<IpAddr> = target_stream(config).filter(|ip| !scope.contains(ip)).collect();
+    if outside.is_empty() {
+        return Ok(());
+    }
+    
+    let mut listed = outside.iter()
+        .take(constants::SCOPE_VIOLATIONS_LISTED)
+        .map(|ip| ip.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    if outside.len() > constants::SCOPE_VIOLATIONS_LISTED {
+        listed.push_str(&format!(" and {} more", outside.len() - constants::SCOPE_VIOLATIONS_LISTED));
+    }
+    
+    Err(format!("{} target(s) outside the allowed scope ({}), refusing to scan: {}",
+        outside.len(), config.allowed_scope.join(", "), listed))
+}
+
+/// Find the target specifications that yield no addresses, and why
+///
+/// Hostnames are resolved to check them and the addresses are kept in
+/// `config.resolved_targets`, so scanning this configuration doesn't look them
+/// up again. Scanning skips these targets, so an empty scan can be told apart
+/// from one whose targets never responded.
+pub fn check_targets(config: &mut ScanConfig) -> Vec<resolver::TargetError> {
+    resolver::set_dns_servers(&config.dns_servers);
+    let mut errors = Vec::new();
+    
+    for spec in target_specs(config) {
+        match config.resolved_targets.get(&spec) {
+            Some(ips) if ips.is_empty() => errors.push(resolver::TargetError::Unresolvable(spec)),
+            Some(_) => {},
+            None => match resolver::TargetIterator::try_new(&spec) {
+                Ok(targets) => {
+                    if let Some(ips) = targets.resolved() {
+                        config.resolved_targets.insert(spec, ips);
+                    }
+                },
+                Err(e) => {
+                    // Remember failed lookups too; invalid CIDR blocks and ranges are cheap to re-check
+                    if let resolver::TargetError::Unresolvable(_) = e {
+                        config.resolved_targets.insert(spec, Vec::new());
+                    }
+                    errors.push(e);
+                },
+            },
+        }
+    }
+    
+    errors
+}
+
+/// Number of hosts the configuration will scan, after exclusions
+pub fn count_targets(config: &ScanConfig) -> usize {
+    target_stream(config).count()
+}
+
+/// Lazily yield every configured target, minus any excluded hosts
+///
+/// When targets come from several specifications (the positional target plus
+/// an input file), addresses that appear in more than one are yielded once.
+/// Hostnames already resolved by `check_targets` are not looked up again.
+pub(crate) fn target_stream(config: &ScanConfig) -> impl Iterator<Item = IpAddr> + Send {
+    let specs = target_specs(config);
+    
+    let exclude = exclusions_for_config(config);
+    let resolved = config.resolved_targets.clone();
+    let deduplicate = specs.len() > 1;
+    let mut seen = HashSet::new();
+    
+    specs.into_iter()
+        .flat_map(move |spec| match resolved.get(&spec) {
+            Some(ips) => resolver::TargetIterator::from_list(ips.clone()),
+            None => resolver::TargetIterator::new(&spec),
+        })
+        .filter(move |ip| !exclude.contains(ip))
+        .filter(move |ip| !deduplicate || seen.insert(*ip))
+}
+
+/// The positional target followed by any targets read from an input file
+fn target_specs(config: &ScanConfig) -> Vec<String> {
+    let mut specs: Vec<String> = Vec::new();
+    if !config.target.is_empty() {
+        specs.push(config.target.clone());
     }
+    specs.extend(config.input_targets.iter().cloned());
+    
+    specs
 }
 
-/// Resolve a target specification to a list of IPs
-fn resolve_targets(config: &ScanConfig) -> Vec<IpAddr> {
-    resolver::resolve_targets(&config.target)
+/// Parse the configured exclusions, ignoring entries that aren't valid addresses
+fn exclusions_for_config(config: &ScanConfig) -> resolver::ExcludeList {
+    let valid: Vec<String> = config.exclude.iter()
+        .filter(|spec| resolver::ExcludeList::parse(std::slice::from_ref(spec)).is_ok())
+        .cloned()
+        .collect();
+    
+    resolver::ExcludeList::parse(&valid).unwrap_or_default()
 }
 
 /// Scan a specific port range on a target
@@ -219,25 +1194,17 @@ pub fn scan_port_range(target: &str, start_port: u16, end_port: u16, config: &Sc
     
     // Randomize if requested
     if config.randomize_scan {
-        utils::randomize_ports(&mut ports);
+        utils::randomize_ports(&mut ports, &mut utils::scan_rng(config.random_seed, utils::host_rng_stream(&ip)));
     }
     
     // Scan ports in parallel
-    let open_ports = Arc::new(Mutex::new(Vec::new()));
-    
-    ports.par_iter().for_each(|port| {
-        if utils::is_port_open(&ip, *port, config.timeout_ms) {
-            let mut open_ports_guard = open_ports.lock().unwrap();
-            open_ports_guard.push(*port);
-        }
+    let mut result: Vec<u16> = run_in_pool(config, || {
+        ports.par_iter()
+            .filter(|port| utils::is_port_open(&ip, **port, config.timeout_ms))
+            .cloned()
+            .collect()
     });
     
-    // Return open ports
-    let mut result = Arc::try_unwrap(open_ports)
-        .unwrap()
-        .into_inner()
-        .unwrap();
-    
     // Sort for readability
     result.sort();
     
@@ -256,12 +1223,21 @@ pub fn quick_scan(target: &str, config: &ScanConfig) -> ScanResult {
                     return ScanResult {
                         host: target.to_string(),
                         hostname: target.to_string(),
+                        hostnames: Vec::new(),
                         is_online: false,
                         scan_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
                         open_ports: Vec::new(),
+                        closed_port_count: 0,
+                        filtered_port_count: 0,
                         os_info: None,
                         vulnerabilities_summary: None,
                         attack_paths: None,
+                        misconfigurations: Vec::new(),
+                        host_vulnerabilities: Vec::new(),
+                        mac: None,
+                        vendor: None,
+                        effective_timeout_ms: None,
+                        ike_info: None,
                     };
                 }
                 ips[0] // Use the first resolved IP
@@ -269,12 +1245,21 @@ pub fn quick_scan(target: &str, config: &ScanConfig) -> ScanResult {
                 return ScanResult {
                     host: target.to_string(),
                     hostname: target.to_string(),
+                    hostnames: Vec::new(),
                     is_online: false,
                     scan_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
                     open_ports: Vec::new(),
+                    closed_port_count: 0,
+                    filtered_port_count: 0,
                     os_info: None,
                     vulnerabilities_summary: None,
                     attack_paths: None,
+                    misconfigurations: Vec::new(),
+                    host_vulnerabilities: Vec::new(),
+                    mac: None,
+                    vendor: None,
+                    effective_timeout_ms: None,
+                    ike_info: None,
                 };
             }
         }
@@ -284,7 +1269,7 @@ pub fn quick_scan(target: &str, config: &ScanConfig) -> ScanResult {
     let mut config = config.clone();
     config.ports = constants::COMMON_PORTS.keys().cloned().collect();
     
-    scan_host(&ip, &config)
+    run_in_pool(&config, || scan_host(&ip, &config, &NoProgress, &AtomicBool::new(false)))
 }
 
 /// OT-specific scan focusing on industrial protocols
@@ -299,12 +1284,21 @@ pub fn ot_scan(target: &str, config: &ScanConfig) -> ScanResult {
                     return ScanResult {
                         host: target.to_string(),
                         hostname: target.to_string(),
+                        hostnames: Vec::new(),
                         is_online: false,
                         scan_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
                         open_ports: Vec::new(),
+                        closed_port_count: 0,
+                        filtered_port_count: 0,
                         os_info: None,
                         vulnerabilities_summary: None,
                         attack_paths: None,
+                        misconfigurations: Vec::new(),
+                        host_vulnerabilities: Vec::new(),
+                        mac: None,
+                        vendor: None,
+                        effective_timeout_ms: None,
+                        ike_info: None,
                     };
                 }
                 ips[0] // Use the first resolved IP
@@ -312,12 +1306,21 @@ pub fn ot_scan(target: &str, config: &ScanConfig) -> ScanResult {
                 return ScanResult {
                     host: target.to_string(),
                     hostname: target.to_string(),
+                    hostnames: Vec::new(),
                     is_online: false,
                     scan_time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
                     open_ports: Vec::new(),
+                    closed_port_count: 0,
+                    filtered_port_count: 0,
                     os_info: None,
                     vulnerabilities_summary: None,
                     attack_paths: None,
+                    misconfigurations: Vec::new(),
+                    host_vulnerabilities: Vec::new(),
+                    mac: None,
+                    vendor: None,
+                    effective_timeout_ms: None,
+                    ike_info: None,
                 };
             }
         }
@@ -333,40 +1336,44 @@ pub fn ot_scan(target: &str, config: &ScanConfig) -> ScanResult {
     let mut ot_config = config.clone();
     ot_config.ports = ot_ports;
     
-    scan_host(&ip, &ot_config)
+    run_in_pool(&ot_config, || scan_host(&ip, &ot_config, &NoProgress, &AtomicBool::new(false)))
 }
 
 /// Check a specific vulnerability on a host
 pub fn check_vulnerability(target: &str, port: u16, vuln_id: &str, config: &ScanConfig) -> Option<Vulnerability> {
+    check_vulnerabilities(target, port, &[vuln_id], config).into_iter().next()
+}
+
+/// Check a list of vulnerabilities on a host in one pass
+///
+/// The banner is grabbed and matched once; every detected vulnerability whose
+/// ID is in `vuln_ids` is returned. IDs that don't match are simply absent.
+pub fn check_vulnerabilities(target: &str, port: u16, vuln_ids: &[&str], config: &ScanConfig) -> Vec<Vulnerability> {
     // Parse target as IP
     let ip = match target.parse::<IpAddr>() {
         Ok(ip) => ip,
         Err(_) => {
             // Try to resolve hostname
-            if let Ok(ips) = resolver::resolve_hostname(target) {
-                if ips.is_empty() {
-                    return None;
-                }
-                ips[0] // Use the first resolved IP
-            } else {
-                return None;
+            match resolver::resolve_hostname(target) {
+                Ok(ips) if !ips.is_empty() => ips[0], // Use the first resolved IP
+                _ => return Vec::new(),
             }
         }
     };
     
     // Check if port is open
     if !utils::is_port_open(&ip, port, config.timeout_ms) {
-        return None;
+        return Vec::new();
     }
     
     // Get banner
     let banner = match utils::get_service_banner(&ip, port, config.timeout_ms) {
         Some(banner) => banner,
-        None => return None,
+        None => return Vec::new(),
     };
     
     // Identify service
-    let service = utils::identify_service(port, &banner);
+    let service = utils::identify_service_name(port, &banner);
     
     // Check vulnerabilities
     let vulnerabilities = cveapi::check_service_vulnerabilities(
@@ -375,40 +1382,42 @@ pub fn check_vulnerability(target: &str, port: u16, vuln_id: &str, config: &Scan
         !config.offline_mode
     );
     
-    // Find the requested vulnerability
-    vulnerabilities.into_iter().find(|v| v.id == vuln_id)
+    // Keep only the requested vulnerabilities
+    vulnerabilities.into_iter()
+        .filter(|v| vuln_ids.contains(&v.id.as_str()))
+        .collect()
 }
 
 /// Get available hosts in a network
+///
+/// With `arp_discovery` set, a directly-connected target subnet is swept with
+/// ARP; otherwise, or if the sweep can't run, hosts are pinged.
 pub fn discover_hosts(target: &str, config: &ScanConfig) -> Vec<HostInfo> {
+    if config.arp_discovery {
+        match run_in_pool(config, || utils::discover_hosts_arp(None, target)) {
+            Ok(hosts) => return hosts,
+            Err(e) => warn!("{}; falling back to ping", e),
+        }
+    }
+    
     let targets = resolver::resolve_targets(target);
-    let host_infos = Arc::new(Mutex::new(Vec::new()));
     
-    targets.par_iter().for_each(|ip| {
-        let is_online = utils::ping_host(ip) || utils::tcp_ping_host(ip, config.timeout_ms);
-        
-        if is_online {
-            let hostname = resolver::resolve_hostname_comprehensive(ip);
-            
-            let host_info = HostInfo {
+    run_in_pool(config, || {
+        targets.par_iter()
+            .filter(|ip| utils::host_responds(ip, config.timeout_ms))
+            .map(|ip| HostInfo {
                 ip: ip.to_string(),
-                hostname,
-                is_online,
-            };
-            
-            let mut host_infos_guard = host_infos.lock().unwrap();
-            host_infos_guard.push(host_info);
-        }
-    });
-    
-    Arc::try_unwrap(host_infos)
-        .unwrap()
-        .into_inner()
-        .unwrap()
+                hostname: resolver::resolve_hostname_comprehensive(ip),
+                is_online: true,
+                mac: None,
+                vendor: None,
+            })
+            .collect()
+    })
 }
 
 /// Generate a summary of vulnerabilities from scan results
-fn generate_vulnerability_summary(ports: &[PortResult]) -> crate::models::VulnerabilitySummary {
+pub fn generate_vulnerability_summary(ports: &[PortResult], host_vulnerabilities: &[Vulnerability]) -> crate::models::VulnerabilitySummary {
     use std::collections::HashMap;
     
     // Initialize counters
@@ -419,6 +1428,7 @@ fn generate_vulnerability_summary(ports: &[PortResult]) -> crate::models::Vulner
     let mut info_count = 0;
     let mut actively_exploited_count = 0;
     let mut exploit_available_count = 0;
+    let mut weighted_total = 0.0;
     
     // Initialize category and vector maps
     let mut categories: HashMap<String, usize> = HashMap::new();
@@ -428,62 +1438,54 @@ fn generate_vulnerability_summary(ports: &[PortResult]) -> crate::models::Vulner
     // Recommendations to return based on findings
     let mut recommendations = Vec::new();
     
-    // Analyze all vulnerabilities across all ports
-    for port in ports {
-        for vuln in &port.vulnerabilities {
-            // Count by severity
-            if let Some(severity) = &vuln.severity {
-                match severity.to_uppercase().as_str() {
-                    "CRITICAL" => critical_count += 1,
-                    "HIGH" => high_count += 1,
-                    "MEDIUM" => medium_count += 1,
-                    "LOW" => low_count += 1,
-                    _ => info_count += 1,
-                }
-            } else if let Some(score) = vuln.cvss_score {
-                // Categorize by CVSS score if no explicit severity
-                if score >= 9.0 { critical_count += 1; }
-                else if score >= 7.0 { high_count += 1; }
-                else if score >= 4.0 { medium_count += 1; }
-                else if score >= 0.1 { low_count += 1; }
-                else { info_count += 1; }
-            } else {
-                // No severity or score means we treat it as informational
-                info_count += 1;
-            }
-            
-            // Count actively exploited vulnerabilities
-            if vuln.actively_exploited.unwrap_or(false) {
-                actively_exploited_count += 1;
-            }
-            
-            // Count vulnerabilities with available exploits
-            if vuln.exploit_available.unwrap_or(false) {
-                exploit_available_count += 1;
-            }
-            
-            // Count by category
-            if let Some(category) = &vuln.category {
-                *categories.entry(category.clone()).or_insert(0) += 1;
-            }
-            
-            // Count by attack vector
-            if let Some(vector) = &vuln.attack_vector {
-                *attack_vectors.entry(vector.clone()).or_insert(0) += 1;
-            }
-            
-            // Count by MITRE ATT&CK tactics
-            if let Some(tactics) = &vuln.mitre_tactics {
-                for tactic in tactics {
-                    *mitre_tactics.entry(tactic.clone()).or_insert(0) += 1;
-                }
+    // Analyze all vulnerabilities across all ports and the host itself
+    for vuln in ports.iter().flat_map(|p| &p.vulnerabilities).chain(host_vulnerabilities) {
+        // Count by severity, falling back to the CVSS score when no severity is set
+        let severity_weight = match vuln.severity_level() {
+            Severity::Critical => { critical_count += 1; 10.0 },
+            Severity::High => { high_count += 1; 7.0 },
+            Severity::Medium => { medium_count += 1; 4.0 },
+            Severity::Low => { low_count += 1; 1.0 },
+            Severity::Info => { info_count += 1; 0.0 },
+        };
+        
+        // Blend in the EPSS probability so likely-exploited findings outrank unlikely ones
+        weighted_total += match vuln.epss_score {
+            Some(epss) => severity_weight * (1.0 - constants::EPSS_RISK_WEIGHT) + epss * 10.0 * constants::EPSS_RISK_WEIGHT,
+            None => severity_weight,
+        };
+        
+        // Count actively exploited vulnerabilities
+        if vuln.actively_exploited.unwrap_or(false) {
+            actively_exploited_count += 1;
+        }
+        
+        // Count vulnerabilities with available exploits
+        if vuln.exploit_available.unwrap_or(false) {
+            exploit_available_count += 1;
+        }
+        
+        // Count by category
+        if let Some(category) = &vuln.category {
+            *categories.entry(category.clone()).or_insert(0) += 1;
+        }
+        
+        // Count by attack vector
+        if let Some(vector) = &vuln.attack_vector {
+            *attack_vectors.entry(vector.clone()).or_insert(0) += 1;
+        }
+        
+        // Count by MITRE ATT&CK tactics
+        if let Some(tactics) = &vuln.mitre_tactics {
+            for tactic in tactics {
+                *mitre_tactics.entry(tactic.clone()).or_insert(0) += 1;
             }
-            
-            // Collect mitigation recommendations if available
-            if let Some(mitigation) = &vuln.mitigation {
-                if !recommendations.contains(mitigation) {
-                    recommendations.push(mitigation.clone());
-                }
+        }
+        
+        // Collect mitigation recommendations if available
+        if let Some(mitigation) = &vuln.mitigation {
+            if !recommendations.contains(mitigation) {
+                recommendations.push(mitigation.clone());
             }
         }
     }
@@ -515,7 +1517,7 @@ fn generate_vulnerability_summary(ports: &[PortResult]) -> crate::models::Vulner
     // Calculate a basic risk score (0-10)
     let total_count = critical_count + high_count + medium_count + low_count + info_count;
     let weighted_score = if total_count > 0 {
-        (critical_count as f32 * 10.0 + high_count as f32 * 7.0 + medium_count as f32 * 4.0 + low_count as f32 * 1.0) / total_count as f32
+        weighted_total / total_count as f32
     } else {
         0.0
     };
@@ -544,3 +1546,307 @@ fn generate_vulnerability_summary(ports: &[PortResult]) -> crate::models::Vulner
         mitre_tactics,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, TcpListener};
+    use std::sync::Mutex;
+    use std::thread;
+    
+    /// Accept connections on every loopback address, returning the port
+    fn spawn_open_port() -> u16 {
+        let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                drop(stream);
+            }
+        });
+        port
+    }
+    
+    /// Scan `target` for one port without any lookups or vulnerability analysis
+    fn loopback_config(target: &str, port: u16) -> ScanConfig {
+        ScanConfig {
+            target: target.to_string(),
+            ports: vec![port],
+            threads: 1,
+            timeout_ms: 200,
+            banner_only: true,
+            offline_mode: true,
+            scan_offline_hosts: true,
+            ..ScanConfig::default()
+        }
+    }
+    
+    fn scanned_hosts(results: &[ScanResult]) -> Vec<&str> {
+        let mut hosts: Vec<&str> = results.iter().map(|result| result.host.as_str()).collect();
+        hosts.sort_unstable();
+        hosts
+    }
+    
+    /// Cancels the scan as soon as the first host completes
+    struct CancelAfterFirstHost {
+        cancel: Arc<AtomicBool>,
+        started: AtomicUsize,
+    }
+    
+    impl ScanProgress for CancelAfterFirstHost {
+        fn on_host_started(&self, _ip: &IpAddr) {
+            self.started.fetch_add(1, Ordering::SeqCst);
+        }
+        
+        fn on_host_completed(&self, _result: &ScanResult) {
+            self.cancel.store(true, Ordering::SeqCst);
+        }
+    }
+    
+    #[test]
+    fn cancelling_after_the_first_host_truncates_the_results() {
+        let port = spawn_open_port();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let progress = CancelAfterFirstHost { cancel: cancel.clone(), started: AtomicUsize::new(0) };
+        
+        let results = run_scan(loopback_config("127.0.0.1-127.0.0.4", port), &progress, &cancel).unwrap();
+        
+        assert_eq!(progress.started.load(Ordering::SeqCst), 1);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].open_ports[0].port, port);
+    }
+    
+    /// Panics while one host is being scanned, as a bug in a detector would
+    struct PanicOnHost {
+        host: IpAddr,
+        completed: Mutex<Vec<String>>,
+    }
+    
+    impl ScanProgress for PanicOnHost {
+        fn on_port_open(&self, ip: &IpAddr, _port: u16) {
+            if *ip == self.host {
+                panic!("simulated worker failure on {}", ip);
+            }
+        }
+        
+        fn on_host_completed(&self, result: &ScanResult) {
+            self.completed.lock().unwrap().push(result.host.clone());
+        }
+    }
+    
+    #[test]
+    fn a_panic_scanning_one_host_keeps_the_other_results() {
+        let port = spawn_open_port();
+        let progress = PanicOnHost {
+            host: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)),
+            completed: Mutex::new(Vec::new()),
+        };
+        
+        let results = run_scan(loopback_config("127.0.0.1-127.0.0.3", port), &progress, &Arc::new(AtomicBool::new(false))).unwrap();
+        
+        assert_eq!(scanned_hosts(&results), vec!["127.0.0.1", "127.0.0.3"]);
+        assert!(results.iter().all(|result| result.open_ports.len() == 1));
+        
+        // The failed host is still reported as completed, without results
+        assert_eq!(progress.completed.lock().unwrap().len(), 3);
+    }
+    
+    #[test]
+    fn in_scope_targets_pass_the_scope_check() {
+        let config = ScanConfig {
+            target: "10.1.2.0/28".to_string(),
+            input_targets: vec!["192.168.5.7".to_string()],
+            allowed_scope: vec!["10.0.0.0/8".to_string(), "192.168.5.0/24".to_string()],
+            ..ScanConfig::default()
+        };
+        
+        assert_eq!(check_scope(&config), Ok(()));
+    }
+    
+    /// Counts the hosts the scan starts on
+    #[derive(Default)]
+    struct HostCounter {
+        started: AtomicUsize,
+    }
+    
+    impl ScanProgress for HostCounter {
+        fn on_host_started(&self, _ip: &IpAddr) {
+            self.started.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    
+    #[test]
+    fn out_of_scope_targets_abort_before_scanning() {
+        let port = spawn_open_port();
+        let config = ScanConfig {
+            allowed_scope: vec!["127.0.0.1".to_string()],
+            ..loopback_config("127.0.0.1-127.0.0.2", port)
+        };
+        
+        let error = check_scope(&config).unwrap_err();
+        assert!(error.contains("1 target(s) outside the allowed scope"), "{}", error);
+        assert!(error.ends_with("127.0.0.2"), "{}", error);
+        
+        let progress = HostCounter::default();
+        let error = run_scan(config, &progress, &Arc::new(AtomicBool::new(false))).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(error.to_string().contains("outside the allowed scope"), "{}", error);
+        assert_eq!(progress.started.load(Ordering::SeqCst), 0);
+    }
+    
+    #[test]
+    fn check_targets_keeps_the_addresses_for_the_scan() {
+        let mut config = ScanConfig {
+            target: "10.9.8.7".to_string(),
+            input_targets: vec!["nonexistent.invalid".to_string(), "10.0.0.0/30".to_string()],
+            ..ScanConfig::default()
+        };
+        
+        let errors = check_targets(&mut config);
+        assert_eq!(errors, vec![resolver::TargetError::Unresolvable("nonexistent.invalid".to_string())]);
+        assert_eq!(config.resolved_targets.get("10.9.8.7"), Some(&vec!["10.9.8.7".parse::<IpAddr>().unwrap()]));
+        assert_eq!(config.resolved_targets.get("nonexistent.invalid"), Some(&Vec::new()));
+        assert!(!config.resolved_targets.contains_key("10.0.0.0/30"));
+        
+        // A second check reuses the failed lookup and still reports it
+        assert_eq!(check_targets(&mut config), errors);
+    }
+    
+    #[test]
+    fn resolved_hostnames_are_not_looked_up_again() {
+        let resolved: IpAddr = "192.0.2.10".parse().unwrap();
+        let mut config = ScanConfig {
+            target: "nonexistent.invalid".to_string(),
+            ..ScanConfig::default()
+        };
+        config.resolved_targets.insert(config.target.clone(), vec![resolved]);
+        
+        assert!(check_targets(&mut config).is_empty());
+        assert_eq!(resolve_targets(&config), vec![resolved]);
+        assert_eq!(plan_scan(&config).targets, vec!["192.0.2.10"]);
+    }
+    
+    /// FTP server that accepts only `accepted_user`, counting the connections and USER commands it receives
+    fn spawn_ftp_spy(accepted_user: &'static str) -> (u16, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        use std::io::{BufRead, BufReader, Write};
+        
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let logins = Arc::new(AtomicUsize::new(0));
+        let (connection_count, login_count) = (connections.clone(), logins.clone());
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                connection_count.fetch_add(1, Ordering::SeqCst);
+                let _ = stream.write_all(b"220 spy ready\r\n");
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                let mut user = String::new();
+                while reader.read_line(&mut line).map(|read| read > 0).unwrap_or(false) {
+                    let reply: &[u8] = if let Some(name) = line.strip_prefix("USER ") {
+                        login_count.fetch_add(1, Ordering::SeqCst);
+                        user = name.trim().to_string();
+                        b"331 password required\r\n"
+                    } else if line.starts_with("PASS") && user == accepted_user {
+                        b"230 logged in\r\n"
+                    } else if line.starts_with("PASS") {
+                        b"530 login incorrect\r\n"
+                    } else {
+                        break;
+                    };
+                    let _ = stream.write_all(reply);
+                    line.clear();
+                }
+            }
+        });
+        (port, connections, logins)
+    }
+    
+    #[test]
+    fn safe_mode_makes_no_login_attempts() {
+        let (port, connections, logins) = spawn_ftp_spy("anonymous");
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let config = ScanConfig {
+            check_default_credentials: true,
+            non_intrusive: true,
+            timeout_ms: 500,
+            ..ScanConfig::default()
+        };
+        
+        assert!(check_default_credentials(&ip, port, "ftp", &config).is_empty());
+        assert_eq!(connections.load(Ordering::SeqCst), 0);
+        assert_eq!(logins.load(Ordering::SeqCst), 0);
+        
+        // Nor may the HTTP authentication plugin run
+        let registry = PluginRegistry::with_config(&config);
+        assert!(registry.get_enabled_plugins().iter().all(|plugin| !plugin.is_intrusive()));
+        
+        // Without --safe the same check logs in, so the spy does see attempts
+        let intrusive = ScanConfig { non_intrusive: false, ..config };
+        assert_eq!(check_default_credentials(&ip, port, "ftp", &intrusive).len(), 1);
+        assert!(logins.load(Ordering::SeqCst) > 0);
+    }
+    
+    /// Tracks how many open-port hooks, which run inside the port probes, overlap
+    #[derive(Default)]
+    struct ConcurrencyGauge {
+        current: AtomicUsize,
+        max: AtomicUsize,
+    }
+    
+    impl ScanProgress for ConcurrencyGauge {
+        fn on_port_open(&self, _ip: &IpAddr, _port: u16) {
+            let running = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(running, Ordering::SeqCst);
+            thread::sleep(std::time::Duration::from_millis(20));
+            self.current.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+    
+    fn max_concurrent_probes(threads: usize) -> usize {
+        let ports: Vec<u16> = (0..3).map(|_| spawn_open_port()).collect();
+        let config = ScanConfig {
+            ports,
+            threads,
+            ..loopback_config("127.0.0.1-127.0.0.4", 0)
+        };
+        let gauge = ConcurrencyGauge::default();
+        
+        let results = run_scan(config, &gauge, &Arc::new(AtomicBool::new(false))).unwrap();
+        
+        assert_eq!(results.len(), 4);
+        gauge.max.load(Ordering::SeqCst)
+    }
+    
+    #[test]
+    fn one_thread_runs_one_probe_at_a_time() {
+        assert_eq!(max_concurrent_probes(1), 1);
+        
+        // The gauge does see overlap once more threads are allowed
+        assert!(max_concurrent_probes(4) > 1);
+    }
+    
+    #[test]
+    fn ftp_servers_accepting_a_default_login_are_reported() {
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let config = ScanConfig {
+            check_default_credentials: true,
+            timeout_ms: 500,
+            ..ScanConfig::default()
+        };
+        
+        let (port, _, logins) = spawn_ftp_spy("anonymous");
+        let findings = check_default_credentials(&ip, port, "ftp", &config);
+        
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].id, "DEFAULT-CREDS-FTP");
+        assert_eq!(findings[0].severity_level(), Severity::Critical);
+        assert_eq!(findings[0].description, format!("FTP service at ftp://127.0.0.1:{} accepts default credentials for user 'anonymous'", port));
+        // The first credential worked, so no others were tried
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+        
+        // A server that refuses every default login yields nothing, after each was tried once
+        let (port, _, logins) = spawn_ftp_spy("operator");
+        assert!(check_default_credentials(&ip, port, "ftp", &config).is_empty());
+        assert_eq!(logins.load(Ordering::SeqCst), credentials::credentials_for_service("ftp").len());
+    }
+}
@@ -0,0 +1,159 @@
+// Author: CyberCraft Alchemist
+// Scan scheduling helpers for restricting scans to an allowed time window
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use chrono::{DateTime, FixedOffset, Local, Timelike, Utc};
+
+use crate::models::{ScanWindow, WindowTimezone};
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Parse a window like "22:00-06:00" together with a timezone ("local", "UTC" or "+02:00")
+pub fn parse_window(window_spec: &str, timezone: &str) -> Result<ScanWindow, String> {
+    let (start, end) = window_spec.split_once('-')
+        .ok_or_else(|| format!("Invalid scan window: {} (expected HH:MM-HH:MM)", window_spec))?;
+
+    let start_minute = parse_time_of_day(start)?;
+    let end_minute = parse_time_of_day(end)?;
+
+    if start_minute == end_minute {
+        return Err(format!("Invalid scan window: {} (start and end are equal)", window_spec));
+    }
+
+    Ok(ScanWindow {
+        start_minute,
+        end_minute,
+        timezone: parse_timezone(timezone)?,
+    })
+}
+
+/// Parse a time of day in HH:MM format into minutes after midnight
+fn parse_time_of_day(time: &str) -> Result<u32, String> {
+    let (hours, minutes) = time.trim().split_once(':')
+        .ok_or_else(|| format!("Invalid time: {} (expected HH:MM)", time))?;
+
+    let hours = hours.parse::<u32>().map_err(|_| format!("Invalid hour: {}", hours))?;
+    let minutes = minutes.parse::<u32>().map_err(|_| format!("Invalid minute: {}", minutes))?;
+
+    if hours > 23 || minutes > 59 {
+        return Err(format!("Invalid time: {}", time));
+    }
+
+    Ok(hours * 60 + minutes)
+}
+
+/// Parse a timezone specification: the local timezone or a fixed offset from UTC
+fn parse_timezone(timezone: &str) -> Result<WindowTimezone, String> {
+    match timezone.to_uppercase().as_str() {
+        "LOCAL" => Ok(WindowTimezone::Local),
+        "UTC" | "Z" | "GMT" => Ok(WindowTimezone::Fixed(0)),
+        spec => {
+            let (sign, rest) = match spec.chars().next() {
+                Some('+') => (1, &spec[1..]),
+                Some('-') => (-1, &spec[1..]),
+                _ => return Err(format!("Invalid timezone: {} (use local, UTC or +HH:MM)", timezone)),
+            };
+
+            let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+            let hours = hours.parse::<i32>().map_err(|_| format!("Invalid timezone: {}", timezone))?;
+            let minutes = minutes.parse::<i32>().map_err(|_| format!("Invalid timezone: {}", timezone))?;
+
+            if hours > 14 || minutes > 59 {
+                return Err(format!("Invalid timezone: {}", timezone));
+            }
+
+            Ok(WindowTimezone::Fixed(sign * (hours * 3600 + minutes * 60)))
+        }
+    }
+}
+
+/// Check whether the given instant falls inside the scan window
+pub fn is_within_window(window: &ScanWindow, now: DateTime<Utc>) -> bool {
+    time_until_open(window, now).is_zero()
+}
+
+/// Time remaining until the scan window next opens (zero if it is open now)
+pub fn time_until_open(window: &ScanWindow, now: DateTime<Utc>) -> Duration {
+    // The local offset is looked up for `now`, so daylight saving changes move the window with the clock
+    let local = match window.timezone {
+        WindowTimezone::Local => now.with_timezone(&Local).naive_local(),
+        WindowTimezone::Fixed(offset_seconds) => {
+            let offset = FixedOffset::east_opt(offset_seconds)
+                .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+            now.with_timezone(&offset).naive_local()
+        }
+    };
+    let minute_of_day = local.hour() * 60 + local.minute();
+
+    // Windows where start > end wrap around midnight
+    let is_open = if window.start_minute < window.end_minute {
+        minute_of_day >= window.start_minute && minute_of_day < window.end_minute
+    } else {
+        minute_of_day >= window.start_minute || minute_of_day < window.end_minute
+    };
+
+    if is_open {
+        return Duration::from_secs(0);
+    }
+
+    let minutes_until_start = (window.start_minute + MINUTES_PER_DAY - minute_of_day) % MINUTES_PER_DAY;
+    Duration::from_secs(u64::from(minutes_until_start) * 60 - u64::from(local.second()))
+}
+
+/// Block the calling thread until the scan window is open
+pub fn wait_for_window(window: &ScanWindow) {
+    loop {
+        let remaining = time_until_open(window, Utc::now());
+        if remaining.is_zero() {
+            return;
+        }
+
+        // Re-check periodically so clock changes are picked up
+        thread::sleep(remaining.min(Duration::from_secs(60)));
+    }
+}
+
+/// Block until the scan window is open or the scan is cancelled
+///
+/// Returns false if the wait ended because of cancellation.
+pub fn wait_for_window_cancellable(window: &ScanWindow, cancel: &AtomicBool) -> bool {
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return false;
+        }
+        
+        let remaining = time_until_open(window, Utc::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        
+        // Sleep in short steps so cancellation is noticed promptly
+        thread::sleep(remaining.min(Duration::from_secs(1)));
+    }
+}
+
+/// Format a scan window for display
+pub fn format_window(window: &ScanWindow) -> String {
+    let timezone = match window.timezone {
+        WindowTimezone::Local => "local time".to_string(),
+        WindowTimezone::Fixed(offset_seconds) => {
+            let offset_minutes = offset_seconds / 60;
+            format!(
+                "UTC{}{:02}:{:02}",
+                if offset_minutes < 0 { '-' } else { '+' },
+                offset_minutes.abs() / 60,
+                offset_minutes.abs() % 60
+            )
+        }
+    };
+    format!(
+        "{:02}:{:02}-{:02}:{:02} ({})",
+        window.start_minute / 60,
+        window.start_minute % 60,
+        window.end_minute / 60,
+        window.end_minute % 60,
+        timezone
+    )
+}
@@ -0,0 +1,340 @@
+// Author: CyberCraft Alchemist
+// Interactive terminal dashboard showing a scan's progress as it runs
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Stdout};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Gauge, List, ListItem, Paragraph, Row, Table};
+use ratatui::{Frame, Terminal};
+
+use crate::models::{ScanResult, Severity};
+use crate::progress::ScanProgress;
+
+// How often the dashboard redraws and checks for key presses
+const REFRESH_INTERVAL_MS: u64 = 100;
+
+// Lines kept in the log pane
+const MAX_LOG_LINES: usize = 200;
+
+// Scan events forwarded from the scan threads to the dashboard thread
+#[derive(Debug, Clone)]
+pub enum DashboardEvent {
+    HostStarted(IpAddr),
+    PortOpen(IpAddr, u16),
+    HostCompleted(Box<ScanResult>),
+    Log(String),
+}
+
+// A row of the host table
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostRow {
+    pub ip: IpAddr,
+    pub hostname: String,
+    pub open_ports: Vec<u16>,
+    pub vulnerabilities: usize,
+    pub highest_severity: Option<Severity>,
+    pub completed: bool,
+}
+
+// Everything the dashboard displays, built up from scan events
+#[derive(Debug, Clone, Default)]
+pub struct DashboardState {
+    pub hosts_total: usize,
+    pub hosts_started: usize,
+    pub hosts_completed: usize,
+    pub open_ports: usize,
+    pub severity_counts: HashMap<Severity, usize>,
+    pub hosts: Vec<HostRow>,              // Hosts being scanned, and finished hosts with open ports
+    pub log: VecDeque<String>,            // Most recent log lines, oldest first
+    pub cancelling: bool,
+}
+
+impl DashboardState {
+    pub fn new(hosts_total: usize) -> Self {
+        DashboardState {
+            hosts_total,
+            ..Default::default()
+        }
+    }
+    
+    /// Update the totals, host table and log for one event
+    pub fn apply(&mut self, event: DashboardEvent) {
+        match event {
+            DashboardEvent::HostStarted(ip) => {
+                self.hosts_started += 1;
+                self.hosts.push(HostRow {
+                    ip,
+                    hostname: String::new(),
+                    open_ports: Vec::new(),
+                    vulnerabilities: 0,
+                    highest_severity: None,
+                    completed: false,
+                });
+            },
+            DashboardEvent::PortOpen(ip, port) => {
+                self.open_ports += 1;
+                if let Some(row) = self.hosts.iter_mut().find(|row| row.ip == ip) {
+                    row.open_ports.push(port);
+                    row.open_ports.sort_unstable();
+                }
+                self.push_log(format!("{} open", SocketAddr::new(ip, port)));
+            },
+            DashboardEvent::HostCompleted(result) => {
+                self.hosts_completed += 1;
+    
+                let severities: Vec<Severity> = result.open_ports.iter()
+                    .flat_map(|p| &p.vulnerabilities)
+                    .chain(&result.host_vulnerabilities)
+                    .map(|v| v.severity_level())
+                    .collect();
+                for severity in &severities {
+                    *self.severity_counts.entry(*severity).or_insert(0) += 1;
+                }
+    
+                // Hosts without open ports or UDP findings aren't reported, so they leave the table
+                let ip = result.host.parse::<IpAddr>().ok();
+                let position = self.hosts.iter().position(|row| Some(row.ip) == ip);
+                if !result.is_reportable() {
+                    if let Some(position) = position {
+                        self.hosts.remove(position);
+                    }
+                    return;
+                }
+    
+                if let Some(row) = position.map(|position| &mut self.hosts[position]) {
+                    row.hostname = result.hostname.clone();
+                    row.open_ports = result.open_ports.iter().map(|p| p.port).collect();
+                    row.vulnerabilities = severities.len();
+                    row.highest_severity = severities.iter().max().copied();
+                    row.completed = true;
+                }
+                self.push_log(format!("{} done: {} open ports, {} vulnerabilities",
+                    result.host, result.open_ports.len(), severities.len()));
+            },
+            DashboardEvent::Log(line) => self.push_log(line),
+        }
+    }
+    
+    /// Number of findings at `severity` so far
+    pub fn severity_count(&self, severity: Severity) -> usize {
+        self.severity_counts.get(&severity).copied().unwrap_or(0)
+    }
+    
+    fn push_log(&mut self, line: String) {
+        if self.log.len() == MAX_LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(line);
+    }
+}
+
+/// Forwards scan events to the dashboard thread, then to `inner`
+///
+/// Events go over an unbounded channel, so scan threads never wait on drawing.
+/// Dropping the progress reporter closes the channel and stops the dashboard.
+pub struct TuiProgress<P: ScanProgress> {
+    sender: Mutex<Sender<DashboardEvent>>,
+    inner: P,
+}
+
+impl<P: ScanProgress> TuiProgress<P> {
+    fn send(&self, event: DashboardEvent) {
+        // The dashboard may already have exited, e.g. if drawing failed
+        let _ = self.sender.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).send(event);
+    }
+}
+
+impl<P: ScanProgress> ScanProgress for TuiProgress<P> {
+    fn on_host_started(&self, ip: &IpAddr) {
+        self.send(DashboardEvent::HostStarted(*ip));
+        self.inner.on_host_started(ip);
+    }
+    
+    fn on_host_completed(&self, result: &ScanResult) {
+        self.send(DashboardEvent::HostCompleted(Box::new(result.clone())));
+        self.inner.on_host_completed(result);
+    }
+    
+    fn on_port_open(&self, ip: &IpAddr, port: u16) {
+        self.send(DashboardEvent::PortOpen(*ip, port));
+        self.inner.on_port_open(ip, port);
+    }
+}
+
+/// Take over the terminal and run the dashboard on its own thread
+///
+/// Pressing `q`, Esc or Ctrl-C sets `cancel`. The dashboard keeps drawing
+/// until the returned progress reporter is dropped, then restores the
+/// terminal; join the handle before printing anything else.
+pub fn start_dashboard<P: ScanProgress>(hosts_total: usize, cancel: Arc<AtomicBool>, inner: P)
+    -> io::Result<(TuiProgress<P>, JoinHandle<io::Result<()>>)>
+{
+    let guard = TerminalGuard::enter()?;
+    let terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let (sender, receiver) = mpsc::channel();
+    
+    let handle = thread::Builder::new()
+        .name("dashboard".to_string())
+        .spawn(move || {
+            let result = run_dashboard(terminal, receiver, DashboardState::new(hosts_total), &cancel);
+            drop(guard);
+            result
+        })?;
+    
+    Ok((TuiProgress { sender: Mutex::new(sender), inner }, handle))
+}
+
+/// Redraw until the event channel closes, handling key presses in between
+fn run_dashboard(
+    mut terminal: Terminal<CrosstermBackend<Stdout>>,
+    receiver: Receiver<DashboardEvent>,
+    mut state: DashboardState,
+    cancel: &AtomicBool
+) -> io::Result<()> {
+    loop {
+        // Apply every queued event before drawing
+        loop {
+            match receiver.try_recv() {
+                Ok(event) => state.apply(event),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+    
+        terminal.draw(|frame| draw(frame, &state))?;
+    
+        if event::poll(Duration::from_millis(REFRESH_INTERVAL_MS))? {
+            if let Event::Key(key) = event::read()? {
+                let quit = matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                    || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL));
+                if key.kind == KeyEventKind::Press && quit && !state.cancelling {
+                    cancel.store(true, Ordering::SeqCst);
+                    state.cancelling = true;
+                    state.apply(DashboardEvent::Log("Stopping scan, waiting for in-flight connections to time out...".to_string()));
+                }
+            }
+        }
+    }
+}
+
+/// Lay out the totals, host table and log panes
+fn draw(frame: &mut Frame, state: &DashboardState) {
+    let panes = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(5), Constraint::Min(5), Constraint::Length(8)])
+        .split(frame.size());
+    let top = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Length(1)])
+        .margin(1)
+        .split(panes[0]);
+    
+    let title = if state.cancelling { " RustNet Scan (stopping) " } else { " RustNet Scan (q to stop) " };
+    frame.render_widget(Block::default().borders(Borders::ALL).title(title), panes[0]);
+    
+    let totals = vec![
+        Line::from(vec![
+            Span::styled("Hosts: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!("{}/{} done, {} scanning   ", state.hosts_completed, state.hosts_total,
+                state.hosts_started.saturating_sub(state.hosts_completed))),
+            Span::styled("Open ports: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(state.open_ports.to_string()),
+        ]),
+        Line::from(vec![
+            Span::styled("Vulnerabilities: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(format!("{} critical ", state.severity_count(Severity::Critical)), severity_style(Severity::Critical)),
+            Span::styled(format!("{} high ", state.severity_count(Severity::High)), severity_style(Severity::High)),
+            Span::styled(format!("{} medium ", state.severity_count(Severity::Medium)), severity_style(Severity::Medium)),
+            Span::styled(format!("{} low ", state.severity_count(Severity::Low)), severity_style(Severity::Low)),
+            Span::styled(format!("{} info", state.severity_count(Severity::Info)), severity_style(Severity::Info)),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(totals), top[0]);
+    
+    let ratio = if state.hosts_total > 0 {
+        (state.hosts_completed as f64 / state.hosts_total as f64).min(1.0)
+    } else {
+        0.0
+    };
+    frame.render_widget(Gauge::default().gauge_style(Style::default().fg(Color::Cyan)).ratio(ratio), top[1]);
+    
+    // Show the most recent hosts that fit
+    let visible = usize::from(panes[1].height.saturating_sub(3));
+    let rows: Vec<Row> = state.hosts.iter()
+        .skip(state.hosts.len().saturating_sub(visible))
+        .map(|host| {
+            let ports = host.open_ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",");
+            let status = if host.completed { "done" } else { "scanning" };
+            let style = host.highest_severity.map(severity_style).unwrap_or_default();
+            Row::new(vec![
+                host.ip.to_string(),
+                host.hostname.clone(),
+                status.to_string(),
+                ports,
+                host.vulnerabilities.to_string(),
+            ]).style(style)
+        })
+        .collect();
+    let table = Table::new(rows, [
+            Constraint::Length(39),
+            Constraint::Percentage(25),
+            Constraint::Length(9),
+            Constraint::Percentage(50),
+            Constraint::Length(6),
+        ])
+        .header(Row::new(vec!["Host", "Hostname", "Status", "Open ports", "Vulns"])
+            .style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(Block::default().borders(Borders::ALL).title(" Hosts "));
+    frame.render_widget(table, panes[1]);
+    
+    let log_lines = usize::from(panes[2].height.saturating_sub(2));
+    let items: Vec<ListItem> = state.log.iter()
+        .skip(state.log.len().saturating_sub(log_lines))
+        .map(|line| ListItem::new(line.as_str()))
+        .collect();
+    frame.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(" Log ")), panes[2]);
+}
+
+/// Color used for counts and rows at a severity
+fn severity_style(severity: Severity) -> Style {
+    match severity {
+        Severity::Critical => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        Severity::High => Style::default().fg(Color::LightRed),
+        Severity::Medium => Style::default().fg(Color::Yellow),
+        Severity::Low => Style::default().fg(Color::Green),
+        Severity::Info => Style::default().fg(Color::Blue),
+    }
+}
+
+// Restores the terminal when the dashboard ends, even if it panicked
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<TerminalGuard> {
+        enable_raw_mode()?;
+        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen) {
+            let _ = disable_raw_mode();
+            return Err(e);
+        }
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, crossterm::cursor::Show);
+    }
+}
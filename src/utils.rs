@@ -1,127 +1,2245 @@
 // Author: CyberCraft Alchemist
 // Utility functions for network scanning and service detection
 
-use std::net::{IpAddr, TcpStream};
-use std::time::Duration;
-use std::io::{Read, Write};
-use rand::{thread_rng, Rng, seq::SliceRandom};
-use std::str::FromStr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::{Mutex, RwLock};
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use rand::{thread_rng, Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use log::debug;
+use std::collections::HashMap;
+use pnet::datalink::{self, Channel, NetworkInterface};
+use pnet::ipnetwork::{IpNetwork, Ipv4Network};
+use pnet::packet::{MutablePacket, Packet};
+use pnet::packet::arp::{ArpHardwareTypes, ArpOperations, ArpPacket, MutableArpPacket};
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
+use pnet::util::MacAddr;
+use rayon::prelude::*;
+
+use crate::constants::{ARP_READ_TIMEOUT_MS, ARP_REPLY_WAIT_MS, BANNER_READ_GAP_MS, DEFAULT_MAX_BANNER_BYTES, GRPC_MAX_RESPONSE_BYTES, GRPC_REFLECTION_PATHS, HTTP2_MAX_FRAME_BYTES, HTTP2_PREFACE, IKE_AGGRESSIVE_DH_GROUP, IKE_PORT, IKE_TRANSFORMS, IKE_VENDOR_IDS, MONGODB_MAX_MESSAGE_BYTES, SMB_MAX_MESSAGE_BYTES, SMB2_DIALECTS, SNMP_PORT, SSH_CLIENT_VERSION, SSH_MAX_LINE_BYTES, SSH_MAX_PACKET_BYTES, SSH_MAX_PREAMBLE_LINES, PROBE_RETRY_BASE_DELAY_MS, PROBE_RETRY_JITTER_MS, RANDOM_IPV4_MAX_ATTEMPTS, RTT_SAMPLES, RTT_TIMEOUT_MULTIPLIER, SERVICE_PROBES, SERVICE_CONFIDENCE_BANNER, SERVICE_CONFIDENCE_BANNER_CONFIRMED, SERVICE_CONFIDENCE_PORT, SERVICE_CONFIDENCE_PORT_UNRECOGNISED, HTTP_MAX_BODY_BYTES, HTTP_TECHNOLOGY_SIGNATURES};
+use crate::models::{HostInfo, Http2Info, HttpInfo, HttpTechnology, IdSource, IkeInfo, IkeTransform, ServiceIdentity, ServiceProbeResult, SmbInfo, SshAlgorithms, TlsCertInfo, TlsVersion};
+use crate::proxy;
+use crate::resolver;
+pub use crate::models::PortState;
+
+// Spaces out connection attempts so they never exceed a fixed rate
+struct ConnectionRateLimiter {
+    interval: Duration,
+    next_slot: Instant,
+}
+
+impl ConnectionRateLimiter {
+    /// Reserve the next connection slot, returning how long to wait for it
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let slot = self.next_slot.max(now);
+        self.next_slot = slot + self.interval;
+        slot - now
+    }
+}
+
+// Global connection rate limit shared by every scan thread (None means unlimited)
+lazy_static::lazy_static! {
+    static ref CONNECTION_RATE_LIMITER: Mutex<Option<ConnectionRateLimiter>> = Mutex::new(None);
+}
+
+/// Cap outgoing connection attempts to `max_pps` per second, or remove the cap with None
+pub fn set_max_connection_rate(max_pps: Option<u32>) {
+    let mut limiter = CONNECTION_RATE_LIMITER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *limiter = max_pps.filter(|rate| *rate > 0).map(|rate| ConnectionRateLimiter {
+        interval: Duration::from_secs_f64(1.0 / f64::from(rate)),
+        next_slot: Instant::now(),
+    });
+}
+
+/// Reserve a slot for one connection attempt and return how long to wait before connecting
+///
+/// Returns zero when no rate limit is configured. Async callers should sleep
+/// for the returned duration rather than blocking.
+pub fn reserve_connection_slot() -> Duration {
+    CONNECTION_RATE_LIMITER.lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_mut()
+        .map(|limiter| limiter.reserve())
+        .unwrap_or_default()
+}
+
+// Extra attempts for port probes that time out, shared by every scan thread
+static PROBE_RETRIES: AtomicU8 = AtomicU8::new(1);
+
+/// Retry timed-out port probes up to `retries` more times before giving up
+pub fn set_probe_retries(retries: u8) {
+    PROBE_RETRIES.store(retries, Ordering::Relaxed);
+}
+
+/// Number of extra attempts made for a port probe that times out
+pub fn probe_retries() -> u8 {
+    PROBE_RETRIES.load(Ordering::Relaxed)
+}
+
+// Most bytes read from a service's response, shared by every scan thread
+static MAX_BANNER_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_BANNER_BYTES);
+
+/// Read at most `max_bytes` of each service response
+pub fn set_max_banner_bytes(max_bytes: usize) {
+    MAX_BANNER_BYTES.store(max_bytes, Ordering::Relaxed);
+}
+
+/// Most bytes read from a service's response
+pub fn max_banner_bytes() -> usize {
+    MAX_BANNER_BYTES.load(Ordering::Relaxed)
+}
+
+/// Delay before retry `attempt` (starting at 1) of a timed-out probe: exponential backoff plus jitter
+pub fn probe_retry_delay(attempt: u8) -> Duration {
+    let backoff = PROBE_RETRY_BASE_DELAY_MS << u32::from(attempt.saturating_sub(1)).min(6);
+    Duration::from_millis(backoff + thread_rng().gen_range(0..=PROBE_RETRY_JITTER_MS))
+}
+
+/// Block until the connection rate limit allows another connection attempt
+pub fn wait_for_connection_slot() {
+    let delay = reserve_connection_slot();
+    if !delay.is_zero() {
+        thread::sleep(delay);
+    }
+}
 
 /// Check if a port is open by attempting a TCP connection
 pub fn is_port_open(ip: &IpAddr, port: u16, timeout_ms: u64) -> bool {
-    let addr = format!("{}:{}", ip, port);
+    matches!(probe_port(ip, port, timeout_ms), PortState::Open)
+}
+
+/// Probe a port and classify it as open, closed (refused) or filtered (no response)
+///
+/// Connects that time out are retried up to `probe_retries()` times, since a
+/// single dropped SYN on a lossy link would otherwise hide an open port. A
+/// refused connection is definitive and never retried.
+pub fn probe_port(ip: &IpAddr, port: u16, timeout_ms: u64) -> PortState {
+    let retries = probe_retries();
+    let mut attempt = 0;
+    
+    loop {
+        wait_for_connection_slot();
+        
+        match proxy::connect(&SocketAddr::new(*ip, port), Duration::from_millis(timeout_ms)) {
+            Ok(_) => return PortState::Open,
+            Err(e) => match e.kind() {
+                ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset => return PortState::Closed,
+                ErrorKind::TimedOut | ErrorKind::WouldBlock if attempt < retries => {
+                    attempt += 1;
+                    debug!("{} timed out, retrying ({}/{})", SocketAddr::new(*ip, port), attempt, retries);
+                    thread::sleep(probe_retry_delay(attempt));
+                },
+                // Timeouts and unreachable networks mean probes are being dropped
+                _ => return PortState::Filtered,
+            },
+        }
+    }
+}
+
+/// Get the service banner from an open port
+pub fn get_service_banner(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<String> {
+    probe_service(ip, port, timeout_ms).map(|result| result.banner)
+}
+
+/// Send an open port the probe chosen by `service_probe` and keep the exchange
+pub fn probe_service(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<ServiceProbeResult> {
+    exchange_service_probe(ip, port, service_probe(port), timeout_ms)
+}
+
+/// Bytes sent to a port to draw out its banner
+///
+/// Ports with a protocol-specific entry in `SERVICE_PROBES` are sent that probe;
+/// other ports get a generic HTTP request or line break.
+pub fn service_probe(port: u16) -> &'static [u8] {
+    if let Some(probe) = SERVICE_PROBES.get(&port) {
+        probe
+    } else if port == 80 || port == 443 || port == 8080 || port == 8443 {
+        // For HTTP ports, send a basic GET request
+        b"GET / HTTP/1.0\r\nHost: unknown\r\n\r\n"
+    } else {
+        // For other services, send a basic probe
+        b"\r\n"
+    }
+}
+
+/// Send a specific service probe to an open port
+pub fn send_service_probe(ip: &IpAddr, port: u16, probe: &[u8], timeout_ms: u64) -> Option<String> {
+    exchange_service_probe(ip, port, probe, timeout_ms).map(|result| result.banner)
+}
+
+/// Send a specific service probe to an open port, returning the banner with the bytes exchanged
+pub fn exchange_service_probe(ip: &IpAddr, port: u16, probe: &[u8], timeout_ms: u64) -> Option<ServiceProbeResult> {
+    wait_for_connection_slot();
+    
+    match proxy::connect(&SocketAddr::new(*ip, port), Duration::from_millis(timeout_ms)) {
+        Ok(mut stream) => {
+            // Set read timeout
+            if stream.set_read_timeout(Some(Duration::from_millis(timeout_ms))).is_err() {
+                return None;
+            }
+            
+            // Send the probe
+            if stream.write_all(probe).is_err() {
+                return None;
+            }
+            
+            // Read the response
+            let response = read_service_response(&mut stream, timeout_ms);
+            if response.is_empty() {
+                return None;
+            }
+            
+            Some(ServiceProbeResult {
+                banner: format_banner(&response),
+                probe: probe.to_vec(),
+                response,
+            })
+        },
+        Err(_) => None,
+    }
+}
+
+/// Read a service's response until it goes quiet, closes the connection or reaches `max_banner_bytes()`
+///
+/// The first segment gets the full timeout. Later segments, such as the rest
+/// of a multi-line SMTP or FTP greeting, are waited on for `BANNER_READ_GAP_MS`.
+fn read_service_response(stream: &mut TcpStream, timeout_ms: u64) -> Vec<u8> {
+    let max_bytes = max_banner_bytes();
+    let mut response = Vec::new();
+    let mut buffer = [0; 4096];
+    
+    while response.len() < max_bytes {
+        let size = match stream.read(&mut buffer) {
+            Ok(size) if size > 0 => size,
+            _ => break,
+        };
+        response.extend_from_slice(&buffer[..size.min(max_bytes - response.len())]);
+        
+        if stream.set_read_timeout(Some(Duration::from_millis(BANNER_READ_GAP_MS.min(timeout_ms)))).is_err() {
+            break;
+        }
+    }
+    
+    response
+}
+
+/// Perform a TLS handshake and describe the certificate the service presents
+///
+/// Certificate and hostname validation are disabled so that self-signed and
+/// expired certificates can still be inspected.
+pub fn inspect_tls_certificate(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<TlsCertInfo> {
+    let timeout = Duration::from_millis(timeout_ms);
+    
+    wait_for_connection_slot();
+    let stream = proxy::connect(&SocketAddr::new(*ip, port), timeout).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    stream.set_write_timeout(Some(timeout)).ok()?;
+    
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .ok()?;
+    
+    let tls_stream = connector.connect(&ip.to_string(), stream).ok()?;
+    let der = tls_stream.peer_certificate().ok()??.to_der().ok()?;
+    
+    parse_certificate(&der)
+}
+
+/// Extract the interesting fields from a DER-encoded X.509 certificate
+fn parse_certificate(der: &[u8]) -> Option<TlsCertInfo> {
+    use x509_parser::extensions::GeneralName;
+    
+    let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+    
+    let subject_cn = cert.subject().iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .map(|cn| cn.to_string());
+    
+    let subject_alt_names = match cert.subject_alternative_name() {
+        Ok(Some(san)) => san.value.general_names.iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(dns) => Some(dns.to_string()),
+                GeneralName::IPAddress(bytes) => match bytes.len() {
+                    4 => <[u8; 4]>::try_from(*bytes).ok().map(|b| IpAddr::from(b).to_string()),
+                    16 => <[u8; 16]>::try_from(*bytes).ok().map(|b| IpAddr::from(b).to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    
+    let validity = cert.validity();
+    let format_time = |timestamp: i64| {
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| timestamp.to_string())
+    };
+    
+    Some(TlsCertInfo {
+        subject: cert.subject().to_string(),
+        subject_cn,
+        subject_alt_names,
+        issuer: cert.issuer().to_string(),
+        not_before: format_time(validity.not_before.timestamp()),
+        not_after: format_time(validity.not_after.timestamp()),
+        signature_algorithm: signature_algorithm_name(&cert.signature_algorithm.algorithm.to_id_string()),
+        is_self_signed: cert.subject().as_raw() == cert.issuer().as_raw(),
+        is_expired: !validity.is_valid(),
+    })
+}
+
+/// Map a signature algorithm OID to its common name
+fn signature_algorithm_name(oid: &str) -> String {
+    match oid {
+        "1.2.840.113549.1.1.2" => "md2WithRSAEncryption",
+        "1.2.840.113549.1.1.4" => "md5WithRSAEncryption",
+        "1.2.840.113549.1.1.5" => "sha1WithRSAEncryption",
+        "1.2.840.113549.1.1.10" => "rsassaPss",
+        "1.2.840.113549.1.1.11" => "sha256WithRSAEncryption",
+        "1.2.840.113549.1.1.12" => "sha384WithRSAEncryption",
+        "1.2.840.113549.1.1.13" => "sha512WithRSAEncryption",
+        "1.2.840.10040.4.3" => "dsaWithSHA1",
+        "1.2.840.10045.4.1" => "ecdsaWithSHA1",
+        "1.2.840.10045.4.3.2" => "ecdsaWithSHA256",
+        "1.2.840.10045.4.3.3" => "ecdsaWithSHA384",
+        "1.2.840.10045.4.3.4" => "ecdsaWithSHA512",
+        "1.3.101.112" => "Ed25519",
+        "1.3.101.113" => "Ed448",
+        other => other,
+    }.to_string()
+}
+
+/// Determine which SSL/TLS protocol versions a service accepts
+///
+/// A ClientHello offering only one version is sent per connection and the
+/// ServerHello is checked for that version. Raw handshakes are used because
+/// the local TLS library may refuse to offer SSLv3 or force TLS 1.3.
+pub fn enumerate_tls_versions(ip: &IpAddr, port: u16, timeout_ms: u64) -> Vec<TlsVersion> {
+    TlsVersion::ALL.iter()
+        .copied()
+        .filter(|version| probe_tls_version(ip, port, *version, timeout_ms))
+        .collect()
+}
+
+/// Attempt a handshake offering a single protocol version
+fn probe_tls_version(ip: &IpAddr, port: u16, version: TlsVersion, timeout_ms: u64) -> bool {
+    let timeout = Duration::from_millis(timeout_ms);
+    
+    wait_for_connection_slot();
+    let mut stream = match proxy::connect(&SocketAddr::new(*ip, port), timeout) {
+        Ok(stream) => stream,
+        Err(_) => return false,
+    };
+    if stream.set_read_timeout(Some(timeout)).is_err() || stream.set_write_timeout(Some(timeout)).is_err() {
+        return false;
+    }
+    
+    if stream.write_all(&build_client_hello(version)).is_err() {
+        return false;
+    }
+    
+    // Record header: content type, version, length
+    let mut header = [0u8; 5];
+    if stream.read_exact(&mut header).is_err() || header[0] != 0x16 {
+        return false; // Alert or garbage, the version was refused
+    }
+    
+    let length = u16::from_be_bytes([header[3], header[4]]) as usize;
+    let mut record = vec![0u8; length];
+    if stream.read_exact(&mut record).is_err() {
+        return false;
+    }
+    
+    server_hello_version(&record) == Some(version.wire_version())
+}
+
+/// Build a ClientHello record offering only the given protocol version
+fn build_client_hello(version: TlsVersion) -> Vec<u8> {
+    let mut rng = thread_rng();
+    let is_tls13 = version == TlsVersion::Tls13;
+    
+    // TLS 1.3 is negotiated through the supported_versions extension
+    let legacy_version = if is_tls13 { 0x0303 } else { version.wire_version() };
+    
+    let mut cipher_suites: Vec<u16> = vec![
+        0xc02b, 0xc02f, 0xc02c, 0xc030, 0xcca9, 0xcca8, 0xc009, 0xc013, 0xc00a, 0xc014,
+        0x009c, 0x009d, 0x002f, 0x0035, 0x000a, 0x0005, 0x0004,
+    ];
+    if is_tls13 {
+        cipher_suites = vec![0x1301, 0x1302, 0x1303];
+    }
+    cipher_suites.push(0x00ff); // Renegotiation info SCSV
+    
+    let mut extensions = Vec::new();
+    if version != TlsVersion::Ssl3 {
+        // supported_groups: x25519, secp256r1, secp384r1
+        push_extension(&mut extensions, 0x000a, &[0x00, 0x06, 0x00, 0x1d, 0x00, 0x17, 0x00, 0x18]);
+        // ec_point_formats: uncompressed
+        push_extension(&mut extensions, 0x000b, &[0x01, 0x00]);
+        // signature_algorithms
+        let algorithms: [u16; 11] = [0x0403, 0x0503, 0x0603, 0x0804, 0x0805, 0x0806, 0x0401, 0x0501, 0x0601, 0x0203, 0x0201];
+        let mut data = ((algorithms.len() * 2) as u16).to_be_bytes().to_vec();
+        algorithms.iter().for_each(|alg| data.extend_from_slice(&alg.to_be_bytes()));
+        push_extension(&mut extensions, 0x000d, &data);
+    }
+    if is_tls13 {
+        // supported_versions: TLS 1.3 only
+        push_extension(&mut extensions, 0x002b, &[0x02, 0x03, 0x04]);
+        // key_share: a random x25519 public key is enough to get a ServerHello
+        let mut data = vec![0x00, 0x24, 0x00, 0x1d, 0x00, 0x20];
+        data.extend((0..32).map(|_| rng.gen::<u8>()));
+        push_extension(&mut extensions, 0x0033, &data);
+    }
+    
+    let mut body = legacy_version.to_be_bytes().to_vec();
+    body.extend((0..32).map(|_| rng.gen::<u8>())); // Client random
+    body.push(0); // Empty session ID
+    body.extend_from_slice(&((cipher_suites.len() * 2) as u16).to_be_bytes());
+    cipher_suites.iter().for_each(|suite| body.extend_from_slice(&suite.to_be_bytes()));
+    body.extend_from_slice(&[0x01, 0x00]); // Null compression only
+    if !extensions.is_empty() {
+        body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        body.extend(extensions);
+    }
+    
+    let mut handshake = vec![0x01]; // ClientHello
+    handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend(body);
+    
+    // The record layer stays at TLS 1.0 for compatibility, except when probing SSLv3
+    let record_version: u16 = if version == TlsVersion::Ssl3 { 0x0300 } else { 0x0301 };
+    let mut record = vec![0x16];
+    record.extend_from_slice(&record_version.to_be_bytes());
+    record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+    record.extend(handshake);
+    record
+}
+
+/// Append a TLS extension with the given type and payload
+fn push_extension(extensions: &mut Vec<u8>, extension_type: u16, data: &[u8]) {
+    extensions.extend_from_slice(&extension_type.to_be_bytes());
+    extensions.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    extensions.extend_from_slice(data);
+}
+
+/// Extract the negotiated protocol version from a ServerHello handshake record
+fn server_hello_version(record: &[u8]) -> Option<u16> {
+    // Handshake header: type (ServerHello = 2) and 3-byte length
+    if record.len() < 4 || record[0] != 0x02 {
+        return None;
+    }
+    let hello = &record[4..];
+    let legacy_version = u16::from_be_bytes([*hello.first()?, *hello.get(1)?]);
+    
+    // Skip version, random and session ID to reach the extensions
+    let session_id_len = *hello.get(34)? as usize;
+    let extensions_start = 35 + session_id_len + 3; // Cipher suite and compression method
+    let extensions_len = match hello.get(extensions_start..extensions_start + 2) {
+        Some(len) => u16::from_be_bytes([len[0], len[1]]) as usize,
+        None => return Some(legacy_version), // No extensions
+    };
+    
+    let mut extensions = hello.get(extensions_start + 2..extensions_start + 2 + extensions_len)?;
+    while extensions.len() >= 4 {
+        let extension_type = u16::from_be_bytes([extensions[0], extensions[1]]);
+        let len = u16::from_be_bytes([extensions[2], extensions[3]]) as usize;
+        let data = extensions.get(4..4 + len)?;
+        
+        // supported_versions carries the real version for TLS 1.3
+        if extension_type == 0x002b && data.len() == 2 {
+            return Some(u16::from_be_bytes([data[0], data[1]]));
+        }
+        extensions = &extensions[4 + len..];
+    }
+    
+    Some(legacy_version)
+}
+
+/// Request a DNS zone transfer (AXFR) over TCP and report whether the server sent records
+pub fn attempt_zone_transfer(ip: &IpAddr, port: u16, zone: &str, timeout_ms: u64) -> bool {
+    let timeout = Duration::from_millis(timeout_ms);
+    
+    wait_for_connection_slot();
+    let mut stream = match proxy::connect(&SocketAddr::new(*ip, port), timeout) {
+        Ok(stream) => stream,
+        Err(_) => return false,
+    };
+    if stream.set_read_timeout(Some(timeout)).is_err() || stream.set_write_timeout(Some(timeout)).is_err() {
+        return false;
+    }
+    
+    // Header: random ID, no flags, one question
+    let mut query = thread_rng().gen::<u16>().to_be_bytes().to_vec();
+    query.extend_from_slice(&[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    for label in zone.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return false;
+        }
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&[0x00, 0xfc, 0x00, 0x01]); // QTYPE AXFR, QCLASS IN
+    
+    // DNS over TCP prefixes each message with its length
+    let mut message = (query.len() as u16).to_be_bytes().to_vec();
+    message.extend(query);
+    if stream.write_all(&message).is_err() {
+        return false;
+    }
+    
+    let mut length = [0u8; 2];
+    let mut header = [0u8; 12];
+    if stream.read_exact(&mut length).is_err() || stream.read_exact(&mut header).is_err() {
+        return false;
+    }
+    
+    // A response with NOERROR and answer records means the transfer was allowed
+    let is_response = header[2] & 0x80 != 0;
+    let rcode = header[3] & 0x0f;
+    let answer_count = u16::from_be_bytes([header[6], header[7]]);
+    is_response && rcode == 0 && answer_count > 0
+}
+
+/// Negotiate with an SMB server and report its dialects, signing policy and OS strings
+///
+/// SMB2/3 and SMB1 are negotiated on separate connections, since a server
+/// offered both picks SMB2 and never reveals whether SMB1 is still enabled.
+/// When SMB1 is accepted and `null_session` is set, an anonymous (null)
+/// session setup is attempted to read the native OS and LAN manager strings;
+/// no credentials are sent.
+pub fn probe_smb(ip: &IpAddr, port: u16, timeout_ms: u64, null_session: bool) -> Option<SmbInfo> {
+    let smb2 = smb_connect(ip, port, timeout_ms)
+        .and_then(|mut stream| smb_transact(&mut stream, &build_smb2_negotiate()))
+        .and_then(|response| parse_smb2_negotiate_response(&response));
+    let smb1 = probe_smb1(ip, port, timeout_ms, null_session);
+    
+    let info = match (smb2, smb1) {
+        (Some(smb2), None) => smb2,
+        (None, Some(smb1)) => smb1,
+        (Some(mut smb2), Some(smb1)) => {
+            // Signing is only required if every dialect the server speaks requires it
+            smb2.dialects.extend(smb1.dialects);
+            smb2.smb1_enabled = true;
+            smb2.signing_enabled |= smb1.signing_enabled;
+            smb2.signing_required &= smb1.signing_required;
+            smb2.native_os = smb1.native_os;
+            smb2.native_lan_manager = smb1.native_lan_manager;
+            smb2.domain = smb1.domain;
+            smb2.server_name = smb1.server_name;
+            smb2
+        },
+        (None, None) => return None,
+    };
+    
+    Some(info)
+}
+
+/// Negotiate NT LM 0.12 and, if the server accepts it, optionally read its OS strings from a null session setup
+fn probe_smb1(ip: &IpAddr, port: u16, timeout_ms: u64, null_session: bool) -> Option<SmbInfo> {
+    let mut stream = smb_connect(ip, port, timeout_ms)?;
+    let response = smb_transact(&mut stream, &build_smb1_negotiate())?;
+    let (mut info, session_key) = parse_smb1_negotiate_response(&response)?;
+    if !null_session {
+        return Some(info);
+    }
+    
+    if let Some(setup) = smb_transact(&mut stream, &build_smb1_session_setup(session_key)) {
+        if let Some((native_os, native_lan_manager, domain)) = parse_smb1_session_setup_response(&setup) {
+            info.native_os = native_os;
+            info.native_lan_manager = native_lan_manager;
+            info.domain = domain.or(info.domain);
+        }
+    }
+    
+    Some(info)
+}
+
+/// Open a connection for SMB, starting a NetBIOS session first on port 139
+fn smb_connect(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<TcpStream> {
+    let mut stream = probe_connect(ip, port, timeout_ms)?;
+    
+    if port == 139 {
+        // Session request to the wildcard server name, answered with 0x82 when accepted
+        let mut request = vec![0x81, 0x00, 0x00, 0x44];
+        request.extend_from_slice(&netbios_encode_name("*SMBSERVER", 0x20));
+        request.extend_from_slice(&netbios_encode_name("RUSTNETSCAN", 0x00));
+        stream.write_all(&request).ok()?;
+        
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).ok()?;
+        if reply[0] != 0x82 {
+            return None;
+        }
+    }
+    
+    Some(stream)
+}
+
+/// Send one SMB message in a NetBIOS session frame and read the reply
+fn smb_transact(stream: &mut TcpStream, message: &[u8]) -> Option<Vec<u8>> {
+    let mut frame = vec![0x00];
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes()[1..]);
+    frame.extend_from_slice(message);
+    stream.write_all(&frame).ok()?;
+    
+    // Skip keep-alives until a session message arrives
+    loop {
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).ok()?;
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        if length > SMB_MAX_MESSAGE_BYTES {
+            return None;
+        }
+        
+        let mut body = vec![0u8; length];
+        stream.read_exact(&mut body).ok()?;
+        if header[0] == 0x00 {
+            return Some(body);
+        }
+    }
+}
+
+/// Encode a NetBIOS name in the first-level encoding used by session requests
+fn netbios_encode_name(name: &str, suffix: u8) -> Vec<u8> {
+    let mut padded = [b' '; 16];
+    for (slot, byte) in padded.iter_mut().zip(name.bytes().take(15)) {
+        *slot = byte.to_ascii_uppercase();
+    }
+    padded[15] = suffix;
+    
+    let mut encoded = vec![0x20];
+    for byte in padded {
+        encoded.push(b'A' + (byte >> 4));
+        encoded.push(b'A' + (byte & 0x0f));
+    }
+    encoded.push(0x00);
+    encoded
+}
+
+/// Build an SMB2 NEGOTIATE request offering every SMB2/3 dialect
+fn build_smb2_negotiate() -> Vec<u8> {
+    // 64-byte SMB2 header: NEGOTIATE command, one credit requested
+    let mut message = vec![0xfe, b'S', b'M', b'B', 0x40, 0x00];
+    message.extend_from_slice(&[0u8; 6]);                       // Credit charge, status
+    message.extend_from_slice(&[0x00, 0x00, 0x01, 0x00]);       // Command, credits requested
+    message.extend_from_slice(&[0u8; 52]);                      // Flags, next command, message ID, tree, session, signature
+    
+    // Contexts follow the dialect list, aligned to 8 bytes from the header
+    let dialects_end = 64 + 36 + SMB2_DIALECTS.len() * 2;
+    let context_offset = dialects_end + (8 - dialects_end % 8) % 8;
+    
+    message.extend_from_slice(&[0x24, 0x00]);                   // Structure size 36
+    message.extend_from_slice(&(SMB2_DIALECTS.len() as u16).to_le_bytes());
+    message.extend_from_slice(&[0x01, 0x00, 0x00, 0x00]);       // Signing enabled, reserved
+    message.extend_from_slice(&[0u8; 4]);                       // Capabilities
+    message.extend_from_slice(&thread_rng().gen::<[u8; 16]>()); // Client GUID
+    message.extend_from_slice(&(context_offset as u32).to_le_bytes());
+    message.extend_from_slice(&[0x01, 0x00, 0x00, 0x00]);       // One negotiate context
+    for (dialect, _) in &SMB2_DIALECTS {
+        message.extend_from_slice(&dialect.to_le_bytes());
+    }
+    message.resize(context_offset, 0);
+    
+    // SMB 3.1.1 requires a pre-authentication integrity context (SHA-512)
+    message.extend_from_slice(&[0x01, 0x00, 0x26, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    message.extend_from_slice(&[0x01, 0x00, 0x20, 0x00, 0x01, 0x00]);
+    message.extend_from_slice(&thread_rng().gen::<[u8; 32]>());
+    
+    message
+}
+
+/// Parse an SMB2 NEGOTIATE response into the dialect chosen and the signing policy
+pub fn parse_smb2_negotiate_response(response: &[u8]) -> Option<SmbInfo> {
+    if response.len() < 72 || !response.starts_with(&[0xfe, b'S', b'M', b'B']) {
+        return None;
+    }
+    
+    let status = u32::from_le_bytes([response[8], response[9], response[10], response[11]]);
+    let command = u16::from_le_bytes([response[12], response[13]]);
+    let structure_size = u16::from_le_bytes([response[64], response[65]]);
+    if status != 0 || command != 0 || structure_size != 65 {
+        return None;
+    }
+    
+    let security_mode = u16::from_le_bytes([response[66], response[67]]);
+    let dialect = u16::from_le_bytes([response[68], response[69]]);
+    let dialect_name = SMB2_DIALECTS.iter()
+        .find(|(code, _)| *code == dialect)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("SMB2 dialect {:#06x}", dialect));
+    
+    Some(SmbInfo {
+        dialects: vec![dialect_name],
+        signing_enabled: security_mode & 0x01 != 0,
+        signing_required: security_mode & 0x02 != 0,
+        ..Default::default()
+    })
+}
+
+/// Build an SMB1 header for `command`, asking for Unicode strings and NT status codes
+fn smb1_header(command: u8) -> Vec<u8> {
+    let mut header = vec![0xff, b'S', b'M', b'B', command];
+    header.extend_from_slice(&[0u8; 4]);                        // Status
+    header.push(0x18);                                          // Flags: case-insensitive, canonical paths
+    header.extend_from_slice(&[0x01, 0xc0]);                    // Flags2: Unicode, NT status, long names
+    header.extend_from_slice(&[0u8; 12]);                       // PID high, security features, reserved
+    header.extend_from_slice(&[0x00, 0x00, 0xff, 0xfe]);        // Tree ID, process ID
+    header.extend_from_slice(&[0u8; 4]);                        // User ID, multiplex ID
+    header
+}
+
+/// Build an SMB1 NEGOTIATE request offering only NT LM 0.12
+fn build_smb1_negotiate() -> Vec<u8> {
+    let dialect = b"\x02NT LM 0.12\x00";
+    
+    let mut message = smb1_header(0x72);
+    message.push(0x00);                                         // No parameter words
+    message.extend_from_slice(&(dialect.len() as u16).to_le_bytes());
+    message.extend_from_slice(dialect);
+    message
+}
+
+/// Parse an SMB1 NEGOTIATE response, returning what it reveals and the session key to echo
+fn parse_smb1_negotiate_response(response: &[u8]) -> Option<(SmbInfo, u32)> {
+    if response.len() < 37 || !response.starts_with(&[0xff, b'S', b'M', b'B']) || response[4] != 0x72 {
+        return None;
+    }
+    
+    // NT LM 0.12 responses carry 17 parameter words; index 0xFFFF means no dialect was accepted
+    let dialect_index = u16::from_le_bytes([response[33], response[34]]);
+    if response[32] != 17 || dialect_index == 0xffff || response.len() < 69 {
+        return None;
+    }
+    
+    let security_mode = response[35];
+    let session_key = u32::from_le_bytes([response[48], response[49], response[50], response[51]]);
+    let capabilities = u32::from_le_bytes([response[52], response[53], response[54], response[55]]);
+    let challenge_length = usize::from(response[66]);
+    
+    let mut info = SmbInfo {
+        dialects: vec!["NT LM 0.12".to_string()],
+        smb1_enabled: true,
+        signing_enabled: security_mode & 0x04 != 0,
+        signing_required: security_mode & 0x08 != 0,
+        ..Default::default()
+    };
+    
+    // Without extended security the challenge is followed by the domain and server names
+    if capabilities & 0x8000_0000 == 0 {
+        let unicode = response[11] & 0x80 != 0;
+        let data = response.get(69 + challenge_length..).unwrap_or(&[]);
+        let mut names = smb_strings(data, unicode).into_iter();
+        info.domain = names.next().filter(|name| !name.is_empty());
+        info.server_name = names.next().filter(|name| !name.is_empty());
+    }
+    
+    Some((info, session_key))
+}
+
+/// Build an anonymous SMB1 SESSION SETUP ANDX request (empty user name and passwords)
+fn build_smb1_session_setup(session_key: u32) -> Vec<u8> {
+    let mut message = smb1_header(0x73);
+    message.push(13);                                           // Parameter words
+    message.extend_from_slice(&[0xff, 0x00, 0x00, 0x00]);       // No further AndX command
+    message.extend_from_slice(&[0xff, 0xff, 0x02, 0x00]);       // Max buffer size, max pending requests
+    message.extend_from_slice(&[0x00, 0x00]);                   // Virtual circuit number
+    message.extend_from_slice(&session_key.to_le_bytes());
+    message.extend_from_slice(&[0u8; 8]);                       // Password lengths, reserved
+    message.extend_from_slice(&[0x44, 0x00, 0x00, 0x00]);       // Capabilities: Unicode, NT status
+    
+    // Pad byte aligns the Unicode strings, then empty account, domain, OS and LAN manager names
+    let data = [0u8; 9];
+    message.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    message.extend_from_slice(&data);
+    message
+}
+
+/// Parse the native OS, LAN manager and domain strings from an SMB1 SESSION SETUP ANDX response
+fn parse_smb1_session_setup_response(response: &[u8]) -> Option<(Option<String>, Option<String>, Option<String>)> {
+    if response.len() < 35 || !response.starts_with(&[0xff, b'S', b'M', b'B']) || response[4] != 0x73 {
+        return None;
+    }
+    
+    // Servers that refuse null sessions answer with no parameters and no strings
+    let word_count = usize::from(response[32]);
+    if word_count != 3 {
+        return None;
+    }
+    
+    let data_start = 33 + word_count * 2 + 2;
+    let byte_count = usize::from(u16::from_le_bytes([response[33 + word_count * 2], response[34 + word_count * 2]]));
+    let unicode = response[11] & 0x80 != 0;
+    
+    // Unicode strings start on an even offset from the SMB header
+    let offset = if unicode && data_start % 2 == 1 { data_start + 1 } else { data_start };
+    let data = response.get(offset..(data_start + byte_count).min(response.len()))?;
+    
+    let mut strings = smb_strings(data, unicode).into_iter().map(|s| Some(s).filter(|s| !s.is_empty()));
+    Some((strings.next().flatten(), strings.next().flatten(), strings.next().flatten()))
+}
+
+/// Split null-terminated SMB strings, UTF-16LE when `unicode` is set
+fn smb_strings(data: &[u8], unicode: bool) -> Vec<String> {
+    if unicode {
+        let units: Vec<u16> = data.chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        units.split(|unit| *unit == 0)
+            .map(|units| String::from_utf16_lossy(units).trim().to_string())
+            .collect()
+    } else {
+        data.split(|byte| *byte == 0)
+            .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+            .collect()
+    }
+}
+
+/// Fetch a single OID from the SNMP agent on 161/udp with a v2c GetRequest
+///
+/// Returns the value rendered as text, or `None` if the agent did not answer
+/// (agents silently drop requests with an unknown community) or has no such object.
+pub fn snmp_get(ip: &IpAddr, community: &str, oid: &str, timeout_ms: u64) -> Option<String> {
+    snmp_get_on_port(ip, SNMP_PORT, community, oid, timeout_ms)
+}
+
+/// Fetch a single OID from an SNMP agent listening on `port`
+///
+/// SNMP runs over UDP, which the proxy cannot carry, so proxied scans skip it.
+pub fn snmp_get_on_port(ip: &IpAddr, port: u16, community: &str, oid: &str, timeout_ms: u64) -> Option<String> {
+    if proxy::is_enabled() {
+        return None;
+    }
+    
+    let request_id = thread_rng().gen_range(1..0x7fff_ffff);
+    let request = build_snmp_get_request(community, oid, request_id)?;
+    
+    let socket = proxy::bind_udp(ip).ok()?;
+    let target = SocketAddr::new(*ip, port);
+    socket.send_to(&request, target).ok()?;
+    
+    // Skip stray datagrams until the matching response arrives or time runs out
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut buffer = [0u8; 1500];
+    loop {
+        let remaining = deadline.checked_duration_since(Instant::now()).filter(|d| !d.is_zero())?;
+        socket.set_read_timeout(Some(remaining)).ok()?;
+        let (size, from) = socket.recv_from(&mut buffer).ok()?;
+        if from != target {
+            continue;
+        }
+        if let Some(value) = parse_snmp_get_response(&buffer[..size], request_id) {
+            return value;
+        }
+    }
+}
+
+/// Build an SNMP v2c GetRequest for a single OID
+fn build_snmp_get_request(community: &str, oid: &str, request_id: u32) -> Option<Vec<u8>> {
+    let varbind = [ber_encode(0x06, &encode_oid(oid)?), ber_encode(0x05, &[])].concat();
+    let varbind_list = ber_encode(0x30, &ber_encode(0x30, &varbind));
+    
+    let pdu = [
+        ber_encode_integer(request_id),
+        ber_encode_integer(0), // Error status
+        ber_encode_integer(0), // Error index
+        varbind_list,
+    ].concat();
+    
+    let message = [
+        ber_encode_integer(1), // Version: v2c
+        ber_encode(0x04, community.as_bytes()),
+        ber_encode(0xa0, &pdu), // GetRequest
+    ].concat();
+    
+    Some(ber_encode(0x30, &message))
+}
+
+/// Parse an SNMP GetResponse to the request with `request_id`
+///
+/// Returns `None` if the datagram is not that response, and `Some(None)` if the
+/// agent answered with an error or an exception such as noSuchObject.
+fn parse_snmp_get_response(response: &[u8], request_id: u32) -> Option<Option<String>> {
+    let (tag, message, _) = ber_decode(response)?;
+    if tag != 0x30 {
+        return None;
+    }
+    let (_, _version, rest) = ber_decode(message)?;
+    let (_, _community, rest) = ber_decode(rest)?;
+    let (tag, pdu, _) = ber_decode(rest)?;
+    if tag != 0xa2 {
+        return None;
+    }
+    
+    let (_, id, rest) = ber_decode(pdu)?;
+    if ber_decode_integer(id)? != request_id as i64 {
+        return None;
+    }
+    let (_, error_status, rest) = ber_decode(rest)?;
+    if ber_decode_integer(error_status)? != 0 {
+        return Some(None);
+    }
+    let (_, _error_index, rest) = ber_decode(rest)?;
+    let (_, varbind_list, _) = ber_decode(rest)?;
+    let (_, varbind, _) = ber_decode(varbind_list)?;
+    let (_, _oid, rest) = ber_decode(varbind)?;
+    let (tag, value, _) = ber_decode(rest)?;
+    
+    let value = match tag {
+        0x04 => Some(String::from_utf8_lossy(value).trim_matches(|c: char| c.is_whitespace() || c == '\0').to_string()),
+        0x02 => ber_decode_integer(value).map(|n| n.to_string()),
+        0x06 => Some(decode_oid(value)),
+        0x40 if value.len() == 4 => Some(Ipv4Addr::new(value[0], value[1], value[2], value[3]).to_string()),
+        0x41 | 0x42 | 0x43 | 0x46 => Some(value.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64).to_string()),
+        _ => None, // NULL, noSuchObject, noSuchInstance, endOfMibView
+    };
+    Some(value)
+}
+
+/// Encode a BER TLV with a definite length
+fn ber_encode(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    let len = value.len();
+    if len < 0x80 {
+        encoded.push(len as u8);
+    } else if len <= 0xff {
+        encoded.extend_from_slice(&[0x81, len as u8]);
+    } else {
+        encoded.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]);
+    }
+    encoded.extend_from_slice(value);
+    encoded
+}
+
+/// Encode a non-negative BER INTEGER in its shortest form
+fn ber_encode_integer(value: u32) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    // Keep a leading zero byte when the next byte's high bit would read as a sign
+    let start = (0..3)
+        .find(|&i| bytes[i] != 0 || bytes[i + 1] & 0x80 != 0)
+        .unwrap_or(3);
+    ber_encode(0x02, &bytes[start..])
+}
+
+/// Split the first BER TLV off `data`, returning its tag, value and the remaining bytes
+fn ber_decode(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    
+    let (len, rest) = if first & 0x80 == 0 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let len = rest[..count].iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, &rest[count..])
+    };
+    
+    if rest.len() < len {
+        return None;
+    }
+    Some((tag, &rest[..len], &rest[len..]))
+}
+
+/// Decode a signed BER INTEGER of up to eight bytes
+fn ber_decode_integer(value: &[u8]) -> Option<i64> {
+    if value.is_empty() || value.len() > 8 {
+        return None;
+    }
+    let initial = if value[0] & 0x80 != 0 { -1i64 } else { 0 };
+    Some(value.iter().fold(initial, |acc, b| (acc << 8) | *b as i64))
+}
+
+/// Encode a dotted OID such as "1.3.6.1.2.1.1.1.0" into its BER contents
+fn encode_oid(oid: &str) -> Option<Vec<u8>> {
+    let arcs: Vec<u32> = oid.trim_start_matches('.')
+        .split('.')
+        .map(|arc| arc.parse().ok())
+        .collect::<Option<_>>()?;
+    if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] >= 40) {
+        return None;
+    }
+    
+    let mut encoded = Vec::new();
+    for arc in std::iter::once(arcs[0] * 40 + arcs[1]).chain(arcs[2..].iter().copied()) {
+        // Base-128, most significant group first, with the high bit marking continuation
+        let mut groups = vec![(arc & 0x7f) as u8];
+        let mut remaining = arc >> 7;
+        while remaining > 0 {
+            groups.push((remaining & 0x7f) as u8 | 0x80);
+            remaining >>= 7;
+        }
+        encoded.extend(groups.iter().rev());
+    }
+    Some(encoded)
+}
+
+/// Decode BER OID contents into dotted notation
+fn decode_oid(value: &[u8]) -> String {
+    let mut arcs = Vec::new();
+    let mut current = 0u64;
+    for byte in value {
+        current = (current << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (current / 40).min(2);
+                arcs.push(first);
+                arcs.push(current - first * 40);
+            } else {
+                arcs.push(current);
+            }
+            current = 0;
+        }
+    }
+    arcs.iter().map(|arc| arc.to_string()).collect::<Vec<_>>().join(".")
+}
+
+// ISAKMP exchange types and payload types used by the IKE probes
+const IKE_HEADER_LEN: usize = 28;
+const IKE_EXCHANGE_MAIN_MODE: u8 = 2;
+const IKE_EXCHANGE_AGGRESSIVE: u8 = 4;
+const IKE_EXCHANGE_SA_INIT: u8 = 34;
+const IKE_FLAG_ENCRYPTED: u8 = 0x01;
+const IKE_FLAG_INITIATOR: u8 = 0x08;
+const IKE_PAYLOAD_SA: u8 = 1;
+const IKE_PAYLOAD_TRANSFORM: u8 = 3;
+const IKE_PAYLOAD_KE: u8 = 4;
+const IKE_PAYLOAD_ID: u8 = 5;
+const IKE_PAYLOAD_NONCE: u8 = 10;
+const IKE_PAYLOAD_NOTIFY: u8 = 11;
+const IKE_PAYLOAD_VENDOR_ID: u8 = 13;
+const IKEV2_PAYLOAD_SA: u8 = 33;
+const IKEV2_PAYLOAD_KE: u8 = 34;
+const IKEV2_PAYLOAD_NONCE: u8 = 40;
+const IKEV2_PAYLOAD_NOTIFY: u8 = 41;
+const IKEV2_PAYLOAD_VENDOR_ID: u8 = 43;
+
+/// An IKE message sent back by a responder
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IkeResponse {
+    pub initiator_spi: [u8; 8],        // Echo of the initiator cookie/SPI the request carried
+    pub version: u8,                   // Major version, 1 or 2
+    pub exchange_type: u8,
+    pub accepted: bool,                // Whether the message carried an SA payload, i.e. a proposal was chosen
+    pub transforms: Vec<IkeTransform>, // Transforms in an IKEv1 SA payload
+    pub vendor_ids: Vec<String>,
+    pub notification: Option<u16>,     // Notify message type, e.g. 14 for NO-PROPOSAL-CHOSEN
+}
+
+/// Fingerprint an IKE/IPsec VPN endpoint on 500/udp
+///
+/// Each IKEv1 main-mode transform in `IKE_TRANSFORMS` is offered on its own to
+/// learn which ones the responder accepts, then aggressive mode and IKEv2 are
+/// tried. Returns `None` when nothing answers. IKE runs over UDP, which the
+/// proxy cannot carry, so proxied scans skip it.
+pub fn probe_ike(ip: &IpAddr, timeout_ms: u64) -> Option<IkeInfo> {
+    if proxy::is_enabled() {
+        return None;
+    }
+    
+    let mut info = IkeInfo::default();
+    let record = |info: &mut IkeInfo, response: &IkeResponse| {
+        let version = format!("IKEv{}", response.version);
+        if !info.versions.contains(&version) {
+            info.versions.push(version);
+        }
+        for vendor_id in &response.vendor_ids {
+            if !info.vendor_ids.contains(vendor_id) {
+                info.vendor_ids.push(vendor_id.clone());
+            }
+        }
+    };
+    
+    let mut ikev1 = false;
+    for (index, transform) in IKE_TRANSFORMS.iter().enumerate() {
+        let cookie: [u8; 8] = thread_rng().gen();
+        match ike_exchange(ip, &build_ikev1_request(&cookie, IKE_EXCHANGE_MAIN_MODE, &[*transform]), &cookie, timeout_ms) {
+            Some(response) => {
+                record(&mut info, &response);
+                ikev1 |= response.version == 1;
+                if response.accepted && response.exchange_type == IKE_EXCHANGE_MAIN_MODE {
+                    info.transforms.extend(response.transforms);
+                }
+            },
+            // A silent first probe means no IKEv1 responder; the rest would only time out too
+            None if index == 0 => break,
+            None => {},
+        }
+    }
+    
+    if ikev1 {
+        // Aggressive mode sends its key exchange up front, so offer only transforms of that DH group
+        let transforms: Vec<(u16, u16, u16, u16)> = IKE_TRANSFORMS.iter()
+            .filter(|(_, _, _, group)| *group == IKE_AGGRESSIVE_DH_GROUP)
+            .copied()
+            .collect();
+        let cookie: [u8; 8] = thread_rng().gen();
+        if let Some(response) = ike_exchange(ip, &build_ikev1_request(&cookie, IKE_EXCHANGE_AGGRESSIVE, &transforms), &cookie, timeout_ms) {
+            record(&mut info, &response);
+            info.aggressive_mode = response.accepted && response.exchange_type == IKE_EXCHANGE_AGGRESSIVE;
+        }
+    }
+    
+    let spi: [u8; 8] = thread_rng().gen();
+    if let Some(response) = ike_exchange(ip, &build_ikev2_sa_init(&spi), &spi, timeout_ms) {
+        // IKEv1-only responders may answer with a v1 INVALID-MAJOR-VERSION notification
+        if response.version == 2 {
+            record(&mut info, &response);
+        }
+    }
+    
+    if info.versions.is_empty() {
+        None
+    } else {
+        Some(info)
+    }
+}
+
+/// Send one IKE request to 500/udp and wait for the response to it
+fn ike_exchange(ip: &IpAddr, request: &[u8], initiator_spi: &[u8; 8], timeout_ms: u64) -> Option<IkeResponse> {
+    let socket = proxy::bind_udp(ip).ok()?;
+    let target = SocketAddr::new(*ip, IKE_PORT);
+    socket.send_to(request, target).ok()?;
+    
+    // Skip stray datagrams until the matching response arrives or time runs out
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut buffer = [0u8; 4096];
+    loop {
+        let remaining = deadline.checked_duration_since(Instant::now()).filter(|d| !d.is_zero())?;
+        socket.set_read_timeout(Some(remaining)).ok()?;
+        let (size, from) = socket.recv_from(&mut buffer).ok()?;
+        if from != target {
+            continue;
+        }
+        match parse_ike_response(&buffer[..size]) {
+            Some(response) if response.initiator_spi == *initiator_spi => return Some(response),
+            _ => continue,
+        }
+    }
+}
+
+/// Build an IKEv1 main-mode or aggressive-mode request proposing `transforms`
+///
+/// Transforms are (encryption, key length, hash, DH group) attribute values and
+/// all use pre-shared key authentication. Aggressive mode also needs a key
+/// exchange for the transforms' DH group, which is filled with random bytes.
+fn build_ikev1_request(cookie: &[u8; 8], exchange_type: u8, transforms: &[(u16, u16, u16, u16)]) -> Vec<u8> {
+    let mut transform_payloads = Vec::new();
+    for (index, &(encryption, key_length, hash, group)) in transforms.iter().enumerate() {
+        let mut transform = vec![index as u8 + 1, 1, 0, 0]; // Transform number, KEY_IKE
+        ike_attribute(&mut transform, 1, encryption);
+        if key_length != 0 {
+            ike_attribute(&mut transform, 14, key_length);
+        }
+        ike_attribute(&mut transform, 2, hash);
+        ike_attribute(&mut transform, 3, 1); // Pre-shared key
+        ike_attribute(&mut transform, 4, group);
+        ike_attribute(&mut transform, 11, 1); // Lifetime in seconds...
+        ike_attribute(&mut transform, 12, 28800); // ...of 8 hours
+        let next = if index + 1 < transforms.len() { IKE_PAYLOAD_TRANSFORM } else { 0 };
+        transform_payloads.extend(ike_payload(next, &transform));
+    }
+    
+    let mut proposal = vec![1, 1, 0, transforms.len() as u8]; // Proposal 1, PROTO_ISAKMP, no SPI
+    proposal.extend(transform_payloads);
+    let mut sa = vec![0, 0, 0, 1, 0, 0, 0, 1]; // IPsec DOI, identity-only situation
+    sa.extend(ike_payload(0, &proposal));
+    
+    let payloads = if exchange_type == IKE_EXCHANGE_AGGRESSIVE {
+        let key_exchange: Vec<u8> = (0..128).map(|_| thread_rng().gen()).collect(); // 1024-bit group 2 public value
+        let nonce: [u8; 20] = thread_rng().gen();
+        let mut identity = vec![2, 17, 0x01, 0xf4]; // ID_FQDN, UDP port 500
+        identity.extend_from_slice(b"vpn.example.com");
+        [
+            ike_payload(IKE_PAYLOAD_KE, &sa),
+            ike_payload(IKE_PAYLOAD_NONCE, &key_exchange),
+            ike_payload(IKE_PAYLOAD_ID, &nonce),
+            ike_payload(0, &identity),
+        ].concat()
+    } else {
+        ike_payload(0, &sa)
+    };
+    
+    ike_message(cookie, IKE_PAYLOAD_SA, 0x10, exchange_type, 0, &payloads)
+}
+
+/// Build an IKEv2 IKE_SA_INIT request with a single proposal of common algorithms
+///
+/// Responders that want other algorithms still answer, with NO_PROPOSAL_CHOSEN
+/// or INVALID_KE_PAYLOAD, which is enough to show they speak IKEv2.
+fn build_ikev2_sa_init(spi: &[u8; 8]) -> Vec<u8> {
+    // (transform type, transform ID, key length)
+    let transforms: [(u8, u16, u16); 4] = [
+        (1, 12, 256), // ENCR_AES_CBC-256
+        (2, 5, 0),    // PRF_HMAC_SHA2_256
+        (3, 12, 0),   // AUTH_HMAC_SHA2_256_128
+        (4, 14, 0),   // 2048-bit MODP group
+    ];
+    
+    let mut proposal = vec![1, 1, 0, transforms.len() as u8]; // Proposal 1, IKE, no SPI
+    for (index, &(transform_type, id, key_length)) in transforms.iter().enumerate() {
+        let mut transform = vec![transform_type, 0];
+        transform.extend_from_slice(&id.to_be_bytes());
+        if key_length != 0 {
+            ike_attribute(&mut transform, 14, key_length);
+        }
+        let more = if index + 1 < transforms.len() { 3 } else { 0 };
+        proposal.extend(ike_payload(more, &transform));
+    }
+    
+    let mut key_exchange = vec![0, 14, 0, 0]; // DH group 14
+    key_exchange.extend((0..256).map(|_| thread_rng().gen::<u8>()));
+    let nonce: [u8; 32] = thread_rng().gen();
+    
+    let payloads = [
+        ike_payload(IKEV2_PAYLOAD_KE, &ike_payload(0, &proposal)),
+        ike_payload(IKEV2_PAYLOAD_NONCE, &key_exchange),
+        ike_payload(0, &nonce),
+    ].concat();
+    
+    ike_message(spi, IKEV2_PAYLOAD_SA, 0x20, IKE_EXCHANGE_SA_INIT, IKE_FLAG_INITIATOR, &payloads)
+}
+
+/// Prefix a payload body with the generic payload header naming the payload after it
+fn ike_payload(next_payload: u8, body: &[u8]) -> Vec<u8> {
+    let mut payload = vec![next_payload, 0];
+    payload.extend_from_slice(&((body.len() + 4) as u16).to_be_bytes());
+    payload.extend_from_slice(body);
+    payload
+}
+
+/// Append a fixed-length (type/value) IKE attribute
+fn ike_attribute(buffer: &mut Vec<u8>, attribute_type: u16, value: u16) {
+    buffer.extend_from_slice(&(0x8000 | attribute_type).to_be_bytes());
+    buffer.extend_from_slice(&value.to_be_bytes());
+}
+
+/// Wrap payloads in an ISAKMP header from the initiator, with no responder SPI yet
+fn ike_message(initiator_spi: &[u8; 8], first_payload: u8, version: u8, exchange_type: u8, flags: u8, payloads: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(IKE_HEADER_LEN + payloads.len());
+    message.extend_from_slice(initiator_spi);
+    message.extend_from_slice(&[0; 8]);
+    message.extend_from_slice(&[first_payload, version, exchange_type, flags]);
+    message.extend_from_slice(&[0; 4]); // Message ID
+    message.extend_from_slice(&((IKE_HEADER_LEN + payloads.len()) as u32).to_be_bytes());
+    message.extend_from_slice(payloads);
+    message
+}
+
+/// Parse an IKEv1 or IKEv2 message sent by a responder
+///
+/// Payloads of encrypted messages are not read.
+pub fn parse_ike_response(data: &[u8]) -> Option<IkeResponse> {
+    let header = data.get(..IKE_HEADER_LEN)?;
+    let length = u32::from_be_bytes([header[24], header[25], header[26], header[27]]) as usize;
+    let version = header[17] >> 4;
+    if length < IKE_HEADER_LEN || length > data.len() || !(1..=2).contains(&version) {
+        return None;
+    }
+    
+    let mut response = IkeResponse {
+        initiator_spi: header[..8].try_into().ok()?,
+        version,
+        exchange_type: header[18],
+        ..Default::default()
+    };
+    if header[19] & IKE_FLAG_ENCRYPTED != 0 {
+        return Some(response);
+    }
+    
+    let mut payload_type = header[16];
+    let mut payloads = &data[IKE_HEADER_LEN..length];
+    while payload_type != 0 {
+        let (next, body, rest) = split_ike_payload(payloads)?;
+        match payload_type {
+            IKE_PAYLOAD_SA => {
+                response.accepted = true;
+                response.transforms = parse_ikev1_sa(body).unwrap_or_default();
+            },
+            IKEV2_PAYLOAD_SA => response.accepted = true,
+            // IKEv1 notifications start with a DOI, IKEv2 ones do not
+            IKE_PAYLOAD_NOTIFY => response.notification = body.get(6..8).map(|t| u16::from_be_bytes([t[0], t[1]])),
+            IKEV2_PAYLOAD_NOTIFY => response.notification = body.get(2..4).map(|t| u16::from_be_bytes([t[0], t[1]])),
+            IKE_PAYLOAD_VENDOR_ID | IKEV2_PAYLOAD_VENDOR_ID => response.vendor_ids.push(ike_vendor_name(body)),
+            _ => {},
+        }
+        payload_type = next;
+        payloads = rest;
+    }
+    
+    Some(response)
+}
+
+/// Split the first payload off a payload chain into (next payload type, body, rest)
+fn split_ike_payload(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let length = u16::from_be_bytes([*data.get(2)?, *data.get(3)?]) as usize;
+    if length < 4 || length > data.len() {
+        return None;
+    }
+    Some((data[0], &data[4..length], &data[length..]))
+}
+
+/// Read the transforms out of an IKEv1 SA payload body
+fn parse_ikev1_sa(sa: &[u8]) -> Option<Vec<IkeTransform>> {
+    let mut transforms = Vec::new();
+    
+    // Skip the DOI and situation, then walk the proposals
+    let mut proposals = sa.get(8..)?;
+    loop {
+        let (next, proposal, rest) = split_ike_payload(proposals)?;
+        let spi_size = *proposal.get(2)? as usize;
+        let mut payloads = proposal.get(4 + spi_size..)?;
+        while !payloads.is_empty() {
+            let (next, transform, rest) = split_ike_payload(payloads)?;
+            transforms.push(parse_ikev1_transform(transform.get(4..)?));
+            if next == 0 {
+                break;
+            }
+            payloads = rest;
+        }
+        if next == 0 {
+            return Some(transforms);
+        }
+        proposals = rest;
+    }
+}
+
+/// Decode the attributes of an IKEv1 transform
+fn parse_ikev1_transform(mut attributes: &[u8]) -> IkeTransform {
+    let (mut encryption, mut key_length, mut hash, mut auth, mut group) = (0, 0, 0, 0, 0);
+    
+    while attributes.len() >= 4 {
+        let attribute_type = u16::from_be_bytes([attributes[0], attributes[1]]);
+        let field = u16::from_be_bytes([attributes[2], attributes[3]]);
+        // Fixed-length attributes carry the value in place of a length
+        let (value, size) = if attribute_type & 0x8000 != 0 {
+            (field as u64, 4)
+        } else {
+            let value = attributes.iter().skip(4).take(field as usize).fold(0u64, |acc, b| (acc << 8) | *b as u64);
+            (value, 4 + field as usize)
+        };
+        match attribute_type & 0x7fff {
+            1 => encryption = value,
+            2 => hash = value,
+            3 => auth = value,
+            4 => group = value,
+            14 => key_length = value,
+            _ => {},
+        }
+        attributes = attributes.get(size..).unwrap_or_default();
+    }
+    
+    IkeTransform {
+        encryption: ike_encryption_name(encryption, key_length),
+        hash: match hash {
+            1 => "MD5".to_string(),
+            2 => "SHA1".to_string(),
+            3 => "Tiger".to_string(),
+            4 => "SHA2-256".to_string(),
+            5 => "SHA2-384".to_string(),
+            6 => "SHA2-512".to_string(),
+            other => format!("hash {}", other),
+        },
+        auth: match auth {
+            1 => "PSK".to_string(),
+            2 => "DSS-Sig".to_string(),
+            3 => "RSA-Sig".to_string(),
+            4 => "RSA-Enc".to_string(),
+            5 => "RSA-RevEnc".to_string(),
+            64221 => "Hybrid".to_string(),
+            65001 => "XAUTH-PSK".to_string(),
+            65005 => "XAUTH-RSA-Sig".to_string(),
+            other => format!("auth {}", other),
+        },
+        dh_group: group as u16,
+    }
+}
+
+/// Name an IKEv1 encryption algorithm, with its key length when variable
+fn ike_encryption_name(encryption: u64, key_length: u64) -> String {
+    match encryption {
+        1 => "DES".to_string(),
+        2 => "IDEA".to_string(),
+        3 => "Blowfish".to_string(),
+        4 => "RC5".to_string(),
+        5 => "3DES".to_string(),
+        6 => "CAST".to_string(),
+        // AES defaults to a 128-bit key when none is given
+        7 => format!("AES-{}", if key_length == 0 { 128 } else { key_length }),
+        other => format!("cipher {}", other),
+    }
+}
+
+/// Name a vendor ID payload when it is well known, otherwise hex-encode it
+fn ike_vendor_name(vendor_id: &[u8]) -> String {
+    let hex: String = vendor_id.iter().map(|b| format!("{:02x}", b)).collect();
+    IKE_VENDOR_IDS.iter()
+        .find(|(prefix, _)| hex.starts_with(prefix))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or(hex)
+}
+
+/// Convert a raw service response into a banner string
+///
+/// Text responses are returned trimmed; binary protocol responses (Modbus,
+/// DNP3, BACnet, etc.) are hex-encoded so they can still be pattern matched.
+pub fn format_banner(response: &[u8]) -> String {
+    let is_text = matches!(
+        std::str::from_utf8(response),
+        Ok(text) if text.chars().all(|c| !c.is_control() || c == '\r' || c == '\n' || c == '\t')
+    );
+    
+    if is_text {
+        String::from_utf8_lossy(response).trim().to_string()
+    } else {
+        response.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Make a banner safe to store, print and write to reports
+///
+/// Line breaks and tabs become single spaces, other control characters
+/// (including the ESC that starts terminal escape sequences) are shown as
+/// `\xNN`, and banners longer than `max_len` characters are cut with `...`.
+pub fn sanitize_banner(raw: &[u8], max_len: usize) -> String {
+    let mut sanitized = String::new();
+    let mut length = 0;
+    
+    for c in String::from_utf8_lossy(raw).trim().chars() {
+        let piece = match c {
+            '\r' | '\n' | '\t' if sanitized.ends_with(' ') => continue,
+            '\r' | '\n' | '\t' => " ".to_string(),
+            c if c.is_control() => format!("\\x{:02x}", c as u32),
+            c => c.to_string(),
+        };
+        
+        length += piece.chars().count();
+        if length > max_len {
+            sanitized.push_str("...");
+            break;
+        }
+        sanitized.push_str(&piece);
+    }
+    
+    sanitized
+}
+
+/// Format a host and port as an endpoint, bracketing IPv6 addresses (`[fe80::1]:80`)
+pub fn format_endpoint(host: &str, port: u16) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, port).to_string(),
+        Err(_) => format!("{}:{}", host, port),
+    }
+}
+
+/// Identify service based on port number and banner
+///
+/// A recognisable banner overrides the port's conventional service; a bare
+/// port label is only a guess and gets low confidence.
+pub fn identify_service(port: u16, banner: &str) -> ServiceIdentity {
+    use crate::constants::COMMON_PORTS;
+    
+    match (banner_service(banner), COMMON_PORTS.get(&port)) {
+        // The banner agrees with the port, whose label is usually more specific (e.g. HTTPS)
+        (Some(name), Some(label)) if label.to_lowercase().contains(name) => ServiceIdentity {
+            name: label.to_string(),
+            confidence: SERVICE_CONFIDENCE_BANNER_CONFIRMED,
+            source: IdSource::Banner,
+        },
+        (Some(name), _) => ServiceIdentity {
+            name: name.to_string(),
+            confidence: SERVICE_CONFIDENCE_BANNER,
+            source: IdSource::Banner,
+        },
+        (None, Some(label)) => ServiceIdentity {
+            name: label.to_string(),
+            confidence: if banner.is_empty() { SERVICE_CONFIDENCE_PORT } else { SERVICE_CONFIDENCE_PORT_UNRECOGNISED },
+            source: IdSource::Port,
+        },
+        (None, None) => ServiceIdentity {
+            name: "unknown".to_string(),
+            confidence: 0.0,
+            source: IdSource::Port,
+        },
+    }
+}
+
+/// Identify service based on port number and banner, returning just its name
+pub fn identify_service_name(port: u16, banner: &str) -> String {
+    identify_service(port, banner).name
+}
+
+/// Service named by a recognisable banner
+fn banner_service(banner: &str) -> Option<&'static str> {
+    if banner.contains("SSH") || banner.contains("OpenSSH") {
+        Some("ssh")
+    } else if banner.contains("HTTP") || banner.contains("http") {
+        Some("http")
+    } else if banner.contains("FTP") {
+        Some("ftp")
+    } else if banner.contains("SMTP") || banner.contains("Postfix") || banner.contains("mail") {
+        Some("smtp")
+    } else if banner.contains("Telnet") {
+        Some("telnet")
+    } else {
+        None
+    }
+}
+
+/// Parse the HTTP status and headers from a captured service banner
+pub fn parse_http_info(banner: &str, is_https: bool) -> Option<HttpInfo> {
+    let mut lines = banner.lines();
+    let status_line = lines.next()?;
+    
+    if !status_line.starts_with("HTTP/") {
+        return None;
+    }
+    
+    let status_code = status_line.split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok());
+    
+    // Headers end at the first blank line
+    let mut headers = HashMap::new();
+    for line in lines {
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+    
+    let technologies = detect_http_technologies(&headers, "");
+    
+    Some(HttpInfo {
+        status_code,
+        server: headers.get("server").cloned(),
+        powered_by: headers.get("x-powered-by").cloned(),
+        headers,
+        is_https,
+        title: None,
+        technologies,
+    })
+}
+
+/// Open a connection for a protocol probe, with read and write timeouts set
+fn probe_connect(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<TcpStream> {
+    let timeout = Duration::from_millis(timeout_ms);
+    
+    wait_for_connection_slot();
+    let stream = proxy::connect(&SocketAddr::new(*ip, port), timeout).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    stream.set_write_timeout(Some(timeout)).ok()?;
+    Some(stream)
+}
+
+/// Check whether Redis answers commands without authentication
+///
+/// Sends `PING`, then `INFO keyspace` to list the databases holding keys.
+/// Servers that require a password answer `-NOAUTH`, giving `None`.
+pub fn probe_redis(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<Vec<String>> {
+    let mut stream = probe_connect(ip, port, timeout_ms)?;
+    let mut buffer = [0u8; 4096];
+    
+    stream.write_all(b"PING\r\n").ok()?;
+    let size = stream.read(&mut buffer).ok()?;
+    if !buffer[..size].starts_with(b"+PONG") {
+        return None;
+    }
+    
+    stream.write_all(b"INFO keyspace\r\n").ok()?;
+    let size = stream.read(&mut buffer).ok()?;
+    if !buffer[..size].starts_with(b"$") {
+        return None;
+    }
+    
+    Some(parse_redis_keyspace(&String::from_utf8_lossy(&buffer[..size])))
+}
+
+/// List the databases in a Redis `INFO keyspace` reply, e.g. "db0 (12 keys)"
+pub fn parse_redis_keyspace(info: &str) -> Vec<String> {
+    info.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .filter(|(name, _)| name.starts_with("db"))
+        .map(|(name, stats)| {
+            match stats.split(',').find_map(|stat| stat.strip_prefix("keys=")) {
+                Some(keys) => format!("{} ({} keys)", name, keys),
+                None => name.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Check whether MongoDB lists its databases without authentication
+///
+/// Sends `listDatabases` as an OP_MSG, which MongoDB 3.6 and later accept.
+/// Servers with access control enabled answer with an Unauthorized error,
+/// giving `None`.
+pub fn probe_mongodb(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<Vec<String>> {
+    let mut stream = probe_connect(ip, port, timeout_ms)?;
+    stream.write_all(&build_mongodb_list_databases()).ok()?;
+    
+    // The reply's header starts with its total length
+    let mut message = vec![0u8; 16];
+    stream.read_exact(&mut message).ok()?;
+    let length = u32::from_le_bytes([message[0], message[1], message[2], message[3]]) as usize;
+    if !(16..=MONGODB_MAX_MESSAGE_BYTES).contains(&length) {
+        return None;
+    }
+    message.resize(length, 0);
+    stream.read_exact(&mut message[16..]).ok()?;
+    
+    parse_mongodb_list_databases(&message)
+}
+
+/// Build an OP_MSG running `{listDatabases: 1, nameOnly: true, $db: "admin"}`
+fn build_mongodb_list_databases() -> Vec<u8> {
+    let mut elements = Vec::new();
+    bson_element(&mut elements, 0x10, "listDatabases", &1i32.to_le_bytes());
+    bson_element(&mut elements, 0x08, "nameOnly", &[1]);
+    bson_element(&mut elements, 0x02, "$db", &[&6i32.to_le_bytes()[..], b"admin\0"].concat());
+    
+    let mut document = ((elements.len() + 5) as i32).to_le_bytes().to_vec();
+    document.extend_from_slice(&elements);
+    document.push(0);
+    
+    let mut message = ((16 + 4 + 1 + document.len()) as i32).to_le_bytes().to_vec();
+    message.extend_from_slice(&1i32.to_le_bytes());    // Request ID
+    message.extend_from_slice(&0i32.to_le_bytes());    // Response to
+    message.extend_from_slice(&2013i32.to_le_bytes()); // OP_MSG
+    message.extend_from_slice(&0u32.to_le_bytes());    // Flag bits
+    message.push(0);                                   // Body section
+    message.extend_from_slice(&document);
+    message
+}
+
+/// Append a BSON element of type `kind` to a document being built
+fn bson_element(document: &mut Vec<u8>, kind: u8, name: &str, value: &[u8]) {
+    document.push(kind);
+    document.extend_from_slice(name.as_bytes());
+    document.push(0);
+    document.extend_from_slice(value);
+}
+
+/// Names of the databases in a MongoDB `listDatabases` OP_MSG reply, or `None` if the command failed
+pub fn parse_mongodb_list_databases(message: &[u8]) -> Option<Vec<String>> {
+    // 16-byte header, flag bits, then the body section holding the reply document
+    if message.get(12..16)? != 2013i32.to_le_bytes() || *message.get(20)? != 0 {
+        return None;
+    }
+    let reply = bson_elements(message.get(21..)?)?;
+    
+    let ok = reply.iter().any(|(kind, name, value)| *name == "ok" && match kind {
+        0x01 => f64::from_le_bytes(value[..8].try_into().unwrap_or_default()) > 0.5,
+        0x10 | 0x12 => value.iter().any(|b| *b != 0),
+        _ => false,
+    });
+    if !ok {
+        return None;
+    }
+    
+    let (_, _, databases) = reply.iter().find(|(kind, name, _)| *kind == 0x04 && *name == "databases")?;
+    Some(bson_elements(databases)?.iter()
+        .filter(|(kind, _, _)| *kind == 0x03)
+        .filter_map(|(_, _, database)| bson_elements(database))
+        .filter_map(|fields| {
+            let (_, _, name) = fields.iter().find(|(kind, name, _)| *kind == 0x02 && *name == "name")?;
+            Some(String::from_utf8_lossy(name.get(4..name.len().checked_sub(1)?)?).into_owned())
+        })
+        .collect())
+}
+
+/// Split a BSON document into (type, name, value) elements
+///
+/// Parsing stops at the first element of a type whose size isn't known here.
+fn bson_elements(document: &[u8]) -> Option<Vec<(u8, &str, &[u8])>> {
+    let length = u32::from_le_bytes(document.get(0..4)?.try_into().ok()?) as usize;
+    let mut rest = document.get(4..length.checked_sub(1)?)?;
+    let mut elements = Vec::new();
+    
+    while let Some((&kind, after)) = rest.split_first() {
+        let name_end = after.iter().position(|&b| b == 0)?;
+        let name = std::str::from_utf8(&after[..name_end]).ok()?;
+        let value = &after[name_end + 1..];
+        let prefixed = || Some(u32::from_le_bytes(value.get(0..4)?.try_into().ok()?) as usize);
+        
+        let size = match kind {
+            0x01 | 0x09 | 0x11 | 0x12 => 8, // Double, datetime, timestamp, int64
+            0x02 => 4 + prefixed()?,         // String
+            0x03 | 0x04 => prefixed()?,      // Document, array
+            0x07 => 12,                      // ObjectId
+            0x08 => 1,                       // Boolean
+            0x0A => 0,                       // Null
+            0x10 => 4,                       // Int32
+            _ => break,
+        };
+        elements.push((kind, name, value.get(..size)?));
+        rest = &value[size..];
+    }
+    
+    Some(elements)
+}
+
+/// Check whether Elasticsearch lists its indices without authentication
+///
+/// The root endpoint must identify an Elasticsearch cluster before
+/// `GET /_cat/indices` is requested; clusters with security enabled answer
+/// 401, giving `None`.
+pub fn probe_elasticsearch(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<Vec<String>> {
+    let client = proxy::client_builder()
+        .timeout(Duration::from_millis(timeout_ms.max(1000)))
+        .danger_accept_invalid_certs(true)
+        .user_agent("Rust-Scanner/1.0")
+        .build()
+        .ok()?;
+    let base = format!("http://{}", SocketAddr::new(*ip, port));
+    
+    let get = |path: &str| -> Option<String> {
+        wait_for_connection_slot();
+        let response = client.get(format!("{}{}", base, path)).send().ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let mut body = String::new();
+        response.take(HTTP_MAX_BODY_BYTES).read_to_string(&mut body).ok()?;
+        Some(body)
+    };
+    
+    if !get("/")?.contains("\"cluster_name\"") {
+        return None;
+    }
+    
+    Some(get("/_cat/indices?h=index&s=index")?
+        .lines()
+        .map(str::trim)
+        .filter(|index| !index.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+// SSH message number of the key exchange offer
+const SSH_MSG_KEXINIT: u8 = 20;
+
+/// Read the version and the algorithms an SSH server offers in its key exchange
+///
+/// Completes the version exchange and reads the server's SSH_MSG_KEXINIT,
+/// which servers send without waiting for the client's. No keys are
+/// exchanged and nothing is authenticated.
+pub fn probe_ssh_algorithms(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<SshAlgorithms> {
+    let mut stream = probe_connect(ip, port, timeout_ms)?;
+    stream.write_all(format!("{}\r\n", SSH_CLIENT_VERSION).as_bytes()).ok()?;
+    let mut reader = BufReader::new(stream);
+    
+    // Servers may send other lines before their identification string
+    let mut version = None;
+    for _ in 0..SSH_MAX_PREAMBLE_LINES {
+        let mut line = Vec::new();
+        reader.by_ref().take(SSH_MAX_LINE_BYTES).read_until(b'\n', &mut line).ok()?;
+        if line.is_empty() {
+            return None;
+        }
+        let line = String::from_utf8_lossy(&line).trim_end().to_string();
+        if line.starts_with("SSH-") {
+            version = Some(line);
+            break;
+        }
+    }
+    let version = version?;
+    
+    let payload = read_ssh_packet(&mut reader)?;
+    let algorithms = parse_ssh_kexinit(&payload)?;
+    Some(SshAlgorithms { version, ..algorithms })
+}
+
+/// Read one unencrypted SSH binary packet, returning its payload without padding
+fn read_ssh_packet(reader: &mut impl Read) -> Option<Vec<u8>> {
+    let mut header = [0u8; 5];
+    reader.read_exact(&mut header).ok()?;
+    let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let padding = header[4] as usize;
+    if length <= padding || length > SSH_MAX_PACKET_BYTES {
+        return None;
+    }
+    
+    let mut payload = vec![0u8; length - 1];
+    reader.read_exact(&mut payload).ok()?;
+    payload.truncate(length - 1 - padding);
+    Some(payload)
+}
+
+/// Parse the algorithm name-lists of an SSH_MSG_KEXINIT payload
+///
+/// The version is left empty. Client-to-server and server-to-client lists
+/// are combined, since servers offer the same algorithms in both directions.
+pub fn parse_ssh_kexinit(payload: &[u8]) -> Option<SshAlgorithms> {
+    if payload.first() != Some(&SSH_MSG_KEXINIT) {
+        return None;
+    }
+    
+    // Message number and 16-byte cookie, then ten name-lists of which the first eight are read
+    let mut rest = payload.get(17..)?;
+    let mut lists: Vec<Vec<String>> = Vec::with_capacity(8);
+    for _ in 0..8 {
+        let length = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let names = std::str::from_utf8(rest.get(4..4 + length)?).ok()?;
+        lists.push(names.split(',').filter(|name| !name.is_empty()).map(str::to_string).collect());
+        rest = &rest[4 + length..];
+    }
+    
+    let combined = |a: &[String], b: &[String]| -> Vec<String> {
+        let mut names = a.to_vec();
+        names.extend(b.iter().filter(|name| !a.contains(name)).cloned());
+        names
+    };
+    
+    Some(SshAlgorithms {
+        version: String::new(),
+        kex: lists[0].clone(),
+        host_key: lists[1].clone(),
+        ciphers: combined(&lists[2], &lists[3]),
+        macs: combined(&lists[4], &lists[5]),
+        compression: combined(&lists[6], &lists[7]),
+    })
+}
+
+/// Fetch the root page of a web service and fingerprint the response
+///
+/// One redirect is followed, so services that bounce `/` to a login page are
+/// fingerprinted by that page. Certificate validation is disabled so that
+/// self-signed services can still be examined.
+pub fn probe_http(ip: &IpAddr, port: u16, tls: bool, timeout_ms: u64) -> Option<HttpInfo> {
+    use reqwest::redirect::Policy;
+    
+    let client = proxy::client_builder()
+        .timeout(Duration::from_millis(timeout_ms.max(1000)))
+        .redirect(Policy::none())
+        .danger_accept_invalid_certs(true)
+        .user_agent("Rust-Scanner/1.0")
+        .build()
+        .ok()?;
+    
+    let scheme = if tls { "https" } else { "http" };
+    let url = format!("{}://{}/", scheme, SocketAddr::new(*ip, port));
+    
+    wait_for_connection_slot();
+    let mut response = match client.get(&url).send() {
+        Ok(response) => response,
+        Err(e) => {
+            debug!("HTTP probe of {} failed: {}", url, e);
+            return None;
+        }
+    };
     
-    match TcpStream::connect_timeout(&addr.parse().unwrap(), Duration::from_millis(timeout_ms)) {
-        Ok(_) => true,
-        Err(_) => false,
+    // Follow a single redirect, resolving relative locations against the request URL
+    if response.status().is_redirection() {
+        let location = response.headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|l| response.url().join(l).ok());
+        
+        if let Some(location) = location {
+            wait_for_connection_slot();
+            if let Ok(redirected) = client.get(location).send() {
+                response = redirected;
+            }
+        }
     }
+    
+    let status_code = Some(response.status().as_u16());
+    let is_https = response.url().scheme() == "https";
+    let headers: HashMap<String, String> = response.headers().iter()
+        .filter_map(|(name, value)| Some((name.as_str().to_lowercase(), value.to_str().ok()?.to_string())))
+        .collect();
+    
+    let mut body = Vec::new();
+    let _ = response.take(HTTP_MAX_BODY_BYTES).read_to_end(&mut body);
+    let body = String::from_utf8_lossy(&body);
+    
+    Some(HttpInfo {
+        status_code,
+        server: headers.get("server").cloned(),
+        powered_by: headers.get("x-powered-by").cloned(),
+        title: extract_html_title(&body),
+        technologies: detect_http_technologies(&headers, &body),
+        headers,
+        is_https,
+    })
 }
 
-/// Get the service banner from an open port
-pub fn get_service_banner(ip: &IpAddr, port: u16, timeout_ms: u64) -> Option<String> {
-    let addr = format!("{}:{}", ip, port);
+/// Ask a web service which methods it allows with an `OPTIONS /` request
+///
+/// Methods are read from the `Allow` header, along with the `Public` header
+/// IIS uses. Returns None when the request fails or neither header is sent.
+pub fn probe_http_methods(ip: &IpAddr, port: u16, tls: bool, timeout_ms: u64) -> Option<Vec<String>> {
+    use reqwest::redirect::Policy;
     
-    match TcpStream::connect_timeout(&addr.parse().unwrap(), Duration::from_millis(timeout_ms)) {
-        Ok(mut stream) => {
-            // Set read timeout
-            if stream.set_read_timeout(Some(Duration::from_millis(timeout_ms))).is_err() {
-                return None;
-            }
-            
-            // For HTTP ports, send a basic GET request
-            if port == 80 || port == 443 || port == 8080 || port == 8443 {
-                if stream.write_all(b"GET / HTTP/1.0\r\nHost: unknown\r\n\r\n").is_err() {
-                    return None;
-                }
-            } else {
-                // For other services, send a basic probe
-                if stream.write_all(b"\r\n").is_err() {
-                    return None;
+    let client = proxy::client_builder()
+        .timeout(Duration::from_millis(timeout_ms.max(1000)))
+        .redirect(Policy::none())
+        .danger_accept_invalid_certs(true)
+        .user_agent("Rust-Scanner/1.0")
+        .build()
+        .ok()?;
+    
+    let scheme = if tls { "https" } else { "http" };
+    let url = format!("{}://{}/", scheme, SocketAddr::new(*ip, port));
+    
+    wait_for_connection_slot();
+    let response = match client.request(reqwest::Method::OPTIONS, &url).send() {
+        Ok(response) => response,
+        Err(e) => {
+            debug!("OPTIONS request to {} failed: {}", url, e);
+            return None;
+        }
+    };
+    
+    let advertised: Vec<&str> = ["allow", "public"].iter()
+        .flat_map(|name| response.headers().get_all(*name).iter())
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    if advertised.is_empty() {
+        return None;
+    }
+    
+    Some(parse_allow_header(&advertised.join(",")))
+}
+
+/// Parse the comma-separated methods of an `Allow` header, uppercased and deduplicated
+pub fn parse_allow_header(value: &str) -> Vec<String> {
+    let mut methods: Vec<String> = Vec::new();
+    for method in value.split(',').map(|m| m.trim().to_ascii_uppercase()) {
+        if !method.is_empty() && !methods.contains(&method) {
+            methods.push(method);
+        }
+    }
+    methods
+}
+
+/// Extract the page title from an HTML document
+fn extract_html_title(body: &str) -> Option<String> {
+    lazy_static::lazy_static! {
+        static ref TITLE_REGEX: regex::Regex = regex::Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+    }
+    
+    let title = TITLE_REGEX.captures(body)?.get(1)?.as_str();
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.chars().take(200).collect())
+    }
+}
+
+/// Match HTTP headers and body against `HTTP_TECHNOLOGY_SIGNATURES`
+fn detect_http_technologies(headers: &HashMap<String, String>, body: &str) -> Vec<HttpTechnology> {
+    let header_lines: Vec<String> = headers.iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect();
+    
+    let mut technologies: Vec<HttpTechnology> = Vec::new();
+    
+    for (name, location, pattern) in HTTP_TECHNOLOGY_SIGNATURES.iter() {
+        let captures = match *location {
+            "header" => header_lines.iter().find_map(|line| pattern.captures(line)),
+            _ => pattern.captures(body),
+        };
+        
+        let version = match captures {
+            Some(caps) => caps.get(1).map(|v| v.as_str().to_string()),
+            None => continue,
+        };
+        
+        // Several signatures can identify the same technology; keep the one with a version
+        match technologies.iter_mut().find(|t| t.name == *name) {
+            Some(existing) => {
+                if existing.version.is_none() {
+                    existing.version = version;
                 }
-            }
-            
-            // Read the response
-            let mut buffer = [0; 2048];
-            match stream.read(&mut buffer) {
-                Ok(size) => {
-                    if size > 0 {
-                        // Try to interpret as UTF-8, fall back to lossy conversion
-                        match std::str::from_utf8(&buffer[..size]) {
-                            Ok(s) => Some(s.trim().to_string()),
-                            Err(_) => Some(String::from_utf8_lossy(&buffer[..size]).trim().to_string()),
-                        }
-                    } else {
-                        None
-                    }
-                },
-                Err(_) => None,
-            }
-        },
-        Err(_) => None,
+            },
+            None => technologies.push(HttpTechnology { name: name.to_string(), version }),
+        }
     }
+    
+    technologies
 }
 
-/// Send a specific service probe to an open port
-pub fn send_service_probe(ip: &IpAddr, port: u16, probe: &[u8], timeout_ms: u64) -> Option<String> {
-    let addr = format!("{}:{}", ip, port);
+// HTTP/2 frame types and flags used when probing
+const HTTP2_DATA: u8 = 0x0;
+const HTTP2_HEADERS: u8 = 0x1;
+const HTTP2_RST_STREAM: u8 = 0x3;
+const HTTP2_SETTINGS: u8 = 0x4;
+const HTTP2_PING: u8 = 0x6;
+const HTTP2_GOAWAY: u8 = 0x7;
+const HTTP2_FLAG_END_STREAM: u8 = 0x1;
+const HTTP2_FLAG_ACK: u8 = 0x1;
+const HTTP2_FLAG_END_HEADERS: u8 = 0x4;
+const HTTP2_FLAG_PADDED: u8 = 0x8;
+
+// One HTTP/2 frame read from a connection
+struct Http2Frame {
+    frame_type: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: Vec<u8>,
+}
+
+/// Detect HTTP/2 on a port and list the services of gRPC servers through reflection
+///
+/// TLS services must select "h2" by ALPN; cleartext services are sent the
+/// connection preface directly (h2c with prior knowledge). Returns None when
+/// the service doesn't answer the preface with a SETTINGS frame.
+pub fn probe_http2(ip: &IpAddr, port: u16, tls: bool, timeout_ms: u64) -> Option<Http2Info> {
+    let timeout = Duration::from_millis(timeout_ms);
+    let authority = SocketAddr::new(*ip, port).to_string();
     
-    match TcpStream::connect_timeout(&addr.parse().unwrap(), Duration::from_millis(timeout_ms)) {
-        Ok(mut stream) => {
-            // Set read timeout
-            if stream.set_read_timeout(Some(Duration::from_millis(timeout_ms))).is_err() {
-                return None;
-            }
-            
-            // Send the probe
-            if stream.write_all(probe).is_err() {
-                return None;
-            }
-            
-            // Read the response
-            let mut buffer = [0; 4096];
-            match stream.read(&mut buffer) {
-                Ok(size) => {
-                    if size > 0 {
-                        // Try to interpret as UTF-8, fall back to lossy conversion
-                        match std::str::from_utf8(&buffer[..size]) {
-                            Ok(s) => Some(s.trim().to_string()),
-                            Err(_) => Some(String::from_utf8_lossy(&buffer[..size]).trim().to_string()),
-                        }
-                    } else {
-                        None
-                    }
-                },
-                Err(_) => None,
-            }
-        },
-        Err(_) => None,
+    wait_for_connection_slot();
+    let mut stream = proxy::connect(&SocketAddr::new(*ip, port), timeout).ok()?;
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    stream.set_write_timeout(Some(timeout)).ok()?;
+    
+    if !tls {
+        return http2_session(&mut stream, "h2c", "http", &authority);
+    }
+    
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .request_alpns(&["h2"])
+        .build()
+        .ok()?;
+    
+    let mut tls_stream = connector.connect(&ip.to_string(), stream).ok()?;
+    if tls_stream.negotiated_alpn().ok()?.as_deref() != Some(&b"h2"[..]) {
+        return None;
     }
+    
+    http2_session(&mut tls_stream, "h2", "https", &authority)
 }
 
-/// Identify service based on port number and banner
-pub fn identify_service(port: u16, banner: &str) -> String {
-    use crate::constants::COMMON_PORTS;
+/// Exchange connection prefaces, then ask for gRPC services on one stream per reflection path
+fn http2_session<S: Read + Write>(stream: &mut S, protocol: &str, scheme: &str, authority: &str) -> Option<Http2Info> {
+    let mut preface = HTTP2_PREFACE.to_vec();
+    preface.extend(http2_frame(HTTP2_SETTINGS, 0, 0, &[]));
+    stream.write_all(&preface).ok()?;
     
-    // Check if there's a standard service for this port
-    if let Some(service) = COMMON_PORTS.get(&port) {
-        return service.to_string();
+    // An HTTP/2 server's preface is a SETTINGS frame; HTTP/1 servers answer with an error page
+    let settings = read_http2_frame(stream)?;
+    if settings.frame_type != HTTP2_SETTINGS || settings.stream_id != 0 || settings.flags & HTTP2_FLAG_ACK != 0 {
+        return None;
     }
+    stream.write_all(&http2_frame(HTTP2_SETTINGS, HTTP2_FLAG_ACK, 0, &[])).ok()?;
     
-    // Check for common service patterns in banner
-    if banner.contains("SSH") || banner.contains("OpenSSH") {
-        return "ssh".to_string();
-    } else if banner.contains("HTTP") || banner.contains("http") {
-        return "http".to_string();
-    } else if banner.contains("FTP") {
-        return "ftp".to_string();
-    } else if banner.contains("SMTP") || banner.contains("Postfix") || banner.contains("mail") {
-        return "smtp".to_string();
-    } else if banner.contains("Telnet") {
-        return "telnet".to_string();
+    let mut info = Http2Info {
+        protocol: protocol.to_string(),
+        ..Http2Info::default()
+    };
+    
+    // Client streams use odd identifiers
+    for (stream_id, path) in (1u32..).step_by(2).zip(GRPC_REFLECTION_PATHS) {
+        let (is_grpc, services) = match grpc_list_services(stream, stream_id, scheme, authority, path) {
+            Some(reply) => reply,
+            None => break,
+        };
+        info.grpc |= is_grpc;
+        if !services.is_empty() {
+            info.grpc_services = services;
+            break;
+        }
+    }
+    
+    Some(info)
+}
+
+/// Call a gRPC reflection method with a list_services request
+///
+/// Returns whether the reply looked like gRPC and the service names it
+/// listed, or None when the connection failed or was closed.
+fn grpc_list_services<S: Read + Write>(stream: &mut S, stream_id: u32, scheme: &str, authority: &str, path: &str) -> Option<(bool, Vec<String>)> {
+    // :method POST and :scheme come from the HPACK static table
+    let mut headers = vec![0x83, if scheme == "https" { 0x87 } else { 0x86 }];
+    for (name, value) in [(":path", path), (":authority", authority), ("content-type", "application/grpc"), ("te", "trailers")] {
+        hpack_literal(&mut headers, name, value);
+    }
+    
+    // ServerReflectionRequest with an empty list_services (field 7), in an uncompressed gRPC message
+    let request = [0x3a, 0x00];
+    let mut message = vec![0x00];
+    message.extend_from_slice(&(request.len() as u32).to_be_bytes());
+    message.extend_from_slice(&request);
+    
+    let mut frames = http2_frame(HTTP2_HEADERS, HTTP2_FLAG_END_HEADERS, stream_id, &headers);
+    frames.extend(http2_frame(HTTP2_DATA, HTTP2_FLAG_END_STREAM, stream_id, &message));
+    stream.write_all(&frames).ok()?;
+    
+    let mut is_grpc = false;
+    let mut data = Vec::new();
+    
+    while data.len() < GRPC_MAX_RESPONSE_BYTES {
+        let frame = read_http2_frame(stream)?;
+        match frame.frame_type {
+            HTTP2_GOAWAY => return None,
+            HTTP2_PING if frame.flags & HTTP2_FLAG_ACK == 0 => {
+                stream.write_all(&http2_frame(HTTP2_PING, HTTP2_FLAG_ACK, 0, &frame.payload)).ok()?;
+            },
+            _ if frame.stream_id != stream_id => {},
+            HTTP2_RST_STREAM => break,
+            // Response headers are HPACK-coded, so only uncompressed gRPC header names are recognised
+            HTTP2_HEADERS => {
+                is_grpc |= contains_bytes(&frame.payload, b"grpc-status") || contains_bytes(&frame.payload, b"application/grpc");
+            },
+            HTTP2_DATA => data.extend_from_slice(strip_http2_padding(&frame)),
+            _ => {},
+        }
+        
+        if frame.stream_id == stream_id && frame.flags & HTTP2_FLAG_END_STREAM != 0
+            && (frame.frame_type == HTTP2_HEADERS || frame.frame_type == HTTP2_DATA)
+        {
+            break;
+        }
+    }
+    
+    match parse_grpc_list_services(&data) {
+        Some(services) => Some((true, services)),
+        None => Some((is_grpc, Vec::new())),
+    }
+}
+
+/// Parse the service names from a gRPC-framed reflection list_services response
+pub fn parse_grpc_list_services(data: &[u8]) -> Option<Vec<String>> {
+    // Length-prefixed message: compressed flag, 4-byte big-endian length
+    if data.len() < 5 || data[0] != 0 {
+        return None;
+    }
+    let length = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+    let response = data.get(5..5 + length)?;
+    
+    // ServerReflectionResponse.list_services_response (6) -> repeated ServiceResponse (1) -> name (1)
+    let list = protobuf_fields(response)?.into_iter().find(|(field, _)| *field == 6)?.1;
+    let services = protobuf_fields(list)?.into_iter()
+        .filter(|(field, _)| *field == 1)
+        .filter_map(|(_, service)| protobuf_fields(service)?.into_iter().find(|(field, _)| *field == 1))
+        .filter_map(|(_, name)| std::str::from_utf8(name).ok().map(|name| name.to_string()))
+        .collect();
+    
+    Some(services)
+}
+
+/// Length-delimited fields of a protobuf message as (field number, bytes), skipping other wire types
+fn protobuf_fields(mut message: &[u8]) -> Option<Vec<(u64, &[u8])>> {
+    let mut fields = Vec::new();
+    
+    while !message.is_empty() {
+        let (key, rest) = read_protobuf_varint(message)?;
+        message = match key & 0x7 {
+            0 => read_protobuf_varint(rest)?.1,
+            1 => rest.get(8..)?,
+            2 => {
+                let (length, rest) = read_protobuf_varint(rest)?;
+                let length = usize::try_from(length).ok()?;
+                fields.push((key >> 3, rest.get(..length)?));
+                &rest[length..]
+            },
+            5 => rest.get(4..)?,
+            _ => return None,
+        };
+    }
+    
+    Some(fields)
+}
+
+/// Decode a protobuf varint, returning it and the bytes that follow
+fn read_protobuf_varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+/// Build an HTTP/2 frame
+fn http2_frame(frame_type: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+    let mut frame = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+    frame.push(frame_type);
+    frame.push(flags);
+    frame.extend_from_slice(&(stream_id & 0x7fff_ffff).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Read one HTTP/2 frame, refusing payloads larger than `HTTP2_MAX_FRAME_BYTES`
+fn read_http2_frame<S: Read>(stream: &mut S) -> Option<Http2Frame> {
+    let mut header = [0u8; 9];
+    stream.read_exact(&mut header).ok()?;
+    
+    let length = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    if length > HTTP2_MAX_FRAME_BYTES {
+        return None;
+    }
+    
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload).ok()?;
+    
+    Some(Http2Frame {
+        frame_type: header[3],
+        flags: header[4],
+        stream_id: u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff,
+        payload,
+    })
+}
+
+/// Data carried by a DATA frame, without its padding
+fn strip_http2_padding(frame: &Http2Frame) -> &[u8] {
+    if frame.flags & HTTP2_FLAG_PADDED == 0 {
+        return &frame.payload;
     }
     
-    // Default to "unknown"
-    "unknown".to_string()
+    match frame.payload.split_first() {
+        Some((&padding, data)) if usize::from(padding) <= data.len() => &data[..data.len() - usize::from(padding)],
+        _ => &[],
+    }
+}
+
+/// Append a header to an HPACK block as a literal without indexing, with no Huffman coding
+fn hpack_literal(block: &mut Vec<u8>, name: &str, value: &str) {
+    block.push(0x00);
+    for string in [name, value] {
+        // String length is an integer with a 7-bit prefix
+        let mut length = string.len();
+        if length < 0x7f {
+            block.push(length as u8);
+        } else {
+            block.push(0x7f);
+            length -= 0x7f;
+            while length >= 0x80 {
+                block.push((length % 0x80) as u8 | 0x80);
+                length /= 0x80;
+            }
+            block.push(length as u8);
+        }
+        block.extend_from_slice(string.as_bytes());
+    }
+}
+
+/// Whether `needle` appears anywhere in `haystack`
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
 }
 
 /// Check if a host is alive using ICMP ping
@@ -171,12 +2289,22 @@ pub fn ping_host(ip: &IpAddr) -> bool {
     }
 }
 
+/// Check if a host is alive using ICMP ping, falling back to TCP probing
+///
+/// ICMP cannot be carried by a proxy, so proxied scans only use TCP probing.
+/// Hosts on a subnet covered by an ARP sweep are judged by the sweep alone.
+pub fn host_responds(ip: &IpAddr, timeout_ms: u64) -> bool {
+    if let Some(answered) = arp_liveness(ip) {
+        return answered;
+    }
+    
+    (!proxy::is_enabled() && ping_host(ip)) || tcp_ping_host(ip, timeout_ms)
+}
+
 /// Check if a host is alive using TCP probing of common ports
 pub fn tcp_ping_host(ip: &IpAddr, timeout_ms: u64) -> bool {
     // Check common ports that are likely to be open
-    const COMMON_PORTS: [u16; 7] = [80, 443, 22, 445, 3389, 8080, 23];
-    
-    for port in &COMMON_PORTS {
+    for port in &crate::constants::TCP_PING_PORTS {
         if is_port_open(ip, *port, timeout_ms) {
             return true;
         }
@@ -185,16 +2313,300 @@ pub fn tcp_ping_host(ip: &IpAddr, timeout_ms: u64) -> bool {
     false
 }
 
+/// Measure a host's round-trip time from TCP connects to common ports
+///
+/// Both accepted and refused connections count as a sample, since either is a
+/// full round trip. Up to `RTT_SAMPLES` samples are taken and the slowest is
+/// returned, or `None` if no port answered within `timeout_ms`.
+pub fn measure_rtt(ip: &IpAddr, timeout_ms: u64) -> Option<Duration> {
+    let mut samples = Vec::with_capacity(RTT_SAMPLES);
+    
+    for port in &crate::constants::TCP_PING_PORTS {
+        if samples.len() >= RTT_SAMPLES {
+            break;
+        }
+        wait_for_connection_slot();
+        
+        let start = Instant::now();
+        match proxy::connect(&SocketAddr::new(*ip, *port), Duration::from_millis(timeout_ms)) {
+            Ok(_) => samples.push(start.elapsed()),
+            Err(e) if e.kind() == ErrorKind::ConnectionRefused => samples.push(start.elapsed()),
+            Err(_) => {},
+        }
+    }
+    
+    samples.into_iter().max()
+}
+
+/// Whether every one of a host's sentinel ports (`TCP_PING_PORTS`) times out
+///
+/// Each port gets a single connect with no retries. A host that accepts or
+/// refuses any connection is not treated as firewalled.
+pub fn sentinel_ports_filtered(ip: &IpAddr, timeout_ms: u64) -> bool {
+    crate::constants::TCP_PING_PORTS.iter().all(|port| {
+        wait_for_connection_slot();
+        match proxy::connect(&SocketAddr::new(*ip, *port), Duration::from_millis(timeout_ms)) {
+            Ok(_) => false,
+            Err(e) => !matches!(e.kind(), ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset),
+        }
+    })
+}
+
+/// Port timeout for a host with the given RTT: `RTT_TIMEOUT_MULTIPLIER` times the RTT, clamped to `min_ms..=max_ms`
+pub fn adaptive_timeout_ms(rtt: Duration, min_ms: u64, max_ms: u64) -> u64 {
+    let scaled = (rtt * RTT_TIMEOUT_MULTIPLIER).as_millis();
+    let scaled = u64::try_from(scaled).unwrap_or(u64::MAX);
+    scaled.max(min_ms).min(max_ms)
+}
+
+// Hosts that answered an ARP sweep of a directly-connected subnet
+#[derive(Debug, Clone)]
+pub struct ArpSweep {
+    pub interface: String,                 // Interface the requests were sent from
+    pub network: Ipv4Network,              // Subnet that was swept
+    pub hosts: HashMap<Ipv4Addr, MacAddr>, // Addresses that replied, with their MAC addresses
+}
+
+// ARP sweeps consulted by `host_responds` for the current scan
+lazy_static::lazy_static! {
+    static ref ARP_SWEEPS: RwLock<Vec<ArpSweep>> = RwLock::new(Vec::new());
+}
+
+/// Use these ARP sweeps to decide which hosts on their subnets are online
+pub fn set_arp_sweeps(sweeps: Vec<ArpSweep>) {
+    *ARP_SWEEPS.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = sweeps;
+}
+
+/// Whether `ip` answered ARP, or None when no sweep covered its subnet
+pub fn arp_liveness(ip: &IpAddr) -> Option<bool> {
+    let ip = match ip {
+        IpAddr::V4(ip) => *ip,
+        IpAddr::V6(_) => return None,
+    };
+    
+    ARP_SWEEPS.read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .find(|sweep| sweep.network.contains(ip))
+        .map(|sweep| sweep.hosts.contains_key(&ip))
+}
+
+/// MAC address `ip` answered an ARP sweep with, if any
+pub fn arp_mac(ip: &IpAddr) -> Option<String> {
+    let ip = match ip {
+        IpAddr::V4(ip) => *ip,
+        IpAddr::V6(_) => return None,
+    };
+    
+    ARP_SWEEPS.read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .find_map(|sweep| sweep.hosts.get(&ip))
+        .map(|mac| mac.to_string())
+}
+
+/// Find live hosts on a directly-connected IPv4 subnet with ARP
+///
+/// Every host has to answer ARP to talk on its LAN, so this finds hosts that
+/// drop ICMP and have no common ports open. Hostnames are resolved for the
+/// hosts that reply.
+pub fn discover_hosts_arp(interface: Option<&str>, cidr: &str) -> Result<Vec<HostInfo>, String> {
+    let sweep = arp_sweep(interface, cidr)?;
+    
+    let mut hosts: Vec<HostInfo> = sweep.hosts.into_par_iter()
+        .map(|(ip, mac)| {
+            let mac = mac.to_string();
+            HostInfo {
+                ip: ip.to_string(),
+                hostname: resolver::resolve_hostname_comprehensive(&IpAddr::V4(ip)),
+                is_online: true,
+                vendor: resolver::oui_lookup(&mac),
+                mac: Some(mac),
+            }
+        })
+        .collect();
+    hosts.sort_by_key(|host| host.ip.parse::<Ipv4Addr>().ok());
+    
+    Ok(hosts)
+}
+
+/// Send an ARP request to every address in `cidr` and collect the replies
+///
+/// The subnet must lie inside a network configured on `interface`, or on any
+/// up, non-loopback interface when none is named. Opening the raw socket needs
+/// root or CAP_NET_RAW; lacking it is reported as an error rather than an
+/// empty sweep.
+pub fn arp_sweep(interface: Option<&str>, cidr: &str) -> Result<ArpSweep, String> {
+    let network: Ipv4Network = cidr.trim().parse()
+        .map_err(|_| format!("ARP discovery needs an IPv4 address or CIDR block, not '{}'", cidr))?;
+    let (iface, source_ip) = arp_interface_for(interface, &network)?;
+    let source_mac = iface.mac.unwrap_or_else(MacAddr::zero);
+    
+    let channel_config = datalink::Config {
+        read_timeout: Some(Duration::from_millis(ARP_READ_TIMEOUT_MS)),
+        ..Default::default()
+    };
+    let (mut tx, mut rx) = match datalink::channel(&iface, channel_config) {
+        Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
+        Ok(_) => return Err(format!("Interface {} does not provide an Ethernet channel", iface.name)),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => return Err(format!(
+            "ARP discovery needs raw socket privileges on {} (run as root or grant CAP_NET_RAW)", iface.name)),
+        Err(e) => return Err(format!("Could not open {} for ARP discovery: {}", iface.name, e)),
+    };
+    
+    // Skip the network and broadcast addresses unless the block has no others
+    let skip_edges = network.prefix() < 31;
+    for target in network.iter() {
+        if target == source_ip || (skip_edges && (target == network.network() || target == network.broadcast())) {
+            continue;
+        }
+        
+        wait_for_connection_slot();
+        if let Some(Err(e)) = tx.send_to(&build_arp_request(source_mac, source_ip, target), None) {
+            return Err(format!("Failed to send ARP request on {}: {}", iface.name, e));
+        }
+    }
+    
+    // Replies queue in the socket buffer while requests are sent, so collect them afterwards
+    let mut hosts = HashMap::new();
+    let deadline = Instant::now() + Duration::from_millis(ARP_REPLY_WAIT_MS);
+    while Instant::now() < deadline {
+        match rx.next() {
+            Ok(frame) => {
+                if let Some((ip, mac)) = parse_arp_reply(frame) {
+                    if network.contains(ip) && ip != source_ip {
+                        debug!("ARP reply from {} ({})", ip, mac);
+                        hosts.insert(ip, mac);
+                    }
+                }
+            },
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => {},
+            Err(e) => return Err(format!("Failed to read ARP replies on {}: {}", iface.name, e)),
+        }
+    }
+    
+    Ok(ArpSweep {
+        interface: iface.name,
+        network,
+        hosts,
+    })
+}
+
+/// Addresses assigned to a local interface, IPv4 first
+pub fn interface_addresses(name: &str) -> Result<Vec<IpAddr>, String> {
+    let iface = datalink::interfaces().into_iter()
+        .find(|iface| iface.name == name)
+        .ok_or_else(|| format!("No network interface named {}", name))?;
+    
+    let mut addresses: Vec<IpAddr> = iface.ips.iter().map(|ip| ip.ip()).collect();
+    addresses.sort_by_key(|ip| ip.is_ipv6());
+    if addresses.is_empty() {
+        return Err(format!("Interface {} has no IP addresses", name));
+    }
+    Ok(addresses)
+}
+
+/// Pick the interface, and our address on it, whose subnet contains `network`
+fn arp_interface_for(name: Option<&str>, network: &Ipv4Network) -> Result<(NetworkInterface, Ipv4Addr), String> {
+    for iface in datalink::interfaces() {
+        if let Some(name) = name {
+            if iface.name != name {
+                continue;
+            }
+        }
+        if !iface.is_up() || iface.is_loopback() || iface.mac.is_none() {
+            continue;
+        }
+        
+        let source_ip = iface.ips.iter().find_map(|ip| match ip {
+            IpNetwork::V4(local) if local.prefix() <= network.prefix() && local.contains(network.network()) => Some(local.ip()),
+            _ => None,
+        });
+        if let Some(source_ip) = source_ip {
+            return Ok((iface, source_ip));
+        }
+    }
+    
+    Err(match name {
+        Some(name) => format!("{} is not on a subnet directly connected to interface {}", network, name),
+        None => format!("{} is not on a directly connected subnet", network),
+    })
+}
+
+/// Build a broadcast Ethernet frame asking who has `target_ip`
+fn build_arp_request(source_mac: MacAddr, source_ip: Ipv4Addr, target_ip: Ipv4Addr) -> Vec<u8> {
+    // 14-byte Ethernet header followed by a 28-byte IPv4 ARP payload
+    let mut frame = vec![0u8; 42];
+    
+    if let Some(mut ethernet) = MutableEthernetPacket::new(&mut frame) {
+        ethernet.set_destination(MacAddr::broadcast());
+        ethernet.set_source(source_mac);
+        ethernet.set_ethertype(EtherTypes::Arp);
+        
+        if let Some(mut arp) = MutableArpPacket::new(ethernet.payload_mut()) {
+            arp.set_hardware_type(ArpHardwareTypes::Ethernet);
+            arp.set_protocol_type(EtherTypes::Ipv4);
+            arp.set_hw_addr_len(6);
+            arp.set_proto_addr_len(4);
+            arp.set_operation(ArpOperations::Request);
+            arp.set_sender_hw_addr(source_mac);
+            arp.set_sender_proto_addr(source_ip);
+            arp.set_target_hw_addr(MacAddr::zero());
+            arp.set_target_proto_addr(target_ip);
+        }
+    }
+    
+    frame
+}
+
+/// Extract the sender's IPv4 and MAC address from an Ethernet frame carrying an ARP reply
+pub fn parse_arp_reply(frame: &[u8]) -> Option<(Ipv4Addr, MacAddr)> {
+    let ethernet = EthernetPacket::new(frame)?;
+    if ethernet.get_ethertype() != EtherTypes::Arp {
+        return None;
+    }
+    
+    let arp = ArpPacket::new(ethernet.payload())?;
+    if arp.get_operation() != ArpOperations::Reply
+        || arp.get_hardware_type() != ArpHardwareTypes::Ethernet
+        || arp.get_protocol_type() != EtherTypes::Ipv4 {
+        return None;
+    }
+    
+    Some((arp.get_sender_proto_addr(), arp.get_sender_hw_addr()))
+}
+
+/// Random source for shuffling scan order, reproducible when seeded
+///
+/// `stream` keeps independent shuffles made under one seed apart, such as the
+/// port orders of different hosts. Without a seed, `stream` is ignored and the
+/// generator is seeded from OS entropy.
+pub fn scan_rng(seed: Option<u64>, stream: u64) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed ^ stream.wrapping_mul(0x9e37_79b9_7f4a_7c15)),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// Stream number for shuffles tied to one host, for use with `scan_rng`
+pub fn host_rng_stream(ip: &IpAddr) -> u64 {
+    match ip {
+        IpAddr::V4(v4) => u32::from(*v4) as u64,
+        IpAddr::V6(v6) => {
+            let bits = u128::from(*v6);
+            (bits as u64) ^ ((bits >> 64) as u64)
+        },
+    }
+}
+
 /// Randomize the order of ports to scan
-pub fn randomize_ports(ports: &mut Vec<u16>) {
-    let mut rng = thread_rng();
-    ports.shuffle(&mut rng);
+pub fn randomize_ports<R: Rng + ?Sized>(ports: &mut [u16], rng: &mut R) {
+    ports.shuffle(rng);
 }
 
 /// Randomize the order of hosts to scan
-pub fn randomize_hosts(hosts: &mut Vec<IpAddr>) {
-    let mut rng = thread_rng();
-    hosts.shuffle(&mut rng);
+pub fn randomize_hosts<R: Rng + ?Sized>(hosts: &mut [IpAddr], rng: &mut R) {
+    hosts.shuffle(rng);
 }
 
 /// Get a random port from a range
@@ -238,6 +2650,8 @@ pub fn fingerprint_os(banners: &[String]) -> Option<String> {
         return Some("Red Hat Linux".to_string());
     } else if lower_banner.contains("fedora") {
         return Some("Fedora Linux".to_string());
+    } else if lower_banner.contains("raspbian") {
+        return Some("Raspbian Linux".to_string());
     } else if lower_banner.contains("linux") {
         return Some("Linux".to_string());
     } else if lower_banner.contains("freebsd") {
@@ -246,6 +2660,12 @@ pub fn fingerprint_os(banners: &[String]) -> Option<String> {
         return Some("OpenBSD".to_string());
     } else if lower_banner.contains("macos") || lower_banner.contains("mac os") {
         return Some("macOS".to_string());
+    } else if lower_banner.contains("cisco ios") || lower_banner.contains("ssh-2.0-cisco-") {
+        return Some("Cisco IOS".to_string());
+    } else if lower_banner.contains("junos") {
+        return Some("Juniper Junos".to_string());
+    } else if lower_banner.contains("ssh-2.0-rosssh") {
+        return Some("MikroTik RouterOS".to_string());
     }
     
     None
@@ -266,32 +2686,259 @@ pub fn generate_random_mac() -> String {
     )
 }
 
-/// Generate a random IPv4 address
+/// Generate a random public IPv4 address
 pub fn generate_random_ipv4() -> IpAddr {
     let mut rng = thread_rng();
-    let a = rng.gen::<u8>();
-    let b = rng.gen::<u8>();
-    let c = rng.gen::<u8>();
-    let d = rng.gen::<u8>();
     
-    // Avoid private IP ranges
-    if (a == 10) || 
-       (a == 172 && b >= 16 && b <= 31) || 
-       (a == 192 && b == 168) || 
-       (a == 127) || 
-       (a == 0) || 
-       (a >= 224) {
-        return generate_random_ipv4();
+    // Rejection sampling keeps the distribution uniform over public space
+    for _ in 0..RANDOM_IPV4_MAX_ATTEMPTS {
+        let ip = Ipv4Addr::from(rng.gen::<u32>());
+        if !is_reserved_ipv4(ip) {
+            return IpAddr::V4(ip);
+        }
     }
     
-    IpAddr::from_str(&format!("{}.{}.{}.{}", a, b, c, d)).unwrap()
+    // Fall back to remapping the first octet into 11-99, which is entirely public
+    let [a, b, c, d] = rng.gen::<u32>().to_be_bytes();
+    IpAddr::V4(Ipv4Addr::new(11 + a % 89, b, c, d))
+}
+
+/// Check whether an IPv4 address is private, reserved, or otherwise not publicly routable
+pub fn is_reserved_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    
+    a == 0                                          // "This" network
+        || a == 10                                  // Private
+        || (a == 100 && (64..=127).contains(&b))    // Carrier-grade NAT (100.64.0.0/10)
+        || a == 127                                 // Loopback
+        || (a == 169 && b == 254)                   // Link-local
+        || (a == 172 && (16..=31).contains(&b))     // Private
+        || (a == 192 && b == 0 && c == 0)           // IETF protocol assignments
+        || (a == 192 && b == 0 && c == 2)           // Documentation (TEST-NET-1)
+        || (a == 192 && b == 168)                   // Private
+        || (a == 198 && (b == 18 || b == 19))       // Benchmarking (198.18.0.0/15)
+        || (a == 198 && b == 51 && c == 100)        // Documentation (TEST-NET-2)
+        || (a == 203 && b == 0 && c == 113)         // Documentation (TEST-NET-3)
+        || a >= 224                                 // Multicast, reserved and broadcast
 }
 
 /// Format an IP range for display
+///
+/// The `start-end` form is the one `resolver::parse_range` accepts, so the
+/// output can be passed back in as a scan target for either address family.
 pub fn format_ip_range(start: &IpAddr, end: &IpAddr) -> String {
-    if let (IpAddr::V4(start_v4), IpAddr::V4(end_v4)) = (start, end) {
-        format!("{}-{}", start_v4, end_v4)
-    } else {
-        format!("{}..{}", start, end)
+    format!("{}-{}", start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{TcpListener, UdpSocket};
+    use std::sync::Arc;
+    
+    /// SNMP agent that answers sysDescr GetRequests for one community and drops the rest,
+    /// recording every community it was asked with
+    fn spawn_snmp_agent(community: &'static str, sys_descr: &'static str) -> (u16, Arc<Mutex<Vec<String>>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let asked = seen.clone();
+        thread::spawn(move || {
+            let mut buffer = [0u8; 1500];
+            while let Ok((size, from)) = socket.recv_from(&mut buffer) {
+                let request = || -> Option<(String, Vec<u8>, Vec<u8>)> {
+                    let (_, message, _) = ber_decode(&buffer[..size])?;
+                    let (_, _version, rest) = ber_decode(message)?;
+                    let (_, requested, rest) = ber_decode(rest)?;
+                    let (_, pdu, _) = ber_decode(rest)?;
+                    let (_, id, rest) = ber_decode(pdu)?;
+                    let (_, _, rest) = ber_decode(rest)?;
+                    let (_, _, rest) = ber_decode(rest)?;
+                    let (_, varbind_list, _) = ber_decode(rest)?;
+                    let (_, varbind, _) = ber_decode(varbind_list)?;
+                    let (_, oid, _) = ber_decode(varbind)?;
+                    Some((String::from_utf8_lossy(requested).to_string(), id.to_vec(), oid.to_vec()))
+                };
+                let (requested, id, oid) = match request() {
+                    Some(request) => request,
+                    None => continue,
+                };
+                asked.lock().unwrap().push(requested.clone());
+                if requested != community {
+                    continue;
+                }
+                
+                let varbind = [ber_encode(0x06, &oid), ber_encode(0x04, sys_descr.as_bytes())].concat();
+                let pdu = [ber_encode(0x02, &id), ber_encode_integer(0), ber_encode_integer(0), ber_encode(0x30, &ber_encode(0x30, &varbind))].concat();
+                let response = ber_encode(0x30, &[ber_encode_integer(1), ber_encode(0x04, community.as_bytes()), ber_encode(0xa2, &pdu)].concat());
+                let _ = socket.send_to(&response, from);
+            }
+        });
+        (port, seen)
+    }
+    
+    #[test]
+    fn snmp_community_guessing_finds_the_accepted_community() {
+        let (port, seen) = spawn_snmp_agent("s3cret", "Mock switch 1.0");
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        
+        let found = crate::credentials::find_snmp_community(&ip, port, &["public", "s3cret", "private"], 300);
+        
+        assert_eq!(found, Some(("s3cret".to_string(), "Mock switch 1.0".to_string())));
+        // Communities are tried in order and guessing stops at the first accepted one
+        assert_eq!(*seen.lock().unwrap(), vec!["public", "s3cret"]);
+    }
+    
+    #[test]
+    fn snmp_agents_that_drop_every_community_yield_nothing() {
+        let (port, seen) = spawn_snmp_agent("s3cret", "Mock switch 1.0");
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        
+        assert_eq!(crate::credentials::find_snmp_community(&ip, port, &["public", "private"], 200), None);
+        assert_eq!(seen.lock().unwrap().len(), 2);
+    }
+    
+    #[test]
+    fn sanitized_banners_neutralize_terminal_escapes_and_binary() {
+        let sanitized = sanitize_banner(b"220 FTP\x1b[2J\x01\x00\r\n\tready", 100);
+        
+        assert_eq!(sanitized, "220 FTP\\x1b[2J\\x01\\x00 ready");
+        assert!(!sanitized.chars().any(|c| c.is_control()));
+        
+        // Bytes that aren't UTF-8 become replacement characters rather than raw bytes
+        assert_eq!(sanitize_banner(&[b'S', 0xff, 0xfe, b'!'], 100), "S\u{fffd}\u{fffd}!");
+    }
+    
+    #[test]
+    fn long_banners_are_truncated_with_an_ellipsis() {
+        assert_eq!(sanitize_banner(&[b'A'; 50], 10), "AAAAAAAAAA...");
+        assert_eq!(sanitize_banner(b"short", 10), "short");
+        
+        // An escaped character is kept whole or cut entirely
+        assert_eq!(sanitize_banner(b"AB\x1b[2J", 4), "AB...");
+    }
+    
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+    
+    #[test]
+    fn ikev1_main_mode_response_yields_the_chosen_transform() {
+        // Main-mode reply choosing 3DES/MD5/PSK with DH group 2, plus a DPD vendor ID
+        let captured = from_hex(concat!(
+            "0102030405060708a1b2c3d4e5f60718011002000000000000000068",
+            "0d00003800000001000000010000002c010100010000002401010000",
+            "80010005800200018003000180040002800b0001000c000400007080",
+            "00000014afcad71368a1f1c96b8696fc77570100",
+        ));
+        
+        let response = parse_ike_response(&captured).unwrap();
+        
+        assert_eq!(response.initiator_spi, [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(response.version, 1);
+        assert_eq!(response.exchange_type, IKE_EXCHANGE_MAIN_MODE);
+        assert!(response.accepted);
+        assert_eq!(response.transforms, vec![IkeTransform {
+            encryption: "3DES".to_string(),
+            hash: "MD5".to_string(),
+            auth: "PSK".to_string(),
+            dh_group: 2,
+        }]);
+        assert!(response.transforms[0].is_weak());
+        assert_eq!(response.vendor_ids, vec!["Dead Peer Detection"]);
+        assert_eq!(response.notification, None);
+        
+        // A truncated capture is rejected rather than misread
+        assert_eq!(parse_ike_response(&captured[..captured.len() - 1]), None);
+    }
+    
+    /// Listener whose accept queue is already full, so the next connect to it goes
+    /// unanswered until the queue is drained `drain_after` later
+    fn spawn_backlogged_listener(drain_after: Duration) -> u16 {
+        let socket = socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+        socket.bind(&SocketAddr::from(([127, 0, 0, 1], 0)).into()).unwrap();
+        socket.listen(0).unwrap();
+        let listener: TcpListener = socket.into();
+        let port = listener.local_addr().unwrap().port();
+        let queued = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        thread::spawn(move || {
+            thread::sleep(drain_after);
+            drop(queued);
+            for stream in listener.incoming() {
+                drop(stream);
+            }
+        });
+        port
+    }
+    
+    #[test]
+    fn a_timed_out_connect_is_retried_until_the_port_accepts() {
+        let timeout_ms = 150;
+        let port = spawn_backlogged_listener(Duration::from_millis(timeout_ms + 20));
+        assert_eq!(probe_retries(), 1);
+        
+        assert!(matches!(probe_port(&IpAddr::from([127, 0, 0, 1]), port, timeout_ms), PortState::Open));
+    }
+    
+    #[test]
+    fn a_refused_connect_is_not_retried() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        assert_eq!(probe_retries(), 1);
+        
+        let started = Instant::now();
+        assert!(matches!(probe_port(&IpAddr::from([127, 0, 0, 1]), port, 1000), PortState::Closed));
+        assert!(started.elapsed() < Duration::from_millis(PROBE_RETRY_BASE_DELAY_MS), "refused connect took {:?}", started.elapsed());
+    }
+    
+    /// Host order then each host's port order, shuffled the way the scanner does it
+    fn seeded_scan_order(seed: u64) -> Vec<(IpAddr, Vec<u16>)> {
+        let mut hosts: Vec<IpAddr> = (1..=32).map(|last| IpAddr::from([10, 0, 0, last])).collect();
+        randomize_hosts(&mut hosts, &mut scan_rng(Some(seed), 0));
+        hosts.into_iter()
+            .map(|ip| {
+                let mut ports: Vec<u16> = (1..=100).collect();
+                randomize_ports(&mut ports, &mut scan_rng(Some(seed), host_rng_stream(&ip)));
+                (ip, ports)
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn the_same_seed_gives_the_same_scan_order() {
+        assert_eq!(seeded_scan_order(42), seeded_scan_order(42));
+    }
+    
+    #[test]
+    fn different_seeds_give_different_scan_orders() {
+        let first = seeded_scan_order(42);
+        let second = seeded_scan_order(43);
+        
+        let hosts = |order: &[(IpAddr, Vec<u16>)]| order.iter().map(|(ip, _)| *ip).collect::<Vec<IpAddr>>();
+        assert_ne!(hosts(&first), hosts(&second));
+        let ports_of = |order: &[(IpAddr, Vec<u16>)], ip: &IpAddr| order.iter().find(|(host, _)| host == ip).unwrap().1.clone();
+        assert_ne!(ports_of(&first, &first[0].0), ports_of(&second, &first[0].0));
+    }
+    
+    #[test]
+    fn modbus_ports_get_the_modbus_probe_and_a_hex_banner() {
+        // Read Holding Registers reply carrying two registers, 0x0102 and 0x0304
+        const REPLY: &[u8] = b"\x00\x01\x00\x00\x00\x07\x01\x03\x04\x01\x02\x03\x04";
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let received = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 12];
+            stream.read_exact(&mut request).unwrap();
+            stream.write_all(REPLY).unwrap();
+            request.to_vec()
+        });
+        
+        let probe = service_probe(502);
+        let result = exchange_service_probe(&IpAddr::from([127, 0, 0, 1]), port, probe, 1000).unwrap();
+        
+        assert_eq!(probe, SERVICE_PROBES[&502].as_slice());
+        assert_eq!(received.join().unwrap(), probe);
+        assert_eq!(result.response, REPLY);
+        assert_eq!(result.banner, "00010000000701030401020304");
     }
 }
@@ -0,0 +1,77 @@
+// Exit codes of the scanner binary, which CI jobs gate on with --fail-on
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+
+/// FTP server that lets anyone log in, a seeded critical default-credential finding
+fn spawn_open_ftp_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || {
+                let mut writer = stream.try_clone().unwrap();
+                let _ = writer.write_all(b"220 Mock FTP ready\r\n");
+                for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                    let reply: &[u8] = match line.get(..4).map(|command| command.to_ascii_uppercase()).as_deref() {
+                        Some("USER") => b"331 Password required\r\n",
+                        Some("PASS") => b"230 Logged in\r\n",
+                        Some("QUIT") => b"221 Bye\r\n",
+                        _ => b"500 Unknown command\r\n",
+                    };
+                    if writer.write_all(reply).is_err() || reply.starts_with(b"221") {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    port
+}
+
+/// Run the scanner with `args`, writing its report to a scratch file, and return its exit code
+fn run_scanner(name: &str, args: &[&str]) -> i32 {
+    let report: PathBuf = std::env::temp_dir().join(format!("rustnet-exit-{}-{}.txt", name, std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_rustnet_scan"))
+        .args(args)
+        .args(["--offline", "--quiet", "--timeout", "500", "-o"])
+        .arg(&report)
+        .output()
+        .unwrap()
+        .status;
+    let _ = std::fs::remove_file(&report);
+    status.code().unwrap()
+}
+
+#[test]
+fn findings_at_or_above_the_threshold_exit_with_1() {
+    let port = spawn_open_ftp_server().to_string();
+    let scan = ["127.0.0.1", "-p", port.as_str(), "--scan-offline", "--check-default-creds"];
+    
+    assert_eq!(run_scanner("high", &[&scan[..], &["--fail-on", "high"]].concat()), 1);
+    assert_eq!(run_scanner("critical", &[&scan[..], &["--fail-on", "critical"]].concat()), 1);
+    // Without a threshold the same findings leave the exit code clean
+    assert_eq!(run_scanner("no-threshold", &scan), 0);
+}
+
+#[test]
+fn a_clean_scan_exits_with_0() {
+    let port = spawn_open_ftp_server().to_string();
+    
+    assert_eq!(run_scanner("clean", &["127.0.0.1", "-p", port.as_str(), "--scan-offline", "--fail-on", "high"]), 0);
+}
+
+#[test]
+fn usage_errors_exit_with_2() {
+    assert_eq!(run_scanner("bad-severity", &["127.0.0.1", "--fail-on", "severe"]), 2);
+    assert_eq!(run_scanner("bad-threads", &["127.0.0.1", "--threads", "0"]), 2);
+}
+
+#[test]
+fn unreachable_targets_exit_with_3() {
+    // TEST-NET-1 is reserved for documentation, so nothing answers there
+    assert_eq!(run_scanner("unreachable", &["192.0.2.1", "-p", "80", "--fail-on", "high"]), 3);
+}